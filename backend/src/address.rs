@@ -35,7 +35,7 @@ impl fmt::Debug for AccountAddress {
 
 pub type ContractIndex = UnsignedLong; // TODO check format.
 
-#[derive(Debug, SimpleObject, serde::Serialize, serde::Deserialize, Clone, Copy)]
+#[derive(Debug, SimpleObject, serde::Serialize, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
 #[graphql(complex)]
 pub struct ContractAddress {
     pub index: ContractIndex,
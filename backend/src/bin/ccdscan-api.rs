@@ -5,7 +5,7 @@ use concordium_scan::{
     graphql_api::{self, node_status::NodeInfoReceiver},
     migrations::{self, SchemaVersion},
     monitoring::database_metrics_collector::DatabaseMetricsCollector,
-    rest_api, router,
+    rate_limit, rest_api, router, webhooks,
 };
 use prometheus_client::{
     metrics::{family::Family, gauge::Gauge},
@@ -68,6 +68,10 @@ struct Cli {
     monitoring_listen: SocketAddr,
     #[command(flatten, next_help_heading = "Configuration")]
     api_config: graphql_api::ApiServiceConfig,
+    #[command(flatten, next_help_heading = "Transaction submission")]
+    transaction_submission_config: graphql_api::transaction_submission::TransactionSubmissionConfig,
+    #[command(flatten, next_help_heading = "Webhooks")]
+    webhook_config: webhooks::WebhookConfig,
     /// The maximum log level. Possible values are: `trace`, `debug`, `info`,
     /// `warn`, and `error`.
     #[arg(long, default_value = "info", env = "LOG_LEVEL")]
@@ -125,6 +129,13 @@ struct Cli {
     // This argument is actually handled before hand using `DotenvCli`.
     #[arg(long)]
     schema_out: Option<PathBuf>,
+    /// Outputs the OpenAPI document for the REST API and then exits. The
+    /// output is stored as a file at the provided path or to stdout when '-'
+    /// is provided.
+    // This is only part of this struct in order to generate help information.
+    // This argument is actually handled before hand using `DotenvCli`.
+    #[arg(long)]
+    openapi_out: Option<PathBuf>,
 }
 
 /// CLI argument parser first used for parsing only the --dotenv option.
@@ -141,6 +152,8 @@ struct PreCli {
     dotenv: Option<PathBuf>,
     #[arg(long)]
     schema_out: Option<PathBuf>,
+    #[arg(long)]
+    openapi_out: Option<PathBuf>,
 }
 
 #[tokio::main]
@@ -164,6 +177,20 @@ async fn main() -> anyhow::Result<()> {
         }
         return Ok(());
     }
+    if let Some(openapi_file) = pre_cli_args.openapi_out {
+        let openapi = rest_api::Service::openapi_json();
+        if openapi_file.as_path() == std::path::Path::new("-") {
+            eprintln!("Writing OpenAPI document to stdout");
+            print!("{}", openapi);
+        } else {
+            eprintln!(
+                "Writing OpenAPI document to {}",
+                openapi_file.to_string_lossy()
+            );
+            std::fs::write(openapi_file, openapi).context("Failed to write OpenAPI document")?;
+        }
+        return Ok(());
+    }
     let cli = Cli::parse();
     let filter = if std::env::var("RUST_LOG").is_ok() {
         // If RUST_LOG env is defined we fallback to the default behavior of the env
@@ -203,6 +230,7 @@ async fn main() -> anyhow::Result<()> {
         // Exit if we only care about the compatibility.
         return Ok(());
     }
+    let indexed_domains = graphql_api::load_indexed_domains(&pool).await?;
 
     let client = Client::builder()
         .connect_timeout(Duration::from_secs(
@@ -233,6 +261,7 @@ async fn main() -> anyhow::Result<()> {
 
     let (subscription, subscription_listener) =
         graphql_api::Subscription::new(cli.database_retry_delay_secs);
+    let blocks_added = subscription.block_added_receiver();
     let (nodes_status_sender, nodes_status_receiver) = tokio::sync::watch::channel(None);
     let node_status_receiver = nodes_status_receiver.clone();
     let mut pgnotify_listener = {
@@ -241,27 +270,78 @@ async fn main() -> anyhow::Result<()> {
         tokio::spawn(async move { subscription_listener.listen(pool, stop_signal).await })
     };
 
+    let webhooks_service = webhooks::Service::new(
+        &cli.webhook_config,
+        pool.clone(),
+        cancel_token.child_token(),
+        &mut registry,
+    )?;
+    // Served on the monitoring address, such that the admin API is not exposed along with
+    // the public API.
+    let webhooks_admin_router = webhooks_service
+        .as_ref()
+        .map(webhooks::Service::admin_router)
+        .unwrap_or_default();
+    let mut webhooks_task = {
+        let stop_signal = cancel_token.child_token();
+        tokio::spawn(async move {
+            match webhooks_service {
+                Some(service) => service.serve(blocks_added).await,
+                // Keep the task running when webhooks are disabled, as stopping any task
+                // shuts down the service.
+                None => {
+                    stop_signal.cancelled().await;
+                    Ok(())
+                }
+            }
+        })
+    };
+
     let mut queries_task = {
+        let labels = match &cli.api_config.labels_file {
+            Some(path) => graphql_api::label::LabelRegistry::load(path)?,
+            None => graphql_api::label::LabelRegistry::default(),
+        };
+        // Shared between the GraphQL and REST API, such that the rate applies across both.
+        let rate_limit_layer =
+            rate_limit::RateLimitLayer::new(&cli.api_config.rate_limit, &mut registry);
         let config = Arc::new(cli.api_config);
+        let transaction_submitter = graphql_api::transaction_submission::TransactionSubmitter::new(
+            &cli.transaction_submission_config,
+            &mut registry,
+        )?;
         let graphql_service = graphql_api::Service::new(
             subscription,
             &mut registry,
             pool.clone(),
             config.clone(),
             nodes_status_receiver,
+            transaction_submitter.clone(),
+            Arc::new(labels),
+            indexed_domains,
+            rate_limit_layer.clone(),
+        );
+        let rest_service = rest_api::Service::new(
+            pool.clone(),
+            config,
+            transaction_submitter,
+            indexed_domains,
+            rate_limit_layer,
+            &mut registry,
         );
-        let rest_service = rest_api::Service::new(pool.clone(), config, &mut registry);
         let tcp_listener = TcpListener::bind(cli.listen)
             .await
             .context("Parsing TCP listener address failed")?;
         let stop_signal = cancel_token.child_token();
         info!("Server is running at {:?}", cli.listen);
+        let router = axum::Router::new()
+            .merge(graphql_service.as_router())
+            .merge(rest_service.as_router());
         tokio::spawn(async move {
             axum::serve(
                 tcp_listener,
-                axum::Router::new()
-                    .merge(graphql_service.as_router())
-                    .merge(rest_service.as_router()),
+                // Provide the address of the client for rate limiting.
+                router.into_make_service_with_connect_info::<SocketAddr>(),
             )
             .with_graceful_shutdown(stop_signal.cancelled_owned())
             .await
@@ -301,6 +381,7 @@ async fn main() -> anyhow::Result<()> {
             tcp_listener,
             stop_signal,
             health_routes,
+            webhooks_admin_router,
         ))
     };
 
@@ -338,6 +419,13 @@ async fn main() -> anyhow::Result<()> {
             }
             cancel_token.cancel();
         }
+        result = &mut webhooks_task => {
+            error!("Webhooks task stopped.");
+            if let Err(err) = result? {
+                error!("Webhooks error: {}", err);
+            }
+            cancel_token.cancel();
+        }
     }
     info!("Shutting down");
     // Ensure all tasks have stopped
@@ -345,7 +433,8 @@ async fn main() -> anyhow::Result<()> {
         monitoring_task,
         queries_task,
         pgnotify_listener,
-        node_collector_task
+        node_collector_task,
+        webhooks_task
     );
     Ok(())
 }
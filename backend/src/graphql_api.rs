@@ -1,60 +1,76 @@
 //! TODO
 //! - Enable GraphiQL through flag instead of always.
 
-mod account;
+pub(crate) mod account;
 mod account_metrics;
 mod baker;
 mod baker_and_delegator_types;
 mod baker_metrics;
 mod block;
 mod block_metrics;
-mod contract;
+pub(crate) mod contract;
 mod db;
+pub mod label;
 mod module_reference_event;
 pub mod node_status;
 mod passive_delegation;
 mod plt;
 mod plt_transfer_metrics;
+mod response_cache;
 mod reward_metrics;
 mod search_result;
+pub(crate) mod supply_metrics;
 mod suspended_validators;
 mod token;
 mod transaction;
 mod transaction_metrics;
+pub mod transaction_submission;
 
 use crate::{
+    address::{ContractAddress, ContractIndex},
     connection::ConnectionQuery,
     graphql_api::search_result::SearchResult,
+    indexing_profile::{self, DomainNotIndexed, IndexingDomain, IndexingDomains},
     migrations::{current_schema_version, SchemaVersion},
-    scalar_types::{BlockHeight, DateTime, TimeSpan, UnsignedLong},
-    transaction_event::smart_contracts::InvalidContractVersionError,
+    rate_limit::{RateLimitConfig, RateLimitLayer},
+    scalar_types::{BakerId, BlockHash, BlockHeight, DateTime, TimeSpan, UnsignedLong},
+    transaction_event::{
+        protocol_level_tokens::{TokenUpdateEventType, TokenUpdateModuleType},
+        smart_contracts::InvalidContractVersionError,
+    },
 };
 use anyhow::Context as _;
 use async_graphql::{
+    extensions::apollo_persisted_queries::{ApolloPersistedQueries, LruCacheStorage},
     http::{playground_source, GraphQLPlaygroundConfig, GraphiQLSource},
-    types::connection,
-    ComplexObject, Context, EmptyMutation, Enum, MergedObject, Object, SDLExportOptions, Schema,
-    SimpleObject, Subscription, Union,
+    types::{self, connection},
+    ComplexObject, Context, Enum, MergedObject, Object, SDLExportOptions, Schema, SimpleObject,
+    Subscription, Union,
 };
 use async_graphql_axum::GraphQLSubscription;
+use baker::ValidatorEvent;
 use block::Block;
 
 use chrono::{Duration, TimeDelta, Utc};
 use concordium_rust_sdk::{
     base::contracts_common::schema::VersionedSchemaError, id::types as sdk_types,
 };
+use contract::ContractEventsSubscriptionItem;
 use derive_more::Display;
 use futures::prelude::*;
+use label::LabelRegistry;
 use node_status::NodeStatus;
+use plt::{PltEvent, PltEventAddedItem};
 use prometheus_client::registry::Registry;
 use sqlx::PgPool;
-use std::{error::Error, str::FromStr, sync::Arc};
+use std::{error::Error, num::NonZeroUsize, str::FromStr, sync::Arc};
 use tokio::sync::{broadcast, watch::Receiver};
 use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
 use tokio_util::sync::CancellationToken;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::{error, info};
-use transaction::Transaction;
+use transaction::{Transaction, TransactionAddedFilter, TransactionAddedItem};
+use transaction_submission::TransactionSubmitter;
 
 const VERSION: &str = clap::crate_version!();
 
@@ -243,6 +259,61 @@ pub struct ApiServiceConfig {
         default_value = "10"
     )]
     plt_by_account_address_connection_limit: u64,
+    /// The most buckets which can be queried at once for the supply history.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_SUPPLY_HISTORY_BUCKETS_LIMIT",
+        default_value = "1000"
+    )]
+    supply_history_buckets_limit: u64,
+    /// The most query documents stored for automatic persisted queries. When
+    /// exceeded the least recently used documents are evicted.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_PERSISTED_QUERIES_LIMIT",
+        default_value = "1000"
+    )]
+    persisted_queries_limit: NonZeroUsize,
+    /// Top-level query fields, where the responses of queries only selecting
+    /// these fields are cached until a new block is added.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_RESPONSE_CACHE_QUERIES",
+        value_delimiter = ',',
+        default_value = "blocks,latestChainParameters,paydayStatus,accountsMetrics,bakerMetrics,\
+                         rewardMetrics,blockMetrics,transactionMetrics,globalPltMetrics"
+    )]
+    response_cache_queries: Vec<String>,
+    /// The most responses stored in the response cache, above which the least
+    /// recently used response is evicted.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_RESPONSE_CACHE_LIMIT",
+        default_value = "1000"
+    )]
+    response_cache_limit: NonZeroUsize,
+    /// The maximum depth of nested fields in a query.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_QUERY_DEPTH_LIMIT",
+        default_value = "16"
+    )]
+    query_depth_limit: usize,
+    /// The maximum complexity of a query, where every field in the query
+    /// counts as one.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_CONFIG_QUERY_COMPLEXITY_LIMIT",
+        default_value = "2000"
+    )]
+    query_complexity_limit: usize,
+    /// Path to a JSON file with labels of known accounts, smart contract
+    /// instances and modules. No entities are labeled when not provided.
+    #[arg(long, env = "CCDSCAN_API_CONFIG_LABELS_FILE")]
+    pub labels_file: Option<std::path::PathBuf>,
+    /// Configuration of the rate limiting of requests per client.
+    #[command(flatten)]
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(MergedObject, Default)]
@@ -263,15 +334,22 @@ pub struct Query(
     reward_metrics::QueryRewardMetrics,
     block_metrics::QueryBlockMetrics,
     transaction_metrics::QueryTransactionMetrics,
+    supply_metrics::QuerySupplyMetrics,
     plt_transfer_metrics::QueryGlobalPltMetrics,
     plt_transfer_metrics::QueryPltTransferMetricsByTokenId,
     plt::QueryPltEvent,
     plt::QueryPlt,
     plt::QueryPltAccountAmount,
+    transaction_submission::QueryTransactionSubmission,
 );
 
+#[derive(MergedObject, Default)]
+pub struct Mutation(transaction_submission::MutationTransactionSubmission);
+
 pub struct Service {
-    schema: Schema<Query, EmptyMutation, Subscription>,
+    schema: Schema<Query, Mutation, Subscription>,
+    /// Layer limiting the rate of requests per client.
+    rate_limit_layer: RateLimitLayer,
 }
 impl Service {
     /// Route for posting queries to the GraphQL API.
@@ -285,22 +363,46 @@ impl Service {
         pool: PgPool,
         config: Arc<ApiServiceConfig>,
         receiver: Receiver<Option<Vec<NodeStatus>>>,
+        transaction_submitter: TransactionSubmitter,
+        labels: Arc<LabelRegistry>,
+        indexed_domains: IndexingDomains,
+        rate_limit_layer: RateLimitLayer,
     ) -> Self {
-        let schema = Schema::build(Query::default(), EmptyMutation, subscription)
+        let persisted_queries =
+            ApolloPersistedQueries::new(LruCacheStorage::new(config.persisted_queries_limit.get()));
+        let response_cache = response_cache::ResponseCacheExtension::new(
+            &config.response_cache_queries,
+            config.response_cache_limit,
+            subscription.block_added.resubscribe(),
+            registry,
+        );
+        let schema = Schema::build(Query::default(), Mutation::default(), subscription)
             .extension(async_graphql::extensions::Tracing)
+            .extension(persisted_queries)
             .extension(monitor::MonitorExtension::new(registry))
             .extension(logging::LoggingExtension)
+            .extension(get_request::RejectMutationsExtension)
+            // Registered after the persisted queries, such that the query document is resolved.
+            .extension(response_cache)
+            .limit_depth(config.query_depth_limit)
+            .limit_complexity(config.query_complexity_limit)
             .data(receiver)
             .data(pool)
             .data(config)
+            .data(transaction_submitter)
+            .data(labels)
+            .data(indexed_domains)
             .finish();
-        Self { schema }
+        Self {
+            schema,
+            rate_limit_layer,
+        }
     }
 
     /// Construct the GraphQL Schema Definition Language used by the service.
     pub fn sdl() -> String {
         let (subscription, _) = Subscription::new(0);
-        let schema = Schema::build(Query::default(), EmptyMutation, subscription).finish();
+        let schema = Schema::build(Query::default(), Mutation::default(), subscription).finish();
         schema.sdl_with_options(SDLExportOptions::new().prefer_single_line_descriptions())
     }
 
@@ -310,20 +412,56 @@ impl Service {
             .allow_origin(Any) // Open access to selected route
             .allow_methods(Any)
             .allow_headers(Any);
+        let graphql = async_graphql_axum::GraphQL::new(self.schema.clone());
+        let schema = self.schema.clone();
         axum::Router::new()
             .route("/", axum::routing::get(Self::graphiql))
             .route("/playground", axum::routing::get(Self::playground))
+            // GET requests allow for sending automatic persisted queries using only the hash
+            // of the query in the URL, making the responses cacheable.
             .route(
                 Self::API_GRAPHQL_ROUTE,
-                axum::routing::post_service(async_graphql_axum::GraphQL::new(self.schema.clone())),
+                axum::routing::post_service(graphql)
+                    .get(move |request| Self::graphql_get(schema.clone(), request)),
             )
             .route_service(
                 Self::WEBSOCKET_GRAPHQL_ROUTE,
                 GraphQLSubscription::new(self.schema),
             )
+            .layer(self.rate_limit_layer)
             .layer(cors_layer)
     }
 
+    /// Execute a query received using the GET method. Mutations are rejected
+    /// with `405 Method Not Allowed`, as GET requests must not have side
+    /// effects and might be triggered by cross-site requests.
+    async fn graphql_get(
+        schema: Schema<Query, Mutation, Subscription>,
+        request: async_graphql_axum::GraphQLRequest,
+    ) -> axum::response::Response {
+        use axum::response::IntoResponse;
+        let response = schema
+            .execute(request.into_inner().data(get_request::GetRequest))
+            .await;
+        let is_mutation = response.errors.iter().any(|err| {
+            err.source
+                .as_ref()
+                .and_then(|source| source.downcast_ref::<ApiError>())
+                .is_some_and(|err| matches!(err, ApiError::MutationInGetRequest))
+        });
+        let response = async_graphql_axum::GraphQLResponse::from(response);
+        if is_mutation {
+            (
+                axum::http::StatusCode::METHOD_NOT_ALLOWED,
+                [(axum::http::header::ALLOW, "POST")],
+                response,
+            )
+                .into_response()
+        } else {
+            response.into_response()
+        }
+    }
+
     async fn graphiql() -> impl axum::response::IntoResponse {
         axum::response::Html(
             GraphiQLSource::build()
@@ -341,6 +479,60 @@ impl Service {
     }
 }
 
+/// Module containing types and logic for building an async_graphql extension
+/// which rejects mutations in requests received using the GET method.
+mod get_request {
+    use super::ApiError;
+    use async_graphql::{
+        async_trait::async_trait,
+        extensions::{Extension, ExtensionContext, ExtensionFactory, NextParseQuery},
+        parser::types::{ExecutableDocument, OperationType},
+        Pos, ServerResult, Variables,
+    };
+    use std::sync::Arc;
+
+    /// Request data marking a request received using the GET method.
+    #[derive(Debug, Clone, Copy)]
+    pub struct GetRequest;
+
+    /// Reject documents containing mutations in requests marked by
+    /// [`GetRequest`]. The document is checked after being parsed, such that
+    /// automatic persisted queries only sending the hash of the query are
+    /// checked as well.
+    #[derive(Debug, Clone)]
+    pub struct RejectMutationsExtension;
+
+    impl ExtensionFactory for RejectMutationsExtension {
+        fn create(&self) -> Arc<dyn Extension> {
+            Arc::new(self.clone())
+        }
+    }
+
+    #[async_trait]
+    impl Extension for RejectMutationsExtension {
+        async fn parse_query(
+            &self,
+            ctx: &ExtensionContext<'_>,
+            query: &str,
+            variables: &Variables,
+            next: NextParseQuery<'_>,
+        ) -> ServerResult<ExecutableDocument> {
+            let document = next.run(ctx, query, variables).await?;
+            let has_mutation = document
+                .operations
+                .iter()
+                .any(|(_, operation)| operation.node.ty == OperationType::Mutation);
+            if has_mutation && ctx.data_opt::<GetRequest>().is_some() {
+                return Err(
+                    async_graphql::Error::new_with_source(ApiError::MutationInGetRequest)
+                        .into_server_error(Pos::default()),
+                );
+            }
+            Ok(document)
+        }
+    }
+}
+
 /// Module containing types and logic for building an async_graphql extension
 /// which captures internal errors and logs them.
 mod logging {
@@ -406,6 +598,13 @@ mod monitor {
         /// Identifier of the top level query.
         query: String,
     }
+    /// Type representing the Prometheus labels used for counting queries
+    /// rejected by the limits of the schema.
+    #[derive(Debug, Clone, EncodeLabelSet, PartialEq, Eq, Hash)]
+    struct RejectedLabels {
+        /// The limit exceeded by the query.
+        limit: String,
+    }
     /// Extension for async_graphql adding monitoring.
     #[derive(Clone)]
     pub struct MonitorExtension {
@@ -417,6 +616,9 @@ mod monitor {
         request_duration: Family<QueryLabels, Histogram>,
         /// Metric tracking current open subscriptions.
         active_subscriptions: Gauge,
+        /// Metric counting queries rejected for exceeding the depth or
+        /// complexity limits.
+        rejected_queries: Family<RejectedLabels, Counter>,
     }
     impl MonitorExtension {
         pub fn new(registry: &mut Registry) -> Self {
@@ -447,11 +649,18 @@ mod monitor {
                 "Current number of active subscriptions",
                 active_subscriptions.clone(),
             );
+            let rejected_queries: Family<RejectedLabels, Counter> = Default::default();
+            registry.register(
+                "rejected_queries",
+                "Total number of queries rejected for exceeding the depth or complexity limits",
+                rejected_queries.clone(),
+            );
             MonitorExtension {
                 in_flight_requests,
                 total_requests,
                 request_duration,
                 active_subscriptions,
+                rejected_queries,
             }
         }
     }
@@ -462,6 +671,31 @@ mod monitor {
     }
     #[async_trait]
     impl async_graphql::extensions::Extension for MonitorExtension {
+        async fn validation(
+            &self,
+            ctx: &async_graphql::extensions::ExtensionContext<'_>,
+            next: async_graphql::extensions::NextValidation<'_>,
+        ) -> Result<async_graphql::ValidationResult, Vec<async_graphql::ServerError>> {
+            let result = next.run(ctx).await;
+            if let Err(errors) = &result {
+                for error in errors {
+                    // The limits are checked by async_graphql as part of validating the
+                    // query, and are only distinguishable by the error message.
+                    let limit = match error.message.as_str() {
+                        "Query is nested too deep." => "depth",
+                        "Query is too complex." => "complexity",
+                        _ => continue,
+                    };
+                    self.rejected_queries
+                        .get_or_create(&RejectedLabels {
+                            limit: limit.to_owned(),
+                        })
+                        .inc();
+                }
+            }
+            result
+        }
+
         async fn execute(
             &self,
             ctx: &async_graphql::extensions::ExtensionContext<'_>,
@@ -545,6 +779,12 @@ pub enum InternalError {
     InvalidVersionedModuleSchema(#[from] VersionedSchemaError),
     #[error("Internal error: {0}")]
     InternalError(String),
+    #[error("Internal error (NoTransactionSubmitter): {}", .0.message)]
+    NoTransactionSubmitter(async_graphql::Error),
+    #[error("Internal error (NoLabelRegistry): {}", .0.message)]
+    NoLabelRegistry(async_graphql::Error),
+    #[error("Internal error (NoIndexedDomains): {}", .0.message)]
+    NoIndexedDomains(async_graphql::Error),
 }
 
 /// All the errors that may be produced by the GraphQL API.
@@ -564,6 +804,8 @@ pub enum ApiError {
     DurationOutOfRange(Arc<Box<dyn Error + Send + Sync>>),
     #[error("The \"first\" and \"last\" parameters cannot exist at the same time")]
     QueryConnectionFirstLast,
+    #[error("Exactly one of the \"blockHeight\" and \"time\" parameters must be provided")]
+    QueryBlockHeightOrTime,
     #[error("Internal server error")]
     InternalServerError(#[from] InternalError),
     #[error("Invalid integer: {0}")]
@@ -576,6 +818,29 @@ pub enum ApiError {
     Unavailable(String),
     #[error("Invalid ID format: {0}")]
     InvalidIdFormat(String),
+    #[error("Invalid account address: {0}")]
+    InvalidAccountAddress(String),
+    #[error("The contract address sub index cannot be provided without the index")]
+    ContractAddressSubIndexWithoutIndex,
+    #[error("Submission of transactions is not enabled")]
+    TransactionSubmissionDisabled,
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Transaction rejected by the node: {0}")]
+    TransactionRejected(String),
+    #[error("Invalid transaction hash: {0}")]
+    InvalidTransactionHash(String),
+    #[error("Invalid time range: {0}")]
+    InvalidTimeRange(String),
+    #[error(transparent)]
+    DomainNotIndexed(#[from] DomainNotIndexed),
+    #[error("Mutations must be sent using the POST method")]
+    MutationInGetRequest,
+    #[error(
+        "The transaction is included in the block {0} which is not yet finalized, use \
+         `tentativeTransactionByTransactionHash`"
+    )]
+    TransactionNotFinalized(BlockHash),
 }
 
 impl From<sqlx::Error> for InternalError {
@@ -612,6 +877,30 @@ pub fn get_config<'a>(ctx: &Context<'a>) -> ApiResult<&'a ApiServiceConfig> {
     Ok(config.as_ref())
 }
 
+/// Load the domains of derived data populated in the database, which are
+/// provided to the GraphQL and REST API at startup. Changes of the indexing
+/// profile of the indexer take effect once the API is restarted.
+pub async fn load_indexed_domains(pool: &PgPool) -> anyhow::Result<IndexingDomains> {
+    let mut connection = pool.acquire().await?;
+    indexing_profile::populated_domains(&mut connection).await
+}
+
+/// Get the domains of derived data populated in the database from the context.
+pub fn get_indexed_domains(ctx: &Context<'_>) -> ApiResult<IndexingDomains> {
+    let domains = ctx
+        .data::<IndexingDomains>()
+        .map_err(InternalError::NoIndexedDomains)?;
+    Ok(*domains)
+}
+
+/// Get the registry of labels for known entities from the context.
+pub fn get_labels<'a>(ctx: &Context<'a>) -> ApiResult<&'a LabelRegistry> {
+    let labels = ctx
+        .data::<Arc<LabelRegistry>>()
+        .map_err(InternalError::NoLabelRegistry)?;
+    Ok(labels.as_ref())
+}
+
 #[derive(Default)]
 struct BaseQuery;
 
@@ -669,6 +958,7 @@ impl BaseQuery {
     }
 
     async fn payday_status<'a>(&self, ctx: &Context<'a>) -> ApiResult<PaydayStatus> {
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Payday)?;
         let row = sqlx::query_as!(
             CurrentChainParameters,
             "SELECT 
@@ -707,26 +997,50 @@ impl BaseQuery {
 pub struct Subscription {
     block_added: broadcast::Receiver<Block>,
     accounts_updated: broadcast::Receiver<AccountsUpdatedSubscriptionItem>,
+    transaction_added: broadcast::Receiver<TransactionAddedItem>,
+    contract_event_added: broadcast::Receiver<ContractEventsSubscriptionItem>,
+    plt_event_added: broadcast::Receiver<PltEventAddedItem>,
+    validator_event: broadcast::Receiver<ValidatorEvent>,
 }
 
 impl Subscription {
     pub fn new(retry_delay_sec: u64) -> (Self, SubscriptionContext) {
         let (block_added_sender, block_added) = broadcast::channel(100);
         let (accounts_updated_sender, accounts_updated) = broadcast::channel(100);
+        let (transaction_added_sender, transaction_added) = broadcast::channel(1000);
+        let (contract_event_added_sender, contract_event_added) = broadcast::channel(1000);
+        let (plt_event_added_sender, plt_event_added) = broadcast::channel(1000);
+        let (validator_event_sender, validator_event) = broadcast::channel(100);
         (
             Subscription {
                 block_added,
                 accounts_updated,
+                transaction_added,
+                contract_event_added,
+                plt_event_added,
+                validator_event,
             },
             SubscriptionContext {
                 block_added_sender,
                 accounts_updated_sender,
+                transaction_added_sender,
+                contract_event_added_sender,
+                plt_event_added_sender,
+                validator_event_sender,
                 retry_delay_sec,
             },
         )
     }
 }
 
+impl Subscription {
+    /// Receiver of the blocks added, for waking up other services of the API
+    /// when blocks are indexed.
+    pub fn block_added_receiver(&self) -> broadcast::Receiver<Block> {
+        self.block_added.resubscribe()
+    }
+}
+
 #[Subscription]
 impl Subscription {
     async fn block_added(&self) -> impl Stream<Item = Result<Block, BroadcastStreamRecvError>> {
@@ -767,17 +1081,122 @@ impl Subscription {
             },
         )
     }
+
+    /// Subscribe to transactions as they are added, optionally only yielding
+    /// the transactions matching the filter.
+    async fn transaction_added(
+        &self,
+        filter: Option<TransactionAddedFilter>,
+    ) -> Result<impl Stream<Item = Result<Arc<Transaction>, BroadcastStreamRecvError>>, ApiError>
+    {
+        let filter = filter.unwrap_or_default().prepare()?;
+        let stream =
+            tokio_stream::wrappers::BroadcastStream::new(self.transaction_added.resubscribe());
+        Ok(stream.filter_map(move |item| {
+            let result = match item {
+                Ok(item) => filter.matches(&item).then_some(Ok(item.transaction)),
+                // Pass on errors.
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(result)
+        }))
+    }
+
+    /// Subscribe to the events of a smart contract instance as they are added,
+    /// optionally only yielding the events of updates with the receive name
+    /// in the format '<contract_name>.<entrypoint>'.
+    async fn contract_events(
+        &self,
+        ctx: &Context<'_>,
+        contract_address_index: ContractIndex,
+        contract_address_sub_index: ContractIndex,
+        receive_name: Option<String>,
+    ) -> ApiResult<
+        impl Stream<Item = Result<ContractEventsSubscriptionItem, BroadcastStreamRecvError>>,
+    > {
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Contracts)?;
+        let contract_address = ContractAddress {
+            index: contract_address_index,
+            sub_index: contract_address_sub_index,
+        };
+        let stream =
+            tokio_stream::wrappers::BroadcastStream::new(self.contract_event_added.resubscribe());
+        Ok(stream.filter_map(move |item| {
+            let result = match item {
+                Ok(item) => item
+                    .matches(contract_address, receive_name.as_deref())
+                    .then_some(Ok(item)),
+                // Pass on errors.
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(result)
+        }))
+    }
+
+    /// Subscribe to protocol-level token events as they are added, optionally
+    /// only yielding the events of the token, of the event type and of the
+    /// token module event type, such as changes of the allow and deny lists.
+    async fn plt_events(
+        &self,
+        ctx: &Context<'_>,
+        token_id: Option<types::ID>,
+        event_type: Option<TokenUpdateEventType>,
+        token_module_type: Option<TokenUpdateModuleType>,
+    ) -> ApiResult<impl Stream<Item = Result<PltEvent, BroadcastStreamRecvError>>> {
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
+        let token_id = token_id.map(|id| id.0);
+        let stream =
+            tokio_stream::wrappers::BroadcastStream::new(self.plt_event_added.resubscribe());
+        Ok(stream.filter_map(move |item| {
+            let result = match item {
+                Ok(item) => item
+                    .matches(token_id.as_ref(), event_type, token_module_type)
+                    .then_some(Ok(item.event)),
+                // Pass on errors.
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(result)
+        }))
+    }
+
+    /// Subscribe to changes in the lifecycle of validators, such as being
+    /// primed for suspension, suspended or removed, optionally only yielding
+    /// the changes of a single validator.
+    async fn validator_events(
+        &self,
+        baker_id: Option<BakerId>,
+    ) -> impl Stream<Item = Result<ValidatorEvent, BroadcastStreamRecvError>> {
+        let stream =
+            tokio_stream::wrappers::BroadcastStream::new(self.validator_event.resubscribe());
+        stream.filter_map(move |item| {
+            let result = match item {
+                Ok(event) => baker_id
+                    .is_none_or(|baker_id| event.baker_id.0 == baker_id.0)
+                    .then_some(Ok(event)),
+                // Pass on errors.
+                Err(e) => Some(Err(e)),
+            };
+            future::ready(result)
+        })
+    }
 }
 
 pub struct SubscriptionContext {
     block_added_sender: broadcast::Sender<Block>,
     accounts_updated_sender: broadcast::Sender<AccountsUpdatedSubscriptionItem>,
+    transaction_added_sender: broadcast::Sender<TransactionAddedItem>,
+    contract_event_added_sender: broadcast::Sender<ContractEventsSubscriptionItem>,
+    plt_event_added_sender: broadcast::Sender<PltEventAddedItem>,
+    validator_event_sender: broadcast::Sender<ValidatorEvent>,
     retry_delay_sec: u64,
 }
 
 impl SubscriptionContext {
     const ACCOUNTS_UPDATED_CHANNEL: &'static str = "account_updated";
     const BLOCK_ADDED_CHANNEL: &'static str = "block_added";
+    const CONTRACT_EVENT_ADDED_CHANNEL: &'static str = "contract_event_added";
+    const PLT_EVENT_ADDED_CHANNEL: &'static str = "plt_event_added";
+    const VALIDATOR_EVENT_CHANNEL: &'static str = "validator_event";
 
     pub async fn listen(self, pool: PgPool, stop_signal: CancellationToken) -> anyhow::Result<()> {
         loop {
@@ -813,35 +1232,18 @@ impl SubscriptionContext {
             .context("Failed to create a PostgreSQL listener")?;
 
         listener
-            .listen_all([Self::BLOCK_ADDED_CHANNEL, Self::ACCOUNTS_UPDATED_CHANNEL])
+            .listen_all([
+                Self::BLOCK_ADDED_CHANNEL,
+                Self::ACCOUNTS_UPDATED_CHANNEL,
+                Self::CONTRACT_EVENT_ADDED_CHANNEL,
+                Self::PLT_EVENT_ADDED_CHANNEL,
+                Self::VALIDATOR_EVENT_CHANNEL,
+            ])
             .await
             .context("Failed to listen to PostgreSQL notifications")?;
 
         let exit = stop_signal
-            .run_until_cancelled(async move {
-                loop {
-                    let notification = listener.recv().await?;
-                    match notification.channel() {
-                        Self::BLOCK_ADDED_CHANNEL => {
-                            let block_height = BlockHeight::from_str(notification.payload())
-                                .context("Failed to parse payload of block added")?;
-                            let block = Block::query_by_height(pool, block_height).await?;
-                            self.block_added_sender.send(block)?;
-                        }
-
-                        Self::ACCOUNTS_UPDATED_CHANNEL => {
-                            self.accounts_updated_sender
-                                .send(AccountsUpdatedSubscriptionItem {
-                                    address: notification.payload().to_string(),
-                                })?;
-                        }
-
-                        unknown => {
-                            anyhow::bail!("Received notification on unknown channel: {unknown}");
-                        }
-                    }
-                }
-            })
+            .run_until_cancelled(self.receive_notifications(pool, listener))
             .await;
 
         // Handle early exit due to stop signal or errors
@@ -851,6 +1253,100 @@ impl SubscriptionContext {
 
         Ok(())
     }
+
+    /// Receive notifications until the connection of the listener fails.
+    async fn receive_notifications(
+        &self,
+        pool: &PgPool,
+        mut listener: sqlx::postgres::PgListener,
+    ) -> anyhow::Result<()> {
+        loop {
+            let notification = listener.recv().await?;
+            // Failing to handle a single notification must not stop the other
+            // subscriptions sharing the listener.
+            if let Err(err) = self.handle_notification(pool, &notification).await {
+                error!(
+                    "Failed to handle notification on channel {}: {:#}",
+                    notification.channel(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Load the data of a notification and pass it on to the subscriptions.
+    async fn handle_notification(
+        &self,
+        pool: &PgPool,
+        notification: &sqlx::postgres::PgNotification,
+    ) -> anyhow::Result<()> {
+        match notification.channel() {
+            Self::BLOCK_ADDED_CHANNEL => {
+                let block_height = BlockHeight::from_str(notification.payload())
+                    .context("Failed to parse payload of block added")?;
+                let block = Block::query_by_height(pool, block_height).await?;
+                self.block_added_sender.send(block)?;
+                // The transactions of the block are committed along with the block, so they
+                // are loaded using a single query once per block. Only load them when
+                // subscribed to, as the receiver held by `Subscription` is always present.
+                if self.transaction_added_sender.receiver_count() > 1 {
+                    for item in TransactionAddedItem::query_by_block(pool, block_height).await? {
+                        self.transaction_added_sender
+                            .send(item)
+                            .map_err(|_| anyhow::anyhow!("Transaction added channel is closed"))?;
+                    }
+                }
+            }
+
+            Self::ACCOUNTS_UPDATED_CHANNEL => {
+                self.accounts_updated_sender
+                    .send(AccountsUpdatedSubscriptionItem {
+                        address: notification.payload().to_string(),
+                    })?;
+            }
+
+            Self::CONTRACT_EVENT_ADDED_CHANNEL => {
+                // Only load the event when subscribed to, as the receiver held by
+                // `Subscription` is always present.
+                if self.contract_event_added_sender.receiver_count() <= 1 {
+                    return Ok(());
+                }
+                let index = i64::from_str(notification.payload())
+                    .context("Failed to parse payload of contract event added")?;
+                if let Some(item) =
+                    ContractEventsSubscriptionItem::query_by_index(pool, index).await?
+                {
+                    self.contract_event_added_sender
+                        .send(item)
+                        .map_err(|_| anyhow::anyhow!("Contract event added channel is closed"))?;
+                }
+            }
+
+            Self::PLT_EVENT_ADDED_CHANNEL => {
+                // Only load the event when subscribed to, as the receiver held by
+                // `Subscription` is always present.
+                if self.plt_event_added_sender.receiver_count() <= 1 {
+                    return Ok(());
+                }
+                let index = i64::from_str(notification.payload())
+                    .context("Failed to parse payload of PLT event added")?;
+                if let Some(item) = PltEventAddedItem::query_by_index(pool, index).await? {
+                    self.plt_event_added_sender.send(item)?;
+                }
+            }
+
+            Self::VALIDATOR_EVENT_CHANNEL => {
+                let event: ValidatorEvent = serde_json::from_str(notification.payload())
+                    .context("Failed to parse payload of validator event")?;
+                self.validator_event_sender.send(event)?;
+            }
+
+            unknown => {
+                anyhow::bail!("Received notification on unknown channel: {unknown}");
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Clone, Debug, SimpleObject)]
@@ -987,7 +1483,7 @@ impl TryFrom<ApyPeriod> for sqlx::postgres::types::PgInterval {
     }
 }
 
-#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Enum, Clone, Copy, PartialEq, Eq)]
 enum MetricsPeriod {
     LastHour,
     #[graphql(name = "LAST24_HOURS")]
@@ -1026,6 +1522,18 @@ impl MetricsPeriod {
             MetricsPeriod::LastYear => Duration::days(15),
         }
     }
+
+    /// Width of the rolled up buckets of the metrics tables read for a given
+    /// metrics period, or `None` when reading the per-block rows. The width
+    /// must divide the bucket width, and the shorter periods must be within
+    /// the retention window of the per-block rows of the indexer.
+    fn rollup_width(&self) -> Option<Duration> {
+        match self {
+            MetricsPeriod::LastHour | MetricsPeriod::Last24Hours => None,
+            MetricsPeriod::Last7Days | MetricsPeriod::Last30Days => Some(Duration::hours(1)),
+            MetricsPeriod::Last90Days | MetricsPeriod::LastYear => Some(Duration::days(1)),
+        }
+    }
 }
 
 #[derive(Debug, Enum, Clone, Copy, Display, PartialEq, Eq, sqlx::Type)]
@@ -1052,9 +1560,71 @@ pub enum PltAccountStatementEntryType {
     TransferOut,
 }
 
+#[derive(Debug, Enum, Clone, Copy, Display, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "cis2_account_statement_entry_type")]
+pub enum Cis2AccountStatementEntryType {
+    Mint,
+    Burn,
+    TransferIn,
+    TransferOut,
+}
+
 /// A sort direction, either ascending or descending.
 #[derive(Debug, Clone, Copy)]
 enum OrderDir {
     Asc,
     Desc,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{get_request, ApiError, MetricsPeriod, Mutation, Query, Subscription};
+    use async_graphql::{Request, Schema};
+    use chrono::Duration;
+
+    #[tokio::test]
+    async fn test_mutation_in_get_request_is_rejected() {
+        let (subscription, _) = Subscription::new(0);
+        let schema = Schema::build(Query::default(), Mutation::default(), subscription)
+            .extension(get_request::RejectMutationsExtension)
+            .finish();
+        let mutation = r#"mutation { submitTransaction(transaction: "00") { status } }"#;
+        let response = schema
+            .execute(Request::new(mutation).data(get_request::GetRequest))
+            .await;
+        let rejected = response.errors.iter().any(|err| {
+            err.source
+                .as_ref()
+                .and_then(|source| source.downcast_ref::<ApiError>())
+                .is_some_and(|err| matches!(err, ApiError::MutationInGetRequest))
+        });
+        assert!(rejected, "{:?}", response.errors);
+
+        let response = schema
+            .execute(Request::new("{ __typename }").data(get_request::GetRequest))
+            .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
+
+    #[test]
+    fn test_metrics_rollup_width_divides_bucket_width() {
+        for period in [
+            MetricsPeriod::LastHour,
+            MetricsPeriod::Last24Hours,
+            MetricsPeriod::Last7Days,
+            MetricsPeriod::Last30Days,
+            MetricsPeriod::Last90Days,
+            MetricsPeriod::LastYear,
+        ] {
+            match period.rollup_width() {
+                Some(rollup_width) => {
+                    let bucket_width = period.bucket_width().num_seconds();
+                    assert_eq!(bucket_width % rollup_width.num_seconds(), 0, "{period:?}");
+                }
+                // Periods read from the per-block rows must be within the minimum retention
+                // window of the indexer.
+                None => assert!(period.as_duration() <= Duration::days(1), "{period:?}"),
+            }
+        }
+    }
+}
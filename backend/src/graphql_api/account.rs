@@ -1,11 +1,13 @@
 use super::{
-    baker::Baker, get_config, get_pool, plt::AccountProtocolToken, token::AccountToken,
-    AccountStatementEntryType, ApiError, ApiResult, ConnectionQuery, InternalError, OrderDir,
+    baker::Baker, get_config, get_indexed_domains, get_labels, get_pool, label::Label,
+    plt::AccountProtocolToken, token::AccountToken, AccountStatementEntryType, ApiError, ApiResult,
+    ConnectionQuery, InternalError, OrderDir,
 };
 use crate::{
     address::AccountAddress,
     connection::{ConnectionBounds, DescendingI64, Reversed},
     graphql_api::{block::Block, token::AccountTokenInterim, Transaction},
+    indexing_profile::IndexingDomain,
     scalar_types::{AccountIndex, Amount, BlockHeight, DateTime, Long, TransactionIndex},
     transaction_event::{
         delegation::{BakerDelegationTarget, DelegationTarget, PassiveDelegationTarget},
@@ -23,7 +25,7 @@ use async_graphql::{
     types, ComplexObject, Context, Enum, InputObject, Object, SimpleObject, Union,
 };
 use futures::TryStreamExt;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use std::{
     cmp::{max, min, Ordering},
     str::FromStr,
@@ -823,6 +825,67 @@ impl Account {
     }
 }
 
+/// Query the CCD balance in micro CCD of an account at the end of the last
+/// block with a slot time before or at `time`.
+///
+/// The balance is resolved from the account statements. When no statement
+/// exists up to `time` (e.g. for genesis accounts), the balance prior to the
+/// first statement of the account is used, falling back to the current
+/// balance for accounts which never had any statements.
+/// Returns `None` if the account was created after `time`.
+pub(crate) async fn query_account_balance_at(
+    connection: &mut PgConnection,
+    account_index: AccountIndex,
+    time: DateTime,
+) -> Result<Option<i64>, sqlx::Error> {
+    let Some(account) = sqlx::query!(
+        r#"SELECT
+            accounts.amount,
+            blocks.slot_time AS created_at
+        FROM accounts
+            LEFT JOIN transactions ON transactions.index = accounts.transaction_index
+            JOIN blocks ON blocks.height = COALESCE(transactions.block_height, 0)
+        WHERE accounts.index = $1"#,
+        account_index
+    )
+    .fetch_optional(&mut *connection)
+    .await?
+    else {
+        return Ok(None);
+    };
+    if account.created_at > time {
+        return Ok(None);
+    }
+    let balance = sqlx::query_scalar!(
+        "SELECT account_balance
+        FROM account_statements
+        WHERE account_index = $1
+            AND slot_time <= $2
+        ORDER BY slot_time DESC, id DESC
+        LIMIT 1",
+        account_index,
+        time
+    )
+    .fetch_optional(&mut *connection)
+    .await?;
+    if balance.is_some() {
+        return Ok(balance);
+    }
+    let balance_before_first_statement = sqlx::query_scalar!(
+        r#"SELECT (account_balance - amount) AS "balance!"
+        FROM account_statements
+        WHERE account_index = $1
+        ORDER BY id ASC
+        LIMIT 1"#,
+        account_index
+    )
+    .fetch_optional(&mut *connection)
+    .await?;
+    Ok(Some(
+        balance_before_first_statement.unwrap_or(account.amount),
+    ))
+}
+
 #[Object]
 impl Account {
     pub async fn id(&self) -> types::ID {
@@ -834,11 +897,39 @@ impl Account {
         &self.address
     }
 
+    /// Label of the account when it is a known entity.
+    async fn label<'a>(&self, ctx: &Context<'a>) -> ApiResult<Option<&'a Label>> {
+        Ok(get_labels(ctx)?.account(&self.address.to_string()))
+    }
+
     /// The total amount of CCD hold by the account.
     pub async fn amount(&self) -> ApiResult<Amount> {
         Ok(self.amount.try_into()?)
     }
 
+    /// The amount of CCD hold by the account at the end of the given block or
+    /// at the end of the last block baked before or at the given time.
+    /// Exactly one of `blockHeight` and `time` must be provided.
+    /// Null when the account did not exist at that point.
+    async fn balance_at(
+        &self,
+        ctx: &Context<'_>,
+        block_height: Option<BlockHeight>,
+        time: Option<DateTime>,
+    ) -> ApiResult<Option<Amount>> {
+        let pool = get_pool(ctx)?;
+        let time = match (block_height, time) {
+            (Some(block_height), None) => {
+                Block::query_by_height(pool, block_height).await?.slot_time
+            }
+            (None, Some(time)) => time,
+            _ => return Err(ApiError::QueryBlockHeightOrTime),
+        };
+        let balance =
+            query_account_balance_at(pool.acquire().await?.as_mut(), self.index, time).await?;
+        Ok(balance.map(Amount::try_from).transpose()?)
+    }
+
     pub async fn baker(&self, ctx: &Context<'_>) -> ApiResult<Option<Baker>> {
         Ok(Baker::query_by_id(get_pool(ctx)?, self.index).await?)
     }
@@ -922,6 +1013,7 @@ impl Account {
         type Cursor = DescendingI64;
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Cis2)?;
         let query = ConnectionQuery::<Cursor>::new(
             first,
             after,
@@ -1583,3 +1675,98 @@ enum AccountOrderField {
 struct AccountFilterInput {
     is_delegator: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::{TimeDelta, TimeZone, Utc};
+
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    #[tokio::test]
+    async fn test_account_balance_at_time() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let created_at = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        for offset in 0..3 {
+            test_database::insert_block(
+                tx.as_mut(),
+                HEIGHT + offset,
+                created_at + TimeDelta::days(offset),
+            )
+            .await
+            .unwrap();
+        }
+        test_database::insert_transaction(tx.as_mut(), TRANSACTION, HEIGHT)
+            .await
+            .unwrap();
+        let address = concordium_rust_sdk::id::types::AccountAddress([0xe5; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &address, 70)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE accounts SET transaction_index = $1 WHERE index = $2")
+            .bind(TRANSACTION)
+            .bind(ACCOUNT)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        // The account is created with a balance of 10, receives 90 the next day and
+        // sends 30 the day after.
+        for (offset, entry_type, amount, balance) in
+            [(1, "TransferIn", 90, 100), (2, "TransferOut", -30, 70)]
+        {
+            sqlx::query(
+                "INSERT INTO account_statements (
+                    account_index,
+                    entry_type,
+                    amount,
+                    account_balance,
+                    block_height,
+                    slot_time
+                )
+                SELECT $1, $2::account_statement_entry_type, $3, $4, height, slot_time
+                FROM blocks WHERE height = $5",
+            )
+            .bind(ACCOUNT)
+            .bind(entry_type)
+            .bind(amount)
+            .bind(balance)
+            .bind(HEIGHT + offset)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let cases = [
+            // Before the account was created.
+            (created_at - TimeDelta::hours(1), None),
+            // Before the first statement, the balance prior to the statement.
+            (created_at, Some(10)),
+            (created_at + TimeDelta::hours(1), Some(10)),
+            // Exactly at the blocks of the statements.
+            (created_at + TimeDelta::days(1), Some(100)),
+            (created_at + TimeDelta::days(2), Some(70)),
+            // Between blocks, the balance at the end of the last block before.
+            (
+                created_at + TimeDelta::days(1) + TimeDelta::hours(12),
+                Some(100),
+            ),
+            (created_at + TimeDelta::days(3), Some(70)),
+        ];
+        for (time, expected) in cases {
+            assert_eq!(
+                query_account_balance_at(tx.as_mut(), ACCOUNT, time)
+                    .await
+                    .unwrap(),
+                expected,
+                "Balance at {}",
+                time
+            );
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use super::{
     account::Account,
     baker_and_delegator_types::{CommissionRates, DelegationSummary, PaydayPoolReward},
-    get_config, get_pool,
+    get_config, get_indexed_domains, get_pool,
     transaction::Transaction,
     ApiError, ApiResult, ApiServiceConfig, ApyPeriod, ConnectionQuery, InternalError,
 };
@@ -11,6 +11,7 @@ use crate::{
         Reversed,
     },
     graphql_api::node_status::{NodeInfoReceiver, NodeStatus},
+    indexing_profile::IndexingDomain,
     scalar_types::{Amount, BakerId, DateTime, Decimal, MetadataUrl},
     transaction_event::{baker::BakerPoolOpenStatus, Event},
     transaction_reject::TransactionRejectReason,
@@ -3404,6 +3405,7 @@ impl<'a> BakerPool<'a> {
 
     async fn apy(&self, ctx: &Context<'_>, period: ApyPeriod) -> ApiResult<PoolApy> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Payday)?;
         let apy = match period {
             ApyPeriod::Last7Days => {
                 sqlx::query_as!(
@@ -3489,9 +3491,38 @@ struct Ranking {
     total: i64,
 }
 
+/// Type of change in the lifecycle of a validator/baker.
+#[derive(Enum, Clone, Copy, PartialEq, Eq, Debug, serde::Deserialize)]
+pub enum ValidatorEventType {
+    /// The validator got primed for suspension due to inactivity.
+    PrimedForSuspension,
+    /// The validator got suspended, either by itself or due to inactivity.
+    Suspended,
+    /// The validator resumed after being suspended.
+    Resumed,
+    /// The validator changed one or more of its commission rates.
+    CommissionChanged,
+    /// The validator changed the delegation open status of its pool.
+    OpenStatusChanged,
+    /// The validator got removed.
+    Removed,
+}
+
+/// Change in the lifecycle of a validator/baker, yielded by the
+/// `validatorEvents` subscription.
+/// Deserialized from the payload of the `validator_event` database
+/// notifications.
+#[derive(SimpleObject, Clone, Debug, serde::Deserialize)]
+pub struct ValidatorEvent {
+    /// ID of the validator/baker.
+    pub baker_id: BakerId,
+    /// The type of change.
+    pub event_type: ValidatorEventType,
+}
+
 #[cfg(test)]
 mod test {
-    use super::BakerFieldDescCursor;
+    use super::{BakerFieldDescCursor, ValidatorEvent, ValidatorEventType};
     use crate::connection::ConnectionBounds;
     use async_graphql::connection::CursorType;
     use std::cmp::Ordering;
@@ -3546,4 +3577,17 @@ mod test {
             .expect("Failed decoding cursor");
         assert_eq!(cursor, encode_decode);
     }
+
+    #[test]
+    fn test_validator_event_from_notification_payload() {
+        let event: ValidatorEvent =
+            serde_json::from_str(r#"{"baker_id" : 42, "event_type" : "PrimedForSuspension"}"#)
+                .expect("Payload should be a valid validator event");
+        assert_eq!(event.baker_id.0, 42);
+        assert_eq!(event.event_type, ValidatorEventType::PrimedForSuspension);
+        assert!(serde_json::from_str::<ValidatorEvent>(
+            r#"{"baker_id" : 42, "event_type" : "Added"}"#
+        )
+        .is_err());
+    }
 }
@@ -1,5 +1,6 @@
 use crate::{
-    graphql_api::{get_pool, ApiError, ApiResult, MetricsPeriod},
+    graphql_api::{get_indexed_domains, get_pool, ApiError, ApiResult, MetricsPeriod},
+    indexing_profile::IndexingDomain,
     scalar_types::{DateTime, TimeSpan},
 };
 use async_graphql::{Context, Object, SimpleObject};
@@ -26,6 +27,7 @@ impl QueryBakerMetrics {
         period: MetricsPeriod,
     ) -> ApiResult<BakerMetrics> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Metrics)?;
         let end_time = Utc::now();
         let before_time = end_time - period.as_duration();
         let bucket_width = period.bucket_width();
@@ -34,14 +36,31 @@ impl QueryBakerMetrics {
             .try_into()
             .map_err(|err| ApiError::DurationOutOfRange(Arc::new(err)))?;
 
-        let rows = sqlx::query_file!(
-            "src/graphql_api/baker_metrics.sql",
-            end_time,
-            before_time,
-            bucket_interval
-        )
-        .fetch_all(pool)
-        .await?;
+        let rows = if let Some(rollup_width) = period.rollup_width() {
+            let rollup_interval: PgInterval = rollup_width
+                .try_into()
+                .map_err(|err| ApiError::DurationOutOfRange(Arc::new(err)))?;
+            sqlx::query_file_as!(
+                BakerMetricsRow,
+                "src/graphql_api/baker_metrics_rollups.sql",
+                end_time,
+                before_time,
+                bucket_interval,
+                rollup_interval
+            )
+            .fetch_all(pool)
+            .await?
+        } else {
+            sqlx::query_file_as!(
+                BakerMetricsRow,
+                "src/graphql_api/baker_metrics.sql",
+                end_time,
+                before_time,
+                bucket_interval
+            )
+            .fetch_all(pool)
+            .await?
+        };
 
         let first_row = rows.first().ok_or_else(|| {
             InternalError::InternalError("No metrics found for the given period".to_string())
@@ -95,6 +114,16 @@ impl QueryBakerMetrics {
     }
 }
 
+/// Cumulative number of bakers added and removed before the start and the end
+/// of a bucket.
+struct BakerMetricsRow {
+    bucket_time: DateTime,
+    added_before: i64,
+    removed_before: i64,
+    added_after: i64,
+    removed_after: i64,
+}
+
 #[derive(SimpleObject)]
 pub struct BakerMetricsBuckets {
     /// The width (time interval) of each bucket.
@@ -0,0 +1,85 @@
+-- Baker metrics read from the rolled up buckets of `metrics_bakers`, combined with the per-block
+-- rows which are not yet rolled up. See `baker_metrics.sql` for reading only the per-block rows.
+-- Inputs:
+-- $1::TIMESTAMPTZ - end of the period
+-- $2::TIMESTAMPTZ - start of the period
+-- $3::interval    - width of the buckets of the period
+-- $4::interval    - width of the rolled up buckets, which must divide the width of the buckets
+WITH progress AS (
+    SELECT COALESCE(rolled_up_until, '-infinity') AS rolled_up_until
+    FROM metrics_rollup_progress
+    WHERE bucket_width = $4::interval
+)
+SELECT
+    bucket_time.bucket_start as "bucket_time!",
+    COALESCE(before_bucket.total_bakers_added, 0) as "added_before!",
+    COALESCE(before_bucket.total_bakers_removed, 0) as "removed_before!",
+    COALESCE(after_bucket.total_bakers_added, 0) as "added_after!",
+    COALESCE(after_bucket.total_bakers_removed, 0) as "removed_after!"
+FROM
+    date_bin_series(
+        $3::interval,
+        $2,
+        $1
+    ) AS bucket_time
+-- The latest cumulative numbers before the start of the bucket. The per-block rows which are not
+-- yet rolled up are newer than every rolled up bucket, and are preferred when present.
+LEFT JOIN LATERAL (
+    SELECT total_bakers_added, total_bakers_removed
+    FROM (
+        (SELECT
+            metrics_bakers.total_bakers_added,
+            metrics_bakers.total_bakers_removed,
+            0 AS source
+        FROM metrics_bakers
+        JOIN blocks ON blocks.height = metrics_bakers.block_height
+        WHERE
+            blocks.slot_time >= (SELECT rolled_up_until FROM progress)
+            AND blocks.slot_time < bucket_time.bucket_start
+        ORDER BY metrics_bakers.block_height DESC
+        LIMIT 1)
+        UNION ALL
+        (SELECT
+            total_bakers_added,
+            total_bakers_removed,
+            1 AS source
+        FROM metrics_bakers_rollups
+        WHERE
+            bucket_width = $4::interval
+            AND bucket_start <= bucket_time.bucket_start - $4::interval
+        ORDER BY bucket_start DESC
+        LIMIT 1)
+    ) latest
+    ORDER BY source
+    LIMIT 1
+) before_bucket ON true
+-- The latest cumulative numbers before the end of the bucket.
+LEFT JOIN LATERAL (
+    SELECT total_bakers_added, total_bakers_removed
+    FROM (
+        (SELECT
+            metrics_bakers.total_bakers_added,
+            metrics_bakers.total_bakers_removed,
+            0 AS source
+        FROM metrics_bakers
+        JOIN blocks ON blocks.height = metrics_bakers.block_height
+        WHERE
+            blocks.slot_time >= (SELECT rolled_up_until FROM progress)
+            AND blocks.slot_time < bucket_time.bucket_end
+        ORDER BY metrics_bakers.block_height DESC
+        LIMIT 1)
+        UNION ALL
+        (SELECT
+            total_bakers_added,
+            total_bakers_removed,
+            1 AS source
+        FROM metrics_bakers_rollups
+        WHERE
+            bucket_width = $4::interval
+            AND bucket_start <= bucket_time.bucket_end - $4::interval
+        ORDER BY bucket_start DESC
+        LIMIT 1)
+    ) latest
+    ORDER BY source
+    LIMIT 1
+) after_bucket ON true
@@ -1,4 +1,6 @@
-use super::{get_config, get_pool, ApiError, ApiResult, ConnectionQuery};
+use super::{
+    get_config, get_pool, transaction::TentativeTransaction, ApiError, ApiResult, ConnectionQuery,
+};
 use crate::{
     block_special_event::{SpecialEvent, SpecialEventTypeFilter},
     connection::DescendingI64,
@@ -33,6 +35,11 @@ impl QueryBlocks {
         Block::query_by_hash(get_pool(ctx)?, block_hash).await
     }
 
+    /// Query the last block baked before or at the provided time.
+    async fn block_at_time<'a>(&self, ctx: &Context<'a>, time: DateTime) -> ApiResult<Block> {
+        Block::query_at_time(get_pool(ctx)?, time).await
+    }
+
     /// Query the list of blocks ordered descendingly by block height.
     async fn blocks(
         &self,
@@ -59,24 +66,51 @@ impl QueryBlocks {
         // returned (outer `ORDER BY`). If the `last` input parameter is set,
         // the inner `ORDER BY` reverses the block order to allow the range be applied
         // starting from the last element.
+        // Blocks of the tentative head, which are not yet finalized, are included above
+        // the last finalized block when present.
         let rows = sqlx::query_as!(
             Block,
-            "SELECT * FROM (
-                SELECT
-                    hash,
-                    height,
-                    slot_time,
-                    block_time,
-                    finalization_time,
-                    baker_id,
-                    total_amount
-                FROM blocks
+            r#"SELECT
+                hash AS "hash!",
+                height AS "height!",
+                slot_time AS "slot_time!",
+                block_time AS "block_time!",
+                finalization_time,
+                baker_id,
+                total_amount AS "total_amount!",
+                finalized AS "finalized!"
+            FROM (
+                SELECT * FROM (
+                    SELECT
+                        hash,
+                        height,
+                        slot_time,
+                        block_time,
+                        finalization_time,
+                        baker_id,
+                        total_amount,
+                        true AS finalized
+                    FROM blocks
+                    UNION ALL
+                    SELECT
+                        hash,
+                        height,
+                        slot_time,
+                        block_time,
+                        NULL::INTEGER AS finalization_time,
+                        baker_id,
+                        total_amount,
+                        false AS finalized
+                    FROM tentative_blocks
+                    WHERE height > (SELECT MAX(height) FROM blocks)
+                ) AS all_blocks
                 WHERE height < $1 AND height > $2
                 ORDER BY
                     (CASE WHEN $4 THEN height END) ASC,
                     (CASE WHEN NOT $4 THEN height END) DESC
                 LIMIT $3
-            ) ORDER BY height DESC",
+            ) AS page
+            ORDER BY height DESC"#,
             i64::from(query.from),
             i64::from(query.to),
             query.limit,
@@ -86,10 +120,14 @@ impl QueryBlocks {
         .await?;
 
         let has_prev_page = if let Some(first) = rows.first() {
-            let max_height_option: Option<i64> =
-                sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
-                    .fetch_one(pool)
-                    .await?;
+            let max_height_option: Option<i64> = sqlx::query_scalar!(
+                "SELECT GREATEST(
+                    (SELECT MAX(height) FROM blocks),
+                    (SELECT MAX(height) FROM tentative_blocks)
+                )"
+            )
+            .fetch_one(pool)
+            .await?;
             max_height_option.is_some_and(|height| height > first.height)
         } else {
             false
@@ -127,22 +165,38 @@ pub struct Block {
     pub(crate) finalization_time: Option<i32>,
     pub(crate) baker_id: Option<i64>,
     pub(crate) total_amount: i64,
+    /// Whether the block is finalized. Blocks of the tentative head, indexed
+    /// on the live branch before being finalized, are not.
+    pub(crate) finalized: bool,
 }
 
 impl Block {
     pub async fn query_by_height(pool: &sqlx::PgPool, height: BlockHeight) -> ApiResult<Self> {
         sqlx::query_as!(
             Block,
-            "SELECT
+            r#"SELECT
+                hash AS "hash!",
+                height AS "height!",
+                slot_time AS "slot_time!",
+                block_time AS "block_time!",
+                finalization_time,
+                baker_id,
+                total_amount AS "total_amount!",
+                true AS "finalized!"
+            FROM blocks
+            WHERE height=$1
+            UNION ALL
+            SELECT
                 hash,
                 height,
                 slot_time,
                 block_time,
-                finalization_time,
+                NULL::INTEGER AS finalization_time,
                 baker_id,
-                total_amount
-            FROM blocks
-            WHERE height=$1",
+                total_amount,
+                false AS "finalized!"
+            FROM tentative_blocks
+            WHERE height=$1 AND height > (SELECT MAX(height) FROM blocks)"#,
             height
         )
         .fetch_optional(pool)
@@ -153,17 +207,53 @@ impl Block {
     pub async fn query_by_hash(pool: &sqlx::PgPool, block_hash: BlockHash) -> ApiResult<Self> {
         sqlx::query_as!(
             Block,
-            "SELECT
+            r#"SELECT
+                hash AS "hash!",
+                height AS "height!",
+                slot_time AS "slot_time!",
+                block_time AS "block_time!",
+                finalization_time,
+                baker_id,
+                total_amount AS "total_amount!",
+                true AS "finalized!"
+            FROM blocks
+            WHERE hash=$1
+            UNION ALL
+            SELECT
+                hash,
+                height,
+                slot_time,
+                block_time,
+                NULL::INTEGER AS finalization_time,
+                baker_id,
+                total_amount,
+                false AS "finalized!"
+            FROM tentative_blocks
+            WHERE hash=$1 AND height > (SELECT MAX(height) FROM blocks)"#,
+            block_hash
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or(ApiError::NotFound)
+    }
+
+    pub async fn query_at_time(pool: &sqlx::PgPool, time: DateTime) -> ApiResult<Self> {
+        sqlx::query_as!(
+            Block,
+            r#"SELECT
                 hash,
                 height,
                 slot_time,
                 block_time,
                 finalization_time,
                 baker_id,
-                total_amount
+                total_amount,
+                true AS "finalized!"
             FROM blocks
-            WHERE hash=$1",
-            block_hash
+            WHERE slot_time <= $1
+            ORDER BY slot_time DESC
+            LIMIT 1"#,
+            time
         )
         .fetch_optional(pool)
         .await?
@@ -199,9 +289,10 @@ impl Block {
         &self.slot_time
     }
 
-    /// Whether the block is finalized.
+    /// Whether the block is finalized and processed by the indexer. Blocks of
+    /// the tentative head are not, even once finalized by the node.
     async fn finalized(&self) -> bool {
-        true
+        self.finalized
     }
 
     /// The block statistics:
@@ -217,17 +308,27 @@ impl Block {
 
     /// Number of transactions included in this block.
     async fn transaction_count<'a>(&self, ctx: &Context<'a>) -> ApiResult<i64> {
-        let result = sqlx::query!(
-            "SELECT COUNT(*) FROM transactions WHERE block_height = $1",
-            self.height
-        )
-        .fetch_one(get_pool(ctx)?)
-        .await?;
-        Ok(result.count.unwrap_or(0))
+        let pool = get_pool(ctx)?;
+        let count = if self.finalized {
+            sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM transactions WHERE block_height = $1",
+                self.height
+            )
+            .fetch_one(pool)
+            .await?
+        } else {
+            sqlx::query_scalar!(
+                "SELECT COUNT(*) FROM tentative_transactions WHERE block_hash = $1",
+                self.hash
+            )
+            .fetch_one(pool)
+            .await?
+        };
+        Ok(count.unwrap_or(0))
     }
 
     /// Query the special events (aka. special transaction outcomes) associated
-    /// with this block.
+    /// with this block. Empty for blocks which are not finalized.
     async fn special_events(
         &self,
         ctx: &Context<'_>,
@@ -254,6 +355,9 @@ impl Block {
             before,
             config.special_events_per_block_connection_limit,
         )?;
+        if !self.finalized {
+            return Ok(connection::Connection::new(false, false));
+        }
         let disable_filtering = include_filter.is_none();
         let filters = include_filter.unwrap_or_default();
         let mut row_stream = sqlx::query_as!(
@@ -317,6 +421,8 @@ impl Block {
         Ok(connection)
     }
 
+    /// Query the transactions of this block. Empty for blocks which are not
+    /// finalized, use `tentativeTransactions` for these.
     async fn transactions(
         &self,
         ctx: &Context<'_>,
@@ -338,6 +444,9 @@ impl Block {
             before,
             config.transactions_per_block_connection_limit,
         )?;
+        if !self.finalized {
+            return Ok(connection::Connection::new(false, false));
+        }
 
         // Retrieves the transactions within the specified block. The transactions are
         // ordered in ascending order (outer `ORDER BY`). If the `last` input
@@ -416,6 +525,18 @@ impl Block {
 
         Ok(connection)
     }
+
+    /// The transactions of this block when it is not finalized, in the order of
+    /// the block. Empty for finalized blocks, use `transactions` for these.
+    async fn tentative_transactions<'a>(
+        &self,
+        ctx: &Context<'a>,
+    ) -> ApiResult<Vec<TentativeTransaction>> {
+        if self.finalized {
+            return Ok(Vec::new());
+        }
+        TentativeTransaction::query_by_block(get_pool(ctx)?, &self.hash).await
+    }
 }
 
 #[derive(SimpleObject)]
@@ -1,18 +1,25 @@
 use super::{
-    get_config, get_pool, token::TokensCollectionSegment, ApiError, ApiResult,
-    CollectionSegmentInfo, ConnectionQuery, InternalError,
+    get_config, get_indexed_domains, get_labels, get_pool, label::Label,
+    token::TokensCollectionSegment, ApiError, ApiResult, CollectionSegmentInfo, ConnectionQuery,
+    InternalError,
 };
 use crate::{
-    address::{AccountAddress, ContractIndex},
+    address::{AccountAddress, ContractAddress, ContractIndex},
     connection::DescendingI64,
     graphql_api::token::Token,
+    indexing_profile::IndexingDomain,
     scalar_types::{Amount, BlockHeight, DateTime, TransactionHash},
-    transaction_event::Event,
+    transaction_event::{smart_contracts::decode_contract_logs, Event},
     transaction_reject::TransactionRejectReason,
 };
 use async_graphql::{connection, ComplexObject, Context, Object, SimpleObject};
 use futures::TryStreamExt;
-use std::cmp::{max, min};
+use sqlx::PgPool;
+use std::{
+    cmp::{max, min},
+    sync::Arc,
+};
+use tracing::warn;
 
 #[derive(Default)]
 pub struct QueryContract;
@@ -223,6 +230,14 @@ pub struct Contract {
 
 #[ComplexObject]
 impl Contract {
+    /// Label of the smart contract instance when it is a known entity.
+    async fn label<'a>(&self, ctx: &Context<'a>) -> ApiResult<Option<&'a Label>> {
+        Ok(get_labels(ctx)?.contract(
+            self.contract_address_index.0,
+            self.contract_address_sub_index.0,
+        ))
+    }
+
     // This function returns events from the `contract_events` table as well as
     // one `init_transaction_event` from when the contract was initialized. The
     // `skip` and `take` parameters are used to paginate the events.
@@ -234,6 +249,7 @@ impl Contract {
     ) -> ApiResult<ContractEventsCollectionSegment> {
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Contracts)?;
         let skip = skip.unwrap_or(0);
         let take = take.unwrap_or(config.contract_events_collection_limit);
 
@@ -446,6 +462,7 @@ impl Contract {
     ) -> ApiResult<ContractRejectEventsCollectionSegment> {
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Contracts)?;
 
         let limit = i64::try_from(
             take.map_or(config.contract_reject_events_collection_limit, |t| {
@@ -511,6 +528,7 @@ impl Contract {
     ) -> ApiResult<TokensCollectionSegment> {
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Cis2)?;
 
         let total_count = sqlx::query_scalar!(
             "SELECT MAX(token_index_per_contract) + 1
@@ -613,12 +631,198 @@ struct ContractEventsCollectionSegment {
 }
 
 #[derive(SimpleObject)]
-struct ContractEvent {
+pub struct ContractEvent {
     contract_address_index: ContractIndex,
     contract_address_sub_index: ContractIndex,
     sender: AccountAddress,
     event: Event,
-    block_height: BlockHeight,
-    transaction_hash: String,
+    pub(crate) block_height: BlockHeight,
+    pub(crate) transaction_hash: String,
     block_slot_time: DateTime,
 }
+
+/// Event of a smart contract instance added to the database, yielded by the
+/// `contractEvents` subscription.
+#[derive(SimpleObject, Clone)]
+pub struct ContractEventsSubscriptionItem {
+    /// The added contract event.
+    pub(crate) contract_event: Arc<ContractEvent>,
+    /// The events logged by the smart contract instance in JSON, decoded using
+    /// the event schema embedded in the smart contract module if present.
+    /// Empty when the logs cannot be decoded, leaving only the raw logs of the
+    /// event.
+    pub(crate) decoded_events: Vec<String>,
+    /// Index of the event in the `contract_events` table.
+    #[graphql(skip)]
+    pub(crate) index: i64,
+    #[graphql(skip)]
+    pub(crate) contract_address: ContractAddress,
+    /// Receive name of the update, in the format
+    /// '<contract_name>.<entrypoint>', `None` for events other than updates,
+    /// such as the initialization of the instance.
+    #[graphql(skip)]
+    pub(crate) receive_name: Option<String>,
+}
+
+impl ContractEventsSubscriptionItem {
+    /// Query the event with the provided index in the `contract_events` table.
+    pub async fn query_by_index(pool: &PgPool, index: i64) -> ApiResult<Option<Self>> {
+        let Some(row) = sqlx::query!(
+            "
+                SELECT
+                    contract_events.contract_index,
+                    contract_events.contract_sub_index,
+                    trace_element_index,
+                    transactions.hash as transaction_hash,
+                    transactions.events,
+                    accounts.address as creator,
+                    blocks.slot_time as block_slot_time,
+                    blocks.height as block_height
+                FROM contract_events
+                JOIN transactions
+                    ON contract_events.block_height = transactions.block_height
+                    AND contract_events.transaction_index = transactions.index
+                JOIN accounts
+                    ON transactions.sender_index = accounts.index
+                JOIN blocks
+                    ON contract_events.block_height = blocks.height
+                WHERE contract_events.index = $1
+            ",
+            index
+        )
+        .fetch_optional(pool)
+        .await?
+        else {
+            return Ok(None);
+        };
+
+        // Failing to decode a single event skips the event rather than failing the
+        // subscriptions.
+        let event = match Self::event_of_transaction(row.events, row.trace_element_index) {
+            Ok(event) => event,
+            Err(err) => {
+                warn!("Skipping contract event {index} which failed to decode: {err}");
+                return Ok(None);
+            }
+        };
+
+        let contract_address = ContractAddress::new(row.contract_index, row.contract_sub_index)?;
+        let (contract_logs_raw, receive_name) = match &event {
+            Event::ContractInitialized(initialized) => {
+                (initialized.contract_logs_raw.as_slice(), None)
+            }
+            Event::ContractUpdated(updated) => (
+                updated.contract_logs_raw.as_slice(),
+                Some(updated.receive_name.clone()),
+            ),
+            Event::ContractInterrupted(interrupted) => {
+                (interrupted.contract_logs_raw.as_slice(), None)
+            }
+            _ => (&[][..], None),
+        };
+        let decoded_events = if contract_logs_raw.is_empty() {
+            Vec::new()
+        } else {
+            decoded_or_raw_logs(
+                index,
+                decode_contract_logs(pool, contract_address, contract_logs_raw).await,
+            )?
+        };
+
+        Ok(Some(Self {
+            contract_event: Arc::new(ContractEvent {
+                contract_address_index: contract_address.index,
+                contract_address_sub_index: contract_address.sub_index,
+                sender: row.creator.into(),
+                event,
+                block_height: row.block_height,
+                transaction_hash: row.transaction_hash,
+                block_slot_time: row.block_slot_time,
+            }),
+            decoded_events,
+            index,
+            contract_address,
+            receive_name,
+        }))
+    }
+
+    /// Take the event at the trace element index out of the events of the
+    /// transaction.
+    fn event_of_transaction(
+        events: Option<serde_json::Value>,
+        trace_element_index: i64,
+    ) -> ApiResult<Event> {
+        let Some(events) = events else {
+            return Err(
+                InternalError::InternalError("Missing events in database".to_string()).into(),
+            );
+        };
+        let mut events: Vec<Event> = serde_json::from_value(events).map_err(|e| {
+            InternalError::InternalError(format!(
+                "Failed to deserialize events from database: {}",
+                e
+            ))
+        })?;
+        if trace_element_index as usize >= events.len() {
+            return Err(InternalError::InternalError(
+                "Trace element index does not exist in events".to_string(),
+            )
+            .into());
+        }
+        Ok(events.swap_remove(trace_element_index as usize))
+    }
+
+    /// Whether the event is emitted by the provided smart contract instance
+    /// and, when provided, by an update with the receive name.
+    pub fn matches(&self, contract_address: ContractAddress, receive_name: Option<&str>) -> bool {
+        self.contract_address == contract_address
+            && receive_name.is_none_or(|name| self.receive_name.as_deref() == Some(name))
+    }
+}
+
+/// Keep the decoded logs of a contract event, falling back to only the raw
+/// logs of the event when these cannot be decoded. Failing database queries
+/// are returned instead, such that the event is not skipped.
+fn decoded_or_raw_logs(
+    index: i64,
+    decoded_events: ApiResult<Vec<String>>,
+) -> ApiResult<Vec<String>> {
+    match decoded_events {
+        Err(err @ ApiError::InternalServerError(InternalError::FailedDatabaseQuery(_))) => Err(err),
+        Err(err) => {
+            warn!("Logs of contract event {index} failed to decode, keeping the raw logs: {err}");
+            Ok(Vec::new())
+        }
+        Ok(decoded_events) => Ok(decoded_events),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_failing_database_queries_fail_decoding_logs() {
+        let decoded = vec!["{}".to_string()];
+        assert_eq!(
+            decoded_or_raw_logs(1, Ok(decoded.clone())).unwrap(),
+            decoded
+        );
+        // The contract of the event is missing or its schema cannot be read.
+        assert!(decoded_or_raw_logs(1, Err(ApiError::NotFound))
+            .unwrap()
+            .is_empty());
+        assert!(decoded_or_raw_logs(
+            1,
+            Err(InternalError::InternalError("Invalid schema".to_string()).into())
+        )
+        .unwrap()
+        .is_empty());
+        assert!(matches!(
+            decoded_or_raw_logs(1, Err(sqlx::Error::PoolClosed.into())),
+            Err(ApiError::InternalServerError(
+                InternalError::FailedDatabaseQuery(_)
+            ))
+        ));
+    }
+}
@@ -0,0 +1,336 @@
+//! Module containing the registry of labels for known entities, tagging
+//! accounts, smart contract instances and modules with a name, a category and
+//! the source of the label.
+//!
+//! The labels are loaded at startup from a versioned JSON file of the form:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "labels": [
+//!     {
+//!       "account": "<account address>",
+//!       "name": "Example Exchange",
+//!       "category": "Exchange",
+//!       "source": "https://example.com/announcement"
+//!     },
+//!     {
+//!       "contract": { "index": 9338, "subIndex": 0 },
+//!       "name": "Example Bridge",
+//!       "category": "Bridge",
+//!       "source": "Example Bridge documentation"
+//!     },
+//!     { "module": "<module reference>", "name": "...", "category": "Other", "source": "..." }
+//!   ]
+//! }
+//! ```
+//!
+//! Labels of accounts apply to every alias of the account.
+
+use crate::address::ContractAddress;
+use anyhow::Context as _;
+use async_graphql::{Enum, SimpleObject};
+use concordium_rust_sdk::base::contracts_common::AccountAddress;
+use serde::Deserialize;
+use std::{collections::HashMap, path::Path, str::FromStr};
+
+/// The version of the labels file supported.
+const LABELS_FILE_VERSION: u32 = 1;
+
+/// Category of a known entity.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum LabelCategory {
+    Exchange,
+    Foundation,
+    Bridge,
+    ValidatorOperator,
+    Other,
+}
+
+/// Label of a known account, smart contract instance or module.
+#[derive(SimpleObject, Debug, Clone)]
+pub struct Label {
+    /// Name of the entity.
+    pub name: String,
+    pub category: LabelCategory,
+    /// Where the label originates from, such as a URL.
+    pub source: String,
+    /// The labeled account, as provided in the labels file.
+    pub account_address: Option<String>,
+    /// The labeled smart contract instance.
+    pub contract_address: Option<ContractAddress>,
+    /// The labeled smart contract module.
+    pub module_reference: Option<String>,
+}
+
+/// The contents of the labels file.
+#[derive(Deserialize)]
+struct LabelsFile {
+    version: u32,
+    labels: Vec<LabelEntry>,
+}
+
+/// Label in the labels file, where exactly one of the account, contract and
+/// module must be provided.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LabelEntry {
+    account: Option<String>,
+    contract: Option<ContractAddressEntry>,
+    module: Option<String>,
+    name: String,
+    category: LabelCategory,
+    source: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+struct ContractAddressEntry {
+    index: u64,
+    #[serde(default)]
+    sub_index: u64,
+}
+
+/// Registry of the labels of known entities.
+#[derive(Debug, Default)]
+pub struct LabelRegistry {
+    /// Every label in the order of the labels file.
+    labels: Vec<Label>,
+    /// Position of the label for the canonical address of an account.
+    accounts: HashMap<Vec<u8>, usize>,
+    /// Position of the label for a smart contract instance.
+    contracts: HashMap<(u64, u64), usize>,
+    /// Position of the label for a module reference.
+    modules: HashMap<String, usize>,
+}
+
+impl LabelRegistry {
+    /// Load the labels from the file at the provided path.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read labels file {}", path.display()))?;
+        Self::from_json(&json).with_context(|| format!("Invalid labels file {}", path.display()))
+    }
+
+    fn from_json(json: &str) -> anyhow::Result<Self> {
+        let file: LabelsFile = serde_json::from_str(json)?;
+        anyhow::ensure!(
+            file.version == LABELS_FILE_VERSION,
+            "Unsupported version {} of the labels file, expected version {}",
+            file.version,
+            LABELS_FILE_VERSION
+        );
+        let mut registry = Self::default();
+        for entry in file.labels {
+            let position = registry.labels.len();
+            let label = Label {
+                name: entry.name,
+                category: entry.category,
+                source: entry.source,
+                account_address: None,
+                contract_address: None,
+                module_reference: None,
+            };
+            let (label, duplicate) = match (entry.account, entry.contract, entry.module) {
+                (Some(address), None, None) => {
+                    let canonical_address = AccountAddress::from_str(&address)
+                        .map_err(|_| anyhow::anyhow!("Invalid account address {}", address))?
+                        .get_canonical_address()
+                        .0
+                        .to_vec();
+                    let duplicate = registry
+                        .accounts
+                        .insert(canonical_address, position)
+                        .is_some();
+                    (
+                        Label {
+                            account_address: Some(address),
+                            ..label
+                        },
+                        duplicate,
+                    )
+                }
+                (None, Some(contract), None) => {
+                    let duplicate = registry
+                        .contracts
+                        .insert((contract.index, contract.sub_index), position)
+                        .is_some();
+                    (
+                        Label {
+                            contract_address: Some(ContractAddress {
+                                index: contract.index.into(),
+                                sub_index: contract.sub_index.into(),
+                            }),
+                            ..label
+                        },
+                        duplicate,
+                    )
+                }
+                (None, None, Some(module)) => {
+                    let module = module.to_lowercase();
+                    let duplicate = registry.modules.insert(module.clone(), position).is_some();
+                    (
+                        Label {
+                            module_reference: Some(module),
+                            ..label
+                        },
+                        duplicate,
+                    )
+                }
+                _ => anyhow::bail!(
+                    "Label '{}' must have exactly one of 'account', 'contract' and 'module'",
+                    label.name
+                ),
+            };
+            anyhow::ensure!(
+                !duplicate,
+                "Label '{}' labels an entity which is already labeled",
+                label.name
+            );
+            registry.labels.push(label);
+        }
+        Ok(registry)
+    }
+
+    /// The label of an account, also matching aliases of the account.
+    pub fn account(&self, address: &str) -> Option<&Label> {
+        let canonical_address = AccountAddress::from_str(address)
+            .ok()?
+            .get_canonical_address()
+            .0;
+        let position = self.accounts.get(canonical_address.as_slice())?;
+        self.labels.get(*position)
+    }
+
+    /// The label of a smart contract instance.
+    pub fn contract(&self, index: u64, sub_index: u64) -> Option<&Label> {
+        let position = self.contracts.get(&(index, sub_index))?;
+        self.labels.get(*position)
+    }
+
+    /// The label of a smart contract module.
+    pub fn module(&self, module_reference: &str) -> Option<&Label> {
+        let position = self.modules.get(&module_reference.to_lowercase())?;
+        self.labels.get(*position)
+    }
+
+    /// The labels where the name contains the query ignoring case, or where
+    /// the labeled account or module starts with the query. A query being the
+    /// address of an account also matches the label of any alias of the
+    /// account.
+    pub fn search(&self, query: &str) -> Vec<Label> {
+        let lowercase_query = query.to_lowercase();
+        let aliased_account = AccountAddress::from_str(query).ok().and_then(|address| {
+            self.accounts
+                .get(address.get_canonical_address().0.as_slice())
+                .copied()
+        });
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(|(position, label)| {
+                label.name.to_lowercase().contains(&lowercase_query)
+                    || aliased_account == Some(*position)
+                    || label
+                        .account_address
+                        .as_ref()
+                        .is_some_and(|address| address.starts_with(query))
+                    || label
+                        .module_reference
+                        .as_ref()
+                        .is_some_and(|module| module.starts_with(&lowercase_query))
+            })
+            .map(|(_, label)| label.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountAddress, LabelCategory, LabelRegistry};
+    use std::str::FromStr;
+
+    const ACCOUNT: &str = "3XSLuJcXg6xEua6iBPnWacc3iWh93yEDMCqX8FbE3RDSbEnT9P";
+
+    #[test]
+    fn test_labels_are_found_by_target_and_name() {
+        let json = format!(
+            r#"{{
+                "version": 1,
+                "labels": [
+                    {{ "account": "{ACCOUNT}", "name": "Example Exchange", "category": "Exchange", "source": "test" }},
+                    {{ "contract": {{ "index": 9338 }}, "name": "Example Bridge", "category": "Bridge", "source": "test" }},
+                    {{ "module": "AB12", "name": "Bridge module", "category": "Other", "source": "test" }}
+                ]
+            }}"#
+        );
+        let registry = LabelRegistry::from_json(&json).unwrap();
+        assert_eq!(
+            registry.account(ACCOUNT).map(|label| label.category),
+            Some(LabelCategory::Exchange)
+        );
+        assert_eq!(
+            registry.contract(9338, 0).map(|label| label.name.as_str()),
+            Some("Example Bridge")
+        );
+        assert!(registry.contract(9338, 1).is_none());
+        assert!(registry.module("ab12").is_some());
+        let found: Vec<_> = registry
+            .search("bridge")
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(found, vec!["Example Bridge", "Bridge module"]);
+        let found: Vec<_> = registry
+            .search("3XSLuJ")
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(found, vec!["Example Exchange"]);
+    }
+
+    #[test]
+    fn test_labels_are_found_by_alias_of_account() {
+        let json = format!(
+            r#"{{
+                "version": 1,
+                "labels": [
+                    {{ "account": "{ACCOUNT}", "name": "Example Exchange", "category": "Exchange", "source": "test" }}
+                ]
+            }}"#
+        );
+        let registry = LabelRegistry::from_json(&json).unwrap();
+        let alias = AccountAddress::from_str(ACCOUNT)
+            .unwrap()
+            .get_alias(42)
+            .unwrap()
+            .to_string();
+        assert_ne!(alias, ACCOUNT);
+        assert!(registry.account(&alias).is_some());
+        let found: Vec<_> = registry
+            .search(&alias)
+            .into_iter()
+            .map(|label| label.name)
+            .collect();
+        assert_eq!(found, vec!["Example Exchange"]);
+    }
+
+    #[test]
+    fn test_invalid_labels_file_is_rejected() {
+        let unsupported_version = r#"{ "version": 2, "labels": [] }"#;
+        assert!(LabelRegistry::from_json(unsupported_version).is_err());
+        let without_target = r#"{
+            "version": 1,
+            "labels": [{ "name": "Nothing", "category": "Other", "source": "test" }]
+        }"#;
+        assert!(LabelRegistry::from_json(without_target).is_err());
+        let duplicate = r#"{
+            "version": 1,
+            "labels": [
+                { "contract": { "index": 1 }, "name": "A", "category": "Other", "source": "test" },
+                { "contract": { "index": 1, "subIndex": 0 }, "name": "B", "category": "Other", "source": "test" }
+            ]
+        }"#;
+        assert!(LabelRegistry::from_json(duplicate).is_err());
+    }
+}
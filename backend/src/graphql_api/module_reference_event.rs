@@ -1,4 +1,4 @@
-use super::{get_config, get_pool, ApiError, ApiResult, InternalError};
+use super::{get_config, get_labels, get_pool, label::Label, ApiError, ApiResult, InternalError};
 use crate::{
     address::{AccountAddress, ContractAddress},
     scalar_types::{BlockHeight, DateTime, ModuleReference, TransactionHash, TransactionIndex},
@@ -71,6 +71,11 @@ pub struct ModuleReferenceEvent {
 }
 #[ComplexObject]
 impl ModuleReferenceEvent {
+    /// Label of the module when it is a known entity.
+    async fn label<'a>(&self, ctx: &Context<'a>) -> ApiResult<Option<&'a Label>> {
+        Ok(get_labels(ctx)?.module(&self.module_reference))
+    }
+
     async fn module_reference_reject_events(
         &self,
         ctx: &Context<'_>,
@@ -1,9 +1,10 @@
 use super::{
     baker_and_delegator_types::{CommissionRates, DelegationSummary, PaydayPoolReward},
-    get_config, get_pool, ApiError, ApiResult, ApyPeriod, InternalError,
+    get_config, get_indexed_domains, get_pool, ApiError, ApiResult, ApyPeriod, InternalError,
 };
 use crate::{
     connection::{ConnectionQuery, DescendingI64, NestedCursor},
+    indexing_profile::IndexingDomain,
     scalar_types::{BigInteger, Decimal},
 };
 use async_graphql::{connection, Context, Object, SimpleObject};
@@ -371,6 +372,7 @@ impl PassiveDelegation {
 
     async fn apy(&self, ctx: &Context<'_>, period: ApyPeriod) -> ApiResult<Option<f64>> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Payday)?;
         let interval = PgInterval::try_from(period)?;
         let apy = sqlx::query_scalar!(
             r#"WITH chain_parameter AS (
@@ -6,6 +6,7 @@ use crate::{
     address::AccountAddress,
     connection::{DescendingI64, NestedCursor},
     graphql_api::account::Account,
+    indexing_profile::IndexingDomain,
     scalar_types::{
         ModuleReference, PltIndex, TokenId, TokenIndex, TransactionHash, TransactionIndex,
     },
@@ -16,7 +17,8 @@ use crate::{
 };
 
 use super::{
-    block::Block, get_config, get_pool, ApiError, ApiResult, ConnectionQuery, InternalError,
+    block::Block, get_config, get_indexed_domains, get_pool, ApiError, ApiResult, ConnectionQuery,
+    InternalError,
 };
 
 use async_graphql::connection::CursorType;
@@ -63,6 +65,7 @@ impl QueryPltEvent {
     ) -> ApiResult<connection::Connection<String, PltEvent>> {
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
         let query = ConnectionQuery::<DescendingI64>::new(
             first,
             after,
@@ -136,6 +139,7 @@ impl QueryPltEvent {
         })?;
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
         let query = ConnectionQuery::<DescendingI64>::new(
             first,
             after,
@@ -314,6 +318,47 @@ impl PltEvent {
     }
 }
 
+/// Protocol-level token event added to the database, along with the ID of the
+/// token used for filtering the `pltEvents` subscription.
+#[derive(Debug, Clone)]
+pub struct PltEventAddedItem {
+    pub event: PltEvent,
+    token_id: TokenId,
+}
+
+impl PltEventAddedItem {
+    pub async fn query_by_index(pool: &PgPool, index: i64) -> ApiResult<Option<Self>> {
+        let Some(event) = PltEvent::query_by_index(pool, index).await? else {
+            return Ok(None);
+        };
+        let result = sqlx::query!(
+            "SELECT token_id FROM plt_tokens WHERE index = $1",
+            event.token_index
+        )
+        .fetch_one(pool)
+        .await?;
+        Ok(Some(Self {
+            event,
+            token_id: result.token_id,
+        }))
+    }
+
+    /// Whether the event is of the token, of the event type and of the token
+    /// module event type, when provided.
+    pub fn matches(
+        &self,
+        token_id: Option<&TokenId>,
+        event_type: Option<TokenUpdateEventType>,
+        token_module_type: Option<TokenUpdateModuleType>,
+    ) -> bool {
+        token_id.is_none_or(|token_id| &self.token_id == token_id)
+            && event_type.is_none_or(|event_type| self.event.event_type == Some(event_type))
+            && token_module_type.is_none_or(|token_module_type| {
+                self.event.token_module_type == Some(token_module_type)
+            })
+    }
+}
+
 // --------------------------
 
 #[derive(Default)]
@@ -347,6 +392,7 @@ impl QueryPlt {
     ) -> ApiResult<connection::Connection<String, PltToken>> {
         let config = get_config(ctx)?;
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
 
         let query = ConnectionQuery::<DescendingI64>::new(
             first,
@@ -660,6 +706,7 @@ impl QueryPltAccountAmount {
         token_id: types::ID,
     ) -> ApiResult<Option<PltAccountAmount>> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
         let account_address = AccountAddress::from(account.to_string());
         let token_id: TokenId = token_id.to_string();
         PltAccountAmount::query_by_account_and_token(pool, account_address, token_id)
@@ -683,6 +730,7 @@ impl QueryPltAccountAmount {
         connection::Connection<NestedCursor<DescendingI64, DescendingI64>, PltAccountAmount>,
     > {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
         let token_id = TokenId::from_str(token_id.as_ref())
             .map_err(|e| ApiError::InvalidIdFormat(format!("Failed to parse token ID: {}", e)))?;
         let config = get_config(ctx)?;
@@ -819,6 +867,7 @@ impl QueryPltAccountAmount {
 
     async fn plt_unique_accounts(&self, ctx: &Context<'_>) -> ApiResult<i64> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
         let accounts = PltAccountAmount::query_unique_accounts(pool).await?;
         Ok(accounts)
     }
@@ -963,3 +1012,65 @@ impl AccountProtocolToken {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn added_item(
+        token_id: &str,
+        event_type: TokenUpdateEventType,
+        token_module_type: Option<TokenUpdateModuleType>,
+    ) -> PltEventAddedItem {
+        PltEventAddedItem {
+            event: PltEvent {
+                id: 1,
+                transaction_index: 2,
+                event_type: Some(event_type),
+                token_module_type,
+                token_index: 3,
+                token_event: Json(serde_json::Value::Null),
+            },
+            token_id: token_id.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_plt_event_added_matches_filter() {
+        let transfer = added_item("EURR", TokenUpdateEventType::Transfer, None);
+        let add_allow_list = added_item(
+            "EURR",
+            TokenUpdateEventType::TokenModule,
+            Some(TokenUpdateModuleType::AddAllowList),
+        );
+        let pause = added_item(
+            "EURR",
+            TokenUpdateEventType::TokenModule,
+            Some(TokenUpdateModuleType::Pause),
+        );
+        let eurr = "EURR".to_string();
+        let usdr = "USDR".to_string();
+
+        assert!(transfer.matches(None, None, None));
+        assert!(transfer.matches(Some(&eurr), None, None));
+        assert!(!transfer.matches(Some(&usdr), None, None));
+        assert!(transfer.matches(None, Some(TokenUpdateEventType::Transfer), None));
+        assert!(!transfer.matches(None, Some(TokenUpdateEventType::Mint), None));
+
+        // Changes of the lists are told apart from the other token module events.
+        assert!(add_allow_list.matches(None, Some(TokenUpdateEventType::TokenModule), None));
+        assert!(pause.matches(None, Some(TokenUpdateEventType::TokenModule), None));
+        assert!(add_allow_list.matches(
+            Some(&eurr),
+            Some(TokenUpdateEventType::TokenModule),
+            Some(TokenUpdateModuleType::AddAllowList)
+        ));
+        assert!(!pause.matches(None, None, Some(TokenUpdateModuleType::AddAllowList)));
+        assert!(!transfer.matches(None, None, Some(TokenUpdateModuleType::AddAllowList)));
+        assert!(!add_allow_list.matches(
+            Some(&usdr),
+            None,
+            Some(TokenUpdateModuleType::AddAllowList)
+        ));
+    }
+}
@@ -19,7 +19,10 @@ use bigdecimal::BigDecimal;
 use sqlx::postgres::types::PgInterval;
 
 use crate::{
-    graphql_api::{get_pool, ApiError, ApiResult, DateTime, MetricsPeriod, TimeSpan},
+    graphql_api::{
+        get_indexed_domains, get_pool, ApiError, ApiResult, DateTime, MetricsPeriod, TimeSpan,
+    },
+    indexing_profile::IndexingDomain,
     scalar_types::TokenId,
 };
 
@@ -54,6 +57,16 @@ struct PltTransferMetricsBuckets {
     y_transfer_amount: Vec<f64>,
 }
 
+/// Cumulative transfer count and amount of a token at the start of a bucket
+/// and of the previous bucket.
+struct PltTransferMetricsRow {
+    bucket_time: DateTime,
+    cumulative_transfer_count: Option<i64>,
+    cumulative_transfer_amount: Option<BigDecimal>,
+    prev_cumulative_transfer_count: Option<i64>,
+    prev_cumulative_transfer_amount: Option<BigDecimal>,
+}
+
 /// Implementation of the GraphQL query for PLT transfer metrics.
 /// This query retrieves metrics related to PLT token transfer events over a
 /// specified period for a specific token (token_id is mandatory). It provides
@@ -68,6 +81,7 @@ impl QueryPltTransferMetricsByTokenId {
         token_id: TokenId,
     ) -> ApiResult<PltTransferMetricsByTokenId> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Plt)?;
 
         let period_interval: PgInterval = period
             .as_duration()
@@ -90,14 +104,31 @@ impl QueryPltTransferMetricsByTokenId {
         let plt_token_index = record.index;
         let plt_token_decimal = record.decimal;
 
-        let rows = sqlx::query_file!(
-            "src/graphql_api/plt_transfer_metrics.sql",
-            period_interval,
-            bucket_interval,
-            plt_token_index
-        )
-        .fetch_all(pool)
-        .await?;
+        let rows = if let Some(rollup_width) = period.rollup_width() {
+            let rollup_interval: PgInterval = rollup_width
+                .try_into()
+                .map_err(|e| ApiError::DurationOutOfRange(Arc::new(e)))?;
+            sqlx::query_file_as!(
+                PltTransferMetricsRow,
+                "src/graphql_api/plt_transfer_metrics_rollups.sql",
+                period_interval,
+                bucket_interval,
+                plt_token_index,
+                rollup_interval
+            )
+            .fetch_all(pool)
+            .await?
+        } else {
+            sqlx::query_file_as!(
+                PltTransferMetricsRow,
+                "src/graphql_api/plt_transfer_metrics.sql",
+                period_interval,
+                bucket_interval,
+                plt_token_index
+            )
+            .fetch_all(pool)
+            .await?
+        };
 
         let mut x_time = Vec::with_capacity(rows.len());
         let mut y_transfer_count = Vec::with_capacity(rows.len());
@@ -171,6 +202,12 @@ struct GlobalPltMetrics {
     transfer_amount: f64,
 }
 
+/// Cumulative PLT event count and transfer amount across every token.
+struct PltMetricsRow {
+    cumulative_event_count: i64,
+    cumulative_transfer_amount: BigDecimal,
+}
+
 #[Object]
 impl QueryGlobalPltMetrics {
     /// Query for PLT metrics over a specified time period. (across all plts)
@@ -189,16 +226,57 @@ impl QueryGlobalPltMetrics {
             .map_err(|e| ApiError::DurationOutOfRange(Arc::new(e)))?;
 
         // Get start row
-        let start_row = sqlx::query!(
-            "SELECT cumulative_event_count, cumulative_transfer_amount
-             FROM metrics_plt
-             WHERE event_timestamp < NOW() - $1::interval
-             ORDER BY event_timestamp DESC
-             LIMIT 1",
-            period_interval
-        )
-        .fetch_optional(pool)
-        .await?;
+        let start_row = if let Some(rollup_width) = period.rollup_width() {
+            let rollup_interval: PgInterval = rollup_width
+                .try_into()
+                .map_err(|e| ApiError::DurationOutOfRange(Arc::new(e)))?;
+            // The per-block rows which are not yet rolled up are newer than every rolled
+            // up bucket, and are preferred when present.
+            sqlx::query_as!(
+                PltMetricsRow,
+                r#"WITH progress AS (
+                    SELECT COALESCE(rolled_up_until, '-infinity') AS rolled_up_until
+                    FROM metrics_rollup_progress
+                    WHERE bucket_width = $2::interval
+                )
+                SELECT
+                    cumulative_event_count AS "cumulative_event_count!",
+                    cumulative_transfer_amount AS "cumulative_transfer_amount!"
+                FROM (
+                    (SELECT cumulative_event_count, cumulative_transfer_amount, 0 AS source
+                    FROM metrics_plt
+                    WHERE event_timestamp >= (SELECT rolled_up_until FROM progress)
+                        AND event_timestamp < NOW() - $1::interval
+                    ORDER BY event_timestamp DESC
+                    LIMIT 1)
+                    UNION ALL
+                    (SELECT cumulative_event_count, cumulative_transfer_amount, 1 AS source
+                    FROM metrics_plt_rollups
+                    WHERE bucket_width = $2::interval
+                        AND bucket_start <= NOW() - $1::interval - $2::interval
+                    ORDER BY bucket_start DESC
+                    LIMIT 1)
+                ) latest
+                ORDER BY source
+                LIMIT 1"#,
+                period_interval,
+                rollup_interval
+            )
+            .fetch_optional(pool)
+            .await?
+        } else {
+            sqlx::query_as!(
+                PltMetricsRow,
+                "SELECT cumulative_event_count, cumulative_transfer_amount
+                 FROM metrics_plt
+                 WHERE event_timestamp < NOW() - $1::interval
+                 ORDER BY event_timestamp DESC
+                 LIMIT 1",
+                period_interval
+            )
+            .fetch_optional(pool)
+            .await?
+        };
 
         // Get end row
         let end_row = sqlx::query!(
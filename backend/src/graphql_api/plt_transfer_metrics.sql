@@ -23,7 +23,7 @@ latest_per_bucket AS (
   LEFT JOIN LATERAL (
     SELECT *
     FROM metrics_plt_transfer tm
-    WHERE tm.event_timestamp <= b.bucket_start
+    WHERE tm.event_timestamp < b.bucket_start
       AND tm.token_index = $3::BIGINT
     ORDER BY tm.event_timestamp DESC
     LIMIT 1
@@ -42,10 +42,10 @@ delta AS (
 
 SELECT
   bucket_interval_start AS "bucket_time!",
-  COALESCE(cumulative_transfer_count, 0) AS cumulative_transfer_count,
-  COALESCE(cumulative_transfer_amount, 0) AS cumulative_transfer_amount,
-  COALESCE(prev_cumulative_transfer_count, 0) AS prev_cumulative_transfer_count,
-  COALESCE(prev_cumulative_transfer_amount, 0) AS prev_cumulative_transfer_amount
+  COALESCE(cumulative_transfer_count, 0) AS "cumulative_transfer_count?",
+  COALESCE(cumulative_transfer_amount, 0) AS "cumulative_transfer_amount?",
+  COALESCE(prev_cumulative_transfer_count, 0) AS "prev_cumulative_transfer_count?",
+  COALESCE(prev_cumulative_transfer_amount, 0) AS "prev_cumulative_transfer_amount?"
 FROM delta
 
 ORDER BY bucket_interval_start;
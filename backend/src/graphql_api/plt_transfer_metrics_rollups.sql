@@ -0,0 +1,75 @@
+-- PLT transfer metrics read from the rolled up buckets of `metrics_plt_transfer`, combined with the
+-- per-block rows which are not yet rolled up. See `plt_transfer_metrics.sql` for reading only the
+-- per-block rows.
+-- Inputs:
+-- $1::interval - e.g. '30 days'
+-- $2::interval - e.g. '1 days'
+-- $3::BIGINT   - mandatory token filter (token_index)
+-- $4::interval - width of the rolled up buckets, which must divide $2
+
+WITH progress AS (
+  SELECT COALESCE(rolled_up_until, '-infinity') AS rolled_up_until
+  FROM metrics_rollup_progress
+  WHERE bucket_width = $4::interval
+),
+
+buckets AS (
+  SELECT bucket_start
+  FROM date_bin_series(
+    $2::interval,
+    now() - $1::interval,
+    now()
+  ) AS bucket_start
+),
+
+-- The per-block rows which are not yet rolled up are newer than every rolled up bucket, and are
+-- preferred when present.
+latest_per_bucket AS (
+  SELECT
+    b.bucket_start AS bucket_interval_start,
+    tm.cumulative_transfer_count,
+    tm.cumulative_transfer_amount
+  FROM buckets b
+  LEFT JOIN LATERAL (
+    SELECT cumulative_transfer_count, cumulative_transfer_amount
+    FROM (
+      (SELECT cumulative_transfer_count, cumulative_transfer_amount, 0 AS source
+      FROM metrics_plt_transfer
+      WHERE event_timestamp >= (SELECT rolled_up_until FROM progress)
+        AND event_timestamp < b.bucket_start
+        AND token_index = $3::BIGINT
+      ORDER BY event_timestamp DESC
+      LIMIT 1)
+      UNION ALL
+      (SELECT cumulative_transfer_count, cumulative_transfer_amount, 1 AS source
+      FROM metrics_plt_transfer_rollups
+      WHERE bucket_width = $4::interval
+        AND bucket_start <= b.bucket_start - $4::interval
+        AND token_index = $3::BIGINT
+      ORDER BY bucket_start DESC
+      LIMIT 1)
+    ) latest
+    ORDER BY source
+    LIMIT 1
+  ) tm ON true
+),
+
+delta AS (
+  SELECT
+    bucket_interval_start,
+    cumulative_transfer_count,
+    cumulative_transfer_amount,
+    LAG(cumulative_transfer_count) OVER (ORDER BY bucket_interval_start) AS prev_cumulative_transfer_count,
+    LAG(cumulative_transfer_amount) OVER (ORDER BY bucket_interval_start) AS prev_cumulative_transfer_amount
+  FROM latest_per_bucket
+)
+
+SELECT
+  bucket_interval_start AS "bucket_time!",
+  COALESCE(cumulative_transfer_count, 0) AS "cumulative_transfer_count?",
+  COALESCE(cumulative_transfer_amount, 0) AS "cumulative_transfer_amount?",
+  COALESCE(prev_cumulative_transfer_count, 0) AS "prev_cumulative_transfer_count?",
+  COALESCE(prev_cumulative_transfer_amount, 0) AS "prev_cumulative_transfer_amount?"
+FROM delta
+
+ORDER BY bucket_interval_start;
@@ -0,0 +1,350 @@
+//! Module containing an async_graphql extension caching the responses of
+//! queries, which only change when a new block is added.
+//!
+//! Only queries where every top-level field is one of the configured fields are
+//! cached, keyed on the query, operation name and variables. The whole cache
+//! is invalidated whenever a block is added, and the least recently used
+//! responses are evicted when the cache is full.
+
+use super::block::Block;
+use async_graphql::{
+    async_trait::async_trait,
+    extensions::{Extension, ExtensionContext, ExtensionFactory, NextExecute, NextPrepareRequest},
+    parser::types::{OperationType, Selection},
+    Request, Response, ServerResult, Value,
+};
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
+    registry::Registry,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::info;
+
+/// Type representing the Prometheus labels used for counting lookups in the
+/// response cache.
+#[derive(Debug, Clone, EncodeLabelSet, PartialEq, Eq, Hash)]
+struct LookupLabels {
+    /// Whether the response was found in the cache.
+    hit: bool,
+}
+
+/// Cache of query responses shared between requests.
+struct ResponseCache {
+    /// Top-level query fields allowed to be cached.
+    cacheable_fields: HashSet<String>,
+    /// The maximum number of responses stored.
+    limit: usize,
+    /// The stored responses.
+    state: Mutex<CacheState>,
+    /// Metric counting lookups in the cache.
+    lookups: Family<LookupLabels, Counter>,
+    /// Metric tracking the number of responses stored.
+    entries: Gauge,
+}
+
+/// The stored responses and the block generation they belong to.
+#[derive(Default)]
+struct CacheState {
+    /// Incremented every time the cache is invalidated, preventing responses
+    /// computed before the invalidation from being stored.
+    generation: u64,
+    /// Whether the cache stopped receiving notifications of blocks being
+    /// added, meaning responses can no longer be stored.
+    closed: bool,
+    /// The stored responses by the cache key.
+    responses: HashMap<String, CachedResponse>,
+    /// The cache keys of the stored responses by the time they were last used,
+    /// the first being the least recently used.
+    recently_used: BTreeMap<u64, String>,
+    /// Incremented every time a response is stored or looked up, used for
+    /// ordering the responses by when they were last used.
+    clock: u64,
+}
+
+/// A stored response.
+struct CachedResponse {
+    /// The data of the response.
+    data: Value,
+    /// The time the response was last used.
+    last_used: u64,
+}
+
+impl CacheState {
+    /// Advance the clock, returning the new time.
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl ResponseCache {
+    /// Look up the response data for a key, returning the current generation
+    /// on a miss.
+    fn get(&self, key: &str) -> Result<Value, u64> {
+        let mut state = self.state.lock().expect("Response cache lock is poisoned");
+        let now = state.tick();
+        let state = &mut *state;
+        let result = match state.responses.get_mut(key) {
+            Some(response) => {
+                state.recently_used.remove(&response.last_used);
+                state.recently_used.insert(now, key.to_string());
+                response.last_used = now;
+                Ok(response.data.clone())
+            }
+            None => Err(state.generation),
+        };
+        self.lookups
+            .get_or_create(&LookupLabels {
+                hit: result.is_ok(),
+            })
+            .inc();
+        result
+    }
+
+    /// Store response data for a key, unless the cache is invalidated since
+    /// the provided generation. The least recently used response is evicted
+    /// when the cache is full.
+    fn insert(&self, key: String, data: Value, generation: u64) {
+        let mut state = self.state.lock().expect("Response cache lock is poisoned");
+        if state.closed || state.generation != generation {
+            return;
+        }
+        let now = state.tick();
+        let state = &mut *state;
+        if let Some(previous) = state.responses.get(&key) {
+            state.recently_used.remove(&previous.last_used);
+        } else if state.responses.len() >= self.limit {
+            if let Some((_, evicted)) = state.recently_used.pop_first() {
+                state.responses.remove(&evicted);
+            }
+        }
+        state.recently_used.insert(now, key.clone());
+        state.responses.insert(
+            key,
+            CachedResponse {
+                data,
+                last_used: now,
+            },
+        );
+        self.entries
+            .set(state.responses.len().try_into().unwrap_or(i64::MAX));
+    }
+
+    /// Remove every stored response.
+    fn invalidate(&self) {
+        let mut state = self.state.lock().expect("Response cache lock is poisoned");
+        state.generation += 1;
+        state.responses.clear();
+        state.recently_used.clear();
+        self.entries.set(0);
+    }
+
+    /// Remove every stored response and stop storing new responses.
+    fn close(&self) {
+        self.invalidate();
+        self.state
+            .lock()
+            .expect("Response cache lock is poisoned")
+            .closed = true;
+    }
+
+    /// Compute the cache key for a request, `None` if the request is not
+    /// cacheable.
+    /// The extension is registered after the extension for automatic persisted
+    /// queries, which resolves the query document of requests only providing
+    /// the hash of the query.
+    fn key(&self, request: &mut Request) -> ServerResult<Option<String>> {
+        let query = request.query.clone();
+        let operation_name = request.operation_name.clone();
+        let document = request.parsed_query()?;
+        let operation = document
+            .operations
+            .iter()
+            .find(|(name, _)| match (&operation_name, name) {
+                (Some(operation_name), Some(name)) => name.as_str() == operation_name,
+                (None, _) => true,
+                (Some(_), None) => false,
+            })
+            .map(|(_, operation)| &operation.node);
+        let Some(operation) = operation else {
+            return Ok(None);
+        };
+        if operation.ty != OperationType::Query {
+            return Ok(None);
+        }
+        let cacheable = operation.selection_set.node.items.iter().all(|selection| {
+            matches!(
+                &selection.node,
+                Selection::Field(field) if self.cacheable_fields.contains(field.node.name.node.as_str())
+            )
+        });
+        if !cacheable {
+            return Ok(None);
+        }
+        let variables = serde_json::to_string(&request.variables).unwrap_or_default();
+        Ok(Some(format!(
+            "{}\n{}\n{}",
+            operation_name.unwrap_or_default(),
+            variables,
+            query
+        )))
+    }
+}
+
+/// Extension for async_graphql caching the responses of queries.
+#[derive(Clone)]
+pub struct ResponseCacheExtension {
+    cache: Arc<ResponseCache>,
+}
+
+impl ResponseCacheExtension {
+    /// Construct the extension and spawn a task invalidating the cache for
+    /// every block added.
+    pub fn new(
+        cacheable_fields: &[String],
+        limit: NonZeroUsize,
+        mut block_added: broadcast::Receiver<Block>,
+        registry: &mut Registry,
+    ) -> Self {
+        let lookups: Family<LookupLabels, Counter> = Default::default();
+        registry.register(
+            "response_cache_lookups",
+            "Total number of lookups in the response cache",
+            lookups.clone(),
+        );
+        let entries: Gauge = Default::default();
+        registry.register(
+            "response_cache_entries",
+            "Current number of responses stored in the response cache",
+            entries.clone(),
+        );
+        let cache = Arc::new(ResponseCache {
+            cacheable_fields: cacheable_fields.iter().cloned().collect(),
+            limit: limit.get(),
+            state: Default::default(),
+            lookups,
+            entries,
+        });
+        let invalidated_cache = Arc::clone(&cache);
+        tokio::spawn(async move {
+            loop {
+                match block_added.recv().await {
+                    // Missing notifications also means new blocks were added.
+                    Ok(_) | Err(RecvError::Lagged(_)) => invalidated_cache.invalidate(),
+                    Err(RecvError::Closed) => {
+                        info!("Block added channel closed, disabling the response cache.");
+                        invalidated_cache.close();
+                        break;
+                    }
+                }
+            }
+        });
+        Self { cache }
+    }
+}
+
+impl ExtensionFactory for ResponseCacheExtension {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(ResponseCacheRequest {
+            cache: Arc::clone(&self.cache),
+            key: Default::default(),
+        })
+    }
+}
+
+/// Instance of the extension for a single request.
+struct ResponseCacheRequest {
+    cache: Arc<ResponseCache>,
+    /// The cache key of the request, `None` if the request is not cacheable.
+    key: Mutex<Option<String>>,
+}
+
+#[async_trait]
+impl Extension for ResponseCacheRequest {
+    async fn prepare_request(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        mut request: Request,
+        next: NextPrepareRequest<'_>,
+    ) -> ServerResult<Request> {
+        let key = self.cache.key(&mut request)?;
+        *self
+            .key
+            .lock()
+            .expect("Response cache key lock is poisoned") = key;
+        next.run(ctx, request).await
+    }
+
+    async fn execute(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        operation_name: Option<&str>,
+        next: NextExecute<'_>,
+    ) -> Response {
+        let key = self
+            .key
+            .lock()
+            .expect("Response cache key lock is poisoned")
+            .take();
+        let Some(key) = key else {
+            return next.run(ctx, operation_name).await;
+        };
+        let generation = match self.cache.get(&key) {
+            Ok(data) => return Response::new(data),
+            Err(generation) => generation,
+        };
+        let response = next.run(ctx, operation_name).await;
+        if response.errors.is_empty() {
+            self.cache.insert(key, response.data.clone(), generation);
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CacheState, ResponseCache};
+    use async_graphql::Value;
+    use std::sync::Mutex;
+
+    fn cache(limit: usize) -> ResponseCache {
+        ResponseCache {
+            cacheable_fields: Default::default(),
+            limit,
+            state: Mutex::new(CacheState::default()),
+            lookups: Default::default(),
+            entries: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_full_cache_evicts_least_recently_used() {
+        let cache = cache(2);
+        cache.insert("a".to_string(), Value::from(1), 0);
+        cache.insert("b".to_string(), Value::from(2), 0);
+        // Looking up "a" makes "b" the least recently used.
+        assert_eq!(cache.get("a"), Ok(Value::from(1)));
+        cache.insert("c".to_string(), Value::from(3), 0);
+        assert_eq!(cache.get("a"), Ok(Value::from(1)));
+        assert_eq!(cache.get("b"), Err(0));
+        assert_eq!(cache.get("c"), Ok(Value::from(3)));
+    }
+
+    #[test]
+    fn test_invalidated_cache_rejects_stale_responses() {
+        let cache = cache(2);
+        cache.insert("a".to_string(), Value::from(1), 0);
+        cache.invalidate();
+        assert_eq!(cache.get("a"), Err(1));
+        cache.insert("a".to_string(), Value::from(1), 0);
+        assert_eq!(cache.get("a"), Err(1));
+        cache.insert("a".to_string(), Value::from(2), 1);
+        assert_eq!(cache.get("a"), Ok(Value::from(2)));
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    graphql_api::{get_pool, ApiError, ApiResult, MetricsPeriod},
+    graphql_api::{get_indexed_domains, get_pool, ApiError, ApiResult, MetricsPeriod},
+    indexing_profile::IndexingDomain,
     scalar_types::{DateTime, Long, TimeSpan},
 };
 use async_graphql::{types, Context, Object, SimpleObject};
@@ -17,7 +18,9 @@ impl QueryRewardMetrics {
         ctx: &Context<'_>,
         period: MetricsPeriod,
     ) -> ApiResult<RewardMetrics> {
-        reward_metrics(period, None, get_pool(ctx)?).await
+        let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Metrics)?;
+        reward_metrics(period, None, pool).await
     }
 
     async fn reward_metrics_for_account(
@@ -26,7 +29,9 @@ impl QueryRewardMetrics {
         period: MetricsPeriod,
         account_id: types::ID,
     ) -> ApiResult<RewardMetrics> {
-        reward_metrics(period, Some(account_id), get_pool(ctx)?).await
+        let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Metrics)?;
+        reward_metrics(period, Some(account_id), pool).await
     }
 
     async fn pool_reward_metrics_for_baker_pool(
@@ -36,6 +41,7 @@ impl QueryRewardMetrics {
         baker_id: types::ID,
     ) -> ApiResult<PoolRewardMetrics> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Metrics)?;
         let baker_id: i64 = baker_id.try_into().map_err(ApiError::InvalidIdInt)?;
         pool_reward_metrics(period, Some(baker_id), pool).await
     }
@@ -46,6 +52,7 @@ impl QueryRewardMetrics {
         period: MetricsPeriod,
     ) -> ApiResult<PoolRewardMetrics> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Metrics)?;
         pool_reward_metrics(period, None, pool).await
     }
 }
@@ -134,7 +141,7 @@ async fn pool_reward_metrics(
 async fn reward_metrics(
     period: MetricsPeriod,
     account_id: Option<types::ID>,
-    pool: &PgPool,
+    pool: impl sqlx::PgExecutor<'_>,
 ) -> ApiResult<RewardMetrics> {
     let end_time = Utc::now();
     let before_time = end_time - period.as_duration();
@@ -148,39 +155,101 @@ async fn reward_metrics(
         .map(|x| x.try_into().map_err(ApiError::InvalidIdInt))
         .transpose()?;
 
-    let rows = sqlx::query!(
-        r#"
-        SELECT
-            bucket_time.bucket_start AS "bucket_time!",
-            (SELECT COALESCE(SUM(amount), 0)
-                FROM metrics_rewards
-                WHERE
-                    block_slot_time > bucket_time.bucket_start
-                    AND block_slot_time <= bucket_time.bucket_end
-                    AND (
-                        $4::BIGINT IS NULL
-                        OR account_index = $4::BIGINT
-                    )
-            )::BIGINT AS "accumulated_amount!"
-        FROM
-            date_bin_series(
-                $3::interval,
-                $2,
-                $1
-            ) AS bucket_time
-        "#,
-        end_time,
-        before_time,
-        bucket_interval,
-        value
-    )
-    .fetch_all(pool)
-    .await?;
-
-    let (x_time, y_sum_rewards): (Vec<DateTime>, Vec<i64>) = rows
-        .iter()
-        .map(|row| (row.bucket_time, row.accumulated_amount))
-        .unzip();
+    let (x_time, y_sum_rewards): (Vec<DateTime>, Vec<i64>) =
+        if let Some(rollup_width) = period.rollup_width() {
+            let rollup_interval: PgInterval = rollup_width
+                .try_into()
+                .map_err(|err| ApiError::DurationOutOfRange(Arc::new(err)))?;
+            // Sum the rolled up buckets within the bucket together with the per-block
+            // rows which are not yet rolled up.
+            sqlx::query!(
+                r#"
+                WITH progress AS (
+                    SELECT COALESCE(rolled_up_until, '-infinity') AS rolled_up_until
+                    FROM metrics_rollup_progress
+                    WHERE bucket_width = $5::interval
+                )
+                SELECT
+                    bucket_time.bucket_start AS "bucket_time!",
+                    (
+                        (SELECT COALESCE(SUM(amount), 0)
+                            FROM metrics_rewards_rollups
+                            WHERE
+                                bucket_width = $5::interval
+                                AND bucket_start >= bucket_time.bucket_start
+                                AND bucket_start < bucket_time.bucket_end
+                                AND (
+                                    $4::BIGINT IS NULL
+                                    OR account_index = $4::BIGINT
+                                )
+                        )
+                        + (SELECT COALESCE(SUM(amount), 0)
+                            FROM metrics_rewards
+                            WHERE
+                                block_slot_time >= GREATEST(
+                                    bucket_time.bucket_start,
+                                    (SELECT rolled_up_until FROM progress)
+                                )
+                                AND block_slot_time < bucket_time.bucket_end
+                                AND (
+                                    $4::BIGINT IS NULL
+                                    OR account_index = $4::BIGINT
+                                )
+                        )
+                    )::BIGINT AS "accumulated_amount!"
+                FROM
+                    date_bin_series(
+                        $3::interval,
+                        $2,
+                        $1
+                    ) AS bucket_time
+                "#,
+                end_time,
+                before_time,
+                bucket_interval,
+                value,
+                rollup_interval
+            )
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.bucket_time, row.accumulated_amount))
+            .unzip()
+        } else {
+            // Like the rolled up buckets, each bucket includes its start and excludes its
+            // end.
+            sqlx::query!(
+                r#"
+                SELECT
+                    bucket_time.bucket_start AS "bucket_time!",
+                    (SELECT COALESCE(SUM(amount), 0)
+                        FROM metrics_rewards
+                        WHERE
+                            block_slot_time >= bucket_time.bucket_start
+                            AND block_slot_time < bucket_time.bucket_end
+                            AND (
+                                $4::BIGINT IS NULL
+                                OR account_index = $4::BIGINT
+                            )
+                    )::BIGINT AS "accumulated_amount!"
+                FROM
+                    date_bin_series(
+                        $3::interval,
+                        $2,
+                        $1
+                    ) AS bucket_time
+                "#,
+                end_time,
+                before_time,
+                bucket_interval,
+                value
+            )
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.bucket_time, row.accumulated_amount))
+            .unzip()
+        };
 
     let sum_reward_amount = y_sum_rewards.iter().sum();
 
@@ -237,3 +306,130 @@ pub struct PoolRewardMetrics {
     /// Bucket-wise data for rewards
     buckets: PoolRewardMetricsBuckets,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::{DurationRound, TimeDelta};
+    use concordium_rust_sdk::id::types::AccountAddress;
+    use sqlx::PgConnection;
+
+    /// Heights and indices far above those of any indexed chain, such that the
+    /// rows of the tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    /// Insert the per-block reward rows of the account, or the rolled up rows
+    /// into the hourly buckets.
+    async fn insert_rewards(
+        connection: &mut PgConnection,
+        rows: &[(DateTime, i64)],
+        rolled_up: bool,
+    ) {
+        let sql = if rolled_up {
+            "INSERT INTO metrics_rewards_rollups (bucket_width, bucket_start, account_index, amount)
+            VALUES ('1 hour', $1, $2, $3)"
+        } else {
+            "INSERT INTO metrics_rewards (block_slot_time, account_index, amount, block_height)
+            VALUES ($1, $2, $3, $4)"
+        };
+        for (time, amount) in rows {
+            let mut query = sqlx::query(sql).bind(time).bind(ACCOUNT).bind(amount);
+            if !rolled_up {
+                query = query.bind(HEIGHT);
+            }
+            query.execute(&mut *connection).await.unwrap();
+        }
+    }
+
+    /// Insert the block and account referenced by the reward rows.
+    async fn insert_account(connection: &mut PgConnection) {
+        test_database::insert_block(connection, HEIGHT, Utc::now())
+            .await
+            .unwrap();
+        test_database::insert_account(connection, ACCOUNT, &AccountAddress([0xfe; 32]), 0)
+            .await
+            .unwrap();
+    }
+
+    fn bucket(metrics: &RewardMetrics, bucket_start: DateTime) -> i64 {
+        let index = metrics
+            .buckets
+            .x_time
+            .iter()
+            .position(|time| *time == bucket_start)
+            .expect("The bucket is within the period");
+        metrics.buckets.y_sum_rewards[index]
+    }
+
+    #[tokio::test]
+    async fn test_reward_metrics_combine_rollups_and_per_block_rows() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        insert_account(tx.as_mut()).await;
+        // A bucket of the last 7 days, where the hourly buckets are rolled up until
+        // the first hour of the bucket.
+        let start = Utc::now().duration_trunc(TimeDelta::hours(6)).unwrap() - TimeDelta::hours(12);
+        sqlx::query(
+            "UPDATE metrics_rollup_progress SET rolled_up_until = $1 WHERE bucket_width = '1 hour'",
+        )
+        .bind(start + TimeDelta::hours(1))
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        insert_rewards(
+            tx.as_mut(),
+            &[(start - TimeDelta::hours(1), 100), (start, 10)],
+            true,
+        )
+        .await;
+        // The per-block row within the rolled up hour is already counted by the rolled
+        // up bucket, while the last row is exactly at the start of the next bucket.
+        insert_rewards(
+            tx.as_mut(),
+            &[
+                (start + TimeDelta::minutes(30), 5),
+                (start + TimeDelta::hours(1), 1),
+                (start + TimeDelta::hours(6), 1000),
+            ],
+            false,
+        )
+        .await;
+
+        let metrics = reward_metrics(
+            MetricsPeriod::Last7Days,
+            Some(types::ID(ACCOUNT.to_string())),
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(bucket(&metrics, start - TimeDelta::hours(6)), 100);
+        assert_eq!(bucket(&metrics, start), 10 + 1);
+        assert_eq!(bucket(&metrics, start + TimeDelta::hours(6)), 1000);
+        assert_eq!(metrics.sum_reward_amount, 100 + 10 + 1 + 1000);
+    }
+
+    #[tokio::test]
+    async fn test_reward_metrics_bucket_includes_its_start() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        insert_account(tx.as_mut()).await;
+        let start = Utc::now().duration_trunc(TimeDelta::hours(1)).unwrap() - TimeDelta::hours(3);
+        insert_rewards(tx.as_mut(), &[(start, 7)], false).await;
+
+        let metrics = reward_metrics(
+            MetricsPeriod::Last24Hours,
+            Some(types::ID(ACCOUNT.to_string())),
+            tx.as_mut(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(bucket(&metrics, start - TimeDelta::hours(1)), 0);
+        assert_eq!(bucket(&metrics, start), 7);
+    }
+}
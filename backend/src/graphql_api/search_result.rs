@@ -2,7 +2,8 @@ use super::{
     baker::{self, Baker},
     block::Block,
     contract::{self, Contract, ContractSnapshot},
-    db, get_config, get_pool,
+    db, get_config, get_labels, get_pool,
+    label::Label,
     module_reference_event::ModuleReferenceEvent,
     node_status::NodeInfoReceiver,
     token::Token,
@@ -435,7 +436,7 @@ impl SearchResult {
             async |db_connection: &mut PoolConnection<Postgres>| {
                 let mut rows = sqlx::query_as!(
                     Block,
-                    "SELECT * FROM (
+                    r#"SELECT * FROM (
                 SELECT
                     hash,
                     height,
@@ -443,7 +444,8 @@ impl SearchResult {
                     block_time,
                     finalization_time,
                     baker_id,
-                    total_amount
+                    total_amount,
+                    true AS "finalized!"
                 FROM blocks
                 WHERE
                     height = $5
@@ -454,7 +456,7 @@ impl SearchResult {
                     (CASE WHEN $4 THEN height END) ASC,
                     (CASE WHEN NOT $4 THEN height END) DESC
                 LIMIT $3
-            ) ORDER BY height DESC",
+            ) ORDER BY height DESC"#,
                     query.from,
                     query.to,
                     query.limit,
@@ -1085,4 +1087,22 @@ impl SearchResult {
 
         connection_from_slice(nodes, first, after, last, before)
     }
+
+    /// Labels of known entities matching the query by name, account address
+    /// or module reference.
+    async fn labels(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "Returns the first _n_ elements from the list.")] first: Option<usize>,
+        #[graphql(desc = "Returns the elements in the list that come after the specified cursor.")]
+        after: Option<String>,
+        #[graphql(desc = "Returns the last _n_ elements from the list.")] last: Option<usize>,
+        #[graphql(
+            desc = "Returns the elements in the list that come before the specified cursor."
+        )]
+        before: Option<String>,
+    ) -> ApiResult<connection::Connection<String, Label>> {
+        let labels = get_labels(ctx)?.search(&self.query);
+        connection_from_slice(labels, first, after, last, before)
+    }
 }
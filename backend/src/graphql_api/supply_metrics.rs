@@ -0,0 +1,336 @@
+use std::sync::Arc;
+
+use async_graphql::{Context, Object, SimpleObject};
+use chrono::Duration;
+use sqlx::postgres::types::PgInterval;
+
+use super::{get_config, get_pool, ApiError, ApiResult, ApiServiceConfig, DateTime, TimeSpan};
+use crate::scalar_types::Amount;
+
+/// The supply of CCD at the end of a bucket.
+pub(crate) struct SupplyHistoryEntry {
+    /// Start of the bucket time period.
+    pub time: DateTime,
+    /// The total amount of CCD in existence.
+    pub total_amount: u64,
+    /// The total amount of CCD not counting the balances of non circulating
+    /// accounts.
+    pub circulating_amount: u64,
+    /// The total amount of CCD staked.
+    pub staked_amount: u64,
+    /// The total amount of CCD locked in release schedules.
+    pub locked_amount: u64,
+}
+
+/// Query the supply of CCD at the end of each bucket of the given width
+/// between `from` and `to`.
+pub(crate) async fn query_supply_history(
+    pool: impl sqlx::PgExecutor<'_>,
+    config: &ApiServiceConfig,
+    from: DateTime,
+    to: DateTime,
+    bucket_width: Duration,
+) -> ApiResult<Vec<SupplyHistoryEntry>> {
+    if to <= from {
+        return Err(ApiError::InvalidTimeRange(
+            "'from' must be before 'to'".to_string(),
+        ));
+    }
+    if bucket_width <= Duration::zero() {
+        return Err(ApiError::InvalidTimeRange(
+            "The bucket width must be positive".to_string(),
+        ));
+    }
+    let buckets = u64::try_from((to - from).num_seconds() / bucket_width.num_seconds().max(1))? + 1;
+    if buckets > config.supply_history_buckets_limit {
+        return Err(ApiError::InvalidTimeRange(format!(
+            "The time range contains {} buckets, exceeding the maximum of {}",
+            buckets, config.supply_history_buckets_limit
+        )));
+    }
+    let bucket_interval: PgInterval = bucket_width
+        .try_into()
+        .map_err(|err| ApiError::DurationOutOfRange(Arc::new(err)))?;
+    let non_circulating_accounts = config
+        .non_circulating_account
+        .iter()
+        .map(|a| a.to_string())
+        .collect::<Vec<_>>();
+
+    let rows = sqlx::query_file!(
+        "src/graphql_api/supply_metrics.sql",
+        from,
+        to,
+        bucket_interval,
+        non_circulating_accounts.as_slice()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| -> ApiResult<SupplyHistoryEntry> {
+            Ok(SupplyHistoryEntry {
+                time: row.bucket_time,
+                total_amount: row.total_amount.try_into()?,
+                circulating_amount: (row.total_amount - row.non_circulating_amount)
+                    .max(0)
+                    .try_into()?,
+                staked_amount: row.total_staked.try_into()?,
+                locked_amount: row.total_locked.try_into()?,
+            })
+        })
+        .collect()
+}
+
+#[derive(SimpleObject)]
+struct SupplyMetrics {
+    buckets: SupplyMetricsBuckets,
+}
+
+#[derive(SimpleObject)]
+struct SupplyMetricsBuckets {
+    /// The width (time interval) of each bucket.
+    bucket_width: TimeSpan,
+
+    /// Start of the bucket time period. Intended x-axis value.
+    #[graphql(name = "x_Time")]
+    x_time: Vec<DateTime>,
+
+    /// The total amount of CCD in existence at the end of the bucket period.
+    /// Intended y-axis value.
+    #[graphql(name = "y_TotalMicroCcd")]
+    y_total_micro_ccd: Vec<Amount>,
+
+    /// The total amount of CCD not counting the balances of non circulating
+    /// accounts at the end of the bucket period. Intended y-axis value.
+    #[graphql(name = "y_CirculatingMicroCcd")]
+    y_circulating_micro_ccd: Vec<Amount>,
+
+    /// The total amount of CCD staked at the end of the bucket period.
+    /// Intended y-axis value.
+    #[graphql(name = "y_StakedMicroCcd")]
+    y_staked_micro_ccd: Vec<Amount>,
+
+    /// The total amount of CCD locked in release schedules at the end of the
+    /// bucket period. Intended y-axis value.
+    #[graphql(name = "y_LockedInReleaseSchedulesMicroCcd")]
+    y_locked_in_release_schedules_micro_ccd: Vec<Amount>,
+}
+
+#[derive(Default)]
+pub(crate) struct QuerySupplyMetrics;
+
+#[Object]
+impl QuerySupplyMetrics {
+    /// The supply of CCD at the end of each bucket between `from` and `to`.
+    async fn supply_metrics(
+        &self,
+        ctx: &Context<'_>,
+        from: DateTime,
+        to: DateTime,
+        bucket_width: TimeSpan,
+    ) -> ApiResult<SupplyMetrics> {
+        let pool = get_pool(ctx)?;
+        let config = get_config(ctx)?;
+        let entries = query_supply_history(pool, config, from, to, bucket_width.0).await?;
+
+        let mut buckets = SupplyMetricsBuckets {
+            bucket_width,
+            x_time: Vec::with_capacity(entries.len()),
+            y_total_micro_ccd: Vec::with_capacity(entries.len()),
+            y_circulating_micro_ccd: Vec::with_capacity(entries.len()),
+            y_staked_micro_ccd: Vec::with_capacity(entries.len()),
+            y_locked_in_release_schedules_micro_ccd: Vec::with_capacity(entries.len()),
+        };
+        for entry in entries {
+            buckets.x_time.push(entry.time);
+            buckets.y_total_micro_ccd.push(entry.total_amount.into());
+            buckets
+                .y_circulating_micro_ccd
+                .push(entry.circulating_amount.into());
+            buckets.y_staked_micro_ccd.push(entry.staked_amount.into());
+            buckets
+                .y_locked_in_release_schedules_micro_ccd
+                .push(entry.locked_amount.into());
+        }
+        Ok(SupplyMetrics { buckets })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::{TimeZone, Utc};
+    use clap::Parser;
+    use concordium_rust_sdk::id::types::AccountAddress;
+    use sqlx::{PgConnection, PgPool};
+
+    /// Heights and indices far above those of any indexed chain, such that the
+    /// rows of the tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    #[derive(Parser)]
+    struct Cli {
+        #[command(flatten)]
+        config: ApiServiceConfig,
+    }
+
+    fn config(args: &[&str]) -> ApiServiceConfig {
+        Cli::parse_from(std::iter::once("ccdscan-api").chain(args.iter().copied())).config
+    }
+
+    /// Insert the block with the supply, and a transaction of the block.
+    async fn insert_block(
+        connection: &mut PgConnection,
+        offset: i64,
+        slot_time: DateTime,
+        total_amount: i64,
+        total_staked: i64,
+    ) {
+        test_database::insert_block(connection, HEIGHT + offset, slot_time)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE blocks SET total_amount = $2, total_staked = $3 WHERE height = $1")
+            .bind(HEIGHT + offset)
+            .bind(total_amount)
+            .bind(total_staked)
+            .execute(&mut *connection)
+            .await
+            .unwrap();
+        test_database::insert_transaction(connection, TRANSACTION + offset, HEIGHT + offset)
+            .await
+            .unwrap();
+    }
+
+    /// Insert the account created by the transaction of the block.
+    async fn insert_account(
+        connection: &mut PgConnection,
+        offset: i64,
+        block_offset: i64,
+        address: &AccountAddress,
+        amount: i64,
+    ) {
+        test_database::insert_account(connection, ACCOUNT + offset, address, amount)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE accounts SET transaction_index = $2 WHERE index = $1")
+            .bind(ACCOUNT + offset)
+            .bind(TRANSACTION + block_offset)
+            .execute(&mut *connection)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_supply_history_at_bucket_ends() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        let non_circulating = AccountAddress([0xfe; 32]);
+        let non_circulating_later = AccountAddress([0xfd; 32]);
+        let circulating = AccountAddress([0xfc; 32]);
+        insert_block(tx.as_mut(), 0, start + Duration::minutes(10), 1000, 100).await;
+        insert_block(tx.as_mut(), 1, start + Duration::minutes(70), 1100, 200).await;
+        insert_account(tx.as_mut(), 0, 0, &non_circulating, 150).await;
+        insert_account(tx.as_mut(), 1, 0, &circulating, 500).await;
+        // Created in the second bucket, without any account statements.
+        insert_account(tx.as_mut(), 2, 1, &non_circulating_later, 30).await;
+        // The balance of the non circulating account changes in the second bucket.
+        sqlx::query(
+            "INSERT INTO account_statements (
+                account_index, entry_type, amount, block_height, transaction_id,
+                account_balance, slot_time
+            ) VALUES ($1, 'TransferIn', 50, $2, $3, 150, $4)",
+        )
+        .bind(ACCOUNT)
+        .bind(HEIGHT + 1)
+        .bind(TRANSACTION + 1)
+        .bind(start + Duration::minutes(70))
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        // The first release is exactly at the end of the first bucket, where the amount
+        // is still locked.
+        for (release_time, amount) in [
+            (start + Duration::hours(1), 40),
+            (start + Duration::hours(3), 5),
+        ] {
+            sqlx::query(
+                "INSERT INTO scheduled_release_history (
+                    transaction_index, locked_at, release_time, amount
+                ) VALUES ($1, $2, $3, $4)",
+            )
+            .bind(TRANSACTION)
+            .bind(start + Duration::minutes(10))
+            .bind(release_time)
+            .bind(amount)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let config = config(&[
+            "--non-circulating-account",
+            &format!("{},{}", non_circulating, non_circulating_later),
+        ]);
+        let entries = query_supply_history(
+            tx.as_mut(),
+            &config,
+            start,
+            start + Duration::minutes(90),
+            Duration::hours(1),
+        )
+        .await
+        .unwrap();
+        let entries: Vec<_> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.time,
+                    entry.total_amount,
+                    entry.circulating_amount,
+                    entry.staked_amount,
+                    entry.locked_amount,
+                )
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                (start, 1000, 1000 - 100, 100, 40 + 5),
+                (start + Duration::hours(1), 1100, 1100 - 150 - 30, 200, 5),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_supply_history_bucket_limit() {
+        // The bucket limit is checked before querying the database.
+        let pool = PgPool::connect_lazy("postgres://localhost/unused").unwrap();
+        let config = config(&["--supply-history-buckets-limit", "5"]);
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        let result = query_supply_history(
+            &pool,
+            &config,
+            start,
+            start + Duration::hours(5),
+            Duration::hours(1),
+        )
+        .await;
+        assert!(matches!(result, Err(ApiError::InvalidTimeRange(_))));
+        let result = query_supply_history(
+            &pool,
+            &config,
+            start,
+            start + Duration::hours(4),
+            Duration::hours(1),
+        )
+        .await;
+        assert!(!matches!(result, Err(ApiError::InvalidTimeRange(_))));
+    }
+}
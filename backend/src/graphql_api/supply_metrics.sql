@@ -0,0 +1,54 @@
+-- The supply of CCD at the end of each bucket, using the last block before the end of the bucket.
+-- The balance of a non-circulating account at the end of a bucket is the balance of the last
+-- account statement before the end of the bucket, falling back to the balance before the first
+-- statement or the current balance when the account has no statements.
+SELECT
+  bucket.bucket_start AS "bucket_time!",
+  COALESCE(last_block.total_amount, 0) AS "total_amount!",
+  COALESCE(last_block.total_staked, 0) AS "total_staked!",
+  COALESCE(
+    (
+      SELECT SUM(amount)
+      FROM scheduled_release_history
+      WHERE locked_at < bucket.bucket_end
+        AND bucket.bucket_end <= release_time
+    ), 0
+  )::BIGINT AS "total_locked!",
+  COALESCE(
+    (
+      SELECT SUM(
+        COALESCE(
+          (
+            SELECT account_balance
+            FROM account_statements
+            WHERE account_index = accounts.index
+              AND slot_time < bucket.bucket_end
+            ORDER BY slot_time DESC, id DESC
+            LIMIT 1
+          ),
+          (
+            SELECT account_balance - amount
+            FROM account_statements
+            WHERE account_index = accounts.index
+            ORDER BY id ASC
+            LIMIT 1
+          ),
+          accounts.amount
+        )
+      )
+      FROM accounts
+        LEFT JOIN transactions ON transactions.index = accounts.transaction_index
+        JOIN blocks ON blocks.height = COALESCE(transactions.block_height, 0)
+      WHERE accounts.address = ANY($4)
+        AND blocks.slot_time < bucket.bucket_end
+    ), 0
+  )::BIGINT AS "non_circulating_amount!"
+FROM date_bin_series($3::interval, $1, $2) AS bucket
+LEFT JOIN LATERAL (
+  SELECT total_amount, total_staked
+  FROM blocks
+  WHERE slot_time < bucket.bucket_end
+  ORDER BY slot_time DESC
+  LIMIT 1
+) last_block ON true
+ORDER BY bucket.bucket_start;
@@ -1,10 +1,11 @@
 use super::{
-    account::Account, get_config, get_pool, transaction::Transaction, ApiError, ApiResult,
-    CollectionSegmentInfo, InternalError,
+    account::Account, get_config, get_indexed_domains, get_pool, transaction::Transaction,
+    ApiError, ApiResult, CollectionSegmentInfo, InternalError,
 };
 use crate::{
     address::ContractIndex,
     connection::{ConnectionQuery, DescendingI64},
+    indexing_profile::IndexingDomain,
     scalar_types::{BigInteger, TransactionIndex},
     transaction_event::CisEvent,
 };
@@ -24,8 +25,10 @@ impl QueryToken {
         contract_sub_index: ContractIndex,
         token_id: String,
     ) -> ApiResult<Token> {
+        let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Cis2)?;
         Token::query_by_contract_and_id(
-            get_pool(ctx)?,
+            pool,
             contract_index.0 as i64,
             contract_sub_index.0 as i64,
             &token_id,
@@ -46,6 +49,7 @@ impl QueryToken {
         before: Option<String>,
     ) -> ApiResult<connection::Connection<String, Token>> {
         let pool = get_pool(ctx)?;
+        get_indexed_domains(ctx)?.ensure_indexed(IndexingDomain::Cis2)?;
         let config = get_config(ctx)?;
         let query = ConnectionQuery::<DescendingI64>::new(
             first,
@@ -2,9 +2,12 @@ use super::{
     block::Block, get_config, get_pool, ApiError, ApiResult, ConnectionQuery, InternalError,
 };
 use crate::{
-    address::AccountAddress,
+    address::{AccountAddress, ContractAddress, ContractIndex},
     connection::DescendingI64,
-    scalar_types::{AccountIndex, Amount, BlockHeight, Energy, TransactionHash, TransactionIndex},
+    scalar_types::{
+        AccountIndex, Amount, BlockHash, BlockHeight, Energy, TransactionHash, TransactionIndex,
+        UnsignedLong,
+    },
     transaction_event::Event,
     transaction_reject::TransactionRejectReason,
     transaction_type::{
@@ -14,11 +17,11 @@ use crate::{
     },
 };
 use async_graphql::{
-    connection, connection::Connection, types, Context, Object, SimpleObject, Union,
+    connection, connection::Connection, types, Context, InputObject, Object, SimpleObject, Union,
 };
 use futures::TryStreamExt;
 use sqlx::PgPool;
-use std::str::FromStr;
+use std::{str::FromStr, sync::Arc};
 
 #[derive(Default)]
 pub struct QueryTransactions;
@@ -32,14 +35,35 @@ impl QueryTransactions {
             .ok_or(ApiError::NotFound)
     }
 
+    /// Query a finalized transaction. Fails with an error referring to
+    /// `tentativeTransactionByTransactionHash` for a transaction only included
+    /// in a block of the tentative head.
     async fn transaction_by_transaction_hash<'a>(
         &self,
         ctx: &Context<'a>,
         transaction_hash: TransactionHash,
     ) -> ApiResult<Transaction> {
-        Transaction::query_by_hash(get_pool(ctx)?, transaction_hash)
-            .await?
-            .ok_or(ApiError::NotFound)
+        let pool = get_pool(ctx)?;
+        if let Some(transaction) =
+            Transaction::query_by_hash(pool, transaction_hash.clone()).await?
+        {
+            return Ok(transaction);
+        }
+        match TentativeTransaction::query_by_hash(pool, transaction_hash).await? {
+            Some(tentative) => Err(ApiError::TransactionNotFinalized(tentative.block_hash)),
+            None => Err(ApiError::NotFound),
+        }
+    }
+
+    /// Query a transaction included in a block on the live branch of the chain
+    /// which is not yet finalized. Only available when the indexer is indexing
+    /// the tentative head.
+    async fn tentative_transaction_by_transaction_hash<'a>(
+        &self,
+        ctx: &Context<'a>,
+        transaction_hash: TransactionHash,
+    ) -> ApiResult<Option<TentativeTransaction>> {
+        TentativeTransaction::query_by_hash(get_pool(ctx)?, transaction_hash).await
     }
 
     async fn transactions<'a>(
@@ -364,3 +388,438 @@ impl Success<'_> {
 struct Rejected<'a> {
     reason: &'a TransactionRejectReason,
 }
+
+/// Transaction included in a block on the live branch of the chain, which is
+/// not yet finalized.
+pub struct TentativeTransaction {
+    hash: TransactionHash,
+    block_hash: BlockHash,
+    sender: Option<String>,
+    success: bool,
+}
+
+impl TentativeTransaction {
+    /// Query the transaction of the tentative head, which is not yet processed
+    /// as finalized.
+    pub async fn query_by_hash(
+        pool: &PgPool,
+        transaction_hash: TransactionHash,
+    ) -> ApiResult<Option<Self>> {
+        let transaction = sqlx::query_as!(
+            TentativeTransaction,
+            "SELECT
+                tentative_transactions.hash,
+                tentative_transactions.block_hash,
+                tentative_transactions.sender,
+                tentative_transactions.success
+            FROM tentative_transactions
+                JOIN tentative_blocks ON tentative_blocks.hash = tentative_transactions.block_hash
+            WHERE tentative_transactions.hash = $1
+                AND tentative_blocks.height > (SELECT MAX(height) FROM blocks)",
+            transaction_hash
+        )
+        .fetch_optional(pool)
+        .await?;
+        Ok(transaction)
+    }
+
+    /// Query the transactions of the tentative block, in the order of the
+    /// block.
+    pub async fn query_by_block(pool: &PgPool, block_hash: &BlockHash) -> ApiResult<Vec<Self>> {
+        let transactions = sqlx::query_as!(
+            TentativeTransaction,
+            "SELECT hash, block_hash, sender, success
+            FROM tentative_transactions
+            WHERE block_hash = $1
+            ORDER BY index_in_block",
+            block_hash
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(transactions)
+    }
+}
+
+#[Object]
+impl TentativeTransaction {
+    async fn transaction_hash(&self) -> &TransactionHash {
+        &self.hash
+    }
+
+    /// The tentative block including the transaction.
+    async fn block<'a>(&self, ctx: &Context<'a>) -> ApiResult<Block> {
+        Block::query_by_hash(get_pool(ctx)?, self.block_hash.clone()).await
+    }
+
+    async fn sender_account_address(&self) -> Option<AccountAddress> {
+        self.sender.clone().map(AccountAddress::from)
+    }
+
+    /// Whether the transaction was accepted when executed in the tentative
+    /// block.
+    async fn success(&self) -> bool {
+        self.success
+    }
+}
+
+/// Filter for the transactions yielded by the `transactionAdded` subscription,
+/// where a transaction is yielded when it matches every provided field.
+#[derive(InputObject, Default)]
+pub struct TransactionAddedFilter {
+    /// Address of the account sending the transaction.
+    sender: Option<String>,
+    /// Address of an account affected by the transaction.
+    affected_account: Option<String>,
+    /// Type of the account transaction.
+    account_transaction_type: Option<AccountTransactionType>,
+    /// Type of the credential deployment transaction.
+    credential_deployment_transaction_type: Option<CredentialDeploymentTransactionType>,
+    /// Type of the update transaction.
+    update_transaction_type: Option<UpdateTransactionType>,
+    /// Whether the transaction was successful or rejected.
+    success: Option<bool>,
+    /// Index of a smart contract instance either emitting an event or
+    /// rejecting the transaction.
+    contract_address_index: Option<ContractIndex>,
+    /// Sub index of the smart contract instance, defaults to 0 when only the
+    /// index is provided.
+    contract_address_sub_index: Option<ContractIndex>,
+}
+
+impl TransactionAddedFilter {
+    /// Parse the account addresses of the filter, resolving them to their
+    /// canonical address, such that aliases of an account also match.
+    pub fn prepare(self) -> ApiResult<PreparedTransactionAddedFilter> {
+        let canonical_address = |address: Option<String>| {
+            address
+                .map(|address| {
+                    let address =
+                        concordium_rust_sdk::base::contracts_common::AccountAddress::from_str(
+                            &address,
+                        )
+                        .map_err(|_| ApiError::InvalidAccountAddress(address))?;
+                    Ok::<_, ApiError>(address.get_canonical_address().0.to_vec())
+                })
+                .transpose()
+        };
+        let contract_address = match (self.contract_address_index, self.contract_address_sub_index)
+        {
+            (Some(index), sub_index) => Some(ContractAddress {
+                index,
+                sub_index: sub_index.unwrap_or(UnsignedLong(0)),
+            }),
+            (None, Some(_)) => return Err(ApiError::ContractAddressSubIndexWithoutIndex),
+            (None, None) => None,
+        };
+        Ok(PreparedTransactionAddedFilter {
+            sender: canonical_address(self.sender)?,
+            affected_account: canonical_address(self.affected_account)?,
+            account_transaction_type: self.account_transaction_type,
+            credential_deployment_transaction_type: self.credential_deployment_transaction_type,
+            update_transaction_type: self.update_transaction_type,
+            success: self.success,
+            contract_address,
+        })
+    }
+}
+
+/// Filter of the `transactionAdded` subscription with the account addresses
+/// resolved to canonical addresses.
+pub struct PreparedTransactionAddedFilter {
+    sender: Option<Vec<u8>>,
+    affected_account: Option<Vec<u8>>,
+    account_transaction_type: Option<AccountTransactionType>,
+    credential_deployment_transaction_type: Option<CredentialDeploymentTransactionType>,
+    update_transaction_type: Option<UpdateTransactionType>,
+    success: Option<bool>,
+    contract_address: Option<ContractAddress>,
+}
+
+impl PreparedTransactionAddedFilter {
+    /// Whether the added transaction matches every field of the filter.
+    pub fn matches(&self, item: &TransactionAddedItem) -> bool {
+        let transaction = &item.transaction;
+        self.sender
+            .as_ref()
+            .is_none_or(|sender| item.sender.as_ref() == Some(sender))
+            && self
+                .affected_account
+                .as_ref()
+                .is_none_or(|account| item.affected_accounts.contains(account))
+            && self
+                .account_transaction_type
+                .is_none_or(|tt| transaction.type_account == Some(tt))
+            && self
+                .credential_deployment_transaction_type
+                .is_none_or(|tt| transaction.type_credential_deployment == Some(tt))
+            && self
+                .update_transaction_type
+                .is_none_or(|tt| transaction.type_update == Some(tt))
+            && self
+                .success
+                .is_none_or(|success| transaction.success == success)
+            && self
+                .contract_address
+                .is_none_or(|contract| item.contracts.contains(&contract))
+    }
+}
+
+/// Transaction added to the database, along with the information used for
+/// filtering the `transactionAdded` subscription.
+#[derive(Clone)]
+pub struct TransactionAddedItem {
+    /// The added transaction, shared between every subscription.
+    pub transaction: Arc<Transaction>,
+    /// Canonical address of the account sending the transaction.
+    sender: Option<Vec<u8>>,
+    /// Canonical addresses of the accounts affected by the transaction.
+    pub(crate) affected_accounts: Vec<Vec<u8>>,
+    /// Smart contract instances emitting events or rejecting the transaction.
+    contracts: Vec<ContractAddress>,
+}
+
+impl TransactionAddedItem {
+    /// Load every transaction of the block at the height, along with the
+    /// canonical addresses of the sender and affected accounts, using a single
+    /// query.
+    pub async fn query_by_block(pool: &PgPool, block_height: BlockHeight) -> ApiResult<Vec<Self>> {
+        let rows = sqlx::query!(
+            r#"SELECT
+                transactions.index,
+                transactions.block_height,
+                transactions.hash,
+                transactions.ccd_cost,
+                transactions.energy_cost,
+                transactions.sender_index,
+                transactions.sponsor_index,
+                transactions.type as "tx_type: DbTransactionType",
+                transactions.type_account as "type_account: AccountTransactionType",
+                transactions.type_credential_deployment as "type_credential_deployment: CredentialDeploymentTransactionType",
+                transactions.type_update as "type_update: UpdateTransactionType",
+                transactions.success,
+                transactions.events as "events: sqlx::types::Json<Vec<Event>>",
+                transactions.reject as "reject: sqlx::types::Json<TransactionRejectReason>",
+                senders.canonical_address as "sender?",
+                ARRAY(
+                    SELECT accounts.canonical_address
+                    FROM affected_accounts
+                        JOIN accounts ON accounts.index = affected_accounts.account_index
+                    WHERE affected_accounts.transaction_index = transactions.index
+                ) as "affected_accounts!"
+            FROM transactions
+                LEFT JOIN accounts AS senders ON senders.index = transactions.sender_index
+            WHERE transactions.block_height = $1
+            ORDER BY transactions.index"#,
+            block_height
+        )
+        .fetch_all(pool)
+        .await?;
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                Self::new(
+                    Transaction {
+                        index: row.index,
+                        block_height: row.block_height,
+                        hash: row.hash,
+                        ccd_cost: row.ccd_cost,
+                        energy_cost: row.energy_cost,
+                        sender_index: row.sender_index,
+                        sponsor_index: row.sponsor_index,
+                        tx_type: row.tx_type,
+                        type_account: row.type_account,
+                        type_credential_deployment: row.type_credential_deployment,
+                        type_update: row.type_update,
+                        success: row.success,
+                        events: row.events,
+                        reject: row.reject,
+                    },
+                    row.sender,
+                    row.affected_accounts,
+                )
+            })
+            .collect();
+        Ok(items)
+    }
+
+    /// Collect the smart contract instances of the transaction used for
+    /// filtering.
+    fn new(
+        transaction: Transaction,
+        sender: Option<Vec<u8>>,
+        affected_accounts: Vec<Vec<u8>>,
+    ) -> Self {
+        let mut contracts: Vec<ContractAddress> = transaction
+            .events
+            .iter()
+            .flat_map(|events| events.iter())
+            .filter_map(Event::contract_address)
+            .chain(
+                transaction
+                    .reject
+                    .as_ref()
+                    .and_then(|reason| reason.contract_address()),
+            )
+            .collect();
+        contracts.dedup();
+        Self {
+            transaction: Arc::new(transaction),
+            sender,
+            affected_accounts,
+            contracts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use concordium_rust_sdk::base::contracts_common::AccountAddress as SdkAccountAddress;
+
+    const SENDER: &str = "3kBx2h5Y2veb4hZgAJWPrr8RyQESKm5TjzF3ti1QQ4VSYLwK1G";
+    const RECEIVER: &str = "4AuT5RRmBwcdkLMA6iVjxTDb1FQmxwAh3wHBS22mggWL8xH6s3";
+
+    fn canonical(address: &str) -> Vec<u8> {
+        SdkAccountAddress::from_str(address)
+            .unwrap()
+            .get_canonical_address()
+            .0
+            .to_vec()
+    }
+
+    fn transfer(success: bool) -> TransactionAddedItem {
+        TransactionAddedItem {
+            transaction: Arc::new(Transaction {
+                index: 7,
+                block_height: 42,
+                hash: "aa".repeat(32),
+                ccd_cost: 100,
+                energy_cost: 300,
+                sender_index: Some(1),
+                sponsor_index: None,
+                tx_type: DbTransactionType::Account,
+                type_account: Some(AccountTransactionType::SimpleTransfer),
+                type_credential_deployment: None,
+                type_update: None,
+                success,
+                events: None,
+                reject: None,
+            }),
+            sender: Some(canonical(SENDER)),
+            affected_accounts: vec![canonical(SENDER), canonical(RECEIVER)],
+            contracts: vec![ContractAddress {
+                index: UnsignedLong(5),
+                sub_index: UnsignedLong(0),
+            }],
+        }
+    }
+
+    fn matches(filter: TransactionAddedFilter, item: &TransactionAddedItem) -> bool {
+        filter.prepare().unwrap().matches(item)
+    }
+
+    #[test]
+    fn test_empty_filter_matches_every_transaction() {
+        assert!(matches(TransactionAddedFilter::default(), &transfer(true)));
+        assert!(matches(TransactionAddedFilter::default(), &transfer(false)));
+    }
+
+    #[test]
+    fn test_filter_matches_aliases_of_accounts() {
+        let alias = SdkAccountAddress::from_str(SENDER)
+            .unwrap()
+            .get_alias(1)
+            .unwrap()
+            .to_string();
+        let item = transfer(true);
+        assert!(matches(
+            TransactionAddedFilter {
+                sender: Some(alias.clone()),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(matches(
+            TransactionAddedFilter {
+                affected_account: Some(alias),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(!matches(
+            TransactionAddedFilter {
+                sender: Some(RECEIVER.to_string()),
+                ..Default::default()
+            },
+            &item
+        ));
+    }
+
+    #[test]
+    fn test_filter_requires_every_field_to_match() {
+        let item = transfer(false);
+        assert!(matches(
+            TransactionAddedFilter {
+                account_transaction_type: Some(AccountTransactionType::SimpleTransfer),
+                success: Some(false),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(!matches(
+            TransactionAddedFilter {
+                account_transaction_type: Some(AccountTransactionType::SimpleTransfer),
+                success: Some(true),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(!matches(
+            TransactionAddedFilter {
+                update_transaction_type: Some(UpdateTransactionType::UpdateProtocol),
+                ..Default::default()
+            },
+            &item
+        ));
+    }
+
+    #[test]
+    fn test_filter_contract_sub_index_defaults_to_zero() {
+        let item = transfer(true);
+        assert!(matches(
+            TransactionAddedFilter {
+                contract_address_index: Some(UnsignedLong(5)),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(!matches(
+            TransactionAddedFilter {
+                contract_address_index: Some(UnsignedLong(5)),
+                contract_address_sub_index: Some(UnsignedLong(1)),
+                ..Default::default()
+            },
+            &item
+        ));
+        assert!(matches!(
+            TransactionAddedFilter {
+                contract_address_sub_index: Some(UnsignedLong(0)),
+                ..Default::default()
+            }
+            .prepare(),
+            Err(ApiError::ContractAddressSubIndexWithoutIndex)
+        ));
+    }
+
+    #[test]
+    fn test_filter_rejects_invalid_account_address() {
+        assert!(matches!(
+            TransactionAddedFilter {
+                sender: Some("not an address".to_string()),
+                ..Default::default()
+            }
+            .prepare(),
+            Err(ApiError::InvalidAccountAddress(_))
+        ));
+    }
+}
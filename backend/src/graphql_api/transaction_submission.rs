@@ -0,0 +1,660 @@
+//! Module containing the submission of transactions to a Concordium node
+//! through the API, and the tracking of the status of submitted transactions
+//! until they are indexed.
+
+use super::{get_pool, transaction::Transaction, ApiError, ApiResult, InternalError};
+use crate::scalar_types::{DateTime, TransactionHash};
+use anyhow::Context as _;
+use async_graphql::{ComplexObject, Context, Enum, Object, SimpleObject};
+use chrono::{TimeDelta, Utc};
+use concordium_rust_sdk::{
+    common::from_bytes,
+    types::{
+        hashes,
+        transactions::{BlockItem, EncodedPayload},
+        TransactionStatus,
+    },
+    v2,
+};
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family},
+    registry::Registry,
+};
+use sqlx::PgPool;
+use std::{io::Cursor, str::FromStr, sync::Arc, time::Duration};
+use tokio::sync::OnceCell;
+use tracing::warn;
+
+/// Configuration of the node receiving transactions submitted through the API.
+#[derive(Debug, clap::Args)]
+pub struct TransactionSubmissionConfig {
+    /// gRPC interface of the node receiving transactions submitted through the
+    /// API. Submission of transactions is disabled when not provided.
+    #[arg(long, env = "CCDSCAN_API_SUBMISSION_GRPC_ENDPOINT")]
+    submission_node: Option<v2::Endpoint>,
+    /// Request timeout in seconds when awaiting response from the node.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_SUBMISSION_NODE_REQUEST_TIMEOUT_SECS",
+        default_value_t = 30
+    )]
+    submission_node_request_timeout_secs: u64,
+    /// Connection timeout in seconds when connecting to the node.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_SUBMISSION_NODE_CONNECT_TIMEOUT_SECS",
+        default_value_t = 10
+    )]
+    submission_node_connect_timeout_secs: u64,
+    /// The number of hours a submission is tracked, after which the
+    /// transaction is either indexed or expired. Submissions beyond this are
+    /// removed from the database.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_SUBMISSION_RETENTION_HOURS",
+        default_value_t = 24
+    )]
+    submission_retention_hours: u32,
+}
+
+/// Type representing the Prometheus labels used for counting transactions
+/// submitted to the node.
+#[derive(Debug, Clone, EncodeLabelSet, PartialEq, Eq, Hash)]
+struct SubmittedLabels {
+    /// Whether the transaction was accepted by the node.
+    accepted: bool,
+}
+
+/// Node receiving the submitted transactions, implemented by the gRPC client
+/// of a Concordium node and mocked in tests.
+#[tonic::async_trait]
+trait SubmissionNode: std::fmt::Debug + Send + Sync {
+    /// Send the block item to the node, returning the hash of the transaction
+    /// when accepted.
+    async fn send_block_item(
+        &self,
+        block_item: &BlockItem<EncodedPayload>,
+    ) -> ApiResult<hashes::TransactionHash>;
+
+    /// Query the status of the transaction, `None` when unknown to the node.
+    async fn block_item_status(
+        &self,
+        hash: &hashes::TransactionHash,
+    ) -> ApiResult<Option<TransactionStatus>>;
+}
+
+/// Concordium node reached using the gRPC API.
+#[derive(Debug)]
+struct GrpcNode {
+    endpoint: v2::Endpoint,
+    /// Client connected to the node, established on first use such that the
+    /// API starts while the node is unavailable.
+    client: OnceCell<v2::Client>,
+}
+
+impl GrpcNode {
+    /// Get the client for the node, connecting if not connected already.
+    async fn client(&self) -> ApiResult<v2::Client> {
+        let client = self
+            .client
+            .get_or_try_init(|| v2::Client::new(self.endpoint.clone()))
+            .await
+            .map_err(|err| ApiError::Unavailable(format!("Failed to connect to node: {}", err)))?;
+        Ok(client.clone())
+    }
+}
+
+#[tonic::async_trait]
+impl SubmissionNode for GrpcNode {
+    async fn send_block_item(
+        &self,
+        block_item: &BlockItem<EncodedPayload>,
+    ) -> ApiResult<hashes::TransactionHash> {
+        self.client()
+            .await?
+            .send_block_item(block_item)
+            .await
+            .map_err(submission_error)
+    }
+
+    async fn block_item_status(
+        &self,
+        hash: &hashes::TransactionHash,
+    ) -> ApiResult<Option<TransactionStatus>> {
+        match self.client().await?.get_block_item_status(hash).await {
+            Ok(status) => Ok(Some(status)),
+            Err(v2::QueryError::NotFound) => Ok(None),
+            Err(err) => Err(ApiError::Unavailable(format!(
+                "Failed to query the status of transaction: {}",
+                err
+            ))),
+        }
+    }
+}
+
+/// Map the error of sending a block item to the node, where invalid arguments
+/// are the node rejecting the transaction.
+fn submission_error(err: v2::RPCError) -> ApiError {
+    match err {
+        v2::RPCError::CallError(status) if status.code() == tonic::Code::InvalidArgument => {
+            ApiError::TransactionRejected(status.message().to_string())
+        }
+        err => ApiError::Unavailable(format!("Failed to submit transaction: {}", err)),
+    }
+}
+
+/// Submits transactions to the node and tracks their status.
+#[derive(Debug, Clone)]
+pub struct TransactionSubmitter {
+    /// The node receiving the transactions, `None` when submission is
+    /// disabled.
+    node: Option<Arc<dyn SubmissionNode>>,
+    /// Time a submission is tracked before being removed.
+    retention: TimeDelta,
+    /// Metric counting transactions submitted to the node.
+    submitted: Family<SubmittedLabels, Counter>,
+}
+
+impl TransactionSubmitter {
+    pub fn new(
+        config: &TransactionSubmissionConfig,
+        registry: &mut Registry,
+    ) -> anyhow::Result<Self> {
+        let endpoint = config
+            .submission_node
+            .clone()
+            .map(|mut endpoint| {
+                // Enable TLS when using HTTPS
+                if endpoint
+                    .uri()
+                    .scheme()
+                    .is_some_and(|x| x == &concordium_rust_sdk::v2::Scheme::HTTPS)
+                {
+                    endpoint = endpoint
+                        .tls_config(tonic::transport::ClientTlsConfig::new())
+                        .context("Unable to construct TLS configuration for the Concordium node.")?
+                }
+                Ok::<_, anyhow::Error>(
+                    endpoint
+                        .timeout(Duration::from_secs(
+                            config.submission_node_request_timeout_secs,
+                        ))
+                        .connect_timeout(Duration::from_secs(
+                            config.submission_node_connect_timeout_secs,
+                        )),
+                )
+            })
+            .transpose()?;
+        let node = endpoint.map(|endpoint| {
+            Arc::new(GrpcNode {
+                endpoint,
+                client: OnceCell::new(),
+            }) as Arc<dyn SubmissionNode>
+        });
+        Ok(Self::with_node(
+            node,
+            TimeDelta::hours(config.submission_retention_hours.into()),
+            registry,
+        ))
+    }
+
+    fn with_node(
+        node: Option<Arc<dyn SubmissionNode>>,
+        retention: TimeDelta,
+        registry: &mut Registry,
+    ) -> Self {
+        let submitted: Family<SubmittedLabels, Counter> = Default::default();
+        registry.register(
+            "submitted_transactions",
+            "Total number of transactions submitted to the node through the API",
+            submitted.clone(),
+        );
+        Self {
+            node,
+            retention,
+            submitted,
+        }
+    }
+
+    /// Get the node receiving the transactions.
+    fn node(&self) -> ApiResult<&dyn SubmissionNode> {
+        self.node
+            .as_deref()
+            .ok_or(ApiError::TransactionSubmissionDisabled)
+    }
+
+    /// Submit the serialized block item to the node and record the
+    /// submission.
+    pub async fn submit(&self, pool: &PgPool, bytes: &[u8]) -> ApiResult<TransactionSubmission> {
+        let block_item = parse_block_item(bytes)?;
+        self.submit_block_item(pool, &block_item).await
+    }
+
+    /// Submit the block item to the node and record the submission, removing
+    /// the submissions beyond the retention.
+    async fn submit_block_item(
+        &self,
+        pool: &PgPool,
+        block_item: &BlockItem<EncodedPayload>,
+    ) -> ApiResult<TransactionSubmission> {
+        let result = self.node()?.send_block_item(block_item).await;
+        self.submitted
+            .get_or_create(&SubmittedLabels {
+                accepted: result.is_ok(),
+            })
+            .inc();
+        let transaction_hash = result?.to_string();
+        let mut tx = pool.begin().await?;
+        // Keep the time of the first submission when resubmitted.
+        let submitted_at = sqlx::query_scalar!(
+            "INSERT INTO transaction_submissions (hash)
+            VALUES ($1)
+            ON CONFLICT (hash) DO UPDATE SET hash = EXCLUDED.hash
+            RETURNING submitted_at",
+            transaction_hash
+        )
+        .fetch_one(tx.as_mut())
+        .await?;
+        sqlx::query!(
+            "DELETE FROM transaction_submissions WHERE submitted_at < $1",
+            Utc::now() - self.retention
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        Ok(TransactionSubmission {
+            transaction_hash,
+            status: TransactionSubmissionStatus::Submitted,
+            submitted_at: Some(submitted_at),
+        })
+    }
+
+    /// Get the status of a transaction submitted through the API or indexed,
+    /// `None` for other transactions.
+    pub async fn status(
+        &self,
+        pool: &PgPool,
+        transaction_hash: TransactionHash,
+    ) -> ApiResult<Option<TransactionSubmission>> {
+        let row = sqlx::query!(
+            r#"SELECT
+                (SELECT submitted_at FROM transaction_submissions WHERE hash = $1) as submitted_at,
+                EXISTS(SELECT FROM transactions WHERE hash = $1) as "indexed!""#,
+            transaction_hash
+        )
+        .fetch_one(pool)
+        .await?;
+        let status = if row.indexed {
+            TransactionSubmissionStatus::Indexed
+        } else if row.submitted_at.is_some() {
+            self.node_status(&transaction_hash).await?
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(TransactionSubmission {
+            transaction_hash,
+            status,
+            submitted_at: row.submitted_at,
+        }))
+    }
+
+    /// Query the status of a transaction not yet indexed from the node.
+    /// Falls back to `Submitted` when the node does not know the transaction
+    /// or cannot be reached.
+    async fn node_status(&self, transaction_hash: &str) -> ApiResult<TransactionSubmissionStatus> {
+        let hash = hashes::TransactionHash::from_str(transaction_hash)
+            .map_err(|_| ApiError::InvalidTransactionHash(transaction_hash.to_string()))?;
+        let Ok(node) = self.node() else {
+            return Ok(TransactionSubmissionStatus::Submitted);
+        };
+        match node.block_item_status(&hash).await {
+            Ok(Some(status)) => Ok(TransactionSubmissionStatus::from(&status)),
+            Ok(None) => Ok(TransactionSubmissionStatus::Submitted),
+            Err(err) => {
+                warn!(
+                    "Failed to query the status of transaction {}: {}",
+                    hash, err
+                );
+                Ok(TransactionSubmissionStatus::Submitted)
+            }
+        }
+    }
+}
+
+/// Parse the serialization of a block item, rejecting trailing bytes.
+fn parse_block_item(bytes: &[u8]) -> ApiResult<BlockItem<EncodedPayload>> {
+    let mut cursor = Cursor::new(bytes);
+    let block_item: BlockItem<EncodedPayload> =
+        from_bytes(&mut cursor).map_err(|err| ApiError::InvalidTransaction(err.to_string()))?;
+    if cursor.position() != bytes.len() as u64 {
+        return Err(ApiError::InvalidTransaction(
+            "Unexpected bytes after the block item".to_string(),
+        ));
+    }
+    Ok(block_item)
+}
+
+/// Get the transaction submitter from the context.
+fn get_transaction_submitter<'a>(ctx: &Context<'a>) -> ApiResult<&'a TransactionSubmitter> {
+    ctx.data::<TransactionSubmitter>()
+        .map_err(|err| InternalError::NoTransactionSubmitter(err).into())
+}
+
+#[derive(Default)]
+pub(crate) struct QueryTransactionSubmission;
+
+#[Object]
+impl QueryTransactionSubmission {
+    /// Status of a transaction submitted through the API, until indexed.
+    /// `null` when the transaction was neither submitted through the API nor
+    /// indexed.
+    async fn transaction_submission(
+        &self,
+        ctx: &Context<'_>,
+        transaction_hash: TransactionHash,
+    ) -> ApiResult<Option<TransactionSubmission>> {
+        get_transaction_submitter(ctx)?
+            .status(get_pool(ctx)?, transaction_hash)
+            .await
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MutationTransactionSubmission;
+
+#[Object]
+impl MutationTransactionSubmission {
+    /// Submit a transaction to the node, tracking its status using the
+    /// `transactionSubmission` query.
+    async fn submit_transaction(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(desc = "The hex encoded serialization of the block item.")] transaction: String,
+    ) -> ApiResult<TransactionSubmission> {
+        let bytes = hex::decode(&transaction)
+            .map_err(|err| ApiError::InvalidTransaction(err.to_string()))?;
+        get_transaction_submitter(ctx)?
+            .submit(get_pool(ctx)?, &bytes)
+            .await
+    }
+}
+
+/// Transaction submitted through the API and its status.
+#[derive(SimpleObject, serde::Serialize)]
+#[graphql(complex)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionSubmission {
+    pub transaction_hash: TransactionHash,
+    pub status: TransactionSubmissionStatus,
+    /// Time of the first submission through the API, `null` when indexed
+    /// without being submitted through the API.
+    pub submitted_at: Option<DateTime>,
+}
+
+#[ComplexObject]
+impl TransactionSubmission {
+    /// The indexed transaction, `null` until indexed.
+    async fn transaction(&self, ctx: &Context<'_>) -> ApiResult<Option<Transaction>> {
+        if self.status != TransactionSubmissionStatus::Indexed {
+            return Ok(None);
+        }
+        Transaction::query_by_hash(get_pool(ctx)?, self.transaction_hash.clone()).await
+    }
+}
+
+/// Status of a transaction submitted through the API.
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub enum TransactionSubmissionStatus {
+    /// Accepted by the node when submitted, but currently unknown to the node.
+    Submitted,
+    /// Received by the node, but not in any block.
+    Received,
+    /// In one or more blocks, none of which are finalized.
+    Committed,
+    /// In a finalized block, but not indexed yet.
+    Finalized,
+    /// Indexed and available using the `transactionByTransactionHash` query.
+    Indexed,
+}
+
+impl From<&TransactionStatus> for TransactionSubmissionStatus {
+    fn from(status: &TransactionStatus) -> Self {
+        match status {
+            TransactionStatus::Received => TransactionSubmissionStatus::Received,
+            TransactionStatus::Committed(_) => TransactionSubmissionStatus::Committed,
+            TransactionStatus::Finalized(_) => TransactionSubmissionStatus::Finalized,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_block_item, submission_error, SubmissionNode, TransactionSubmissionStatus,
+        TransactionSubmitter,
+    };
+    use crate::{
+        graphql_api::{ApiError, ApiResult},
+        test_database,
+    };
+    use chrono::{TimeDelta, Utc};
+    use concordium_rust_sdk::{
+        common::types::{Amount, TransactionSignature, TransactionTime},
+        id::types::AccountAddress,
+        types::{
+            hashes,
+            transactions::{construct, AccountTransaction, BlockItem, EncodedPayload},
+            Nonce, TransactionStatus,
+        },
+        v2,
+    };
+    use prometheus_client::registry::Registry;
+    use sqlx::PgPool;
+    use std::{collections::BTreeMap, sync::Arc};
+
+    /// Node accepting every transaction unless rejecting, and responding with
+    /// the status when queried.
+    #[derive(Debug, Default)]
+    struct MockNode {
+        rejection: Option<String>,
+        status: Option<TransactionStatus>,
+        unavailable: bool,
+    }
+
+    #[tonic::async_trait]
+    impl SubmissionNode for MockNode {
+        async fn send_block_item(
+            &self,
+            block_item: &BlockItem<EncodedPayload>,
+        ) -> ApiResult<hashes::TransactionHash> {
+            match &self.rejection {
+                Some(reason) => Err(ApiError::TransactionRejected(reason.clone())),
+                None => Ok(block_item.hash()),
+            }
+        }
+
+        async fn block_item_status(
+            &self,
+            _hash: &hashes::TransactionHash,
+        ) -> ApiResult<Option<TransactionStatus>> {
+            if self.unavailable {
+                return Err(ApiError::Unavailable("Node is down".to_string()));
+            }
+            Ok(self.status.clone())
+        }
+    }
+
+    fn submitter(node: Option<MockNode>) -> TransactionSubmitter {
+        TransactionSubmitter::with_node(
+            node.map(|node| Arc::new(node) as Arc<dyn SubmissionNode>),
+            TimeDelta::hours(24),
+            &mut Registry::default(),
+        )
+    }
+
+    fn block_item() -> BlockItem<EncodedPayload> {
+        let address = AccountAddress([1; 32]);
+        let transfer = construct::transfer(
+            1,
+            address,
+            Nonce::from(1),
+            TransactionTime::from_seconds(1_700_000_000),
+            address,
+            Amount::from_micro_ccd(1),
+        );
+        BlockItem::AccountTransaction(AccountTransaction {
+            signature: TransactionSignature {
+                signatures: BTreeMap::new(),
+            },
+            header: transfer.header,
+            payload: transfer.encoded,
+        })
+    }
+
+    /// Pool for tests failing before using the database.
+    fn unused_pool() -> PgPool {
+        PgPool::connect_lazy("postgres://localhost/unused").unwrap()
+    }
+
+    #[test]
+    fn test_parse_block_item_rejects_invalid_bytes() {
+        assert!(matches!(
+            parse_block_item(&[]),
+            Err(ApiError::InvalidTransaction(_))
+        ));
+        // Unknown block item kind.
+        assert!(matches!(
+            parse_block_item(&[42, 0, 0, 0]),
+            Err(ApiError::InvalidTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_status_from_node() {
+        assert_eq!(
+            TransactionSubmissionStatus::from(&TransactionStatus::Received),
+            TransactionSubmissionStatus::Received
+        );
+        assert_eq!(
+            TransactionSubmissionStatus::from(&TransactionStatus::Committed(BTreeMap::new())),
+            TransactionSubmissionStatus::Committed
+        );
+        assert_eq!(
+            TransactionSubmissionStatus::from(&TransactionStatus::Finalized(BTreeMap::new())),
+            TransactionSubmissionStatus::Finalized
+        );
+    }
+
+    #[test]
+    fn test_invalid_argument_is_rejection_by_node() {
+        assert!(matches!(
+            submission_error(v2::RPCError::CallError(tonic::Status::invalid_argument(
+                "nonce too small"
+            ))),
+            ApiError::TransactionRejected(reason) if reason == "nonce too small"
+        ));
+        assert!(matches!(
+            submission_error(v2::RPCError::CallError(tonic::Status::unavailable(
+                "shutting down"
+            ))),
+            ApiError::Unavailable(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_submission_rejected_by_node() {
+        let submitter = submitter(Some(MockNode {
+            rejection: Some("nonce too small".to_string()),
+            ..Default::default()
+        }));
+        let result = submitter
+            .submit_block_item(&unused_pool(), &block_item())
+            .await;
+        assert!(matches!(result, Err(ApiError::TransactionRejected(_))));
+    }
+
+    #[tokio::test]
+    async fn test_submission_disabled() {
+        let result = submitter(None)
+            .submit_block_item(&unused_pool(), &block_item())
+            .await;
+        assert!(matches!(
+            result,
+            Err(ApiError::TransactionSubmissionDisabled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_status_lookup_at_node() {
+        let hash = block_item().hash().to_string();
+        let status = |node| {
+            let hash = hash.clone();
+            async move { submitter(node).node_status(&hash).await.unwrap() }
+        };
+        assert_eq!(
+            status(Some(MockNode {
+                status: Some(TransactionStatus::Finalized(BTreeMap::new())),
+                ..Default::default()
+            }))
+            .await,
+            TransactionSubmissionStatus::Finalized
+        );
+        // Unknown to the node.
+        assert_eq!(
+            status(Some(MockNode::default())).await,
+            TransactionSubmissionStatus::Submitted
+        );
+        assert_eq!(
+            status(Some(MockNode {
+                unavailable: true,
+                ..Default::default()
+            }))
+            .await,
+            TransactionSubmissionStatus::Submitted
+        );
+        assert_eq!(status(None).await, TransactionSubmissionStatus::Submitted);
+    }
+
+    #[tokio::test]
+    async fn test_submission_is_recorded_and_expired_submissions_removed() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let expired_hash = "00".repeat(32);
+        sqlx::query("INSERT INTO transaction_submissions (hash, submitted_at) VALUES ($1, $2)")
+            .bind(&expired_hash)
+            .bind(Utc::now() - TimeDelta::hours(25))
+            .execute(&pool)
+            .await
+            .unwrap();
+        let submitter = submitter(Some(MockNode {
+            status: Some(TransactionStatus::Received),
+            ..Default::default()
+        }));
+
+        let submission = submitter
+            .submit_block_item(&pool, &block_item())
+            .await
+            .unwrap();
+        assert_eq!(submission.status, TransactionSubmissionStatus::Submitted);
+        let status = submitter
+            .status(&pool, submission.transaction_hash.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.status, TransactionSubmissionStatus::Received);
+        assert_eq!(status.submitted_at, submission.submitted_at);
+        assert!(submitter
+            .status(&pool, expired_hash)
+            .await
+            .unwrap()
+            .is_none());
+
+        sqlx::query("DELETE FROM transaction_submissions WHERE hash = $1")
+            .bind(&submission.transaction_hash)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+}
@@ -2,24 +2,40 @@
 //! the blocks preprocessing each concurrently  and one task for processing each
 //! block sequentially.
 
+use crate::indexing_profile::{self, IndexingDomain, IndexingProfile};
 use anyhow::Context;
 use concordium_rust_sdk::{indexer::TraverseConfig, types as sdk_types, v2};
-use futures::StreamExt;
+use futures::{StreamExt, TryFutureExt};
 use prometheus_client::registry::Registry;
 use sqlx::{postgres::PgConnectOptions, PgConnection};
-use std::time::Duration;
+use std::{path::PathBuf, time::Duration};
 use tokio_util::sync::CancellationToken;
 use tracing::info;
 
 mod block;
+mod block_archive;
 mod block_preprocessor;
 mod block_processor;
 mod db;
 mod ensure_affected_rows;
+mod event_sink;
 mod genesis_data;
+mod metrics_rollup;
+mod node_queries;
+mod quarantine;
+mod reindex;
+mod standby;
 mod statistics;
+mod tentative_head;
+mod verify;
 
+pub use block_archive::{run_archive, ArchiveArgs};
 pub use db::lock::acquire_indexer_lock;
+pub use event_sink::EventSinkConfig;
+pub use quarantine::run_reprocess_quarantine;
+pub use reindex::{run_reindex, ReindexArgs, ReindexDomain};
+pub use standby::wait_for_indexer_lock;
+pub use verify::{run_verify, VerifyArgs, VerifyCheck};
 
 #[derive(clap::Args)]
 pub struct IndexerServiceConfig {
@@ -86,6 +102,78 @@ pub struct IndexerServiceConfig {
         default_value = "10"
     )]
     pub max_successive_failures: u32,
+    /// Index the blocks on the live branch of the chain before they are
+    /// finalized into separate tables, rolling them back when the branch
+    /// changes.
+    #[arg(long, env = "CCDSCAN_INDEXER_CONFIG_TENTATIVE_HEAD")]
+    pub tentative_head: bool,
+    /// Ingest the blocks from the block archive in the provided directory,
+    /// written by the `archive` subcommand, instead of traversing the
+    /// Concordium nodes. The indexer stops at the end of the archive.
+    #[arg(long, env = "CCDSCAN_INDEXER_CONFIG_BLOCK_ARCHIVE")]
+    pub block_archive: Option<PathBuf>,
+    /// Stop the indexer before saving a block containing block items or
+    /// special transaction outcomes which cannot be parsed, instead of
+    /// quarantining them.
+    #[arg(long, env = "CCDSCAN_INDEXER_CONFIG_HALT_ON_UNKNOWN")]
+    pub halt_on_unknown: bool,
+    /// Continue indexing after a block containing quarantined items, instead
+    /// of stopping once the block is saved. Later blocks are then indexed
+    /// without the effects of the quarantined items, which can no longer be
+    /// re-processed.
+    #[arg(long, env = "CCDSCAN_INDEXER_CONFIG_CONTINUE_PAST_QUARANTINE")]
+    pub continue_past_quarantine: bool,
+    /// Run as a hot standby for another instance of the indexer: wait for the
+    /// indexer lock without any timeout and take over indexing once the
+    /// other instance stops.
+    #[arg(long, env = "CCDSCAN_INDEXER_CONFIG_STANDBY")]
+    pub standby: bool,
+    /// Interval in seconds between the queries checking the availability of
+    /// the nodes while standing by.
+    #[arg(
+        long,
+        env = "CCDSCAN_INDEXER_CONFIG_STANDBY_NODE_CHECK_INTERVAL",
+        default_value = "30"
+    )]
+    pub standby_node_check_interval: u64,
+    /// Additional outputs receiving the content of every indexed block, given
+    /// as `<name>=jsonl:<path>` for appending newline-delimited JSON to a file
+    /// or `<name>=queue:<url>` for posting it to the HTTP ingestion endpoint of
+    /// a message queue. The name identifies the persisted cursor of the event
+    /// sink.
+    #[arg(
+        long = "event-sink",
+        env = "CCDSCAN_INDEXER_CONFIG_EVENT_SINKS",
+        value_delimiter = ','
+    )]
+    pub event_sinks: Vec<EventSinkConfig>,
+    /// Profile selecting the domains of derived data to index, allowing
+    /// lightweight deployments to skip smart contract events, CIS-2 tokens,
+    /// protocol-level tokens, payday pool data and metrics.
+    #[arg(
+        long,
+        env = "CCDSCAN_INDEXER_CONFIG_INDEXING_PROFILE",
+        value_enum,
+        default_value_t = IndexingProfile::Full
+    )]
+    pub indexing_profile: IndexingProfile,
+    /// Number of days for which the per-block rows of the metrics tables are
+    /// kept, before being removed once rolled up into hourly and daily
+    /// buckets. Must be at least 2 days.
+    #[arg(
+        long,
+        env = "CCDSCAN_INDEXER_CONFIG_METRICS_RETENTION_DAYS",
+        default_value = "90"
+    )]
+    pub metrics_retention_days: u32,
+    /// Interval in seconds between rolling up the per-block rows of the
+    /// metrics tables.
+    #[arg(
+        long,
+        env = "CCDSCAN_INDEXER_CONFIG_METRICS_ROLLUP_INTERVAL",
+        default_value = "600"
+    )]
+    pub metrics_rollup_interval: u64,
 }
 
 /// Service traversing each block of the chain, indexing it into a database.
@@ -105,6 +193,17 @@ pub struct IndexerService {
     /// State tracked by the block processor, which is submitting to the
     /// database.
     block_processor: block_processor::BlockProcessor,
+    /// Indexer of the blocks which are not yet finalized, when enabled.
+    tentative_head: Option<tentative_head::TentativeHeadIndexer>,
+    /// Block archive to ingest the blocks from instead of the nodes, when
+    /// provided.
+    block_archive: Option<block_archive::BlockArchive>,
+    /// Dispatchers delivering the indexed blocks to each of the configured
+    /// event sinks.
+    event_sink_dispatchers: Vec<event_sink::EventSinkDispatcher>,
+    /// Task rolling up and removing the per-block rows of the metrics tables,
+    /// unless the metrics are skipped by the indexing profile.
+    metrics_rollup: Option<metrics_rollup::MetricsRollup>,
     config: IndexerServiceConfig,
 }
 
@@ -119,6 +218,14 @@ impl IndexerService {
     ) -> anyhow::Result<Self> {
         let database_indexer_lock_timeout =
             Duration::from_secs(config.database_indexer_lock_timeout);
+        let block_archive = config
+            .block_archive
+            .clone()
+            .map(block_archive::BlockArchive::new);
+        anyhow::ensure!(
+            block_archive.is_none() || !config.tentative_head,
+            "The tentative head cannot be indexed while ingesting a block archive"
+        );
         let last_height_stored = sqlx::query!(
             "
                 SELECT height
@@ -132,9 +239,18 @@ impl IndexerService {
         .map(|r| r.height);
 
         let start_height = if let Some(height) = last_height_stored {
+            if !config.continue_past_quarantine {
+                quarantine::ensure_not_quarantined(db_connection.as_mut(), height).await?;
+            }
             u64::try_from(height)? + 1
         } else {
-            genesis_data::save_genesis_data(endpoints[0].clone(), db_connection.as_mut())
+            let genesis_data = if let Some(block_archive) = &block_archive {
+                block_archive.read_genesis()?
+            } else {
+                genesis_data::GenesisData::fetch(endpoints[0].clone()).await?
+            };
+            genesis_data
+                .save(db_connection.as_mut())
                 .await
                 .context("Failed initializing the database with the genesis block")?;
             1
@@ -145,17 +261,57 @@ impl IndexerService {
                 .await?
                 .hash
                 .parse()?;
+        if let Some(block_archive) = &block_archive {
+            anyhow::ensure!(
+                block_archive.read_genesis()?.block_hash == genesis_block_hash,
+                "The block archive is of another network than the database"
+            );
+        }
+        let domains = config.indexing_profile.domains();
+        indexing_profile::record_indexed_domains(
+            &mut db_connection,
+            domains,
+            start_height.try_into()?,
+        )
+        .await?;
 
         let block_pre_processor = block_preprocessor::BlockPreProcessor::new(
             genesis_block_hash,
             config.max_successive_failures.into(),
+            domains,
             registry.sub_registry_with_prefix("preprocessor"),
         );
+        let tentative_head = config.tentative_head.then(|| {
+            tentative_head::TentativeHeadIndexer::new(
+                endpoints.clone(),
+                db_connect_options.clone(),
+                registry.sub_registry_with_prefix("tentative_head"),
+            )
+        });
+        let event_sink_dispatchers = event_sink::EventSinkDispatcher::new_all(
+            &config.event_sinks,
+            &db_connect_options,
+            registry.sub_registry_with_prefix("event_sink"),
+        )?;
+        let metrics_rollup = domains
+            .contains(IndexingDomain::Metrics)
+            .then(|| {
+                metrics_rollup::MetricsRollup::new(
+                    db_connect_options.clone(),
+                    config.metrics_retention_days,
+                    Duration::from_secs(config.metrics_rollup_interval),
+                    registry.sub_registry_with_prefix("metrics_rollup"),
+                )
+            })
+            .transpose()?;
         let block_processor = block_processor::BlockProcessor::new(
             db_connect_options,
             db_connection,
             database_indexer_lock_timeout,
             config.max_successive_failures,
+            config.halt_on_unknown,
+            config.continue_past_quarantine,
+            !event_sink_dispatchers.is_empty(),
             registry.sub_registry_with_prefix("processor"),
         )
         .await?;
@@ -165,6 +321,10 @@ impl IndexerService {
             start_height,
             block_pre_processor,
             block_processor,
+            tentative_head,
+            block_archive,
+            event_sink_dispatchers,
+            metrics_rollup,
             config,
         })
     }
@@ -172,18 +332,46 @@ impl IndexerService {
     /// Run the service. This future will only stop when signaled by the
     /// `cancel_token`.
     pub async fn run(self, cancel_token: CancellationToken) -> anyhow::Result<()> {
-        let traverse_config = TraverseConfig::new(self.endpoints, self.start_height.into())
-            .context("Failed setting up TraverseConfig")?
-            .set_max_parallel(self.config.max_parallel_block_preprocessors)
-            .set_max_behind(std::time::Duration::from_secs(self.config.node_max_behind));
+        let tentative_head_stop_signal = cancel_token.child_token();
+        let event_sinks_stop_signal = cancel_token.child_token();
+        let metrics_rollup_stop_signal = cancel_token.child_token();
         let processor_config = concordium_rust_sdk::indexer::ProcessorConfig::new()
             .set_stop_signal(cancel_token.cancelled_owned());
 
         let (sender, receiver) = tokio::sync::mpsc::channel(self.config.max_processing_batch);
         let receiver = tokio_stream::wrappers::ReceiverStream::from(receiver)
             .ready_chunks(self.config.max_processing_batch);
-        let traverse_future =
-            tokio::spawn(traverse_config.traverse(self.block_pre_processor, sender));
+        let traverse_future = if let Some(block_archive) = self.block_archive {
+            info!("Ingesting blocks from the block archive");
+            tokio::spawn(block_archive::ingest(
+                block_archive,
+                self.start_height,
+                self.config.max_parallel_block_preprocessors,
+                self.config.indexing_profile.domains(),
+                sender,
+            ))
+        } else {
+            let traverse_config = TraverseConfig::new(self.endpoints, self.start_height.into())
+                .context("Failed setting up TraverseConfig")?
+                .set_max_parallel(self.config.max_parallel_block_preprocessors)
+                .set_max_behind(std::time::Duration::from_secs(self.config.node_max_behind));
+            tokio::spawn(
+                traverse_config
+                    .traverse(self.block_pre_processor, sender)
+                    .err_into::<anyhow::Error>(),
+            )
+        };
+        let tentative_head_future = self.tentative_head.map(|tentative_head| {
+            tokio::spawn(tentative_head.run(tentative_head_stop_signal.clone()))
+        });
+        let event_sink_futures: Vec<_> = self
+            .event_sink_dispatchers
+            .into_iter()
+            .map(|dispatcher| tokio::spawn(dispatcher.run(event_sinks_stop_signal.clone())))
+            .collect();
+        let metrics_rollup_future = self.metrics_rollup.map(|metrics_rollup| {
+            tokio::spawn(metrics_rollup.run(metrics_rollup_stop_signal.clone()))
+        });
         let process_future =
             tokio::spawn(processor_config.process_event_stream(self.block_processor, receiver));
         info!("Indexing from block height {}", self.start_height);
@@ -191,6 +379,20 @@ impl IndexerService {
         // wait for the other which then eventually will stop gracefully as either end
         // of their channel will get dropped.
         let (traverse_result, process_result) = futures::join!(traverse_future, process_future);
+        // Stop indexing the tentative head together with the indexing of finalized blocks.
+        tentative_head_stop_signal.cancel();
+        if let Some(tentative_head_future) = tentative_head_future {
+            tentative_head_future.await??;
+        }
+        // Blocks left in the outbox are delivered once the indexer runs again.
+        event_sinks_stop_signal.cancel();
+        for event_sink_future in event_sink_futures {
+            event_sink_future.await?;
+        }
+        metrics_rollup_stop_signal.cancel();
+        if let Some(metrics_rollup_future) = metrics_rollup_future {
+            metrics_rollup_future.await?;
+        }
         process_result?;
         Ok(traverse_result??)
     }
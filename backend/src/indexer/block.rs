@@ -1,12 +1,16 @@
 //! This module contains the block information computed during the concurrent
 //! preprocessing and the logic for how to do the sequential processing.
-use crate::indexer::{
-    block_preprocessor::BlockData, block_processor::BlockProcessingContext, statistics::Statistics,
+use crate::{
+    indexer::{
+        block_preprocessor::BlockData, block_processor::BlockProcessingContext,
+        event_sink::SinkBlock, node_queries::NodeQueries, quarantine::PreparedQuarantinedItems,
+        reindex::ReindexContext, statistics::Statistics,
+    },
+    indexing_profile::{IndexingDomain, IndexingDomains},
 };
 use anyhow::Context;
 use block_item::PreparedBlockItem;
 use chrono::{DateTime, Utc};
-use concordium_rust_sdk::v2;
 use protocol_update_migration::ProtocolUpdateMigration;
 use special_transaction_outcomes::{
     validator_suspension::PreparedUnmarkPrimedForSuspension, PreparedSpecialTransactionOutcomes,
@@ -18,7 +22,8 @@ pub mod protocol_update_migration;
 pub mod special_transaction_outcomes;
 
 /// Represents the latest staking information for all validators
-#[derive(Clone)]
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ValidatorStakingInformation {
     /// The validator ids
     pub ids: Vec<i64>,
@@ -59,10 +64,18 @@ pub struct PreparedBlock {
     protocol_update_migration: Option<ProtocolUpdateMigration>,
     /// Validator staking information to be updated in the database
     validator_staking_information: ValidatorStakingInformation,
+    /// Block items and special transaction outcomes which could not be parsed.
+    pub quarantined_items: PreparedQuarantinedItems,
+    /// The domains of derived data indexed for the block.
+    domains: IndexingDomains,
 }
 
 impl PreparedBlock {
-    pub async fn prepare(node_client: &mut v2::Client, data: &BlockData) -> anyhow::Result<Self> {
+    pub async fn prepare(
+        node_client: &mut NodeQueries,
+        data: &BlockData,
+        domains: IndexingDomains,
+    ) -> anyhow::Result<Self> {
         let height = i64::try_from(data.finalized_block_info.height.height)?;
         let hash = data.finalized_block_info.block_hash.to_string();
         let block_last_finalized = data.block_info.block_last_finalized.to_string();
@@ -83,8 +96,15 @@ impl PreparedBlock {
         let mut prepared_block_items = Vec::new();
         for (item_summary, item) in data.events.iter().zip(data.items.iter()) {
             prepared_block_items.push(
-                PreparedBlockItem::prepare(node_client, data, item_summary, item, &mut statistics)
-                    .await?,
+                PreparedBlockItem::prepare(
+                    node_client,
+                    data,
+                    item_summary,
+                    item,
+                    domains,
+                    &mut statistics,
+                )
+                .await?,
             )
         }
 
@@ -92,6 +112,8 @@ impl PreparedBlock {
             node_client,
             &data.block_info,
             &data.special_events,
+            &data.special_event_indices()?,
+            domains,
             &mut statistics,
         )
         .await?;
@@ -103,6 +125,7 @@ impl PreparedBlock {
 
         let validator_staking_information: ValidatorStakingInformation =
             data.validator_staking_information.clone();
+        let quarantined_items = PreparedQuarantinedItems::prepare(data)?;
 
         Ok(Self {
             hash,
@@ -118,6 +141,8 @@ impl PreparedBlock {
             statistics,
             protocol_update_migration,
             validator_staking_information,
+            quarantined_items,
+            domains,
         })
     }
 
@@ -152,7 +177,10 @@ impl PreparedBlock {
                     .signed_duration_since(context.last_block_slot_time)
                     .num_milliseconds(),
             );
-            context.last_cumulative_num_txs += block.prepared_block_items.len() as i64;
+            // Quarantined block items are counted, as they are indexed once re-processed.
+            context.last_cumulative_num_txs += (block.prepared_block_items.len()
+                + block.quarantined_items.block_item_count())
+                as i64;
             cumulative_num_txss.push(context.last_cumulative_num_txs);
             context.last_block_slot_time = block.slot_time;
 
@@ -285,7 +313,9 @@ impl PreparedBlock {
                 )
             })?;
         }
-        self.statistics.save(tx).await?;
+        if self.domains.contains(IndexingDomain::Metrics) {
+            self.statistics.save(tx).await?;
+        }
         self.special_transaction_outcomes.save(tx).await?;
 
         // gather vectors for the update query
@@ -315,6 +345,74 @@ impl PreparedBlock {
         }
 
         self.baker_unmark_suspended.save(tx).await?;
+        self.quarantined_items.save(tx).await?;
+        Ok(())
+    }
+
+    /// Write the content of the block to the outbox of the event sinks, must be
+    /// called after processing the content of the block. Blocks rebuilt after
+    /// being indexed are `reemitted`.
+    pub async fn save_to_event_sink_outbox(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        reemitted: bool,
+    ) -> anyhow::Result<()> {
+        let mut transactions = Vec::with_capacity(self.prepared_block_items.len());
+        let mut token_movements = Vec::new();
+        for (index, item) in self.prepared_block_items.iter().enumerate() {
+            transactions.push(
+                item.sink_transaction(tx, index, &mut token_movements)
+                    .await?,
+            );
+        }
+        let block = SinkBlock {
+            height: self.height,
+            hash: self.hash.clone(),
+            slot_time: self.slot_time,
+            baker_id: self.baker_id,
+            total_amount: self.total_amount,
+            total_staked: self.total_staked,
+            transactions,
+            special_events: self.special_transaction_outcomes.sink_special_events(),
+            token_movements,
+            reemitted,
+        };
+        sqlx::query!(
+            "INSERT INTO event_sink_outbox (block_height, payload) VALUES ($1, $2)",
+            self.height,
+            serde_json::to_value(block)?
+        )
+        .execute(tx.as_mut())
+        .await?;
         Ok(())
     }
+
+    /// Rebuild the rows of the domain being re-indexed from the content of this
+    /// block, expecting the rows previously derived from the block to be
+    /// cleared. The indices of the transactions of the block are provided in
+    /// the order of the block items.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_indices: &[i64],
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            transaction_indices.len() == self.prepared_block_items.len(),
+            "Block contains {} block items while {} transactions are indexed",
+            self.prepared_block_items.len(),
+            transaction_indices.len()
+        );
+        for (item, transaction_index) in self.prepared_block_items.iter().zip(transaction_indices) {
+            item.reindex(tx, context, *transaction_index)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed re-indexing block item with hash {} in block {}",
+                        item.block_item_hash, self.hash
+                    )
+                })?;
+        }
+        self.special_transaction_outcomes.reindex(tx, context).await
+    }
 }
@@ -4,9 +4,14 @@
 
 use crate::{
     indexer::{
-        block_preprocessor::BlockData, ensure_affected_rows::EnsureAffectedRows,
+        block_preprocessor::BlockData,
+        ensure_affected_rows::EnsureAffectedRows,
+        event_sink::{SinkTokenMovement, SinkTransaction},
+        node_queries::NodeQueries,
+        reindex::ReindexContext,
         statistics::Statistics,
     },
+    indexing_profile::{IndexingDomain, IndexingDomains},
     transaction_event,
     transaction_reject::PreparedTransactionRejectReason,
     transaction_type::{
@@ -25,7 +30,7 @@ use concordium_rust_sdk::{
         AccountTransactionDetails, AccountTransactionEffects, BlockItemSummary,
         BlockItemSummaryDetails,
     },
-    v2::{self, Upward},
+    v2::Upward,
 };
 
 mod account_creation;
@@ -79,10 +84,11 @@ pub struct PreparedBlockItem {
 
 impl PreparedBlockItem {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         item_summary: &BlockItemSummary,
         item: &BlockItem<EncodedPayload>,
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
         let block_height = i64::try_from(data.finalized_block_info.height.height)?;
@@ -199,9 +205,15 @@ impl PreparedBlockItem {
             .into_iter()
             .collect();
 
-        let prepared_event =
-            PreparedBlockItemEvent::prepare(node_client, data, item_summary, item, statistics)
-                .await?;
+        let prepared_event = PreparedBlockItemEvent::prepare(
+            node_client,
+            data,
+            item_summary,
+            item,
+            domains,
+            statistics,
+        )
+        .await?;
 
         Ok(Self {
             block_item_hash,
@@ -322,6 +334,60 @@ impl PreparedBlockItem {
             })?;
         Ok(())
     }
+
+    /// Rebuild the rows of the domain being re-indexed from this block item.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        self.prepared_event
+            .reindex(tx, context, transaction_index)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed re-indexing block item event from {:?} transaction",
+                    self.transaction_type
+                )
+            })
+    }
+
+    /// Build the transaction delivered to the event sinks, collecting its token
+    /// movements. Must be called after saving the block item, since reject
+    /// reasons of contract updates are decoded using the contract schema.
+    pub async fn sink_transaction(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        index: usize,
+        token_movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<SinkTransaction> {
+        let reject_reason = if let Some(reason) = &self.reject {
+            Some(reason.process(tx).await?)
+        } else {
+            None
+        };
+        self.prepared_event
+            .collect_token_movements(&self.block_item_hash, token_movements)?;
+        let kind = self
+            .account_type
+            .map(|kind| format!("{:?}", kind))
+            .or_else(|| self.credential_type.map(|kind| format!("{:?}", kind)))
+            .or_else(|| self.update_type.map(|kind| format!("{:?}", kind)));
+        Ok(SinkTransaction {
+            index,
+            hash: self.block_item_hash.clone(),
+            sender: self.sender.clone(),
+            sponsor: self.sponsor.clone(),
+            transaction_type: format!("{:?}", self.transaction_type),
+            kind,
+            ccd_cost: self.ccd_cost,
+            energy_cost: self.energy_cost,
+            success: self.success,
+            events: self.events.clone(),
+            reject_reason,
+        })
+    }
 }
 
 /// Different types of block item events that can be prepared.
@@ -339,10 +405,11 @@ enum PreparedBlockItemEvent {
 
 impl PreparedBlockItemEvent {
     async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         item_summary: &BlockItemSummary,
         item: &BlockItem<EncodedPayload>,
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
         match &item_summary.details.as_ref().known_or_err()? {
@@ -358,12 +425,18 @@ impl PreparedBlockItemEvent {
                         data,
                         details,
                         item,
+                        domains,
                         statistics,
                     )
                     .await?,
                 )))
             }
             BlockItemSummaryDetails::Update(_) => Ok(PreparedBlockItemEvent::ChainUpdate),
+            BlockItemSummaryDetails::TokenCreationDetails(_)
+                if !domains.contains(IndexingDomain::Plt) =>
+            {
+                Ok(PreparedBlockItemEvent::ChainUpdate)
+            }
             BlockItemSummaryDetails::TokenCreationDetails(token_creation_details) => {
                 Ok(PreparedBlockItemEvent::TokenCreation(Box::new(
                     plt_token_creation::PreparedTokenCreationDetails::prepare(
@@ -395,4 +468,43 @@ impl PreparedBlockItemEvent {
             }
         }
     }
+
+    async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        match self {
+            PreparedBlockItemEvent::AccountTransaction(account_transaction_event) => {
+                account_transaction_event
+                    .reindex(tx, context, transaction_index)
+                    .await
+            }
+            PreparedBlockItemEvent::TokenCreation(event) => {
+                event.reindex(tx, context, transaction_index).await
+            }
+            PreparedBlockItemEvent::AccountCreation(_) | PreparedBlockItemEvent::ChainUpdate => {
+                Ok(())
+            }
+        }
+    }
+
+    fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        match self {
+            PreparedBlockItemEvent::AccountTransaction(account_transaction_event) => {
+                account_transaction_event.collect_token_movements(transaction_hash, movements)
+            }
+            PreparedBlockItemEvent::TokenCreation(event) => {
+                event.collect_token_movements(transaction_hash, movements)
+            }
+            PreparedBlockItemEvent::AccountCreation(_) | PreparedBlockItemEvent::ChainUpdate => {
+                Ok(())
+            }
+        }
+    }
 }
@@ -14,8 +14,12 @@ use crate::{
         },
         block_preprocessor::BlockData,
         db::update_account_balance::PreparedUpdateAccountBalance,
+        event_sink::SinkTokenMovement,
+        node_queries::NodeQueries,
+        reindex::ReindexContext,
         statistics::Statistics,
     },
+    indexing_profile::{IndexingDomain, IndexingDomains},
 };
 use anyhow::{Context, Ok};
 use chrono::{DateTime, Utc};
@@ -23,7 +27,6 @@ use concordium_rust_sdk::{
     base::transactions::{BlockItem, EncodedPayload},
     id::types::AccountAddress,
     types::{queries::ProtocolVersionInt, AccountTransactionDetails, AccountTransactionEffects},
-    v2::{self},
 };
 
 mod baker_events;
@@ -47,10 +50,11 @@ pub struct PreparedAccountTransaction {
 
 impl PreparedAccountTransaction {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         details: &AccountTransactionDetails,
         item: &BlockItem<EncodedPayload>,
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
         let fee = PreparedUpdateAccountBalance::prepare(
@@ -75,6 +79,7 @@ impl PreparedAccountTransaction {
             details,
             item,
             &details.sender,
+            domains,
             statistics,
         )
         .await?;
@@ -97,6 +102,37 @@ impl PreparedAccountTransaction {
         }
         self.event.save(tx, transaction_index, slot_time).await
     }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        self.fee
+            .reindex(tx, context, Some(transaction_index))
+            .await?;
+        if let Some(sponsor_fee) = &self.sponsor_fee {
+            sponsor_fee
+                .reindex(tx, context, Some(transaction_index))
+                .await?;
+        }
+        self.event
+            .event
+            .reindex(tx, context, transaction_index)
+            .await
+    }
+
+    /// Collect the token movements for delivering to the event sinks.
+    pub fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        self.event
+            .event
+            .collect_token_movements(transaction_hash, movements)
+    }
 }
 
 /// Wraps a prepared event together with metadata needed for its processing.
@@ -113,15 +149,24 @@ struct PreparedEventEnvelope {
 
 impl PreparedEventEnvelope {
     async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         details: &AccountTransactionDetails,
         item: &BlockItem<EncodedPayload>,
         sender: &AccountAddress,
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
-        let event =
-            PreparedEvent::prepare(node_client, data, details, item, sender, statistics).await?;
+        let event = PreparedEvent::prepare(
+            node_client,
+            data,
+            details,
+            item,
+            sender,
+            domains,
+            statistics,
+        )
+        .await?;
         let metadata = EventMetadata {
             protocol_version: data.block_info.protocol_version,
         };
@@ -175,15 +220,21 @@ enum PreparedEvent {
 }
 impl PreparedEvent {
     async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         details: &AccountTransactionDetails,
         item: &BlockItem<EncodedPayload>,
         sender: &AccountAddress,
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
         let height = data.block_info.block_height;
         let prepared_event = match details.effects.as_ref().known_or_err()? {
+            AccountTransactionEffects::None { .. }
+                if !domains.contains(IndexingDomain::Contracts) =>
+            {
+                PreparedEvent::NoOperation
+            }
             AccountTransactionEffects::None {
                 transaction_type,
                 reject_reason,
@@ -207,6 +258,7 @@ impl PreparedEvent {
                         data,
                         event_data,
                         sender,
+                        domains,
                     )
                     .await?,
                 )
@@ -222,6 +274,7 @@ impl PreparedEvent {
                         node_client,
                         data,
                         &known_contract_trace_elements,
+                        domains,
                     )
                     .await?,
                 )
@@ -360,6 +413,11 @@ impl PreparedEvent {
                     },
                 )
             }
+            AccountTransactionEffects::TokenUpdate { .. }
+                if !domains.contains(IndexingDomain::Plt) =>
+            {
+                PreparedEvent::NoOperation
+            }
             AccountTransactionEffects::TokenUpdate { events } => {
                 PreparedEvent::TokenUpdateEvents(PreparedTokenEvents {
                     events: events
@@ -423,4 +481,64 @@ impl PreparedEvent {
             PreparedEvent::NoOperation => Ok(()),
         }
     }
+
+    async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        tx_idx: i64,
+    ) -> anyhow::Result<()> {
+        match self {
+            PreparedEvent::CcdTransfer(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event of CCD transfer"),
+            PreparedEvent::EncryptedBalance(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event of encrypted balance"),
+            PreparedEvent::ContractInitialized(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event with contract initialized"),
+            PreparedEvent::ContractUpdate(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event with contract update"),
+            PreparedEvent::ScheduledTransfer(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event with scheduled transfer"),
+            PreparedEvent::RejectedTransaction(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event with rejected event"),
+            PreparedEvent::TokenUpdateEvents(event) => event
+                .reindex(tx, context, tx_idx)
+                .await
+                .context("Failed re-indexing block item event with token update events"),
+            PreparedEvent::BakerEvents(_)
+            | PreparedEvent::ModuleDeployed(_)
+            | PreparedEvent::AccountDelegationEvents(_)
+            | PreparedEvent::NoOperation => Ok(()),
+        }
+    }
+    fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        match self {
+            PreparedEvent::ContractInitialized(event) => {
+                event.collect_token_movements(transaction_hash, movements)
+            }
+            PreparedEvent::ContractUpdate(event) => {
+                event.collect_token_movements(transaction_hash, movements)
+            }
+            PreparedEvent::TokenUpdateEvents(event) => {
+                event.collect_token_movements(transaction_hash, movements)
+            }
+            _ => Ok(()),
+        }
+    }
 }
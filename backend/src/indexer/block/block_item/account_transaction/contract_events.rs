@@ -4,11 +4,16 @@
 
 use super::module_events::PreparedModuleLinkAction;
 use crate::{
-    graphql_api::AccountStatementEntryType,
+    graphql_api::{AccountStatementEntryType, Cis2AccountStatementEntryType},
     indexer::{
-        block_preprocessor::BlockData, db::update_account_balance::PreparedUpdateAccountBalance,
+        block_preprocessor::BlockData,
+        db::update_account_balance::PreparedUpdateAccountBalance,
         ensure_affected_rows::EnsureAffectedRows,
+        event_sink::SinkTokenMovement,
+        node_queries::NodeQueries,
+        reindex::{ReindexContext, ReindexDomain},
     },
+    indexing_profile::{IndexingDomain, IndexingDomains},
     transaction_event::{
         smart_contracts::ModuleReferenceContractLinkAction, CisBurnEvent, CisEvent, CisMintEvent,
         CisTokenMetadataEvent, CisTransferEvent,
@@ -17,14 +22,13 @@ use crate::{
 use anyhow::Context;
 use bigdecimal::BigDecimal;
 use concordium_rust_sdk::{
-    cis0, cis2,
+    cis2,
     common::types::Amount,
     id::types::AccountAddress,
     types::{
         self as sdk_types, AbsoluteBlockHeight, ContractAddress, ContractInitializedEvent,
         ContractTraceElement,
     },
-    v2,
 };
 use futures::future::join_all;
 
@@ -42,10 +46,11 @@ pub struct PreparedContractInitialized {
 
 impl PreparedContractInitialized {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         event: &ContractInitializedEvent,
         sender_account: &AccountAddress,
+        domains: IndexingDomains,
     ) -> anyhow::Result<Self> {
         let contract_address = event.address;
         let index = i64::try_from(event.address.index)?;
@@ -91,11 +96,17 @@ impl PreparedContractInitialized {
         // handled here while CIS2 events logged in the `ContractInterruptedEvent` and
         // `ContractUpdatedEvent` are handled at its corresponding
         // transaction type.
-        let potential_cis2_events = event
-            .events
-            .iter()
-            .filter_map(|log| log.try_into().ok())
-            .collect::<Vec<_>>();
+        //
+        // CIS-2 events are not gathered at all while skipping the CIS-2 domain.
+        let potential_cis2_events = if domains.contains(IndexingDomain::Cis2) {
+            event
+                .events
+                .iter()
+                .filter_map(|log| log.try_into().ok())
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
 
         // If the vector `potential_cis2_events` is not empty, we verify that the smart
         // contract supports the CIS2 standard before accepting the events as
@@ -103,15 +114,13 @@ impl PreparedContractInitialized {
         let cis2_token_events = if potential_cis2_events.is_empty() {
             vec![]
         } else {
-            let supports_cis2 = cis0::supports(
-                node_client,
-                &v2::BlockIdentifier::AbsoluteHeight(data.block_info.block_height),
-                contract_address,
-                event.init_name.as_contract_name(),
-                cis0::StandardIdentifier::CIS2,
-            )
-            .await
-            .is_ok_and(|r| r.response.is_support());
+            let supports_cis2 = node_client
+                .supports_cis2(
+                    data.block_info.block_height,
+                    contract_address,
+                    Some(event.init_name.clone()),
+                )
+                .await?;
 
             if supports_cis2 {
                 potential_cis2_events
@@ -168,7 +177,7 @@ impl PreparedContractInitialized {
             .context("Failed linking new contract to module")?;
 
         for log in self.cis2_token_events.iter() {
-            process_cis2_token_event(log, self.index, self.sub_index, transaction_index, tx)
+            process_cis2_token_event(log, self.index, self.sub_index, transaction_index, tx, None)
                 .await
                 .context("Failed processing a CIS-2 event")?
         }
@@ -177,6 +186,46 @@ impl PreparedContractInitialized {
             .await?;
         Ok(())
     }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if context.domain == ReindexDomain::Cis2 {
+            for log in self.cis2_token_events.iter() {
+                process_cis2_token_event(
+                    log,
+                    self.index,
+                    self.sub_index,
+                    transaction_index,
+                    tx,
+                    Some(context),
+                )
+                .await
+                .context("Failed re-indexing a CIS-2 event")?
+            }
+        }
+        self.transfer_to_contract
+            .reindex(tx, context, Some(transaction_index))
+            .await
+    }
+
+    /// Collect the CIS-2 token movements for delivering to the event sinks.
+    pub fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        SinkTokenMovement::collect_cis2(
+            &self.cis2_token_events,
+            transaction_hash,
+            self.index,
+            self.sub_index,
+            movements,
+        )
+    }
 }
 
 #[derive(Debug)]
@@ -188,9 +237,10 @@ pub struct PreparedContractUpdates {
 
 impl PreparedContractUpdates {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
         events: &[ContractTraceElement],
+        domains: IndexingDomains,
     ) -> anyhow::Result<Self> {
         let trace_elements = join_all(events.iter().enumerate().map(
             |(trace_element_index, effect)| {
@@ -199,6 +249,7 @@ impl PreparedContractUpdates {
                     data,
                     effect,
                     trace_element_index,
+                    domains,
                 )
             },
         ))
@@ -224,6 +275,44 @@ impl PreparedContractUpdates {
         }
         Ok(())
     }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        for elm in &self.trace_elements {
+            elm.reindex(tx, context, transaction_index)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed re-indexing contract update trace element with index {} related \
+                         to contract: <{},{}>",
+                        elm.trace_element_index, elm.contract_index, elm.contract_sub_index
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Collect the CIS-2 token movements for delivering to the event sinks.
+    pub fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        for elm in &self.trace_elements {
+            SinkTokenMovement::collect_cis2(
+                &elm.cis2_token_events,
+                transaction_hash,
+                elm.contract_index,
+                elm.contract_sub_index,
+                movements,
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -234,14 +323,18 @@ struct PreparedTraceElement {
     trace_element_index: i64,
     cis2_token_events: Vec<CisEvent>,
     trace_event: PreparedContractTraceEvent,
+    /// Whether to insert the trace element into the contract events, false
+    /// while skipping the smart contract events domain.
+    index_contract_event: bool,
 }
 
 impl PreparedTraceElement {
     async fn prepare(
-        mut node_client: v2::Client,
+        mut node_client: NodeQueries,
         data: &BlockData,
         event: &ContractTraceElement,
         trace_element_index: usize,
+        domains: IndexingDomains,
     ) -> anyhow::Result<Self> {
         let contract_address = event.affected_address();
 
@@ -297,7 +390,10 @@ impl PreparedTraceElement {
         // handled here while CIS2 events logged in the
         // `ContractInitializedEvent` are handled at its corresponding
         // transaction type.
+        //
+        // CIS-2 events are not gathered at all while skipping the CIS-2 domain.
         let potential_cis2_events = match event {
+            _ if !domains.contains(IndexingDomain::Cis2) => vec![],
             ContractTraceElement::Updated { data } => data
                 .events
                 .iter()
@@ -318,23 +414,9 @@ impl PreparedTraceElement {
         let cis2_token_events = if potential_cis2_events.is_empty() {
             vec![]
         } else {
-            let contract_info = node_client
-                .get_instance_info(
-                    contract_address,
-                    &v2::BlockIdentifier::AbsoluteHeight(data.block_info.block_height),
-                )
+            let supports_cis2 = node_client
+                .supports_cis2(data.block_info.block_height, contract_address, None)
                 .await?;
-            let contract_name = contract_info.response.name().as_contract_name();
-
-            let supports_cis2 = cis0::supports(
-                &mut node_client,
-                &v2::BlockIdentifier::AbsoluteHeight(data.block_info.block_height),
-                contract_address,
-                contract_name,
-                cis0::StandardIdentifier::CIS2,
-            )
-            .await
-            .is_ok_and(|r| r.response.is_support());
 
             if supports_cis2 {
                 potential_cis2_events
@@ -355,6 +437,7 @@ impl PreparedTraceElement {
             trace_element_index,
             cis2_token_events,
             trace_event,
+            index_contract_event: domains.contains(IndexingDomain::Contracts),
         })
     }
 
@@ -362,9 +445,80 @@ impl PreparedTraceElement {
         &self,
         tx: &mut sqlx::PgTransaction<'_>,
         transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if self.index_contract_event {
+            self.insert_contract_event(tx, transaction_index, None)
+                .await?;
+        }
+
+        self.trace_event
+            .save(tx, transaction_index)
+            .await
+            .context("Failed processing trace event")?;
+
+        for log in self.cis2_token_events.iter() {
+            process_cis2_token_event(
+                log,
+                self.contract_index,
+                self.contract_sub_index,
+                transaction_index,
+                tx,
+                None,
+            )
+            .await
+            .context("Failed processing CIS-2 token event")?
+        }
+        Ok(())
+    }
+
+    async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        match context.domain {
+            ReindexDomain::Contracts => {
+                let index = context.next_reusable_id();
+                self.insert_contract_event(tx, transaction_index, index)
+                    .await?;
+            }
+            ReindexDomain::Statements => {
+                self.trace_event
+                    .reindex(tx, context, transaction_index)
+                    .await
+                    .context("Failed re-indexing trace event")?;
+            }
+            ReindexDomain::Cis2 => {
+                for log in self.cis2_token_events.iter() {
+                    process_cis2_token_event(
+                        log,
+                        self.contract_index,
+                        self.contract_sub_index,
+                        transaction_index,
+                        tx,
+                        Some(context),
+                    )
+                    .await
+                    .context("Failed re-indexing CIS-2 token event")?
+                }
+            }
+            ReindexDomain::Plt | ReindexDomain::Rewards => {}
+        }
+        Ok(())
+    }
+
+    /// Insert the trace element into the contract events, using the provided
+    /// index or otherwise the next index.
+    async fn insert_contract_event(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+        index: Option<i64>,
     ) -> anyhow::Result<()> {
         sqlx::query!(
             "INSERT INTO contract_events (
+                index,
                 transaction_index,
                 trace_element_index,
                 block_height,
@@ -372,7 +526,9 @@ impl PreparedTraceElement {
                 contract_sub_index,
                 event_index_per_contract
             )
+            OVERRIDING SYSTEM VALUE
             VALUES (
+                COALESCE($6, nextval(pg_get_serial_sequence('contract_events', 'index'))),
                 $1, $2, $3, $4, $5, (SELECT COALESCE(MAX(event_index_per_contract) + 1, 0) FROM \
              contract_events WHERE contract_index = $4 AND contract_sub_index = $5)
             )",
@@ -380,27 +536,11 @@ impl PreparedTraceElement {
             self.trace_element_index,
             self.height,
             self.contract_index,
-            self.contract_sub_index
+            self.contract_sub_index,
+            index
         )
         .execute(tx.as_mut())
         .await?;
-
-        self.trace_event
-            .save(tx, transaction_index)
-            .await
-            .context("Failed processing trace event")?;
-
-        for log in self.cis2_token_events.iter() {
-            process_cis2_token_event(
-                log,
-                self.contract_index,
-                self.contract_sub_index,
-                transaction_index,
-                tx,
-            )
-            .await
-            .context("Failed processing CIS-2 token event")?
-        }
         Ok(())
     }
 }
@@ -439,6 +579,27 @@ impl PreparedContractTraceEvent {
             PreparedContractTraceEvent::NoEvent => Ok(()),
         }
     }
+
+    async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        match self {
+            PreparedContractTraceEvent::Transfer(event) => {
+                event
+                    .update_receiving_account
+                    .reindex(tx, context, Some(transaction_index))
+                    .await
+            }
+            PreparedContractTraceEvent::Update(PreparedTraceEventUpdate {
+                sender: PreparedTraceEventUpdateSender::Account(sender),
+                ..
+            }) => sender.reindex(tx, context, Some(transaction_index)).await,
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -658,13 +819,23 @@ impl PreparedUpdateContractBalance {
     }
 }
 
+/// Process a CIS-2 event. When re-indexing, the context is provided and the
+/// token event reuses the index of a cleared token event.
 async fn process_cis2_token_event(
     cis2_token_event: &CisEvent,
     contract_index: i64,
     contract_sub_index: i64,
     transaction_index: i64,
     tx: &mut sqlx::PgTransaction<'_>,
+    reindex: Option<&mut ReindexContext>,
 ) -> anyhow::Result<()> {
+    let reindexing = reindex.is_some();
+    let event_index = match reindex {
+        Some(context) if !matches!(cis2_token_event, CisEvent::Unknown(_)) => {
+            context.next_reusable_id()
+        }
+        _ => None,
+    };
     match cis2_token_event {
         // - The `total_supply` value of a token is inserted/updated in the database here.
         // Only `Mint` and `Burn` events affect the `total_supply` of a
@@ -721,6 +892,34 @@ async fn process_cis2_token_event(
             .await
             .context("Failed inserting or updating token from mint event")?;
 
+            // Insert the token event into the table.
+            let cis2_token_event_index = sqlx::query_scalar!(
+                "INSERT INTO cis2_token_events (
+                    index,
+                    index_per_token,
+                    transaction_index,
+                    token_index,
+                    cis2_token_event
+                )
+                OVERRIDING SYSTEM VALUE
+                SELECT
+                    COALESCE($4, nextval(pg_get_serial_sequence('cis2_token_events', 'index'))),
+                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
+                 cis2_token_events.token_index = tokens.index), 0),
+                    $1,
+                    tokens.index,
+                    $3
+                FROM tokens
+                WHERE tokens.token_address = $2
+                RETURNING index",
+                transaction_index,
+                token_address,
+                serde_json::to_value(cis2_mint_event)?,
+                event_index,
+            )
+            .fetch_one(tx.as_mut())
+            .await?;
+
             // If the owner doesn't already hold this token, insert a new row with a balance
             // of `tokens_minted`. Otherwise, update the existing row by
             // incrementing the owner's balance by `tokens_minted`.
@@ -747,30 +946,18 @@ async fn process_cis2_token_event(
                 .execute(tx.as_mut())
                 .await
                 .context("Failed inserting or updating account balance from mint event")?;
-            }
 
-            // Insert the token event into the table.
-            sqlx::query!(
-                "INSERT INTO cis2_token_events (
-                    index_per_token,
+                insert_cis2_account_statement(
+                    tx,
+                    canonical_address.0.as_slice(),
+                    &token_address,
+                    cis2_token_event_index,
+                    Cis2AccountStatementEntryType::Mint,
+                    tokens_minted,
                     transaction_index,
-                    token_index,
-                    cis2_token_event
                 )
-                SELECT
-                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
-                 cis2_token_events.token_index = tokens.index), 0),
-                    $1,
-                    tokens.index,
-                    $3
-                FROM tokens
-                WHERE tokens.token_address = $2",
-                transaction_index,
-                token_address,
-                serde_json::to_value(cis2_mint_event)?,
-            )
-            .execute(tx.as_mut())
-            .await?;
+                .await?;
+            }
         }
 
         // - The `total_supply` value of a token is inserted/updated in the database here.
@@ -832,6 +1019,34 @@ async fn process_cis2_token_event(
             .await
             .context("Failed inserting or updating token from burn event")?;
 
+            // Insert the token event into the table.
+            let cis2_token_event_index = sqlx::query_scalar!(
+                "INSERT INTO cis2_token_events (
+                    index,
+                    index_per_token,
+                    transaction_index,
+                    token_index,
+                    cis2_token_event
+                )
+                OVERRIDING SYSTEM VALUE
+                SELECT
+                    COALESCE($4, nextval(pg_get_serial_sequence('cis2_token_events', 'index'))),
+                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
+                 cis2_token_events.token_index = tokens.index), 0),
+                    $1,
+                    tokens.index,
+                    $3
+                FROM tokens
+                WHERE tokens.token_address = $2
+                RETURNING index",
+                transaction_index,
+                token_address,
+                serde_json::to_value(cis2_burn_event)?,
+                event_index,
+            )
+            .fetch_one(tx.as_mut())
+            .await?;
+
             if let sdk_types::Address::Account(owner) = owner {
                 let canonical_address = owner.get_canonical_address();
                 sqlx::query!(
@@ -849,37 +1064,24 @@ async fn process_cis2_token_event(
                     DO UPDATE SET balance = account_tokens.balance + EXCLUDED.balance",
                     canonical_address.0.as_slice(),
                     token_address.to_string(),
-                    -tokens_burned
+                    -tokens_burned.clone()
                 )
                 .execute(tx.as_mut())
                 .await
                 .context("Failed inserting or updating account balance from burn event")?
                 .ensure_affected_rows_in_range(0..=1)?;
-            }
 
-            // Insert the token event into the table.
-            sqlx::query!(
-                "INSERT INTO cis2_token_events (
-                    index_per_token,
+                insert_cis2_account_statement(
+                    tx,
+                    canonical_address.0.as_slice(),
+                    &token_address,
+                    cis2_token_event_index,
+                    Cis2AccountStatementEntryType::Burn,
+                    -tokens_burned,
                     transaction_index,
-                    token_index,
-                    cis2_token_event
                 )
-                SELECT
-                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
-                 cis2_token_events.token_index = tokens.index), 0),
-                    $1,
-                    tokens.index,
-                    $3
-                FROM tokens
-                WHERE tokens.token_address = $2",
-                transaction_index,
-                token_address,
-                serde_json::to_value(cis2_burn_event)?,
-            )
-            .execute(tx.as_mut())
-            .await?
-            .ensure_affected_one_row()?;
+                .await?;
+            }
         }
 
         // - The `balance` values of the token are inserted/updated in the database here for the
@@ -933,6 +1135,40 @@ async fn process_cis2_token_event(
             .await
             .context("Failed inserting token from transfer event")?;
 
+            // Insert the token event into the table.
+            let cis2_token_event_index = sqlx::query_scalar!(
+                "INSERT INTO cis2_token_events (
+                    index,
+                    index_per_token,
+                    transaction_index,
+                    token_index,
+                    cis2_token_event
+                )
+                OVERRIDING SYSTEM VALUE
+                SELECT
+                    COALESCE($4, nextval(pg_get_serial_sequence('cis2_token_events', 'index'))),
+                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
+                 cis2_token_events.token_index = tokens.index), 0),
+                    $1,
+                    tokens.index,
+                    $3
+                FROM tokens
+                WHERE tokens.token_address = $2
+                RETURNING index",
+                transaction_index,
+                token_address,
+                serde_json::to_value(cis2_transfer_event)?,
+                event_index,
+            )
+            .fetch_one(tx.as_mut())
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed inserting the token transfer event: {:?}",
+                    cis2_transfer_event
+                )
+            })?;
+
             // If the `from` address doesn't already hold this token, insert a new row with
             // a balance of `-tokens_transferred`. Otherwise, update the existing row
             // by decrementing the owner's balance by `tokens_transferred`.
@@ -961,6 +1197,17 @@ async fn process_cis2_token_event(
                 .context(
                     "Failed inserting or updating account balance from transfer event (sender)",
                 )?;
+
+                insert_cis2_account_statement(
+                    tx,
+                    canonical_address.0.as_slice(),
+                    &token_address,
+                    cis2_token_event_index,
+                    Cis2AccountStatementEntryType::TransferOut,
+                    -tokens_transferred.clone(),
+                    transaction_index,
+                )
+                .await?;
             }
 
             // If the `to` address doesn't already hold this token, insert a new row with a
@@ -990,43 +1237,18 @@ async fn process_cis2_token_event(
                 .await
                 .context("Failed inserting or updating account balance from transfer event (to)")?
                 .ensure_affected_rows_in_range(0..=1)?;
-            }
 
-            // Insert the token event into the table.
-            sqlx::query!(
-                "INSERT INTO cis2_token_events (
-                    index_per_token,
+                insert_cis2_account_statement(
+                    tx,
+                    canonical_address.0.as_slice(),
+                    &token_address,
+                    cis2_token_event_index,
+                    Cis2AccountStatementEntryType::TransferIn,
+                    tokens_transferred,
                     transaction_index,
-                    token_index,
-                    cis2_token_event
                 )
-                SELECT
-                    COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
-                 cis2_token_events.token_index = tokens.index), 0),
-                    $1,
-                    tokens.index,
-                    $3
-                FROM tokens
-                WHERE tokens.token_address = $2",
-                transaction_index,
-                token_address,
-                serde_json::to_value(cis2_transfer_event)?,
-            )
-            .execute(tx.as_mut())
-            .await
-            .with_context(|| {
-                format!(
-                    "Failed inserting the token transfer event: {:?}",
-                    cis2_transfer_event
-                )
-            })?
-            .ensure_affected_one_row()
-            .with_context(|| {
-                format!(
-                    "Failed inserting the token transfer event: {:?}",
-                    cis2_transfer_event
-                )
-            })?;
+                .await?;
+            }
         }
 
         // - The `metadata_url` of a token is inserted/updated in the database here.
@@ -1046,7 +1268,7 @@ async fn process_cis2_token_event(
 
             // If the `token_address` does not exist, insert the new token.
             // If the `token_address` exists, update the `metadata_url` value in the
-            // database.
+            // database, unless re-indexing, since later blocks might have updated it.
             sqlx::query!(
                 "
                     INSERT INTO tokens (index, token_index_per_contract, token_address, \
@@ -1064,13 +1286,15 @@ async fn process_cis2_token_event(
                         $6
                     )
                     ON CONFLICT (token_address)
-                    DO UPDATE SET metadata_url = EXCLUDED.metadata_url",
+                    DO UPDATE SET metadata_url = CASE WHEN $7 THEN tokens.metadata_url ELSE \
+                 EXCLUDED.metadata_url END",
                 token_address,
                 contract_index,
                 contract_sub_index,
                 metadata_url.url(),
                 raw_token_id.to_string(),
-                transaction_index
+                transaction_index,
+                reindexing
             )
             .execute(tx.as_mut())
             .await
@@ -1079,12 +1303,15 @@ async fn process_cis2_token_event(
             // Insert the token event into the table.
             sqlx::query!(
                 "INSERT INTO cis2_token_events (
+                    index,
                     index_per_token,
                     transaction_index,
                     token_index,
                     cis2_token_event
                 )
+                OVERRIDING SYSTEM VALUE
                 SELECT
+                    COALESCE($4, nextval(pg_get_serial_sequence('cis2_token_events', 'index'))),
                     COALESCE((SELECT MAX(index_per_token) + 1 FROM cis2_token_events WHERE \
                  cis2_token_events.token_index = tokens.index), 0),
                     $1,
@@ -1095,6 +1322,7 @@ async fn process_cis2_token_event(
                 transaction_index,
                 token_address,
                 serde_json::to_value(cis2_token_metadata_event)?,
+                event_index,
             )
             .execute(tx.as_mut())
             .await?
@@ -1104,3 +1332,59 @@ async fn process_cis2_token_event(
     }
     Ok(())
 }
+
+/// Add a new row into the CIS-2 accounts statement table.
+/// This reads the current token balance of the account and assumes the balance
+/// is already updated with the amount part of the statement.
+async fn insert_cis2_account_statement(
+    tx: &mut sqlx::PgTransaction<'_>,
+    canonical_address: &[u8],
+    token_address: &str,
+    cis2_token_event_index: i64,
+    entry_type: Cis2AccountStatementEntryType,
+    amount: BigDecimal,
+    transaction_index: i64,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "INSERT INTO cis2_accounts_statement (
+            account_index,
+            cis2_token_event_index,
+            token_index,
+            entry_type,
+            amount,
+            account_balance,
+            slot_time,
+            block_height,
+            transaction_index
+        )
+        SELECT
+            account_tokens.account_index,
+            $3,
+            account_tokens.token_index,
+            $4,
+            $5,
+            account_tokens.balance,
+            blocks.slot_time,
+            blocks.height,
+            $6
+        FROM accounts
+            JOIN tokens ON tokens.token_address = $2
+            JOIN account_tokens
+                ON account_tokens.account_index = accounts.index
+                AND account_tokens.token_index = tokens.index
+            JOIN transactions ON transactions.index = $6
+            JOIN blocks ON blocks.height = transactions.block_height
+        WHERE accounts.canonical_address = $1",
+        canonical_address,
+        token_address,
+        cis2_token_event_index,
+        entry_type as Cis2AccountStatementEntryType,
+        amount,
+        transaction_index
+    )
+    .execute(tx.as_mut())
+    .await
+    .context("Failed inserting into cis2_accounts_statement")?
+    .ensure_affected_rows_in_range(0..=1)?;
+    Ok(())
+}
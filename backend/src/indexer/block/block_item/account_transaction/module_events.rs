@@ -2,12 +2,15 @@
 //! events in an account transaction during the concurrent preprocessing and the
 //! logic for how to do the sequential processing into the database.
 
-use crate::transaction_event::smart_contracts::ModuleReferenceContractLinkAction;
+use crate::{
+    indexer::node_queries::NodeQueries,
+    transaction_event::smart_contracts::ModuleReferenceContractLinkAction,
+};
 use anyhow::Context;
 use concordium_rust_sdk::{
     base::smart_contracts::WasmVersion,
     smart_contracts::engine::utils::{get_embedded_schema_v0, get_embedded_schema_v1},
-    types as sdk_types, v2,
+    types as sdk_types,
 };
 
 #[derive(Debug)]
@@ -18,15 +21,12 @@ pub struct PreparedModuleDeployed {
 
 impl PreparedModuleDeployed {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         module_reference: sdk_types::hashes::ModuleReference,
     ) -> anyhow::Result<Self> {
         // The `get_module_source` query on old blocks are currently not performing
         // well in the node. We query on the `lastFinal` block here as a result (https://github.com/Concordium/concordium-scan/issues/534).
-        let wasm_module = node_client
-            .get_module_source(&module_reference, v2::BlockIdentifier::LastFinal)
-            .await?
-            .response;
+        let wasm_module = node_client.get_module_source(module_reference).await?;
         let schema = match wasm_module.version {
             WasmVersion::V0 => get_embedded_schema_v0(wasm_module.source.as_ref()),
             WasmVersion::V1 => get_embedded_schema_v1(wasm_module.source.as_ref()),
@@ -2,7 +2,13 @@
 //! TokenGovernance). These types are used to prepare and (optionally) persist
 //! token events during block processing.
 
-use crate::transaction_event::protocol_level_tokens::TokenUpdate;
+use crate::{
+    indexer::{
+        event_sink::SinkTokenMovement,
+        reindex::{ReindexContext, ReindexDomain},
+    },
+    transaction_event::protocol_level_tokens::TokenUpdate,
+};
 use chrono::{DateTime, Utc};
 use concordium_rust_sdk::protocol_level_tokens::{self};
 
@@ -27,6 +33,43 @@ impl PreparedTokenEvents {
         }
         Ok(())
     }
+
+    /// Rebuilds the token events when re-indexing.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if context.domain != ReindexDomain::Plt {
+            return Ok(());
+        }
+        for event in &self.events {
+            event
+                .event
+                .reindex(
+                    tx,
+                    transaction_index,
+                    context.slot_time,
+                    context.next_reusable_id(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Collect the token movements for delivering to the event sinks.
+    pub fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        SinkTokenMovement::collect_plt(
+            self.events.iter().map(|event| &event.event),
+            transaction_hash,
+            movements,
+        )
+    }
 }
 
 /// Wraps a token holder event for compatibility with the event processing
@@ -2,6 +2,7 @@
 //! events in an account transaction during the concurrent preprocessing and the
 //! logic for how to do the sequential processing into the database.
 
+use crate::indexer::reindex::{ReindexContext, ReindexDomain};
 use anyhow::Context;
 use concordium_rust_sdk::{
     base::transactions::{BlockItem, EncodedPayload, Payload},
@@ -126,6 +127,22 @@ impl PreparedRejectedEvent {
         }
         Ok(())
     }
+
+    /// Rebuilds the rejected contract update transactions when re-indexing the
+    /// contracts.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if let (ReindexDomain::Contracts, PreparedRejectedEvent::ContractUpdateTransaction(event)) =
+            (context.domain, self)
+        {
+            event.save(tx, transaction_index).await?
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
@@ -7,6 +7,7 @@ use crate::{
     indexer::{
         db::update_account_balance::PreparedUpdateAccountBalance,
         ensure_affected_rows::EnsureAffectedRows,
+        reindex::{ReindexContext, ReindexDomain},
     },
 };
 use anyhow::Context;
@@ -68,6 +69,20 @@ impl PreparedCcdTransferEvent {
             .context("Failed processing receiver balance update")?;
         Ok(())
     }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        self.update_sender
+            .reindex(tx, context, Some(transaction_index))
+            .await?;
+        self.update_receiver
+            .reindex(tx, context, Some(transaction_index))
+            .await
+    }
 }
 
 #[derive(Debug)]
@@ -144,6 +159,7 @@ impl PreparedScheduledReleases {
         .execute(tx.as_mut())
         .await?
         .ensure_affected_rows(self.release_times.len().try_into()?)?;
+        self.save_history(tx, transaction_index).await?;
         self.target_account_balance_update
             .save(tx, Some(transaction_index))
             .await?;
@@ -152,6 +168,56 @@ impl PreparedScheduledReleases {
             .await?;
         Ok(())
     }
+
+    /// Add the locked amounts to the history of scheduled releases, which is
+    /// rebuilt together with the account statements when re-indexing.
+    async fn save_history(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            "INSERT INTO scheduled_release_history (
+                transaction_index,
+                locked_at,
+                release_time,
+                amount
+            )
+            SELECT
+                $1,
+                (SELECT slot_time
+                 FROM transactions
+                     JOIN blocks ON blocks.height = transactions.block_height
+                 WHERE transactions.index = $1),
+                UNNEST($2::TIMESTAMPTZ[]),
+                UNNEST($3::BIGINT[])
+            ",
+            transaction_index,
+            &self.release_times,
+            &self.amounts
+        )
+        .execute(tx.as_mut())
+        .await?
+        .ensure_affected_rows(self.release_times.len().try_into()?)?;
+        Ok(())
+    }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if context.domain == ReindexDomain::Statements {
+            self.save_history(tx, transaction_index).await?;
+        }
+        self.target_account_balance_update
+            .reindex(tx, context, Some(transaction_index))
+            .await?;
+        self.source_account_balance_update
+            .reindex(tx, context, Some(transaction_index))
+            .await
+    }
 }
 
 /// Represents either moving funds from or to the encrypted balance.
@@ -191,6 +257,17 @@ impl PreparedUpdateEncryptedBalance {
             .await?;
         Ok(())
     }
+
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        self.public_balance_change
+            .reindex(tx, context, Some(transaction_index))
+            .await
+    }
 }
 
 pub enum CryptoOperation {
@@ -206,3 +283,91 @@ impl From<CryptoOperation> for AccountStatementEntryType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::TimeZone;
+
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    /// The slot time and amount of the scheduled release history of the
+    /// transaction.
+    async fn history(
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+    ) -> Vec<(DateTime<Utc>, i64)> {
+        sqlx::query_as(
+            "SELECT locked_at, amount FROM scheduled_release_history
+            WHERE transaction_index = $1
+            ORDER BY release_time",
+        )
+        .bind(transaction_index)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_release_history_rebuilt_with_statements() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let slot_time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let target = AccountAddress([0xfe; 32]);
+        let source = AccountAddress([0xfd; 32]);
+        test_database::insert_block(tx.as_mut(), HEIGHT, slot_time)
+            .await
+            .unwrap();
+        test_database::insert_transaction(tx.as_mut(), TRANSACTION, HEIGHT)
+            .await
+            .unwrap();
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &target, 0)
+            .await
+            .unwrap();
+        test_database::insert_account(tx.as_mut(), ACCOUNT + 1, &source, 100)
+            .await
+            .unwrap();
+        let releases = PreparedScheduledReleases::prepare(
+            &target,
+            &source,
+            &[
+                (
+                    Timestamp::from(1_800_000_000_000),
+                    Amount::from_micro_ccd(10),
+                ),
+                (
+                    Timestamp::from(1_900_000_000_000),
+                    Amount::from_micro_ccd(20),
+                ),
+            ],
+            AbsoluteBlockHeight::from(HEIGHT as u64),
+        )
+        .unwrap();
+        releases.save(&mut tx, TRANSACTION).await.unwrap();
+        let saved = history(&mut tx, TRANSACTION).await;
+        assert_eq!(saved, vec![(slot_time, 10), (slot_time, 20)]);
+
+        sqlx::query("DELETE FROM scheduled_release_history WHERE transaction_index = $1")
+            .bind(TRANSACTION)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        let mut context = ReindexContext::new(ReindexDomain::Plt, Vec::new());
+        releases
+            .reindex(&mut tx, &mut context, TRANSACTION)
+            .await
+            .unwrap();
+        assert!(history(&mut tx, TRANSACTION).await.is_empty());
+        let mut context = ReindexContext::new(ReindexDomain::Statements, Vec::new());
+        releases
+            .reindex(&mut tx, &mut context, TRANSACTION)
+            .await
+            .unwrap();
+        assert_eq!(history(&mut tx, TRANSACTION).await, saved);
+    }
+}
@@ -5,8 +5,12 @@ use concordium_rust_sdk::{
     types::TokenCreationDetails,
 };
 
-use crate::transaction_event::protocol_level_tokens::{
-    CreatePlt, InitializationParameters, TokenUpdate,
+use crate::{
+    indexer::{
+        event_sink::SinkTokenMovement,
+        reindex::{ReindexContext, ReindexDomain},
+    },
+    transaction_event::protocol_level_tokens::{CreatePlt, InitializationParameters, TokenUpdate},
 };
 
 #[derive(Debug)]
@@ -112,4 +116,38 @@ impl PreparedTokenCreationDetails {
 
         Ok(())
     }
+
+    /// Rebuild the events of the token creation, the token itself is left
+    /// untouched.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: i64,
+    ) -> anyhow::Result<()> {
+        if context.domain != ReindexDomain::Plt {
+            return Ok(());
+        }
+        for event in &self.events {
+            event
+                .reindex(
+                    tx,
+                    transaction_index,
+                    context.slot_time,
+                    context.next_reusable_id(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Collect the token movements of the token creation, such as the initial
+    /// mint, for delivering to the event sinks.
+    pub fn collect_token_movements(
+        &self,
+        transaction_hash: &str,
+        movements: &mut Vec<SinkTokenMovement>,
+    ) -> anyhow::Result<()> {
+        SinkTokenMovement::collect_plt(&self.events, transaction_hash, movements)
+    }
 }
@@ -5,17 +5,16 @@
 //! protocol version.
 
 use crate::{
-    indexer::{block_preprocessor::BlockData, ensure_affected_rows::EnsureAffectedRows},
+    indexer::{
+        block_preprocessor::BlockData, ensure_affected_rows::EnsureAffectedRows,
+        node_queries::NodeQueries,
+    },
     transaction_event::baker::BakerPoolOpenStatus,
 };
 use anyhow::Context;
-use concordium_rust_sdk::{
-    types::{
-        self as sdk_types, queries::ProtocolVersionInt, PartsPerHundredThousands, ProtocolVersion,
-    },
-    v2,
+use concordium_rust_sdk::types::{
+    self as sdk_types, queries::ProtocolVersionInt, PartsPerHundredThousands, ProtocolVersion,
 };
-use futures::TryStreamExt;
 
 /// Represents a data migration due to an update of the protocol.
 #[derive(Debug)]
@@ -26,7 +25,7 @@ pub enum ProtocolUpdateMigration {
 }
 impl ProtocolUpdateMigration {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         data: &BlockData,
     ) -> anyhow::Result<Option<Self>> {
         if data.block_info.era_block_height != sdk_types::BlockHeight::from(0) {
@@ -72,60 +71,35 @@ pub struct P4ProtocolUpdateMigration {
     finalization_commission_rates: Vec<i64>,
 }
 impl P4ProtocolUpdateMigration {
-    async fn prepare(node_client: &mut v2::Client, data: &BlockData) -> anyhow::Result<Self> {
+    async fn prepare(node_client: &mut NodeQueries, data: &BlockData) -> anyhow::Result<Self> {
         let block_height = data.finalized_block_info.height;
-        let (
-            baker_ids,
-            (
-                open_statuses,
-                (
-                    metadata_urls,
-                    (
-                        transaction_commission_rates,
-                        (baking_commission_rates, finalization_commission_rates),
-                    ),
-                ),
-            ),
-        ) = node_client
-            .get_baker_list(block_height)
-            .await?
-            .response
-            .map_err(anyhow::Error::from)
-            .and_then(|baker_id| {
-                let mut client = node_client.clone();
-                async move {
-                    let pool_info = client.get_pool_info(block_height, baker_id).await?.response;
-                    let status = pool_info
-                        .active_baker_pool_status
-                        .context("Unexpected missing pool info during P4 migration")?;
-                    let pool = status.pool_info;
-                    let validator_id: i64 = baker_id.id.index.try_into()?;
-                    let status = BakerPoolOpenStatus::from(pool.open_status.known_or_err()?);
-                    let metadata_url = String::from(pool.metadata_url);
-                    let transaction_rate = i64::from(u32::from(PartsPerHundredThousands::from(
-                        pool.commission_rates.transaction,
-                    )));
-                    let baking_rate = i64::from(u32::from(PartsPerHundredThousands::from(
-                        pool.commission_rates.baking,
-                    )));
-                    let finalization_rate = i64::from(u32::from(PartsPerHundredThousands::from(
-                        pool.commission_rates.finalization,
-                    )));
-
-                    anyhow::Ok((
-                        validator_id,
-                        (
-                            status,
-                            (
-                                metadata_url,
-                                (transaction_rate, (baking_rate, finalization_rate)),
-                            ),
-                        ),
-                    ))
-                }
-            })
-            .try_collect()
-            .await?;
+        let baker_list = node_client.get_baker_list(block_height).await?;
+        let capacity = baker_list.len();
+        let mut baker_ids = Vec::with_capacity(capacity);
+        let mut open_statuses = Vec::with_capacity(capacity);
+        let mut metadata_urls = Vec::with_capacity(capacity);
+        let mut transaction_commission_rates = Vec::with_capacity(capacity);
+        let mut baking_commission_rates = Vec::with_capacity(capacity);
+        let mut finalization_commission_rates = Vec::with_capacity(capacity);
+        for baker_id in baker_list {
+            let pool_info = node_client.get_pool_info(block_height, baker_id).await?;
+            let status = pool_info
+                .active_baker_pool_status
+                .context("Unexpected missing pool info during P4 migration")?;
+            let pool = status.pool_info;
+            baker_ids.push(i64::try_from(baker_id.id.index)?);
+            open_statuses.push(BakerPoolOpenStatus::from(pool.open_status.known_or_err()?));
+            metadata_urls.push(String::from(pool.metadata_url));
+            transaction_commission_rates.push(i64::from(u32::from(
+                PartsPerHundredThousands::from(pool.commission_rates.transaction),
+            )));
+            baking_commission_rates.push(i64::from(u32::from(PartsPerHundredThousands::from(
+                pool.commission_rates.baking,
+            ))));
+            finalization_commission_rates.push(i64::from(u32::from(
+                PartsPerHundredThousands::from(pool.commission_rates.finalization),
+            )));
+        }
 
         Ok(Self {
             baker_ids,
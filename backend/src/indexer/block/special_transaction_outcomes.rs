@@ -10,7 +10,6 @@ use concordium_rust_sdk::{
     base::contracts_common::CanonicalAccountAddress,
     id::types::AccountAddress,
     types::{queries::BlockInfo, AbsoluteBlockHeight, SpecialTransactionOutcome},
-    v2,
 };
 
 use crate::{
@@ -18,8 +17,13 @@ use crate::{
     graphql_api::AccountStatementEntryType,
     indexer::{
         db::update_account_balance::PreparedUpdateAccountBalance,
-        ensure_affected_rows::EnsureAffectedRows, statistics::Statistics,
+        ensure_affected_rows::EnsureAffectedRows,
+        event_sink::SinkSpecialEvent,
+        node_queries::NodeQueries,
+        reindex::{ReindexContext, ReindexDomain},
+        statistics::Statistics,
     },
+    indexing_profile::{IndexingDomain, IndexingDomains},
 };
 
 pub mod payday;
@@ -39,9 +43,11 @@ pub struct PreparedSpecialTransactionOutcomes {
 
 impl PreparedSpecialTransactionOutcomes {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         block_info: &BlockInfo,
         events: &[SpecialTransactionOutcome],
+        outcome_indices: &[i64],
+        domains: IndexingDomains,
         statistics: &mut Statistics,
     ) -> anyhow::Result<Self> {
         // Return whether the block is a payday block. This is always false for
@@ -56,7 +62,7 @@ impl PreparedSpecialTransactionOutcomes {
             )
         });
 
-        let payday_updates = if is_payday_block {
+        let payday_updates = if is_payday_block && domains.contains(IndexingDomain::Payday) {
             Some(payday::PreparedPayDayBlock::prepare(node_client, block_info).await?)
         } else {
             None
@@ -67,6 +73,7 @@ impl PreparedSpecialTransactionOutcomes {
                 PreparedInsertBlockSpecialTransactionOutcomes::prepare(
                     block_info.block_height,
                     events,
+                    outcome_indices,
                 )?,
             updates: events
                 .iter()
@@ -78,6 +85,29 @@ impl PreparedSpecialTransactionOutcomes {
         })
     }
 
+    /// Prepare a single special transaction outcome of a block on its own, used
+    /// when re-processing a quarantined outcome. The payday of the block is
+    /// not prepared again.
+    pub fn prepare_single(
+        block_info: &BlockInfo,
+        outcome_index: i64,
+        event: &SpecialTransactionOutcome,
+        statistics: &mut Statistics,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            insert_special_transaction_outcomes:
+                PreparedInsertBlockSpecialTransactionOutcomes::prepare(
+                    block_info.block_height,
+                    std::slice::from_ref(event),
+                    &[outcome_index],
+                )?,
+            updates: vec![PreparedSpecialTransactionOutcomeUpdate::prepare(
+                event, block_info, statistics,
+            )?],
+            payday_updates: None,
+        })
+    }
+
     pub async fn save(&self, tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
         self.insert_special_transaction_outcomes.save(tx).await?;
         if let Some(payday_updates) = &self.payday_updates {
@@ -88,6 +118,53 @@ impl PreparedSpecialTransactionOutcomes {
         }
         Ok(())
     }
+
+    /// The special transaction outcomes delivered to the event sinks.
+    pub fn sink_special_events(&self) -> Vec<SinkSpecialEvent> {
+        let insert = &self.insert_special_transaction_outcomes;
+        insert
+            .block_outcome_index
+            .iter()
+            .zip(insert.outcomes.iter())
+            .map(|(&index, outcome)| SinkSpecialEvent {
+                index,
+                outcome: outcome.clone(),
+            })
+            .collect()
+    }
+
+    /// Rebuild the special transaction outcomes, payday rewards and pool stakes
+    /// or the account statements of rewards when re-indexing.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+    ) -> anyhow::Result<()> {
+        match context.domain {
+            ReindexDomain::Rewards => {
+                // Inserting the outcomes inserts the payday rewards of pools and accounts.
+                self.insert_special_transaction_outcomes.save(tx).await?;
+                if let Some(payday_updates) = &self.payday_updates {
+                    payday_updates.reindex(tx).await?;
+                }
+                Ok(())
+            }
+            ReindexDomain::Statements => {
+                for update in self.updates.iter() {
+                    if let PreparedSpecialTransactionOutcomeUpdate::Rewards(events) = update {
+                        for event in events {
+                            event
+                                .update_account_balance
+                                .reindex(tx, context, None)
+                                .await?;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            ReindexDomain::Cis2 | ReindexDomain::Contracts | ReindexDomain::Plt => Ok(()),
+        }
+    }
 }
 
 /// The `SpecialEvents` of a payday block in the order they
@@ -282,6 +359,20 @@ impl PreparedPaydaySpecialTransactionOutcomes {
         .await
         .context("Failed inserting delegator rewards at payday block")?;
 
+        // Track the pool from which each account received its rewards.
+        sqlx::query!(
+            "INSERT INTO payday_account_rewards (payday_block_height, account_index, pool_owner)
+            SELECT $1, accounts.index, rewards.pool_owner
+            FROM UNNEST($2::BIGINT[], $3::BYTEA[]) AS rewards(pool_owner, canonical_address)
+                JOIN accounts ON accounts.canonical_address = rewards.canonical_address",
+            &self.block_height,
+            &self.delegators_rewards_pool_owners as &[Option<i64>],
+            &self.delegators_rewards_canonical_addresses,
+        )
+        .execute(tx.as_mut())
+        .await
+        .context("Failed inserting pools of account rewards at payday block")?;
+
         // Insert the total rewards.
         sqlx::query!(
             "
@@ -336,6 +427,7 @@ impl PreparedInsertBlockSpecialTransactionOutcomes {
     fn prepare(
         block_height: AbsoluteBlockHeight,
         events: &[SpecialTransactionOutcome],
+        outcome_indices: &[i64],
     ) -> anyhow::Result<Self> {
         let block_height = block_height.height.try_into()?;
         let mut block_outcome_index = Vec::with_capacity(events.len());
@@ -345,8 +437,7 @@ impl PreparedInsertBlockSpecialTransactionOutcomes {
         let payday_special_transaction_outcomes =
             PreparedPaydaySpecialTransactionOutcomes::prepare(block_height, events)?;
 
-        for (block_index, event) in events.iter().enumerate() {
-            let outcome_index = block_index.try_into()?;
+        for (event, &outcome_index) in events.iter().zip(outcome_indices) {
             let special_event = SpecialEvent::from_special_transaction_outcome(
                 block_height,
                 outcome_index,
@@ -6,7 +6,7 @@
 //! Version 4, and is the event of rewards being paid out to validators and
 //! finalizers by the end of a reward period.
 
-use crate::indexer::ensure_affected_rows::EnsureAffectedRows;
+use crate::indexer::{ensure_affected_rows::EnsureAffectedRows, node_queries::NodeQueries};
 use anyhow::Context;
 use bigdecimal::BigDecimal;
 use concordium_rust_sdk::{
@@ -17,7 +17,6 @@ use concordium_rust_sdk::{
     },
     v2,
 };
-use futures::TryStreamExt;
 use num_traits::FromPrimitive;
 
 /// Represents a payday block, its payday commission
@@ -45,7 +44,7 @@ pub struct PreparedPayDayBlock {
 
 impl PreparedPayDayBlock {
     pub async fn prepare(
-        node_client: &mut v2::Client,
+        node_client: &mut NodeQueries,
         block_info: &BlockInfo,
     ) -> anyhow::Result<Self> {
         let block_height = block_info.block_height;
@@ -57,19 +56,9 @@ impl PreparedPayDayBlock {
         // caught up to the top of the chain.
         let (baker_reward_period_infos, passive_reward_period_info) =
             if block_info.protocol_version >= ProtocolVersionInt::from(ProtocolVersion::P4) {
-                let baker_info = node_client
-                    .get_bakers_reward_period(v2::BlockIdentifier::AbsoluteHeight(block_height))
-                    .await?
-                    .response
-                    .try_collect()
-                    .await?;
+                let baker_info = node_client.get_bakers_reward_period(block_height).await?;
                 let passive_info = node_client
-                    .get_passive_delegators_reward_period(v2::BlockIdentifier::AbsoluteHeight(
-                        block_height,
-                    ))
-                    .await?
-                    .response
-                    .try_collect()
+                    .get_passive_delegators_reward_period(block_height)
                     .await?;
                 (baker_info, passive_info)
             } else {
@@ -85,27 +74,21 @@ impl PreparedPayDayBlock {
         let baker_payday_commission_rates =
             PreparedBakerPaydayCommissionRates::prepare(baker_reward_period_infos)?;
 
-        let passive_delegation_status = if block_info.protocol_version
-            >= ProtocolVersionInt::from(ProtocolVersion::P4)
-        {
-            Some(
-                node_client
-                    .get_passive_delegation_info(v2::BlockIdentifier::AbsoluteHeight(block_height))
-                    .await?
-                    .response,
-            )
-        } else {
-            None
-        };
+        let passive_delegation_status =
+            if block_info.protocol_version >= ProtocolVersionInt::from(ProtocolVersion::P4) {
+                Some(
+                    node_client
+                        .get_passive_delegation_info(block_height)
+                        .await?,
+                )
+            } else {
+                None
+            };
         let passive_delegation_payday_commission_rates =
             PreparedPassiveDelegationPaydayCommissionRates::prepare(passive_delegation_status)?;
 
-        let election_info = node_client
-            .get_election_info(v2::BlockIdentifier::AbsoluteHeight(block_height))
-            .await?
-            .response;
-        let payday_bakers_lottery_powers =
-            PreparedPaydayLotteryPowers::prepare(election_info.bakers)?;
+        let election_bakers = node_client.get_election_bakers(block_height).await?;
+        let payday_bakers_lottery_powers = PreparedPaydayLotteryPowers::prepare(election_bakers)?;
 
         Ok(Self {
             block_height: block_height.height.try_into()?,
@@ -152,6 +135,23 @@ impl PreparedPayDayBlock {
     }
 }
 
+impl PreparedPayDayBlock {
+    /// Rebuild the pool stakes locked for the reward period after this payday
+    /// when re-indexing the rewards. The commission rates, lottery powers and
+    /// last payday block height only reflect the latest payday and are left
+    /// untouched, and the baker APYs are refreshed once the range is rebuilt.
+    pub async fn reindex(&self, tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
+        self.baker_pool_stakes
+            .save(tx)
+            .await
+            .context("Failed inserting the reward period baker pool stakes")?;
+        self.passive_pool_stake
+            .save(tx)
+            .await
+            .context("Failed inserting the reward period passive pool stake")
+    }
+}
+
 /// Represents the payday pool commission rates to passive delegators captured
 /// from the `get_passive_delegation_info` node endpoint.
 struct PreparedPassiveDelegationPaydayCommissionRates {
@@ -0,0 +1,527 @@
+//! Offline block archive, allowing the indexer to ingest blocks without a
+//! Concordium Node.
+//!
+//! The archive is a directory containing the information of the genesis block
+//! in `genesis.json` and the raw information of every archived block in
+//! `<height>.json`, including the responses of the node queries made while
+//! preparing the block. Blocks in the archive are prepared exactly as when
+//! traversing the node and then processed by the usual block processor.
+
+use super::{
+    block::PreparedBlock,
+    block_preprocessor::{BlockData, BlockPreProcessor, NodeMetricLabels},
+    genesis_data::GenesisData,
+    node_queries::{NodeQueries, RecordedQueries},
+    quarantine::QuarantinedItem,
+    IndexerServiceConfig,
+};
+use crate::indexing_profile::IndexingDomains;
+use anyhow::Context;
+use concordium_rust_sdk::{
+    base::transactions::{BlockItem, EncodedPayload},
+    common::{self, types::Amount},
+    indexer::{Indexer, OnFinalizationResult, TraverseConfig, TraverseError},
+    types::{
+        self as sdk_types, block_certificates::BlockCertificates, queries::BlockInfo,
+        AbsoluteBlockHeight, BlockItemSummary, RewardsOverview, SpecialTransactionOutcome,
+    },
+    v2,
+};
+use futures::{StreamExt, TryStreamExt};
+use prometheus_client::registry::Registry;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Arguments of the `archive` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct ArchiveArgs {
+    /// Height of the first block to archive.
+    #[arg(long)]
+    pub from: u64,
+    /// Height of the last block to archive (inclusive).
+    #[arg(long)]
+    pub to: u64,
+    /// Directory to write the block archive into, created if missing.
+    #[arg(long)]
+    pub dir: PathBuf,
+}
+
+/// Directory containing a block archive.
+#[derive(Debug, Clone)]
+pub struct BlockArchive {
+    dir: PathBuf,
+}
+
+impl BlockArchive {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn genesis_path(&self) -> PathBuf {
+        self.dir.join("genesis.json")
+    }
+
+    fn block_path(&self, height: u64) -> PathBuf {
+        self.dir.join(format!("{}.json", height))
+    }
+
+    /// Read the information of the genesis block.
+    pub fn read_genesis(&self) -> anyhow::Result<GenesisData> {
+        let path = self.genesis_path();
+        let bytes =
+            std::fs::read(&path).with_context(|| format!("Failed reading {}", path.display()))?;
+        serde_json::from_slice(&bytes).with_context(|| format!("Failed parsing {}", path.display()))
+    }
+
+    /// Write the information of the genesis block.
+    fn write_genesis(&self, genesis: &GenesisData) -> anyhow::Result<()> {
+        write_file(&self.genesis_path(), &serde_json::to_vec(genesis)?)
+    }
+
+    /// Read the archived block at the given height. Returns `None` when the
+    /// block is not part of the archive.
+    fn read_block(&self, height: u64) -> anyhow::Result<Option<ArchivedBlock>> {
+        let path = self.block_path(height);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(err).with_context(|| format!("Failed reading {}", path.display()))
+            }
+        };
+        let block: ArchivedBlock = serde_json::from_slice(&bytes)
+            .with_context(|| format!("Failed parsing {}", path.display()))?;
+        anyhow::ensure!(
+            block.height.height == height,
+            "Archived block in {} is at height {}",
+            path.display(),
+            block.height
+        );
+        Ok(Some(block))
+    }
+
+    /// Write an archived block.
+    fn write_block(&self, block: &ArchivedBlock) -> anyhow::Result<()> {
+        write_file(
+            &self.block_path(block.height.height),
+            &serde_json::to_vec(block)?,
+        )
+    }
+
+    /// Read and prepare the archived block at the given height, answering the
+    /// node queries using the recorded responses. Returns `None` when the
+    /// block is not part of the archive.
+    async fn prepare_block(
+        &self,
+        height: u64,
+        domains: IndexingDomains,
+    ) -> anyhow::Result<Option<PreparedBlock>> {
+        let archive = self.clone();
+        let Some(block) = tokio::task::spawn_blocking(move || archive.read_block(height)).await??
+        else {
+            return Ok(None);
+        };
+        let (data, queries) = block.into_parts()?;
+        let prepared_block =
+            PreparedBlock::prepare(&mut NodeQueries::from(queries), &data, domains)
+                .await
+                .with_context(|| format!("Failed preparing archived block at height {}", height))?;
+        Ok(Some(prepared_block))
+    }
+}
+
+/// Write a file of the archive, going through a temporary file such that an
+/// interrupted write never leaves a partial file behind.
+fn write_file(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, contents)
+        .with_context(|| format!("Failed writing {}", tmp_path.display()))?;
+    std::fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed writing {}", path.display()))?;
+    Ok(())
+}
+
+/// Raw information of a block as stored in the archive.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedBlock {
+    block_hash: sdk_types::hashes::BlockHash,
+    height: AbsoluteBlockHeight,
+    block_info: BlockInfo,
+    events: Vec<BlockItemSummary>,
+    /// Block items using their binary serialization, hex encoded.
+    items: Vec<String>,
+    chain_parameters: sdk_types::chain_parameters::ChainParameters,
+    tokenomics_info: RewardsOverview,
+    total_staked: Amount,
+    special_events: Vec<SpecialTransactionOutcome>,
+    certificates: BlockCertificates,
+    validator_staking_information: super::block::ValidatorStakingInformation,
+    /// Block items and special transaction outcomes which could not be parsed.
+    #[serde(default)]
+    quarantined: Vec<QuarantinedItem>,
+    /// Responses of the node queries made while preparing the block.
+    queries: RecordedQueries,
+}
+
+impl ArchivedBlock {
+    fn new(data: BlockData, queries: RecordedQueries) -> Self {
+        Self {
+            block_hash: data.finalized_block_info.block_hash,
+            height: data.finalized_block_info.height,
+            block_info: data.block_info,
+            events: data.events,
+            items: data
+                .items
+                .iter()
+                .map(|item| hex::encode(common::to_bytes(item)))
+                .collect(),
+            chain_parameters: data.chain_parameters,
+            tokenomics_info: data.tokenomics_info,
+            total_staked: data.total_staked,
+            special_events: data.special_events,
+            certificates: data.certificates,
+            validator_staking_information: data.validator_staking_information,
+            quarantined: data.quarantined,
+            queries,
+        }
+    }
+
+    fn into_parts(self) -> anyhow::Result<(BlockData, RecordedQueries)> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| {
+                let bytes = hex::decode(item)?;
+                let item: BlockItem<EncodedPayload> = common::from_bytes(&mut bytes.as_slice())?;
+                anyhow::Ok(item)
+            })
+            .collect::<anyhow::Result<_>>()
+            .context("Failed decoding archived block item")?;
+        let data = BlockData {
+            finalized_block_info: v2::FinalizedBlockInfo {
+                block_hash: self.block_hash,
+                height: self.height,
+            },
+            block_info: self.block_info,
+            events: self.events,
+            items,
+            chain_parameters: self.chain_parameters,
+            tokenomics_info: self.tokenomics_info,
+            total_staked: self.total_staked,
+            special_events: self.special_events,
+            certificates: self.certificates,
+            validator_staking_information: self.validator_staking_information,
+            quarantined: self.quarantined,
+        };
+        Ok((data, self.queries))
+    }
+}
+
+/// Indexer fetching the blocks for the archive, preparing each block while
+/// recording the responses of the node queries made.
+struct BlockArchiver {
+    /// Block preprocessor used for checking the network of the nodes and
+    /// tracking failures.
+    block_pre_processor: BlockPreProcessor,
+}
+
+#[tonic::async_trait]
+impl Indexer for BlockArchiver {
+    type Context = NodeMetricLabels;
+    type Data = ArchivedBlock;
+
+    async fn on_connect<'a>(
+        &mut self,
+        endpoint: v2::Endpoint,
+        client: &'a mut v2::Client,
+    ) -> v2::QueryResult<Self::Context> {
+        self.block_pre_processor.on_connect(endpoint, client).await
+    }
+
+    async fn on_finalized<'a>(
+        &self,
+        client: v2::Client,
+        _label: &'a Self::Context,
+        fbi: v2::FinalizedBlockInfo,
+    ) -> OnFinalizationResult<Self::Data> {
+        let data = BlockData::fetch(&client, fbi).await?;
+        let mut node_queries = NodeQueries::recording(client);
+        // Every domain is prepared, such that the archive can be ingested using any indexing
+        // profile.
+        PreparedBlock::prepare(&mut node_queries, &data, IndexingDomains::all()).await?;
+        let queries = node_queries
+            .take_recorded()
+            .context("Node queries were not recorded")?;
+        Ok(ArchivedBlock::new(data, queries))
+    }
+
+    async fn on_failure(
+        &mut self,
+        endpoint: v2::Endpoint,
+        successive_failures: u64,
+        err: TraverseError,
+    ) -> bool {
+        self.block_pre_processor
+            .on_failure(endpoint, successive_failures, err)
+            .await
+    }
+}
+
+/// Write the blocks in the range `from..=to` fetched from the nodes into the
+/// block archive, together with the information of the genesis block.
+pub async fn run_archive(
+    endpoints: Vec<v2::Endpoint>,
+    config: &IndexerServiceConfig,
+    args: ArchiveArgs,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.from <= args.to,
+        "The start of the range (--from {}) is after the end of the range (--to {})",
+        args.from,
+        args.to
+    );
+    anyhow::ensure!(
+        args.from > 0,
+        "The genesis block is always archived, start the range from height 1"
+    );
+    let endpoint = endpoints
+        .first()
+        .context("At least one Concordium Node must be provided")?
+        .clone();
+    std::fs::create_dir_all(&args.dir)
+        .with_context(|| format!("Failed creating directory {}", args.dir.display()))?;
+    let archive = BlockArchive::new(args.dir);
+    let genesis = GenesisData::fetch(endpoint)
+        .await
+        .context("Failed fetching the genesis block")?;
+    archive.write_genesis(&genesis)?;
+
+    // Metrics of the block preprocessor are not exposed while archiving.
+    let mut registry = Registry::default();
+    let block_archiver = BlockArchiver {
+        block_pre_processor: BlockPreProcessor::new(
+            genesis.block_hash,
+            config.max_successive_failures.into(),
+            IndexingDomains::all(),
+            &mut registry,
+        ),
+    };
+    let traverse_config = TraverseConfig::new(endpoints, args.from.into())
+        .context("Failed setting up TraverseConfig")?
+        .set_max_parallel(config.max_parallel_block_preprocessors);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(config.max_processing_batch);
+    let traverse_future = tokio::spawn(traverse_config.traverse(block_archiver, sender));
+
+    info!(
+        "Archiving block heights {} to {} into {}",
+        args.from,
+        args.to,
+        archive.dir.display()
+    );
+    let mut next_height = args.from;
+    while next_height <= args.to {
+        let Some(block) = receiver.recv().await else {
+            // The traversal stopped before the end of the range, report its error if any.
+            traverse_future.await??;
+            anyhow::bail!(
+                "Fetching blocks from the node stopped at height {}",
+                next_height
+            );
+        };
+        anyhow::ensure!(
+            block.height.height == next_height,
+            "Received block at height {} while expecting height {}",
+            block.height,
+            next_height
+        );
+        archive.write_block(&block)?;
+        if next_height % 1000 == 0 {
+            info!("Archived up to block height {}", next_height);
+        }
+        next_height += 1;
+    }
+    drop(receiver);
+    traverse_future.abort();
+    info!("Archived block heights {} to {}", args.from, args.to);
+    Ok(())
+}
+
+/// Prepare the blocks of the archive starting from `start_height` and send
+/// them to the block processor, until reaching the end of the archive or the
+/// block processor stops. Fails when the block at `start_height` is not part
+/// of the archive.
+pub async fn ingest(
+    archive: BlockArchive,
+    start_height: u64,
+    max_parallel: usize,
+    domains: IndexingDomains,
+    sender: tokio::sync::mpsc::Sender<PreparedBlock>,
+) -> anyhow::Result<()> {
+    let mut blocks = futures::stream::iter(start_height..)
+        .map(|height| {
+            let archive = archive.clone();
+            async move { archive.prepare_block(height, domains).await }
+        })
+        .buffered(max_parallel);
+    let mut next_height = start_height;
+    while let Some(block) = blocks.try_next().await? {
+        let Some(block) = block else {
+            anyhow::ensure!(
+                next_height > start_height,
+                "The block at height {} to continue indexing from is not part of the block \
+                 archive in {}",
+                start_height,
+                archive.dir.display()
+            );
+            info!(
+                "Reached the end of the block archive, no block at height {}",
+                next_height
+            );
+            break;
+        };
+        next_height += 1;
+        if sender.send(block).await.is_err() {
+            // The block processor stopped.
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Directory of a block archive written by the `archive` subcommand, as
+    /// provided by `CCDSCAN_TEST_BLOCK_ARCHIVE`.
+    fn test_archive() -> Option<BlockArchive> {
+        let Ok(dir) = std::env::var("CCDSCAN_TEST_BLOCK_ARCHIVE") else {
+            eprintln!(
+                "Skipping test using a block archive, as CCDSCAN_TEST_BLOCK_ARCHIVE is not set"
+            );
+            return None;
+        };
+        Some(BlockArchive::new(dir.into()))
+    }
+
+    /// Heights of the blocks in the archive.
+    fn archived_heights(archive: &BlockArchive) -> Vec<u64> {
+        let mut heights = Vec::new();
+        for entry in std::fs::read_dir(&archive.dir).unwrap() {
+            let file_name = entry.unwrap().file_name();
+            if let Some(height) = file_name
+                .to_str()
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|height| height.parse::<u64>().ok())
+            {
+                heights.push(height);
+            }
+        }
+        assert!(!heights.is_empty(), "The block archive contains no blocks");
+        heights.sort();
+        heights
+    }
+
+    #[test]
+    fn test_archived_blocks_round_trip() {
+        let Some(archive) = test_archive() else {
+            return;
+        };
+        archive.read_genesis().unwrap();
+        for height in archived_heights(&archive) {
+            let path = archive.block_path(height);
+            let stored: serde_json::Value =
+                serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+            let block = archive.read_block(height).unwrap().unwrap();
+            assert_eq!(
+                serde_json::to_value(&block).unwrap(),
+                stored,
+                "Serializing the block read from {} differs",
+                path.display()
+            );
+            // Rebuilding the archived block from the decoded block items preserves it.
+            let (data, queries) = block.into_parts().unwrap();
+            assert_eq!(
+                serde_json::to_value(ArchivedBlock::new(data, queries)).unwrap(),
+                stored,
+                "Re-encoding the block read from {} differs",
+                path.display()
+            );
+        }
+    }
+
+    /// Expects the archive to be ingested into the test database, as done by
+    /// the CI.
+    #[tokio::test]
+    async fn test_ingested_blocks_match_archive() {
+        let Some(archive) = test_archive() else {
+            return;
+        };
+        let Some(pool) = crate::test_database::connect().await else {
+            return;
+        };
+        let genesis_hash: String = sqlx::query_scalar("SELECT hash FROM blocks WHERE height = 0")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(
+            genesis_hash,
+            archive.read_genesis().unwrap().block_hash.to_string()
+        );
+        for height in archived_heights(&archive) {
+            let block = archive.read_block(height).unwrap().unwrap();
+            let (hash, slot_time): (String, chrono::DateTime<chrono::Utc>) =
+                sqlx::query_as("SELECT hash, slot_time FROM blocks WHERE height = $1")
+                    .bind(i64::try_from(height).unwrap())
+                    .fetch_optional(&pool)
+                    .await
+                    .unwrap()
+                    .unwrap_or_else(|| panic!("Block at height {} is not indexed", height));
+            assert_eq!(hash, block.block_hash.to_string());
+            assert_eq!(slot_time, block.block_info.block_slot_time);
+            // Every block item which could be parsed is indexed as a transaction, in order.
+            let transaction_hashes: Vec<String> = sqlx::query_scalar(
+                "SELECT hash FROM transactions WHERE block_height = $1 ORDER BY index",
+            )
+            .bind(i64::try_from(height).unwrap())
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+            let archived_hashes: Vec<String> = block
+                .events
+                .iter()
+                .map(|event| event.hash.to_string())
+                .collect();
+            assert_eq!(
+                transaction_hashes, archived_hashes,
+                "Transactions of block at height {} differ",
+                height
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ingest_fails_when_start_height_is_missing() {
+        let dir =
+            std::env::temp_dir().join(format!("ccdscan-empty-archive-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(1);
+        let result = ingest(
+            BlockArchive::new(dir.clone()),
+            5,
+            2,
+            IndexingDomains::all(),
+            sender,
+        )
+        .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+        let err = result.unwrap_err();
+        assert!(
+            err.to_string().contains("height 5"),
+            "Unexpected error: {}",
+            err
+        );
+        assert!(receiver.recv().await.is_none());
+    }
+}
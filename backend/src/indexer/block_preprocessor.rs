@@ -4,9 +4,13 @@
 //! and compute as much work as possible without depending on a database
 //! connection, reducing the work needed during the sequential processing step.
 
-use crate::indexer::block::ValidatorStakingInformation;
+use crate::{indexer::block::ValidatorStakingInformation, indexing_profile::IndexingDomains};
 
-use super::block::PreparedBlock;
+use super::{
+    block::PreparedBlock,
+    node_queries::NodeQueries,
+    quarantine::{QuarantinedItem, QuarantinedItemKind},
+};
 use anyhow::Context;
 use concordium_rust_sdk::{
     base::transactions::{BlockItem, EncodedPayload},
@@ -49,11 +53,14 @@ pub struct BlockPreProcessor {
     /// Max number of acceptable successive failures before shutting down the
     /// service.
     max_successive_failures: u64,
+    /// The domains of derived data to prepare.
+    domains: IndexingDomains,
 }
 impl BlockPreProcessor {
     pub fn new(
         genesis_hash: sdk_types::hashes::BlockHash,
         max_successive_failures: u64,
+        domains: IndexingDomains,
         registry: &mut Registry,
     ) -> Self {
         let established_node_connections = Family::default();
@@ -91,6 +98,7 @@ impl BlockPreProcessor {
             blocks_being_preprocessed,
             node_response_time,
             max_successive_failures,
+            domains,
         }
     }
 }
@@ -156,7 +164,7 @@ impl concordium_rust_sdk::indexer::Indexer for BlockPreProcessor {
     /// next endpoint.
     async fn on_finalized<'a>(
         &self,
-        mut client: v2::Client,
+        client: v2::Client,
         label: &'a Self::Context,
         fbi: v2::FinalizedBlockInfo,
     ) -> OnFinalizationResult<Self::Data> {
@@ -165,119 +173,10 @@ impl concordium_rust_sdk::indexer::Indexer for BlockPreProcessor {
         // We block together the computation, so we can update the metric in the error
         // case, before returning early.
         let result: Result<PreparedBlock, OnFinalizationError> = async move {
-            let mut client1 = client.clone();
-            let mut client2 = client.clone();
-            let mut client3 = client.clone();
-            let mut client4 = client.clone();
-            let mut client5 = client.clone();
-            let mut client6 = client.clone();
-            let get_block_info = async move {
-                let block_info = client1.get_block_info(fbi.height).await?.response;
-
-                // Fetching the block certificates prior to P6 results in a InvalidArgument gRPC
-                // error, so we produce the empty type of certificates instead.
-                // The information is only used when preparing blocks for P8 and up.
-                let certificates = if block_info.protocol_version
-                    < ProtocolVersionInt::from(ProtocolVersion::P8)
-                {
-                    BlockCertificates {
-                        quorum_certificate: None,
-                        timeout_certificate: None,
-                        epoch_finalization_entry: None,
-                    }
-                } else {
-                    let response = client1.get_block_certificates(fbi.height).await?;
-                    response.response
-                };
-                Ok::<_, OnFinalizationError>((block_info, certificates))
-            };
-
-            let get_events = async move {
-                let events = client2
-                    .get_block_transaction_events(fbi.height)
-                    .await?
-                    .response
-                    .try_collect::<Vec<_>>()
-                    .await?;
-                Ok(events)
-            };
-
-            let get_tokenomics_info = async move {
-                let tokenomics_info = client3.get_tokenomics_info(fbi.height).await?.response;
-
-                let computed_validator_staking_details = compute_validator_staking_information(
-                    &mut client3,
-                    v2::BlockIdentifier::AbsoluteHeight(fbi.height),
-                )
-                .await?;
-
-                Ok((tokenomics_info, computed_validator_staking_details))
-            };
-
-            let get_items = async move {
-                let upward_block_items = client4
-                    .get_block_items(fbi.height)
-                    .await?
-                    .response
-                    .try_collect::<Vec<_>>()
-                    .await?;
-
-                let items = upward_block_items
-                    .into_iter()
-                    .flat_map(|a| a.known_or_err())
-                    .collect();
-
-                Ok(items)
-            };
-
-            let get_special_items = async move {
-                let upward_special_transaction_outcomes = client5
-                    .get_block_special_events(fbi.height)
-                    .await?
-                    .response
-                    .try_collect::<Vec<_>>()
-                    .await?;
-
-                let items = upward_special_transaction_outcomes
-                    .into_iter()
-                    .flat_map(|a| a.known_or_err())
-                    .collect();
-
-                Ok(items)
-            };
-
             let start_fetching = Instant::now();
-            let (
-                (block_info, certificates),
-                chain_parameters,
-                (tokenomics_info, (total_staked, validator_staking_information)),
-                events,
-                items,
-                special_events,
-            ) = try_join!(
-                get_block_info,
-                client6
-                    .get_block_chain_parameters(fbi.height)
-                    .map_err(OnFinalizationError::from),
-                get_tokenomics_info,
-                get_events,
-                get_items,
-                get_special_items
-            )?;
-            let data = BlockData {
-                finalized_block_info: fbi,
-                block_info,
-                events,
-                items,
-                chain_parameters: chain_parameters.response,
-                tokenomics_info,
-                total_staked,
-                special_events,
-                certificates,
-                validator_staking_information,
-            };
-
-            let prepared_block = PreparedBlock::prepare(&mut client, &data).await?;
+            let data = BlockData::fetch(&client, fbi).await?;
+            let prepared_block =
+                PreparedBlock::prepare(&mut NodeQueries::from(client), &data, self.domains).await?;
 
             let node_response_time = start_fetching.elapsed();
             self.node_response_time
@@ -433,4 +332,190 @@ pub struct BlockData {
     pub certificates: BlockCertificates,
     // validators current staking information
     pub validator_staking_information: ValidatorStakingInformation,
+    /// Block items and special transaction outcomes which could not be parsed,
+    /// these are left out of `items`, `events` and `special_events`.
+    pub quarantined: Vec<QuarantinedItem>,
+}
+
+impl BlockData {
+    /// Fetch the raw information of a finalized block from a Concordium Node.
+    pub async fn fetch(
+        client: &v2::Client,
+        fbi: v2::FinalizedBlockInfo,
+    ) -> OnFinalizationResult<Self> {
+        let mut client1 = client.clone();
+        let mut client2 = client.clone();
+        let mut client3 = client.clone();
+        let mut client4 = client.clone();
+        let mut client5 = client.clone();
+        let mut client6 = client.clone();
+        let get_block_info = async move {
+            let block_info = client1.get_block_info(fbi.height).await?.response;
+
+            // Fetching the block certificates prior to P6 results in a InvalidArgument gRPC
+            // error, so we produce the empty type of certificates instead.
+            // The information is only used when preparing blocks for P8 and up.
+            let certificates =
+                if block_info.protocol_version < ProtocolVersionInt::from(ProtocolVersion::P8) {
+                    BlockCertificates {
+                        quorum_certificate: None,
+                        timeout_certificate: None,
+                        epoch_finalization_entry: None,
+                    }
+                } else {
+                    let response = client1.get_block_certificates(fbi.height).await?;
+                    response.response
+                };
+            Ok::<_, OnFinalizationError>((block_info, certificates))
+        };
+
+        let get_events = async move {
+            let events = client2
+                .get_block_transaction_events(fbi.height)
+                .await?
+                .response
+                .try_collect::<Vec<_>>()
+                .await?;
+            Ok(events)
+        };
+
+        let get_tokenomics_info = async move {
+            let tokenomics_info = client3.get_tokenomics_info(fbi.height).await?.response;
+
+            let computed_validator_staking_details = compute_validator_staking_information(
+                &mut client3,
+                v2::BlockIdentifier::AbsoluteHeight(fbi.height),
+            )
+            .await?;
+
+            Ok((tokenomics_info, computed_validator_staking_details))
+        };
+
+        let get_items = async move {
+            let upward_block_items = client4
+                .get_block_items(fbi.height)
+                .await?
+                .response
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            let mut items = Vec::with_capacity(upward_block_items.len());
+            let mut unknown_indices = Vec::new();
+            for (index, item) in upward_block_items.into_iter().enumerate() {
+                match item.known_or_err() {
+                    Ok(item) => items.push(item),
+                    Err(_) => unknown_indices.push(index),
+                }
+            }
+
+            Ok((items, unknown_indices))
+        };
+
+        let get_special_items = async move {
+            let upward_special_transaction_outcomes = client5
+                .get_block_special_events(fbi.height)
+                .await?
+                .response
+                .try_collect::<Vec<_>>()
+                .await?;
+
+            let mut items = Vec::with_capacity(upward_special_transaction_outcomes.len());
+            let mut unknown_indices = Vec::new();
+            for (index, item) in upward_special_transaction_outcomes.into_iter().enumerate() {
+                match item.known_or_err() {
+                    Ok(item) => items.push(item),
+                    Err(_) => unknown_indices.push(index),
+                }
+            }
+
+            Ok((items, unknown_indices))
+        };
+
+        let (
+            (block_info, certificates),
+            chain_parameters,
+            (tokenomics_info, (total_staked, validator_staking_information)),
+            all_events,
+            (items, unknown_item_indices),
+            (special_events, unknown_special_event_indices),
+        ) = try_join!(
+            get_block_info,
+            client6
+                .get_block_chain_parameters(fbi.height)
+                .map_err(OnFinalizationError::from),
+            get_tokenomics_info,
+            get_events,
+            get_items,
+            get_special_items
+        )?;
+
+        // Block items which could not be parsed are quarantined together with the
+        // summary of their outcome, keeping the remaining summaries aligned with the
+        // block items.
+        let mut quarantined: Vec<QuarantinedItem> = unknown_item_indices
+            .into_iter()
+            .map(|index| QuarantinedItem {
+                kind: QuarantinedItemKind::BlockItem,
+                index,
+                summary: None,
+            })
+            .collect();
+        let mut events = Vec::with_capacity(items.len());
+        for (index, event) in all_events.into_iter().enumerate() {
+            if let Some(item) = quarantined.iter_mut().find(|item| item.index == index) {
+                item.summary = Some(event);
+            } else {
+                events.push(event);
+            }
+        }
+        quarantined.extend(unknown_special_event_indices.into_iter().map(|index| {
+            QuarantinedItem {
+                kind: QuarantinedItemKind::SpecialEvent,
+                index,
+                summary: None,
+            }
+        }));
+        Ok(Self {
+            finalized_block_info: fbi,
+            block_info,
+            events,
+            items,
+            chain_parameters: chain_parameters.response,
+            tokenomics_info,
+            total_staked,
+            special_events,
+            certificates,
+            validator_staking_information,
+            quarantined,
+        })
+    }
+
+    /// Position of a block item or special transaction outcome among those
+    /// which could be parsed, given its index in the block. Returns `None` when
+    /// the item is quarantined.
+    pub fn known_position(&self, kind: QuarantinedItemKind, index: usize) -> Option<usize> {
+        let mut quarantined_before = 0;
+        for item in self.quarantined.iter().filter(|item| item.kind == kind) {
+            if item.index == index {
+                return None;
+            }
+            if item.index < index {
+                quarantined_before += 1;
+            }
+        }
+        Some(index - quarantined_before)
+    }
+
+    /// Indices in the block of the special transaction outcomes which could be
+    /// parsed, skipping those quarantined.
+    pub fn special_event_indices(&self) -> anyhow::Result<Vec<i64>> {
+        (0..)
+            .filter(|index| {
+                self.known_position(QuarantinedItemKind::SpecialEvent, *index)
+                    .is_some()
+            })
+            .take(self.special_events.len())
+            .map(|index| Ok(i64::try_from(index)?))
+            .collect()
+    }
 }
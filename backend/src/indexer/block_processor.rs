@@ -4,7 +4,10 @@
 //! found in the chain. This step has access to a database connection and is
 //! responsible for updating the data in the database.
 
-use super::block::PreparedBlock;
+use super::{
+    block::PreparedBlock,
+    quarantine::{HaltOnUnknownError, QuarantineMetricLabels, StoppedOnQuarantineError},
+};
 use crate::indexer::acquire_indexer_lock;
 use anyhow::Context;
 use chrono::{DateTime, Utc};
@@ -12,6 +15,7 @@ use concordium_rust_sdk::indexer::{async_trait, ProcessEvent};
 use prometheus_client::{
     metrics::{
         counter::Counter,
+        family::Family,
         gauge::Gauge,
         histogram::{self, Histogram},
     },
@@ -48,6 +52,17 @@ pub struct BlockProcessor {
     last_processed_block_height: Gauge<i64>,
     /// Metric tracking the last processed block slot time
     last_processed_block_slot_time: Gauge<i64>,
+    /// Metric counting the block items and special transaction outcomes which
+    /// could not be parsed and were quarantined.
+    quarantined_items: Family<QuarantineMetricLabels, Counter>,
+    /// Stop processing when a block contains items which could not be parsed,
+    /// instead of quarantining them.
+    halt_on_unknown: bool,
+    /// Continue processing after a block containing quarantined items,
+    /// instead of stopping once the block is saved.
+    continue_past_quarantine: bool,
+    /// Write the content of every block to the outbox of the event sinks.
+    event_sinks_enabled: bool,
 }
 impl BlockProcessor {
     /// Construct the block processor by loading the initial state from the
@@ -58,6 +73,9 @@ impl BlockProcessor {
         mut db_connection: PgConnection,
         database_indexer_lock_timeout: Duration,
         max_successive_failures: u32,
+        halt_on_unknown: bool,
+        continue_past_quarantine: bool,
+        event_sinks_enabled: bool,
         registry: &mut Registry,
     ) -> anyhow::Result<Self> {
         let last_finalized_block = sqlx::query!(
@@ -127,6 +145,13 @@ impl BlockProcessor {
             last_processed_block_slot_time.clone(),
         );
 
+        let quarantined_items = Family::default();
+        registry.register(
+            "quarantined_items",
+            "Number of block items and special transaction outcomes which could not be parsed",
+            quarantined_items.clone(),
+        );
+
         Ok(Self {
             db_connect_options,
             db_connection,
@@ -138,6 +163,10 @@ impl BlockProcessor {
             max_successive_failures,
             last_processed_block_height,
             last_processed_block_slot_time,
+            quarantined_items,
+            halt_on_unknown,
+            continue_past_quarantine,
+            event_sinks_enabled,
         })
     }
 }
@@ -160,6 +189,23 @@ impl ProcessEvent for BlockProcessor {
     /// failed attempts.
     async fn process(&mut self, batch: &Self::Data) -> Result<Self::Description, Self::Error> {
         let start_time = Instant::now();
+        if self.halt_on_unknown {
+            for block in batch {
+                block.quarantined_items.ensure_empty()?;
+            }
+        }
+        // Unless continuing past quarantined items, the blocks after the first block
+        // containing these are left for the next start of the indexer, such that the
+        // items can be re-processed as part of the last indexed block.
+        let (batch, stop_at) = match batch
+            .iter()
+            .position(|block| !block.quarantined_items.is_empty())
+        {
+            Some(position) if !self.continue_past_quarantine => {
+                (&batch[..=position], Some(&batch[position]))
+            }
+            _ => (batch.as_slice(), None),
+        };
         let mut out = format!("Processed {} blocks:", batch.len());
         // Clone the context, to avoid mutating the current context until we are certain
         // nothing fails.
@@ -173,6 +219,9 @@ impl ProcessEvent for BlockProcessor {
         PreparedBlock::batch_save(batch, &mut new_context, &mut tx).await?;
         for block in batch {
             block.process_block_content(&mut tx).await?;
+            if self.event_sinks_enabled {
+                block.save_to_event_sink_outbox(&mut tx, false).await?;
+            }
             out.push_str(format!("\n- {}:{}", block.height, block.hash).as_str());
         }
         process_release_schedules(new_context.last_block_slot_time, &mut tx)
@@ -183,6 +232,9 @@ impl ProcessEvent for BlockProcessor {
             .context("Failed to commit SQL transaction")?;
 
         // set prometheus metrics tracking the latest block height and slot time
+        for block in batch {
+            block.quarantined_items.observe(&self.quarantined_items);
+        }
         if let Some(last_block) = batch.last() {
             self.last_processed_block_height.set(last_block.height);
             self.last_processed_block_slot_time
@@ -194,6 +246,9 @@ impl ProcessEvent for BlockProcessor {
         self.processing_duration_seconds
             .observe(duration.as_secs_f64());
         self.current_context = new_context;
+        if let Some(block) = stop_at {
+            block.quarantined_items.stop_after_saving()?;
+        }
         Ok(out)
     }
 
@@ -211,6 +266,10 @@ impl ProcessEvent for BlockProcessor {
         error: Self::Error,
         successive_failures: u32,
     ) -> Result<bool, Self::Error> {
+        // Retrying cannot make the items of the block parsable.
+        if error.is::<HaltOnUnknownError>() || error.is::<StoppedOnQuarantineError>() {
+            return Err(error);
+        }
         info!(
             "Failed processing {} times in row: \n{:?}",
             successive_failures, error
@@ -2,7 +2,11 @@
 //! building the account statements index.
 
 use crate::{
-    graphql_api::AccountStatementEntryType, indexer::ensure_affected_rows::EnsureAffectedRows,
+    graphql_api::AccountStatementEntryType,
+    indexer::{
+        ensure_affected_rows::EnsureAffectedRows,
+        reindex::{ReindexContext, ReindexDomain},
+    },
 };
 use anyhow::Context;
 use concordium_rust_sdk::{
@@ -76,6 +80,38 @@ impl PreparedUpdateAccountBalance {
         self.account_statement.save(tx, transaction_index).await?;
         Ok(())
     }
+
+    /// Update the balance and add the account statement when re-indexing the
+    /// account statements. The account statement reuses the identifier of a
+    /// cleared account statement.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        context: &mut ReindexContext,
+        transaction_index: Option<i64>,
+    ) -> anyhow::Result<()> {
+        if context.domain != ReindexDomain::Statements || self.change == 0 {
+            return Ok(());
+        }
+        sqlx::query!(
+            "UPDATE accounts SET amount = amount + $1 WHERE canonical_address = $2",
+            self.change,
+            self.canonical_address.0.as_slice(),
+        )
+        .execute(tx.as_mut())
+        .await?
+        .ensure_affected_one_row()
+        .with_context(|| {
+            format!(
+                "Failed re-indexing update to account balance, change: {}, canonical address: \
+                 {:?}",
+                self.change, self.canonical_address
+            )
+        })?;
+        self.account_statement
+            .reindex(tx, transaction_index, context.next_reusable_id())
+            .await
+    }
 }
 
 /// Database operation for adding new row into the account statement table.
@@ -133,4 +169,50 @@ impl PreparedAccountStatement {
 
         Ok(())
     }
+
+    /// Insert the account statement using the provided identifier, or otherwise
+    /// the next identifier. The balance of the account after the statement is
+    /// recomputed once the range being re-indexed is rebuilt.
+    async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: Option<i64>,
+        id: Option<i64>,
+    ) -> anyhow::Result<()> {
+        sqlx::query!(
+            "INSERT INTO account_statements (
+                id,
+                account_index,
+                entry_type,
+                amount,
+                block_height,
+                transaction_id,
+                account_balance,
+                slot_time
+            )
+            OVERRIDING SYSTEM VALUE
+            SELECT
+                COALESCE($6, nextval(pg_get_serial_sequence('account_statements', 'id'))),
+                index,
+                $2,
+                $3,
+                $4,
+                $5,
+                amount,
+                (SELECT slot_time FROM blocks WHERE height = $4)
+            FROM accounts
+            WHERE canonical_address = $1",
+            self.canonical_address.0.as_slice(),
+            self.transaction_type as AccountStatementEntryType,
+            self.amount,
+            self.block_height,
+            transaction_index,
+            id
+        )
+        .execute(tx.as_mut())
+        .await?
+        .ensure_affected_one_row()
+        .with_context(|| format!("Failed re-inserting into account_statements: {:?}", self))?;
+        Ok(())
+    }
 }
@@ -0,0 +1,746 @@
+//! Delivery of the content of indexed blocks to additional outputs, called
+//! event sinks, such as files or message queues feeding a data warehouse.
+//!
+//! While event sinks are configured, the block processor writes the content of
+//! every block, including its transactions, events, special transaction
+//! outcomes (such as rewards) and token movements, into the
+//! `event_sink_outbox` table in the same database transaction as the block.
+//! Blocks rebuilt by re-indexing or re-processing quarantined items are
+//! written to the outbox again, marked as re-emitted. A dispatcher per event
+//! sink delivers the blocks in the order they were written and persists the
+//! position of the last delivered entry as the cursor of the event sink in
+//! `event_sink_cursors`. An entry is removed from the outbox once delivered to
+//! every configured event sink.
+//!
+//! Blocks are delivered at least once: failed deliveries are retried until
+//! they succeed, and a block can be delivered again if the dispatcher stops
+//! between delivering it and moving the cursor. Consumers can deduplicate
+//! using the block height, keeping the content delivered last.
+
+use super::ensure_affected_rows::EnsureAffectedRows;
+use crate::{
+    retry::retry_delay,
+    transaction_event::{
+        protocol_level_tokens::{TokenEventDetails, TokenUpdate},
+        CisEvent,
+    },
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::indexer::async_trait;
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family, gauge::Gauge},
+    registry::Registry,
+};
+use reqwest::header;
+use sqlx::{postgres::PgConnectOptions, Connection as _, PgConnection};
+use std::{
+    io::Write as _,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+/// Interval for checking the outbox for blocks to deliver.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The most blocks delivered to an event sink at once.
+const DELIVERY_BATCH_SIZE: i64 = 100;
+/// Delay before retrying after the first failure, doubled for every successive
+/// failure.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+/// The longest delay between retries.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+/// Header containing the name of the event sink in the requests of the queue
+/// event sink.
+const SINK_NAME_HEADER: &str = "x-ccdscan-event-sink";
+
+/// Content of a block delivered to the event sinks.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SinkBlock {
+    pub height: i64,
+    pub hash: String,
+    pub slot_time: DateTime<Utc>,
+    pub baker_id: Option<i64>,
+    pub total_amount: i64,
+    pub total_staked: i64,
+    pub transactions: Vec<SinkTransaction>,
+    /// Special transaction outcomes of the block, such as rewards.
+    pub special_events: Vec<SinkSpecialEvent>,
+    /// CIS-2 and protocol-level token mints, burns and transfers.
+    pub token_movements: Vec<SinkTokenMovement>,
+    /// Whether the block was rebuilt after being delivered, replacing the
+    /// content delivered before.
+    pub reemitted: bool,
+}
+
+/// Transaction of a block delivered to the event sinks.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SinkTransaction {
+    /// Index of the transaction among the transactions of the block, excluding
+    /// quarantined block items.
+    pub index: usize,
+    pub hash: String,
+    pub sender: Option<String>,
+    pub sponsor: Option<String>,
+    /// Either `Account`, `CredentialDeployment` or `Update`.
+    pub transaction_type: String,
+    /// The type of account transaction, credential deployment or update.
+    pub kind: Option<String>,
+    pub ccd_cost: i64,
+    pub energy_cost: i64,
+    pub success: bool,
+    /// Events of successful transactions, as exposed by the API.
+    pub events: Option<serde_json::Value>,
+    /// Reject reason of failed transactions.
+    pub reject_reason: Option<serde_json::Value>,
+}
+
+/// Special transaction outcome of a block delivered to the event sinks.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SinkSpecialEvent {
+    /// Index of the outcome among the special transaction outcomes of the
+    /// block.
+    pub index: i64,
+    pub outcome: serde_json::Value,
+}
+
+/// Movement of a token caused by a transaction, delivered to the event sinks.
+#[derive(Debug, serde::Serialize)]
+#[serde(
+    tag = "standard",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum SinkTokenMovement {
+    /// CIS-2 token event logged by a smart contract instance.
+    Cis2 {
+        transaction_hash: String,
+        contract_index: i64,
+        contract_sub_index: i64,
+        event: serde_json::Value,
+    },
+    /// Protocol-level token event.
+    Plt {
+        transaction_hash: String,
+        event: serde_json::Value,
+    },
+}
+
+impl SinkTokenMovement {
+    /// Collect the CIS-2 events moving tokens, that is transfers, mints and
+    /// burns, logged by the contract instance.
+    pub fn collect_cis2(
+        events: &[CisEvent],
+        transaction_hash: &str,
+        contract_index: i64,
+        contract_sub_index: i64,
+        movements: &mut Vec<Self>,
+    ) -> anyhow::Result<()> {
+        for event in events {
+            if matches!(
+                event,
+                CisEvent::Transfer(_) | CisEvent::Mint(_) | CisEvent::Burn(_)
+            ) {
+                movements.push(Self::Cis2 {
+                    transaction_hash: transaction_hash.to_string(),
+                    contract_index,
+                    contract_sub_index,
+                    event: serde_json::to_value(event)?,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Collect the protocol-level token events moving tokens, that is
+    /// transfers, mints and burns.
+    pub fn collect_plt<'a>(
+        events: impl IntoIterator<Item = &'a TokenUpdate>,
+        transaction_hash: &str,
+        movements: &mut Vec<Self>,
+    ) -> anyhow::Result<()> {
+        for event in events {
+            if !matches!(event.event, TokenEventDetails::Module(_)) {
+                movements.push(Self::Plt {
+                    transaction_hash: transaction_hash.to_string(),
+                    event: serde_json::to_value(event)?,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Configuration of an event sink, provided as `<name>=jsonl:<path>` or
+/// `<name>=queue:<url>`.
+#[derive(Debug, Clone)]
+pub struct EventSinkConfig {
+    /// Name identifying the cursor of the event sink.
+    pub name: String,
+    pub kind: EventSinkKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum EventSinkKind {
+    /// Append every block as a line of JSON to the file.
+    Jsonl(PathBuf),
+    /// POST batches of blocks as newline-delimited JSON to the HTTP ingestion
+    /// endpoint of a message queue.
+    Queue(reqwest::Url),
+}
+
+impl FromStr for EventSinkConfig {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, sink) = s
+            .split_once('=')
+            .context("Expected an event sink of the form <name>=<kind>:<target>")?;
+        anyhow::ensure!(
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+            "The name of an event sink must be non-empty and only contain ASCII letters, digits, \
+             '-' and '_'"
+        );
+        let (kind, target) = sink
+            .split_once(':')
+            .context("Expected an event sink of the form <name>=<kind>:<target>")?;
+        let kind = match kind {
+            "jsonl" => EventSinkKind::Jsonl(PathBuf::from(target)),
+            "queue" => EventSinkKind::Queue(
+                target
+                    .parse()
+                    .with_context(|| format!("Invalid URL of event sink '{}'", name))?,
+            ),
+            kind => anyhow::bail!(
+                "Unknown kind '{}' of event sink '{}', expected 'jsonl' or 'queue'",
+                kind,
+                name
+            ),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            kind,
+        })
+    }
+}
+
+impl EventSinkConfig {
+    fn build(&self) -> Box<dyn EventSink> {
+        match &self.kind {
+            EventSinkKind::Jsonl(path) => Box::new(JsonlSink { path: path.clone() }),
+            EventSinkKind::Queue(url) => Box::new(QueueSink {
+                name: self.name.clone(),
+                client: reqwest::Client::new(),
+                url: url.clone(),
+            }),
+        }
+    }
+}
+
+/// An output receiving the content of the indexed blocks.
+#[async_trait]
+pub trait EventSink: Send {
+    /// Deliver a batch of blocks, in the order they were written to the outbox.
+    /// The batch is delivered again when failing.
+    async fn deliver(&mut self, blocks: &[serde_json::Value]) -> anyhow::Result<()>;
+}
+
+/// Event sink appending every block as a line of JSON to a file.
+struct JsonlSink {
+    path: PathBuf,
+}
+
+#[async_trait]
+impl EventSink for JsonlSink {
+    async fn deliver(&mut self, blocks: &[serde_json::Value]) -> anyhow::Result<()> {
+        let body = ndjson(blocks)?;
+        let path = self.path.clone();
+        tokio::task::spawn_blocking(move || append_file(&path, &body)).await?
+    }
+}
+
+/// Append to the file, waiting for the data to be written to disk.
+fn append_file(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed opening {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("Failed writing {}", path.display()))?;
+    file.sync_data()
+        .with_context(|| format!("Failed writing {}", path.display()))?;
+    Ok(())
+}
+
+/// Event sink sending batches of blocks as newline-delimited JSON in the body
+/// of POST requests, such as to the HTTP ingestion endpoint of a message
+/// queue. Any successful response status acknowledges the batch.
+struct QueueSink {
+    name: String,
+    client: reqwest::Client,
+    url: reqwest::Url,
+}
+
+#[async_trait]
+impl EventSink for QueueSink {
+    async fn deliver(&mut self, blocks: &[serde_json::Value]) -> anyhow::Result<()> {
+        self.client
+            .post(self.url.clone())
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .header(SINK_NAME_HEADER, &self.name)
+            .body(ndjson(blocks)?)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Serialize the blocks as newline-delimited JSON.
+fn ndjson(blocks: &[serde_json::Value]) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    for block in blocks {
+        serde_json::to_writer(&mut body, block)?;
+        body.push(b'\n');
+    }
+    Ok(body)
+}
+
+/// Labels of the metrics of the event sinks.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct SinkLabels {
+    sink: String,
+}
+
+/// Metrics of the event sinks.
+#[derive(Clone)]
+pub struct EventSinkMetrics {
+    /// Number of blocks delivered.
+    delivered_blocks: Family<SinkLabels, Counter>,
+    /// Number of failed deliveries.
+    delivery_failures: Family<SinkLabels, Counter>,
+    /// Height of the last delivered block.
+    last_delivered_height: Family<SinkLabels, Gauge>,
+}
+
+impl EventSinkMetrics {
+    pub fn new(registry: &mut Registry) -> Self {
+        let delivered_blocks = Family::default();
+        registry.register(
+            "delivered_blocks",
+            "Number of blocks delivered to the event sink",
+            delivered_blocks.clone(),
+        );
+        let delivery_failures = Family::default();
+        registry.register(
+            "delivery_failures",
+            "Number of failed deliveries to the event sink",
+            delivery_failures.clone(),
+        );
+        let last_delivered_height = Family::default();
+        registry.register(
+            "last_delivered_height",
+            "Height of the last block delivered to the event sink",
+            last_delivered_height.clone(),
+        );
+        Self {
+            delivered_blocks,
+            delivery_failures,
+            last_delivered_height,
+        }
+    }
+}
+
+/// Delivers the blocks of the outbox to a single event sink.
+pub struct EventSinkDispatcher {
+    name: String,
+    sink: Box<dyn EventSink>,
+    /// Names of every configured event sink, used for removing blocks
+    /// delivered to all of them from the outbox.
+    sink_names: Vec<String>,
+    db_connect_options: PgConnectOptions,
+    metrics: EventSinkMetrics,
+    labels: SinkLabels,
+}
+
+impl EventSinkDispatcher {
+    /// Construct a dispatcher for each of the configured event sinks.
+    pub fn new_all(
+        configs: &[EventSinkConfig],
+        db_connect_options: &PgConnectOptions,
+        registry: &mut Registry,
+    ) -> anyhow::Result<Vec<Self>> {
+        let sink_names: Vec<String> = configs.iter().map(|config| config.name.clone()).collect();
+        for (i, name) in sink_names.iter().enumerate() {
+            anyhow::ensure!(
+                !sink_names[..i].contains(name),
+                "Event sink '{}' is configured more than once",
+                name
+            );
+        }
+        let metrics = EventSinkMetrics::new(registry);
+        Ok(configs
+            .iter()
+            .map(|config| Self {
+                name: config.name.clone(),
+                sink: config.build(),
+                sink_names: sink_names.clone(),
+                db_connect_options: db_connect_options.clone(),
+                metrics: metrics.clone(),
+                labels: SinkLabels {
+                    sink: config.name.clone(),
+                },
+            })
+            .collect())
+    }
+
+    /// Deliver blocks until signaled to stop by the `cancel_token`. Failures
+    /// are retried with exponential backoff.
+    pub async fn run(mut self, cancel_token: CancellationToken) {
+        info!("Delivering blocks to event sink '{}'", self.name);
+        let mut db_connection = None;
+        let mut successive_failures = 0;
+        loop {
+            let result = async {
+                if db_connection.is_none() {
+                    db_connection = Some(
+                        PgConnection::connect_with(&self.db_connect_options)
+                            .await
+                            .context("Failed to establish the database connection")?,
+                    );
+                }
+                let Some(connection) = db_connection.as_mut() else {
+                    unreachable!("Connected above");
+                };
+                self.deliver_next(connection).await
+            }
+            .await;
+            let delay = match result {
+                // Continue right away with the next batch of blocks.
+                Ok(true) => {
+                    successive_failures = 0;
+                    Duration::ZERO
+                }
+                Ok(false) => {
+                    successive_failures = 0;
+                    POLL_INTERVAL
+                }
+                Err(err) => {
+                    error!("Failed delivering to event sink '{}': {:?}", self.name, err);
+                    self.metrics
+                        .delivery_failures
+                        .get_or_create(&self.labels)
+                        .inc();
+                    // Reconnect in case the connection was dropped.
+                    db_connection = None;
+                    successive_failures += 1;
+                    retry_delay(INITIAL_RETRY_DELAY, MAX_RETRY_DELAY, successive_failures)
+                }
+            };
+            if cancel_token
+                .run_until_cancelled(tokio::time::sleep(delay))
+                .await
+                .is_none()
+            {
+                break;
+            }
+        }
+        info!("Stopped delivering blocks to event sink '{}'", self.name);
+    }
+
+    /// Deliver the next batch of blocks from the outbox and move the cursor.
+    /// Returns whether any blocks were delivered.
+    async fn deliver_next(&mut self, db_connection: &mut PgConnection) -> anyhow::Result<bool> {
+        // Event sinks without a cursor start from the blocks indexed from now on.
+        sqlx::query!(
+            "INSERT INTO event_sink_cursors (name, last_delivered_id, last_delivered_height)
+            SELECT
+                $1,
+                (SELECT COALESCE(MAX(id), 0) FROM event_sink_outbox),
+                (SELECT MAX(height) FROM blocks)
+            ON CONFLICT (name) DO NOTHING",
+            self.name
+        )
+        .execute(db_connection.as_mut())
+        .await?;
+        let cursor = sqlx::query!(
+            "SELECT last_delivered_id, last_delivered_height
+            FROM event_sink_cursors
+            WHERE name = $1",
+            self.name
+        )
+        .fetch_one(db_connection.as_mut())
+        .await?;
+        let rows = sqlx::query!(
+            "SELECT id, block_height, payload
+            FROM event_sink_outbox
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2",
+            cursor.last_delivered_id,
+            DELIVERY_BATCH_SIZE
+        )
+        .fetch_all(db_connection.as_mut())
+        .await?;
+        let Some(last) = rows.last() else {
+            return Ok(false);
+        };
+        let last_id = last.id;
+        // Re-emitted blocks are at or below the last delivered height, while the
+        // blocks indexed afterwards continue from it.
+        if let Some(next) = rows
+            .iter()
+            .find(|row| row.block_height > cursor.last_delivered_height)
+        {
+            if next.block_height != cursor.last_delivered_height + 1 {
+                warn!(
+                    "Blocks {} to {} are missing from the outbox of event sink '{}', as they \
+                     were indexed while no event sinks were configured",
+                    cursor.last_delivered_height + 1,
+                    next.block_height - 1,
+                    self.name
+                );
+            }
+        }
+        let last_height = rows
+            .iter()
+            .map(|row| row.block_height)
+            .fold(cursor.last_delivered_height, i64::max);
+        let blocks: Vec<serde_json::Value> = rows.into_iter().map(|row| row.payload).collect();
+        self.sink.deliver(&blocks).await?;
+
+        let mut tx = db_connection.begin().await?;
+        sqlx::query!(
+            "UPDATE event_sink_cursors
+            SET last_delivered_id = $2, last_delivered_height = $3, updated_at = NOW()
+            WHERE name = $1",
+            self.name,
+            last_id,
+            last_height
+        )
+        .execute(tx.as_mut())
+        .await?
+        .ensure_affected_one_row()?;
+        // Remove the blocks delivered to every configured event sink.
+        sqlx::query!(
+            "DELETE FROM event_sink_outbox
+            WHERE id <= (
+                SELECT MIN(last_delivered_id)
+                FROM event_sink_cursors
+                WHERE name = ANY($1)
+            )",
+            &self.sink_names
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+
+        self.metrics
+            .delivered_blocks
+            .get_or_create(&self.labels)
+            .inc_by(blocks.len() as u64);
+        self.metrics
+            .last_delivered_height
+            .get_or_create(&self.labels)
+            .set(last_height);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    /// Height far above those of any indexed chain, such that the rows of the
+    /// tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+
+    /// Event sink recording the delivered blocks.
+    struct RecordingSink {
+        delivered: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    #[async_trait]
+    impl EventSink for RecordingSink {
+        async fn deliver(&mut self, blocks: &[serde_json::Value]) -> anyhow::Result<()> {
+            self.delivered.lock().unwrap().extend_from_slice(blocks);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_parse_event_sink_config() {
+        let config: EventSinkConfig = "warehouse=jsonl:/var/lib/blocks.jsonl".parse().unwrap();
+        assert_eq!(config.name, "warehouse");
+        let EventSinkKind::Jsonl(path) = config.kind else {
+            panic!("Expected a jsonl event sink");
+        };
+        assert_eq!(path.to_str(), Some("/var/lib/blocks.jsonl"));
+
+        let config: EventSinkConfig = "queue-1=queue:http://localhost:8080/topics/blocks"
+            .parse()
+            .unwrap();
+        assert_eq!(config.name, "queue-1");
+        let EventSinkKind::Queue(url) = config.kind else {
+            panic!("Expected a queue event sink");
+        };
+        assert_eq!(url.path(), "/topics/blocks");
+
+        assert!("jsonl:/var/lib/blocks.jsonl"
+            .parse::<EventSinkConfig>()
+            .is_err());
+        assert!("=jsonl:/var/lib/blocks.jsonl"
+            .parse::<EventSinkConfig>()
+            .is_err());
+        assert!("warehouse=kafka:localhost:9092"
+            .parse::<EventSinkConfig>()
+            .is_err());
+        assert!("warehouse=queue:not a url"
+            .parse::<EventSinkConfig>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let body = ndjson(&[json!({ "height": 1 }), json!({ "height": 2 })]).unwrap();
+        assert_eq!(body, b"{\"height\":1}\n{\"height\":2}\n");
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_sink_appends_batches() {
+        let path = std::env::temp_dir().join(format!("ccdscan-sink-{}.jsonl", std::process::id()));
+        let config: EventSinkConfig = format!("warehouse=jsonl:{}", path.display())
+            .parse()
+            .unwrap();
+        let mut sink = config.build();
+        sink.deliver(&[json!({ "height": 1 }), json!({ "height": 2 })])
+            .await
+            .unwrap();
+        sink.deliver(&[json!({ "height": 3 })]).await.unwrap();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(contents, "{\"height\":1}\n{\"height\":2}\n{\"height\":3}\n");
+    }
+
+    #[tokio::test]
+    async fn test_queue_sink_posts_batch_to_configured_url() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/topics/blocks")
+            .match_header("content-type", "application/x-ndjson")
+            .match_header("x-ccdscan-event-sink", "warehouse")
+            .match_body("{\"height\":1}\n{\"height\":2}\n")
+            .with_status(202)
+            .create_async()
+            .await;
+        let config: EventSinkConfig = format!("warehouse=queue:{}/topics/blocks", server.url())
+            .parse()
+            .unwrap();
+        let mut sink = config.build();
+        sink.deliver(&[json!({ "height": 1 }), json!({ "height": 2 })])
+            .await
+            .unwrap();
+        mock.assert_async().await;
+        mock.remove_async().await;
+
+        // Batches which are not acknowledged are delivered again.
+        server
+            .mock("POST", "/topics/blocks")
+            .with_status(503)
+            .create_async()
+            .await;
+        assert!(sink.deliver(&[json!({ "height": 3 })]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dispatcher_delivers_reemitted_blocks_in_outbox_order() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        for height in HEIGHT..HEIGHT + 3 {
+            test_database::insert_block(tx.as_mut(), height, Utc::now())
+                .await
+                .unwrap();
+        }
+        let start_id: i64 =
+            sqlx::query_scalar("SELECT COALESCE(MAX(id), 0) FROM event_sink_outbox")
+                .fetch_one(tx.as_mut())
+                .await
+                .unwrap();
+        sqlx::query(
+            "INSERT INTO event_sink_cursors (name, last_delivered_id, last_delivered_height)
+            VALUES ('test-sink', $1, $2)",
+        )
+        .bind(start_id)
+        .bind(HEIGHT)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        let blocks = [
+            json!({ "height": HEIGHT + 1, "reemitted": false }),
+            json!({ "height": HEIGHT + 2, "reemitted": false }),
+            json!({ "height": HEIGHT + 1, "reemitted": true }),
+        ];
+        let mut last_id = start_id;
+        for block in blocks.iter() {
+            last_id = sqlx::query_scalar(
+                "INSERT INTO event_sink_outbox (block_height, payload) VALUES ($1, $2)
+                RETURNING id",
+            )
+            .bind(block["height"].as_i64())
+            .bind(block)
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let delivered = Arc::new(Mutex::new(Vec::new()));
+        let mut dispatcher = EventSinkDispatcher {
+            name: "test-sink".to_string(),
+            sink: Box::new(RecordingSink {
+                delivered: delivered.clone(),
+            }),
+            sink_names: vec!["test-sink".to_string()],
+            db_connect_options: PgConnectOptions::new(),
+            metrics: EventSinkMetrics::new(&mut Registry::default()),
+            labels: SinkLabels {
+                sink: "test-sink".to_string(),
+            },
+        };
+        assert!(dispatcher.deliver_next(tx.as_mut()).await.unwrap());
+        assert!(!dispatcher.deliver_next(tx.as_mut()).await.unwrap());
+        assert_eq!(*delivered.lock().unwrap(), blocks);
+
+        let (last_delivered_id, last_delivered_height): (i64, i64) = sqlx::query_as(
+            "SELECT last_delivered_id, last_delivered_height
+            FROM event_sink_cursors
+            WHERE name = 'test-sink'",
+        )
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(last_delivered_id, last_id);
+        assert_eq!(last_delivered_height, HEIGHT + 2);
+        let remaining: i64 =
+            sqlx::query_scalar("SELECT COUNT(*) FROM event_sink_outbox WHERE block_height >= $1")
+                .bind(HEIGHT)
+                .fetch_one(tx.as_mut())
+                .await
+                .unwrap();
+        assert_eq!(remaining, 0);
+    }
+}
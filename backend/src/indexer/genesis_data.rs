@@ -4,45 +4,160 @@
 use super::block_preprocessor::compute_validator_staking_information;
 use crate::transaction_event::baker::BakerPoolOpenStatus;
 use anyhow::Context;
+use chrono::{DateTime, Utc};
 use concordium_rust_sdk::{
-    types::{AccountStakingInfo, PartsPerHundredThousands},
+    id::types::AccountAddress,
+    types::{hashes::BlockHash, AccountStakingInfo, PartsPerHundredThousands},
     v2,
 };
 use futures::{StreamExt, TryStreamExt};
 use sqlx::Connection;
 
-/// Function for initializing the database with the genesis block.
-/// This should only be called if the database is empty.
-pub async fn save_genesis_data(
-    endpoint: v2::Endpoint,
-    pool: &mut sqlx::PgConnection,
-) -> anyhow::Result<()> {
-    let mut client = v2::Client::new(endpoint)
-        .await
-        .context("Failed to establish connection to Concordium Node")?;
-    let mut tx = pool
-        .begin()
-        .await
-        .context("Failed to create SQL transaction")?;
-    let genesis_height = v2::BlockIdentifier::AbsoluteHeight(0.into());
+/// Information found in the genesis block, needed for initializing the
+/// database.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisData {
+    /// Hash of the genesis block.
+    pub block_hash: BlockHash,
+    slot_time: DateTime<Utc>,
+    total_amount: i64,
+    total_staked: i64,
+    /// Number of validators in the genesis block.
+    bakers_count: i64,
+    accounts: Vec<GenesisAccount>,
+}
+
+/// Account created in the genesis block.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenesisAccount {
+    index: i64,
+    address: AccountAddress,
+    amount: i64,
+    /// Present when the account is a validator in the genesis block.
+    baker: Option<GenesisBaker>,
+}
+
+/// Validator in the genesis block.
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GenesisBaker {
+    stake: i64,
+    restake_earnings: bool,
+    open_status: Option<BakerPoolOpenStatus>,
+    metadata_url: Option<String>,
+    transaction_commission: Option<i64>,
+    baking_commission: Option<i64>,
+    finalization_commission: Option<i64>,
+}
+
+impl GenesisData {
+    /// Fetch the information of the genesis block from a Concordium Node.
+    pub async fn fetch(endpoint: v2::Endpoint) -> anyhow::Result<Self> {
+        let mut client = v2::Client::new(endpoint)
+            .await
+            .context("Failed to establish connection to Concordium Node")?;
+        let genesis_height = v2::BlockIdentifier::AbsoluteHeight(0.into());
+
+        let genesis_block_info = client.get_block_info(genesis_height).await?.response;
+        let genesis_tokenomics = client.get_tokenomics_info(genesis_height).await?.response;
+
+        let (total_staked_capital, _) =
+            compute_validator_staking_information(&mut client, genesis_height).await?;
+        let total_staked = i64::try_from(total_staked_capital.micro_ccd())?;
+
+        let total_amount = i64::try_from(
+            genesis_tokenomics
+                .common_reward_data()
+                .total_amount
+                .micro_ccd(),
+        )?;
+        let bakers_count: i64 = client
+            .get_baker_list(genesis_height)
+            .await?
+            .response
+            .count()
+            .await
+            .try_into()?;
 
-    let genesis_block_info = client.get_block_info(genesis_height).await?.response;
-    let block_hash = genesis_block_info.block_hash.to_string();
-    let slot_time = genesis_block_info.block_slot_time;
-    let genesis_tokenomics = client.get_tokenomics_info(genesis_height).await?.response;
+        let mut accounts = Vec::new();
+        let mut genesis_accounts = client.get_account_list(genesis_height).await?.response;
+        while let Some(account) = genesis_accounts.try_next().await? {
+            let info = client
+                .get_account_info(&account.into(), genesis_height)
+                .await?
+                .response;
+            let baker = if let Some(upward_account_staking_info) = info.account_stake {
+                if let AccountStakingInfo::Baker {
+                    staked_amount,
+                    restake_earnings,
+                    baker_info: _,
+                    pending_change: _,
+                    pool_info,
+                    is_suspended: _,
+                } = upward_account_staking_info.known_or_err()?
+                {
+                    let open_status = if let Some(baker_pool_info) = pool_info.as_ref() {
+                        let open_status = baker_pool_info.open_status.known_or_err()?;
+                        Some(BakerPoolOpenStatus::from(open_status))
+                    } else {
+                        None
+                    };
+                    Some(GenesisBaker {
+                        stake: i64::try_from(staked_amount.micro_ccd())?,
+                        restake_earnings,
+                        open_status,
+                        metadata_url: pool_info.as_ref().map(|i| i.metadata_url.to_string()),
+                        transaction_commission: pool_info.as_ref().map(|i| {
+                            i64::from(u32::from(PartsPerHundredThousands::from(
+                                i.commission_rates.transaction,
+                            )))
+                        }),
+                        baking_commission: pool_info.as_ref().map(|i| {
+                            i64::from(u32::from(PartsPerHundredThousands::from(
+                                i.commission_rates.baking,
+                            )))
+                        }),
+                        finalization_commission: pool_info.as_ref().map(|i| {
+                            i64::from(u32::from(PartsPerHundredThousands::from(
+                                i.commission_rates.finalization,
+                            )))
+                        }),
+                    })
+                } else {
+                    None
+                }
+            } else {
+                None
+            };
+            accounts.push(GenesisAccount {
+                index: i64::try_from(info.account_index.index)?,
+                address: account,
+                amount: i64::try_from(info.account_amount.micro_ccd)?,
+                baker,
+            });
+        }
 
-    let (total_staked_capital, _) =
-        compute_validator_staking_information(&mut client, genesis_height).await?;
-    let total_staked = i64::try_from(total_staked_capital.micro_ccd())?;
+        Ok(Self {
+            block_hash: genesis_block_info.block_hash,
+            slot_time: genesis_block_info.block_slot_time,
+            total_amount,
+            total_staked,
+            bakers_count,
+            accounts,
+        })
+    }
 
-    let total_amount = i64::try_from(
-        genesis_tokenomics
-            .common_reward_data()
-            .total_amount
-            .micro_ccd(),
-    )?;
-    sqlx::query!(
-        "INSERT INTO blocks (
+    /// Initialize the database with the genesis block.
+    /// This should only be called if the database is empty.
+    pub async fn save(&self, pool: &mut sqlx::PgConnection) -> anyhow::Result<()> {
+        let mut tx = pool
+            .begin()
+            .await
+            .context("Failed to create SQL transaction")?;
+        sqlx::query!(
+            "INSERT INTO blocks (
             height,
             hash,
             slot_time,
@@ -52,109 +167,63 @@ pub async fn save_genesis_data(
             total_staked,
             cumulative_num_txs
         ) VALUES (0, $1, $2, 0, 0, $3, $4, 0);",
-        block_hash,
-        slot_time,
-        total_amount,
-        total_staked,
-    )
-    .execute(&mut *tx)
-    .await?;
-    let genesis_bakers_count: i64 = client
-        .get_baker_list(genesis_height)
-        .await?
-        .response
-        .count()
-        .await
-        .try_into()?;
-    sqlx::query!(
-        "INSERT INTO metrics_bakers (block_height, total_bakers_added, total_bakers_removed)
-        VALUES (0, $1, 0)",
-        genesis_bakers_count,
-    )
-    .execute(&mut *tx)
-    .await?;
-
-    let mut genesis_accounts = client.get_account_list(genesis_height).await?.response;
-    while let Some(account) = genesis_accounts.try_next().await? {
-        let info = client
-            .get_account_info(&account.into(), genesis_height)
-            .await?
-            .response;
-        let index = i64::try_from(info.account_index.index)?;
-        let account_address = account.to_string();
-        let canonical_address = account.get_canonical_address();
-        let amount = i64::try_from(info.account_amount.micro_ccd)?;
-
-        // Note that we override the usual default num_txs = 1 here
-        // because the genesis accounts do not have a creation transaction.
+            self.block_hash.to_string(),
+            self.slot_time,
+            self.total_amount,
+            self.total_staked,
+        )
+        .execute(&mut *tx)
+        .await?;
         sqlx::query!(
-            "INSERT INTO accounts (index, address, amount, canonical_address, num_txs)
-            VALUES ($1, $2, $3, $4, 0)",
-            index,
-            account_address,
-            amount,
-            canonical_address.0.as_slice()
+            "INSERT INTO metrics_bakers (block_height, total_bakers_added, total_bakers_removed)
+        VALUES (0, $1, 0)",
+            self.bakers_count,
         )
         .execute(&mut *tx)
         .await?;
 
-        if let Some(upward_account_staking_info) = info.account_stake {
-            if let AccountStakingInfo::Baker {
-                staked_amount,
-                restake_earnings,
-                baker_info: _,
-                pending_change: _,
-                pool_info,
-                is_suspended: _,
-            } = upward_account_staking_info.known_or_err()?
-            {
-                let stake = i64::try_from(staked_amount.micro_ccd())?;
-                let open_status = if let Some(baker_pool_info) = pool_info.as_ref() {
-                    let open_status = baker_pool_info.open_status.known_or_err()?;
-                    Some(BakerPoolOpenStatus::from(open_status))
-                } else {
-                    None
-                };
-                let metadata_url = pool_info.as_ref().map(|i| i.metadata_url.to_string());
-                let transaction_commission = pool_info.as_ref().map(|i| {
-                    i64::from(u32::from(PartsPerHundredThousands::from(
-                        i.commission_rates.transaction,
-                    )))
-                });
-                let baking_commission = pool_info.as_ref().map(|i| {
-                    i64::from(u32::from(PartsPerHundredThousands::from(
-                        i.commission_rates.baking,
-                    )))
-                });
-                let finalization_commission = pool_info.as_ref().map(|i| {
-                    i64::from(u32::from(PartsPerHundredThousands::from(
-                        i.commission_rates.finalization,
-                    )))
-                });
+        for account in &self.accounts {
+            let account_address = account.address.to_string();
+            let canonical_address = account.address.get_canonical_address();
+
+            // Note that we override the usual default num_txs = 1 here
+            // because the genesis accounts do not have a creation transaction.
+            sqlx::query!(
+                "INSERT INTO accounts (index, address, amount, canonical_address, num_txs)
+            VALUES ($1, $2, $3, $4, 0)",
+                account.index,
+                account_address,
+                account.amount,
+                canonical_address.0.as_slice()
+            )
+            .execute(&mut *tx)
+            .await?;
+
+            if let Some(baker) = &account.baker {
                 sqlx::query!(
                     "INSERT INTO bakers (id, staked, restake_earnings, open_status, metadata_url, \
                         transaction_commission, baking_commission, finalization_commission, \
                         pool_total_staked, pool_delegator_count)
                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
-                    index,
-                    stake,
-                    restake_earnings,
-                    open_status as Option<BakerPoolOpenStatus>,
-                    metadata_url,
-                    transaction_commission,
-                    baking_commission,
-                    finalization_commission,
-                    stake,
+                    account.index,
+                    baker.stake,
+                    baker.restake_earnings,
+                    baker.open_status as Option<BakerPoolOpenStatus>,
+                    baker.metadata_url,
+                    baker.transaction_commission,
+                    baker.baking_commission,
+                    baker.finalization_commission,
+                    baker.stake,
                     0
                 )
                 .execute(&mut *tx)
                 .await?;
             }
         }
-    }
 
-    tx.commit()
-        .await
-        .context("Failed to commit SQL transaction")?;
-    Ok(())
+        tx.commit()
+            .await
+            .context("Failed to commit SQL transaction")?;
+        Ok(())
+    }
 }
@@ -0,0 +1,473 @@
+//! Retention of the per-block rows of the metrics tables `metrics_bakers`,
+//! `metrics_rewards`, `metrics_plt_transfer` and `metrics_plt`.
+//!
+//! The per-block rows are periodically rolled up into aggregated buckets of
+//! each width in `metrics_rollup_progress` (hourly and daily), once every
+//! block of the bucket is indexed. The rewards are summed for each bucket,
+//! while the cumulative metrics keep the last value of each bucket. The
+//! per-block rows older than the retention window are then removed, except for
+//! the last row before the window of the cumulative metrics, which keeps
+//! serving as the starting point for the rows indexed after it.
+//!
+//! The API reads the aggregated buckets for the longer metrics periods,
+//! combined with the per-block rows which are not yet rolled up.
+
+use anyhow::Context;
+use chrono::{DateTime, TimeDelta, Utc};
+use prometheus_client::{metrics::counter::Counter, registry::Registry};
+use sqlx::{
+    postgres::{types::PgInterval, PgConnectOptions},
+    Connection as _, PgConnection,
+};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info};
+
+/// Delay before retrying after a failure.
+const RETRY_DELAY: Duration = Duration::from_secs(60);
+/// The shortest retention window, as the metrics periods up to the last 24
+/// hours are read from the per-block rows only.
+const MIN_RETENTION_DAYS: u32 = 2;
+
+/// Rolls up and removes the per-block rows of the metrics tables.
+pub struct MetricsRollup {
+    db_connect_options: PgConnectOptions,
+    /// Duration for which the per-block rows are kept.
+    retention: TimeDelta,
+    /// Interval between each roll up.
+    interval: Duration,
+    /// Number of per-block rows removed from the metrics tables.
+    removed_rows: Counter,
+    /// Number of failed roll ups.
+    failures: Counter,
+}
+
+impl MetricsRollup {
+    pub fn new(
+        db_connect_options: PgConnectOptions,
+        retention_days: u32,
+        interval: Duration,
+        registry: &mut Registry,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            retention_days >= MIN_RETENTION_DAYS,
+            "The per-block rows of the metrics must be kept for at least {} days",
+            MIN_RETENTION_DAYS
+        );
+        let removed_rows = Counter::default();
+        registry.register(
+            "removed_rows",
+            "Number of per-block rows removed from the metrics tables after rolling them up",
+            removed_rows.clone(),
+        );
+        let failures = Counter::default();
+        registry.register(
+            "failures",
+            "Number of failures rolling up the metrics tables",
+            failures.clone(),
+        );
+        Ok(Self {
+            db_connect_options,
+            retention: TimeDelta::days(retention_days.into()),
+            interval,
+            removed_rows,
+            failures,
+        })
+    }
+
+    /// Roll up the metrics periodically until signaled to stop by the
+    /// `cancel_token`.
+    pub async fn run(self, cancel_token: CancellationToken) {
+        info!(
+            "Rolling up the metrics, keeping the per-block rows for {} days",
+            self.retention.num_days()
+        );
+        let mut db_connection = None;
+        loop {
+            let result = async {
+                if db_connection.is_none() {
+                    db_connection = Some(
+                        PgConnection::connect_with(&self.db_connect_options)
+                            .await
+                            .context("Failed to establish the database connection")?,
+                    );
+                }
+                let Some(connection) = db_connection.as_mut() else {
+                    unreachable!("Connected above");
+                };
+                self.roll_up(connection).await
+            }
+            .await;
+            let delay = match result {
+                Ok(()) => self.interval,
+                Err(err) => {
+                    error!("Failed rolling up the metrics: {:?}", err);
+                    self.failures.inc();
+                    // Reconnect in case the connection was dropped.
+                    db_connection = None;
+                    RETRY_DELAY
+                }
+            };
+            if cancel_token
+                .run_until_cancelled(tokio::time::sleep(delay))
+                .await
+                .is_none()
+            {
+                break;
+            }
+        }
+        info!("Stopped rolling up the metrics");
+    }
+
+    /// Roll up the buckets of every width, which are completely indexed, and
+    /// remove the per-block rows which are rolled up and older than the
+    /// retention window.
+    async fn roll_up(&self, db_connection: &mut PgConnection) -> anyhow::Result<()> {
+        let bucket_widths = sqlx::query_scalar!(
+            "SELECT bucket_width FROM metrics_rollup_progress ORDER BY bucket_width"
+        )
+        .fetch_all(db_connection.as_mut())
+        .await?;
+        for bucket_width in bucket_widths {
+            roll_up_buckets(db_connection, bucket_width).await?;
+        }
+        let rolled_up_until = sqlx::query_scalar!(
+            r#"SELECT
+                CASE WHEN bool_and(rolled_up_until IS NOT NULL) THEN MIN(rolled_up_until) END
+                    AS rolled_up_until
+            FROM metrics_rollup_progress"#
+        )
+        .fetch_one(db_connection.as_mut())
+        .await?;
+        let Some(rolled_up_until) = rolled_up_until else {
+            return Ok(());
+        };
+        // Only rows which are rolled up into the buckets of every width are removed.
+        let cutoff = rolled_up_until.min(Utc::now() - self.retention);
+        let removed_rows = remove_rows_before(db_connection, cutoff).await?;
+        if removed_rows > 0 {
+            debug!(
+                "Removed {} per-block rows of the metrics before {}",
+                removed_rows, cutoff
+            );
+        }
+        self.removed_rows.inc_by(removed_rows);
+        Ok(())
+    }
+}
+
+/// Roll up the per-block rows into the buckets of the width, which have been
+/// completely indexed since the previous roll up.
+async fn roll_up_buckets(
+    db_connection: &mut PgConnection,
+    bucket_width: PgInterval,
+) -> anyhow::Result<()> {
+    let mut tx = db_connection.begin().await?;
+    let progress = sqlx::query!(
+        r#"SELECT
+            rolled_up_until,
+            date_bin(
+                $1::interval,
+                (SELECT slot_time FROM blocks ORDER BY height DESC LIMIT 1),
+                TIMESTAMPTZ '2001-01-01'
+            ) AS "until?"
+        FROM metrics_rollup_progress
+        WHERE bucket_width = $1
+        FOR UPDATE"#,
+        bucket_width
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+    // Blocks are indexed in order, so every block before the start of the bucket
+    // of the last indexed block has been indexed.
+    let Some(until) = progress.until else {
+        return Ok(());
+    };
+    let from = progress.rolled_up_until;
+    if from.is_some_and(|from| from >= until) {
+        return Ok(());
+    }
+    sqlx::query!(
+        "INSERT INTO metrics_bakers_rollups (
+            bucket_width,
+            bucket_start,
+            total_bakers_added,
+            total_bakers_removed
+        )
+        SELECT DISTINCT ON (bucket_start)
+            $1,
+            date_bin($1::interval, blocks.slot_time, TIMESTAMPTZ '2001-01-01') AS bucket_start,
+            total_bakers_added,
+            total_bakers_removed
+        FROM metrics_bakers
+        JOIN blocks ON blocks.height = metrics_bakers.block_height
+        WHERE
+            ($2::TIMESTAMPTZ IS NULL OR blocks.slot_time >= $2)
+            AND blocks.slot_time < $3
+        ORDER BY bucket_start, metrics_bakers.block_height DESC",
+        bucket_width,
+        from,
+        until
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "INSERT INTO metrics_rewards_rollups (bucket_width, bucket_start, account_index, amount)
+        SELECT
+            $1,
+            date_bin($1::interval, block_slot_time, TIMESTAMPTZ '2001-01-01') AS bucket_start,
+            account_index,
+            SUM(amount)::BIGINT
+        FROM metrics_rewards
+        WHERE
+            ($2::TIMESTAMPTZ IS NULL OR block_slot_time >= $2)
+            AND block_slot_time < $3
+        GROUP BY bucket_start, account_index",
+        bucket_width,
+        from,
+        until
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "INSERT INTO metrics_plt_transfer_rollups (
+            bucket_width,
+            bucket_start,
+            token_index,
+            cumulative_transfer_count,
+            cumulative_transfer_amount
+        )
+        SELECT DISTINCT ON (token_index, bucket_start)
+            $1,
+            date_bin($1::interval, event_timestamp, TIMESTAMPTZ '2001-01-01') AS bucket_start,
+            token_index,
+            cumulative_transfer_count,
+            cumulative_transfer_amount
+        FROM metrics_plt_transfer
+        WHERE
+            ($2::TIMESTAMPTZ IS NULL OR event_timestamp >= $2)
+            AND event_timestamp < $3
+        ORDER BY token_index, bucket_start, event_timestamp DESC",
+        bucket_width,
+        from,
+        until
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "INSERT INTO metrics_plt_rollups (
+            bucket_width,
+            bucket_start,
+            cumulative_event_count,
+            cumulative_transfer_amount,
+            unique_account_count
+        )
+        SELECT DISTINCT ON (bucket_start)
+            $1,
+            date_bin($1::interval, event_timestamp, TIMESTAMPTZ '2001-01-01') AS bucket_start,
+            cumulative_event_count,
+            cumulative_transfer_amount,
+            unique_account_count
+        FROM metrics_plt
+        WHERE
+            ($2::TIMESTAMPTZ IS NULL OR event_timestamp >= $2)
+            AND event_timestamp < $3
+        ORDER BY bucket_start, event_timestamp DESC",
+        bucket_width,
+        from,
+        until
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "UPDATE metrics_rollup_progress SET rolled_up_until = $2 WHERE bucket_width = $1",
+        bucket_width,
+        until
+    )
+    .execute(tx.as_mut())
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Remove the per-block rows of the metrics before the cutoff, keeping the
+/// last row before the cutoff of the cumulative metrics. Returns the number of
+/// removed rows.
+async fn remove_rows_before(
+    db_connection: &mut PgConnection,
+    cutoff: DateTime<Utc>,
+) -> anyhow::Result<u64> {
+    let mut tx = db_connection.begin().await?;
+    let mut removed_rows = sqlx::query!(
+        "DELETE FROM metrics_bakers
+        WHERE block_height < (
+            SELECT MAX(metrics_bakers.block_height)
+            FROM metrics_bakers
+            JOIN blocks ON blocks.height = metrics_bakers.block_height
+            WHERE blocks.slot_time < $1
+        )",
+        cutoff
+    )
+    .execute(tx.as_mut())
+    .await?
+    .rows_affected();
+    removed_rows += sqlx::query!(
+        "DELETE FROM metrics_rewards WHERE block_slot_time < $1",
+        cutoff
+    )
+    .execute(tx.as_mut())
+    .await?
+    .rows_affected();
+    removed_rows += sqlx::query!(
+        "DELETE FROM metrics_plt_transfer
+        WHERE event_timestamp < (
+            SELECT MAX(latest.event_timestamp)
+            FROM metrics_plt_transfer latest
+            WHERE
+                latest.token_index = metrics_plt_transfer.token_index
+                AND latest.event_timestamp < $1
+        )",
+        cutoff
+    )
+    .execute(tx.as_mut())
+    .await?
+    .rows_affected();
+    removed_rows += sqlx::query!(
+        "DELETE FROM metrics_plt
+        WHERE event_timestamp < (
+            SELECT MAX(event_timestamp) FROM metrics_plt WHERE event_timestamp < $1
+        )",
+        cutoff
+    )
+    .execute(tx.as_mut())
+    .await?
+    .rows_affected();
+    tx.commit().await?;
+    Ok(removed_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::TimeZone;
+    use concordium_rust_sdk::id::types::AccountAddress;
+
+    /// Heights and indices far above those of any indexed chain, such that the
+    /// rows of the tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    #[tokio::test]
+    async fn test_roll_up_and_remove_rows() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        let bucket_width: PgInterval = TimeDelta::hours(1).try_into().unwrap();
+        sqlx::query(
+            "UPDATE metrics_rollup_progress SET rolled_up_until = $2 WHERE bucket_width = $1",
+        )
+        .bind(bucket_width)
+        .bind(start)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &AccountAddress([0xfe; 32]), 0)
+            .await
+            .unwrap();
+        // The second block is exactly on the boundary between the first two buckets,
+        // and the last block is in the bucket which is not completely indexed.
+        for (offset, minutes, reward, bakers_added) in [
+            (0, 10, 5, 1),
+            (1, 60, 7, 2),
+            (2, 90, 11, 3),
+            (3, 130, 13, 4),
+        ] {
+            let slot_time = start + TimeDelta::minutes(minutes);
+            test_database::insert_block(tx.as_mut(), HEIGHT + offset, slot_time)
+                .await
+                .unwrap();
+            sqlx::query(
+                "INSERT INTO metrics_rewards (block_height, block_slot_time, account_index, amount)
+                VALUES ($1, $2, $3, $4)",
+            )
+            .bind(HEIGHT + offset)
+            .bind(slot_time)
+            .bind(ACCOUNT)
+            .bind(reward)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO metrics_bakers (block_height, total_bakers_added, total_bakers_removed)
+                VALUES ($1, $2, 0)",
+            )
+            .bind(HEIGHT + offset)
+            .bind(bakers_added)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        // Rolling up again without new blocks leaves the buckets unchanged.
+        for _ in 0..2 {
+            roll_up_buckets(tx.as_mut(), bucket_width).await.unwrap();
+        }
+        let rewards: Vec<(DateTime<Utc>, i64)> = sqlx::query_as(
+            "SELECT bucket_start, amount FROM metrics_rewards_rollups
+            WHERE bucket_width = $1 AND account_index = $2
+            ORDER BY bucket_start",
+        )
+        .bind(bucket_width)
+        .bind(ACCOUNT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(
+            rewards,
+            vec![(start, 5), (start + TimeDelta::hours(1), 7 + 11)]
+        );
+        let bakers: Vec<(DateTime<Utc>, i64)> = sqlx::query_as(
+            "SELECT bucket_start, total_bakers_added FROM metrics_bakers_rollups
+            WHERE bucket_width = $1 AND bucket_start >= $2
+            ORDER BY bucket_start",
+        )
+        .bind(bucket_width)
+        .bind(start)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(bakers, vec![(start, 1), (start + TimeDelta::hours(1), 3)]);
+        let rolled_up_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+            "SELECT rolled_up_until FROM metrics_rollup_progress WHERE bucket_width = $1",
+        )
+        .bind(bucket_width)
+        .fetch_one(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(rolled_up_until, Some(start + TimeDelta::hours(2)));
+
+        // The last row of the cumulative metrics before the cutoff is kept.
+        remove_rows_before(tx.as_mut(), start + TimeDelta::hours(2))
+            .await
+            .unwrap();
+        let rewards: Vec<i64> = sqlx::query_scalar(
+            "SELECT block_height FROM metrics_rewards WHERE account_index = $1 ORDER BY block_height",
+        )
+        .bind(ACCOUNT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(rewards, vec![HEIGHT + 3]);
+        let bakers: Vec<i64> = sqlx::query_scalar(
+            "SELECT block_height FROM metrics_bakers WHERE block_height >= $1 ORDER BY block_height",
+        )
+        .bind(HEIGHT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(bakers, vec![HEIGHT + 2, HEIGHT + 3]);
+    }
+}
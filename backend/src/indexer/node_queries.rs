@@ -0,0 +1,334 @@
+//! Queries to a Concordium Node made while preparing a block.
+//!
+//! Most of the information about a block is fetched up front into
+//! [`BlockData`](super::block_preprocessor::BlockData), but preparing some of
+//! the events requires further queries to the node. These are made through
+//! [`NodeQueries`], which either queries the node, optionally recording the
+//! responses such that they can be stored in a block archive, or answers the
+//! queries using the responses recorded in a block archive.
+
+use anyhow::Context;
+use concordium_rust_sdk::{
+    cis0,
+    types::{
+        self as sdk_types, smart_contracts::WasmModule, AbsoluteBlockHeight, BakerId,
+        BakerPoolStatus, BakerRewardPeriodInfo, BirkBaker, ContractAddress,
+        DelegatorRewardPeriodInfo, PassiveDelegationStatus,
+    },
+    v2,
+};
+use futures::TryStreamExt;
+use std::sync::{Arc, Mutex};
+
+/// Responses of the queries made while preparing a single block. Every query
+/// is made against the block itself, except for the module sources, which are
+/// always queried in the last finalized block.
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RecordedQueries {
+    /// Whether smart contract instances support the CIS-2 standard.
+    cis2_support: Vec<(ContractAddress, bool)>,
+    /// Sources of the smart contract modules deployed in the block.
+    module_sources: Vec<(sdk_types::hashes::ModuleReference, WasmModule)>,
+    /// Reward period information of the validator pools, for payday blocks.
+    bakers_reward_period: Option<Vec<BakerRewardPeriodInfo>>,
+    /// Reward period information of the passive delegators, for payday
+    /// blocks.
+    passive_delegators_reward_period: Option<Vec<DelegatorRewardPeriodInfo>>,
+    /// Status of passive delegation, for payday blocks.
+    passive_delegation_info: Option<PassiveDelegationStatus>,
+    /// Validators of the election, for payday blocks.
+    election_bakers: Option<Vec<BirkBaker>>,
+    /// Validators of the block, for the first block of a protocol version.
+    baker_list: Option<Vec<BakerId>>,
+    /// Pool information of the validators, for the first block of a protocol
+    /// version.
+    pool_infos: Vec<(BakerId, BakerPoolStatus)>,
+}
+
+/// Source of the answers to the queries made while preparing a block.
+#[derive(Clone)]
+pub enum NodeQueries {
+    /// Query the Concordium Node, recording the responses when provided.
+    Node {
+        client: v2::Client,
+        recorded: Option<Arc<Mutex<RecordedQueries>>>,
+    },
+    /// Answer the queries using the responses recorded in a block archive.
+    Archive(Arc<RecordedQueries>),
+}
+
+impl From<v2::Client> for NodeQueries {
+    fn from(client: v2::Client) -> Self {
+        Self::Node {
+            client,
+            recorded: None,
+        }
+    }
+}
+
+impl From<RecordedQueries> for NodeQueries {
+    fn from(recorded: RecordedQueries) -> Self {
+        Self::Archive(Arc::new(recorded))
+    }
+}
+
+impl NodeQueries {
+    /// Query the Concordium Node, recording every response.
+    pub fn recording(client: v2::Client) -> Self {
+        Self::Node {
+            client,
+            recorded: Some(Arc::default()),
+        }
+    }
+
+    /// Take the responses recorded so far. Returns `None` when not recording.
+    pub fn take_recorded(&self) -> Option<RecordedQueries> {
+        let Self::Node {
+            recorded: Some(recorded),
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let mut recorded = recorded.lock().expect("Recorded queries lock poisoned");
+        Some(std::mem::take(&mut *recorded))
+    }
+
+    /// Record a response, when recording.
+    fn record(
+        recorded: &Option<Arc<Mutex<RecordedQueries>>>,
+        update: impl FnOnce(&mut RecordedQueries),
+    ) {
+        if let Some(recorded) = recorded {
+            update(&mut recorded.lock().expect("Recorded queries lock poisoned"));
+        }
+    }
+
+    /// Check whether a smart contract instance supports the CIS-2 standard in
+    /// the given block. The name of the contract is looked up, when not
+    /// provided. A failing support query is considered as no support.
+    pub async fn supports_cis2(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+        contract_address: ContractAddress,
+        contract_name: Option<sdk_types::smart_contracts::OwnedContractName>,
+    ) -> anyhow::Result<bool> {
+        match self {
+            Self::Node { client, recorded } => {
+                let block = v2::BlockIdentifier::AbsoluteHeight(block_height);
+                let contract_name = if let Some(contract_name) = contract_name {
+                    contract_name
+                } else {
+                    client
+                        .get_instance_info(contract_address, &block)
+                        .await?
+                        .response
+                        .name()
+                        .clone()
+                };
+                let supports_cis2 = cis0::supports(
+                    client,
+                    &block,
+                    contract_address,
+                    contract_name.as_contract_name(),
+                    cis0::StandardIdentifier::CIS2,
+                )
+                .await
+                .is_ok_and(|r| r.response.is_support());
+                Self::record(recorded, |r| {
+                    r.cis2_support.push((contract_address, supports_cis2))
+                });
+                Ok(supports_cis2)
+            }
+            Self::Archive(recorded) => recorded
+                .cis2_support
+                .iter()
+                .find(|(address, _)| *address == contract_address)
+                .map(|(_, supports_cis2)| *supports_cis2)
+                .with_context(|| {
+                    format!(
+                        "Missing CIS-2 support of contract {} in the block archive",
+                        contract_address
+                    )
+                }),
+        }
+    }
+
+    /// Get the source of a smart contract module in the last finalized block.
+    pub async fn get_module_source(
+        &mut self,
+        module_reference: sdk_types::hashes::ModuleReference,
+    ) -> anyhow::Result<WasmModule> {
+        match self {
+            Self::Node { client, recorded } => {
+                let wasm_module = client
+                    .get_module_source(&module_reference, v2::BlockIdentifier::LastFinal)
+                    .await?
+                    .response;
+                Self::record(recorded, |r| {
+                    r.module_sources
+                        .push((module_reference, wasm_module.clone()))
+                });
+                Ok(wasm_module)
+            }
+            Self::Archive(recorded) => recorded
+                .module_sources
+                .iter()
+                .find(|(reference, _)| *reference == module_reference)
+                .map(|(_, wasm_module)| wasm_module.clone())
+                .with_context(|| {
+                    format!(
+                        "Missing source of module {} in the block archive",
+                        module_reference
+                    )
+                }),
+        }
+    }
+
+    /// Get the reward period information of the validator pools.
+    pub async fn get_bakers_reward_period(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+    ) -> anyhow::Result<Vec<BakerRewardPeriodInfo>> {
+        match self {
+            Self::Node { client, recorded } => {
+                let infos: Vec<_> = client
+                    .get_bakers_reward_period(v2::BlockIdentifier::AbsoluteHeight(block_height))
+                    .await?
+                    .response
+                    .try_collect()
+                    .await?;
+                Self::record(recorded, |r| r.bakers_reward_period = Some(infos.clone()));
+                Ok(infos)
+            }
+            Self::Archive(recorded) => recorded
+                .bakers_reward_period
+                .clone()
+                .context("Missing reward period of the validators in the block archive"),
+        }
+    }
+
+    /// Get the reward period information of the passive delegators.
+    pub async fn get_passive_delegators_reward_period(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+    ) -> anyhow::Result<Vec<DelegatorRewardPeriodInfo>> {
+        match self {
+            Self::Node { client, recorded } => {
+                let infos: Vec<_> = client
+                    .get_passive_delegators_reward_period(v2::BlockIdentifier::AbsoluteHeight(
+                        block_height,
+                    ))
+                    .await?
+                    .response
+                    .try_collect()
+                    .await?;
+                Self::record(recorded, |r| {
+                    r.passive_delegators_reward_period = Some(infos.clone())
+                });
+                Ok(infos)
+            }
+            Self::Archive(recorded) => recorded
+                .passive_delegators_reward_period
+                .clone()
+                .context("Missing reward period of the passive delegators in the block archive"),
+        }
+    }
+
+    /// Get the status of passive delegation.
+    pub async fn get_passive_delegation_info(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+    ) -> anyhow::Result<PassiveDelegationStatus> {
+        match self {
+            Self::Node { client, recorded } => {
+                let status = client
+                    .get_passive_delegation_info(v2::BlockIdentifier::AbsoluteHeight(block_height))
+                    .await?
+                    .response;
+                Self::record(recorded, |r| {
+                    r.passive_delegation_info = Some(status.clone())
+                });
+                Ok(status)
+            }
+            Self::Archive(recorded) => recorded
+                .passive_delegation_info
+                .clone()
+                .context("Missing passive delegation status in the block archive"),
+        }
+    }
+
+    /// Get the validators of the election information.
+    pub async fn get_election_bakers(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+    ) -> anyhow::Result<Vec<BirkBaker>> {
+        match self {
+            Self::Node { client, recorded } => {
+                let bakers = client
+                    .get_election_info(v2::BlockIdentifier::AbsoluteHeight(block_height))
+                    .await?
+                    .response
+                    .bakers;
+                Self::record(recorded, |r| r.election_bakers = Some(bakers.clone()));
+                Ok(bakers)
+            }
+            Self::Archive(recorded) => recorded
+                .election_bakers
+                .clone()
+                .context("Missing election information in the block archive"),
+        }
+    }
+
+    /// Get the list of validators.
+    pub async fn get_baker_list(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+    ) -> anyhow::Result<Vec<BakerId>> {
+        match self {
+            Self::Node { client, recorded } => {
+                let baker_ids: Vec<_> = client
+                    .get_baker_list(block_height)
+                    .await?
+                    .response
+                    .try_collect()
+                    .await?;
+                Self::record(recorded, |r| r.baker_list = Some(baker_ids.clone()));
+                Ok(baker_ids)
+            }
+            Self::Archive(recorded) => recorded
+                .baker_list
+                .clone()
+                .context("Missing list of validators in the block archive"),
+        }
+    }
+
+    /// Get the pool information of a validator.
+    pub async fn get_pool_info(
+        &mut self,
+        block_height: AbsoluteBlockHeight,
+        baker_id: BakerId,
+    ) -> anyhow::Result<BakerPoolStatus> {
+        match self {
+            Self::Node { client, recorded } => {
+                let pool_info = client.get_pool_info(block_height, baker_id).await?.response;
+                Self::record(recorded, |r| {
+                    r.pool_infos.push((baker_id, pool_info.clone()))
+                });
+                Ok(pool_info)
+            }
+            Self::Archive(recorded) => recorded
+                .pool_infos
+                .iter()
+                .find(|(id, _)| *id == baker_id)
+                .map(|(_, pool_info)| pool_info.clone())
+                .with_context(|| {
+                    format!(
+                        "Missing pool information of validator {} in the block archive",
+                        baker_id
+                    )
+                }),
+        }
+    }
+}
@@ -0,0 +1,433 @@
+//! Quarantine of block items and special transaction outcomes which could not
+//! be parsed.
+//!
+//! After a protocol update the node can report kinds of block items and
+//! special transaction outcomes which are unknown to the indexer. Instead of
+//! dropping these, the indexer records their position in the block in the
+//! `quarantined_items` table, saves the rest of the block and stops, unless
+//! configured to halt before saving the block or to continue past it. Once the
+//! indexer is upgraded, the quarantined items are re-processed using the
+//! `reprocess-quarantine` subcommand, which fetches the block containing them
+//! again from the node. As the items are processed on top of the current state,
+//! this is refused once blocks after the quarantined items are indexed, and
+//! the indexer refuses to continue past the block while its items are still
+//! quarantined.
+//!
+//! The raw bytes of the items are not recorded, as the SDK drops the items it
+//! cannot parse without exposing their serialization. Instead, the hash and
+//! the summary of the outcome reported by the node are recorded for block
+//! items, and only the position for special transaction outcomes.
+
+use super::{
+    block::{
+        block_item::PreparedBlockItem,
+        special_transaction_outcomes::PreparedSpecialTransactionOutcomes, PreparedBlock,
+    },
+    block_preprocessor::BlockData,
+    node_queries::NodeQueries,
+    statistics::Statistics,
+    IndexerServiceConfig,
+};
+use crate::indexing_profile;
+use anyhow::Context;
+use concordium_rust_sdk::{
+    types::{self as sdk_types, AbsoluteBlockHeight, BlockItemSummary},
+    v2,
+};
+use prometheus_client::metrics::{counter::Counter, family::Family};
+use sqlx::{Connection as _, PgConnection};
+use std::collections::BTreeMap;
+use tracing::{info, warn};
+
+/// Kind of an item of a block which could not be parsed.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    sqlx::Type,
+    prometheus_client::encoding::EncodeLabelValue,
+)]
+#[sqlx(type_name = "quarantined_item_kind")] // only for PostgreSQL to match a type definition
+pub enum QuarantinedItemKind {
+    BlockItem,
+    SpecialEvent,
+}
+
+/// Item of a block reported by the node which could not be parsed.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuarantinedItem {
+    pub kind: QuarantinedItemKind,
+    /// Index of the item among the block items or the special transaction
+    /// outcomes of the block.
+    pub index: usize,
+    /// Summary of the outcome reported by the node, for block items.
+    pub summary: Option<BlockItemSummary>,
+}
+
+/// Labels of the metric counting the quarantined items.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, prometheus_client::encoding::EncodeLabelSet)]
+pub struct QuarantineMetricLabels {
+    kind: QuarantinedItemKind,
+}
+
+/// Error stopping the block processor when a block contains items which could
+/// not be parsed and the indexer is configured to halt on these.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error(
+    "Block at height {block_height} contains {count} block items or special transaction outcomes \
+     which could not be parsed, upgrade the indexer"
+)]
+pub struct HaltOnUnknownError {
+    block_height: i64,
+    count: usize,
+}
+
+/// Error stopping the block processor after saving a block containing
+/// quarantined items, such that these can be re-processed as part of the last
+/// indexed block once the indexer is upgraded.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error(
+    "Stopped after block at height {block_height} containing {count} quarantined block items or \
+     special transaction outcomes, upgrade the indexer and run the `reprocess-quarantine` \
+     subcommand"
+)]
+pub struct StoppedOnQuarantineError {
+    block_height: i64,
+    count: usize,
+}
+
+/// Quarantined items of a block, ready to be saved in the database.
+pub struct PreparedQuarantinedItems {
+    block_height: i64,
+    kinds: Vec<QuarantinedItemKind>,
+    indices: Vec<i64>,
+    block_item_hashes: Vec<Option<String>>,
+    summaries: Vec<Option<serde_json::Value>>,
+}
+
+impl PreparedQuarantinedItems {
+    pub fn prepare(data: &BlockData) -> anyhow::Result<Self> {
+        let mut kinds = Vec::with_capacity(data.quarantined.len());
+        let mut indices = Vec::with_capacity(data.quarantined.len());
+        let mut block_item_hashes = Vec::with_capacity(data.quarantined.len());
+        let mut summaries = Vec::with_capacity(data.quarantined.len());
+        for item in data.quarantined.iter() {
+            kinds.push(item.kind);
+            indices.push(i64::try_from(item.index)?);
+            block_item_hashes.push(item.summary.as_ref().map(|s| s.hash.to_string()));
+            summaries.push(
+                item.summary
+                    .as_ref()
+                    .map(serde_json::to_value)
+                    .transpose()?,
+            );
+        }
+        Ok(Self {
+            block_height: data.finalized_block_info.height.height.try_into()?,
+            kinds,
+            indices,
+            block_item_hashes,
+            summaries,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kinds.is_empty()
+    }
+
+    /// Number of quarantined block items, which are still counted as
+    /// transactions of the block.
+    pub fn block_item_count(&self) -> usize {
+        self.kinds
+            .iter()
+            .filter(|kind| **kind == QuarantinedItemKind::BlockItem)
+            .count()
+    }
+
+    /// Fail when halting on items which could not be parsed.
+    pub fn ensure_empty(&self) -> Result<(), HaltOnUnknownError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(HaltOnUnknownError {
+                block_height: self.block_height,
+                count: self.kinds.len(),
+            })
+        }
+    }
+
+    /// Fail once the block is saved when it contains quarantined items, unless
+    /// continuing past these.
+    pub fn stop_after_saving(&self) -> Result<(), StoppedOnQuarantineError> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(StoppedOnQuarantineError {
+                block_height: self.block_height,
+                count: self.kinds.len(),
+            })
+        }
+    }
+
+    /// Count the quarantined items in the metric.
+    pub fn observe(&self, quarantined_items: &Family<QuarantineMetricLabels, Counter>) {
+        for kind in self.kinds.iter() {
+            quarantined_items
+                .get_or_create(&QuarantineMetricLabels { kind: *kind })
+                .inc();
+        }
+    }
+
+    pub async fn save(&self, tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        warn!(
+            "Quarantining {} block items or special transaction outcomes of block at height {} \
+             which could not be parsed",
+            self.kinds.len(),
+            self.block_height
+        );
+        sqlx::query!(
+            "INSERT INTO quarantined_items (block_height, kind, item_index, block_item_hash, \
+             summary)
+            SELECT $1, kind, item_index, block_item_hash, summary
+            FROM UNNEST(
+                $2::quarantined_item_kind[],
+                $3::BIGINT[],
+                $4::TEXT[],
+                $5::JSONB[]
+            ) AS items(kind, item_index, block_item_hash, summary)",
+            self.block_height,
+            &self.kinds as &[QuarantinedItemKind],
+            &self.indices,
+            &self.block_item_hashes as &[Option<String>],
+            &self.summaries as &[Option<serde_json::Value>]
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+}
+
+/// Fail when the block at the provided height contains items which are still
+/// quarantined, as indexing the following blocks would leave these without a
+/// way to be re-processed.
+pub async fn ensure_not_quarantined(
+    db_connection: &mut PgConnection,
+    block_height: i64,
+) -> anyhow::Result<()> {
+    let pending = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) AS "count!"
+        FROM quarantined_items
+        WHERE block_height = $1 AND reprocessed_at IS NULL"#,
+        block_height
+    )
+    .fetch_one(db_connection)
+    .await?;
+    anyhow::ensure!(
+        pending == 0,
+        "Block at height {} contains {} quarantined block items or special transaction outcomes. \
+         Re-process these using the `reprocess-quarantine` subcommand of an upgraded indexer, or \
+         start the indexer with `--continue-past-quarantine` to leave them in quarantine",
+        block_height,
+        pending
+    );
+    Ok(())
+}
+
+/// Re-process the quarantined items using the blocks fetched again from the
+/// node. Items which still cannot be parsed are left in quarantine. Each
+/// block is re-processed in its own database transaction.
+///
+/// The items are processed on top of the current state, which is only the
+/// state of their block when no later blocks are indexed. Later blocks were
+/// processed without the effects of the items, and neither the derived data
+/// nor the current state of accounts, validators and contracts can be rebuilt
+/// for these, so re-processing is refused instead. The indexer stops after
+/// saving a block containing quarantined items, unless continuing past them,
+/// such that the items belong to the last indexed block. While event sinks are
+/// configured, the blocks are emitted to them again.
+pub async fn run_reprocess_quarantine(
+    endpoints: Vec<v2::Endpoint>,
+    db_connection: &mut PgConnection,
+    config: &IndexerServiceConfig,
+) -> anyhow::Result<()> {
+    let endpoint = endpoints
+        .first()
+        .context("At least one Concordium Node must be provided")?
+        .clone();
+    let client = v2::Client::new(endpoint)
+        .await
+        .context("Failed to establish connection to Concordium Node")?;
+    let domains = indexing_profile::populated_domains(db_connection).await?;
+    let rows = sqlx::query!(
+        r#"SELECT
+            block_height,
+            kind AS "kind: QuarantinedItemKind",
+            item_index
+        FROM quarantined_items
+        WHERE reprocessed_at IS NULL
+        ORDER BY block_height, kind, item_index"#
+    )
+    .fetch_all(db_connection.as_mut())
+    .await?;
+    let mut blocks: BTreeMap<i64, Vec<(QuarantinedItemKind, usize)>> = BTreeMap::new();
+    for row in rows {
+        blocks
+            .entry(row.block_height)
+            .or_default()
+            .push((row.kind, row.item_index.try_into()?));
+    }
+    if let Some(first_height) = blocks.keys().next() {
+        let last_height = sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
+            .fetch_one(db_connection.as_mut())
+            .await?
+            .context("No blocks are indexed yet")?;
+        anyhow::ensure!(
+            *first_height == last_height,
+            "The quarantined items of block height {} cannot be re-processed, as the blocks up to \
+             height {} are indexed without their effects. Index the chain into a new database \
+             using the upgraded indexer instead",
+            first_height,
+            last_height
+        );
+    }
+    info!("Re-processing quarantined items of {} blocks", blocks.len());
+    let mut reprocessed = 0;
+    let mut remaining = 0;
+    for (block_height, items) in blocks {
+        let block_hash: sdk_types::hashes::BlockHash =
+            sqlx::query_scalar!("SELECT hash FROM blocks WHERE height = $1", block_height)
+                .fetch_one(db_connection.as_mut())
+                .await?
+                .parse()?;
+        let finalized_block_info = v2::FinalizedBlockInfo {
+            block_hash,
+            height: AbsoluteBlockHeight::from(u64::try_from(block_height)?),
+        };
+        let data = BlockData::fetch(&client, finalized_block_info)
+            .await
+            .with_context(|| format!("Failed fetching block at height {}", block_height))?;
+        let slot_time = data.block_info.block_slot_time;
+        let mut node_queries = NodeQueries::from(client.clone());
+        // Statistics are tracked as time series and are not back-filled.
+        let mut statistics = Statistics::new(block_height, slot_time);
+        let mut tx = db_connection.begin().await?;
+        let reprocessed_before = reprocessed;
+        for (kind, index) in items {
+            let Some(position) = data.known_position(kind, index) else {
+                warn!(
+                    "{:?} {} of block at height {} still cannot be parsed",
+                    kind, index, block_height
+                );
+                remaining += 1;
+                continue;
+            };
+            match kind {
+                QuarantinedItemKind::BlockItem => {
+                    let item = PreparedBlockItem::prepare(
+                        &mut node_queries,
+                        &data,
+                        &data.events[position],
+                        &data.items[position],
+                        domains,
+                        &mut statistics,
+                    )
+                    .await?;
+                    item.save(&mut tx, slot_time).await.with_context(|| {
+                        format!(
+                            "Failed re-processing block item {} of block at height {}",
+                            index, block_height
+                        )
+                    })?;
+                }
+                QuarantinedItemKind::SpecialEvent => {
+                    PreparedSpecialTransactionOutcomes::prepare_single(
+                        &data.block_info,
+                        index.try_into()?,
+                        &data.special_events[position],
+                        &mut statistics,
+                    )?
+                    .save(&mut tx)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "Failed re-processing special transaction outcome {} of block at \
+                             height {}",
+                            index, block_height
+                        )
+                    })?;
+                }
+            }
+            sqlx::query!(
+                "UPDATE quarantined_items
+                SET reprocessed_at = NOW()
+                WHERE block_height = $1 AND kind = $2 AND item_index = $3",
+                block_height,
+                kind as QuarantinedItemKind,
+                i64::try_from(index)?
+            )
+            .execute(tx.as_mut())
+            .await?;
+            reprocessed += 1;
+        }
+        if reprocessed > reprocessed_before && !config.event_sinks.is_empty() {
+            PreparedBlock::prepare(&mut node_queries, &data, domains)
+                .await?
+                .save_to_event_sink_outbox(&mut tx, true)
+                .await?;
+        }
+        tx.commit().await?;
+    }
+    info!(
+        "Re-processed {} quarantined items, {} items still cannot be parsed",
+        reprocessed, remaining
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::Utc;
+
+    const HEIGHT: i64 = 1 << 40;
+
+    #[tokio::test]
+    async fn test_block_with_quarantined_items_is_not_continued_past() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        test_database::insert_block(tx.as_mut(), HEIGHT, Utc::now())
+            .await
+            .unwrap();
+        ensure_not_quarantined(tx.as_mut(), HEIGHT).await.unwrap();
+
+        sqlx::query(
+            "INSERT INTO quarantined_items (block_height, kind, item_index)
+            VALUES ($1, 'SpecialEvent', 2)",
+        )
+        .bind(HEIGHT)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        assert!(ensure_not_quarantined(tx.as_mut(), HEIGHT).await.is_err());
+
+        // Once re-processed, the indexer continues with the next block.
+        sqlx::query("UPDATE quarantined_items SET reprocessed_at = NOW() WHERE block_height = $1")
+            .bind(HEIGHT)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        ensure_not_quarantined(tx.as_mut(), HEIGHT).await.unwrap();
+    }
+}
@@ -0,0 +1,1188 @@
+//! Targeted re-indexing of the derived data of a single domain for a range of
+//! already indexed blocks.
+//!
+//! The rows derived for the chosen domain are cleared for the whole range,
+//! reverting the balances and totals they contributed, and then rebuilt from
+//! the blocks, which are fetched and preprocessed again from the node.
+//! Identifiers of the cleared rows are reused by the rebuilt rows, such that
+//! the ordering exposed by the API is preserved. Finally the per-key ordering
+//! columns and the running balances of the affected statements are recomputed.
+//! Everything happens in a single database transaction, meaning the range is
+//! either fully rebuilt or left untouched. The notifications of the rebuilt
+//! rows are suppressed, such that subscribers of the API are not sent the
+//! events of past blocks as if they were new.
+
+use super::{block_preprocessor::BlockPreProcessor, IndexerServiceConfig};
+use crate::{
+    indexing_profile::{self, IndexingDomain},
+    transaction_event::{CisBurnEvent, CisEvent, CisMintEvent},
+};
+use anyhow::Context;
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::{indexer::TraverseConfig, types as sdk_types, v2};
+use prometheus_client::registry::Registry;
+use sqlx::{Connection as _, PgConnection};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use tracing::{info, warn};
+
+/// The domains of derived data which can be rebuilt for a range of blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ReindexDomain {
+    /// CIS-2 token events, token balances and total supply and the CIS-2
+    /// account statements.
+    Cis2,
+    /// Smart contract events and rejected smart contract update transactions.
+    Contracts,
+    /// Protocol-level token events, balances, minted and burned totals and the
+    /// PLT account statements.
+    Plt,
+    /// CCD account statements and account balances.
+    Statements,
+    /// Special transaction outcomes and the payday rewards of pools and
+    /// accounts.
+    Rewards,
+}
+
+impl ReindexDomain {
+    /// The indexing domain containing the derived data, if the indexing
+    /// profile can skip it.
+    fn indexing_domain(self) -> Option<IndexingDomain> {
+        match self {
+            ReindexDomain::Cis2 => Some(IndexingDomain::Cis2),
+            ReindexDomain::Contracts => Some(IndexingDomain::Contracts),
+            ReindexDomain::Plt => Some(IndexingDomain::Plt),
+            ReindexDomain::Statements | ReindexDomain::Rewards => None,
+        }
+    }
+}
+
+/// Arguments of the `reindex` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct ReindexArgs {
+    /// Height of the first block to re-index.
+    #[arg(long)]
+    pub from: u64,
+    /// Height of the last block to re-index (inclusive).
+    #[arg(long)]
+    pub to: u64,
+    /// The domain of derived data to rebuild.
+    #[arg(long, value_enum)]
+    pub domain: ReindexDomain,
+}
+
+/// State provided while rebuilding the rows of the blocks.
+pub struct ReindexContext {
+    /// The domain being rebuilt.
+    pub domain: ReindexDomain,
+    /// Slot time of the block being rebuilt.
+    pub slot_time: DateTime<Utc>,
+    /// Identifiers of the cleared rows not yet reused, in increasing order.
+    reusable_ids: VecDeque<i64>,
+    /// Number of rebuilt rows which got assigned a new identifier.
+    new_ids: usize,
+}
+
+impl ReindexContext {
+    /// Context for rebuilding the domain, reusing the identifiers of the
+    /// cleared rows, which must be in increasing order.
+    pub fn new(domain: ReindexDomain, reusable_ids: Vec<i64>) -> Self {
+        Self {
+            domain,
+            slot_time: DateTime::default(),
+            reusable_ids: reusable_ids.into(),
+            new_ids: 0,
+        }
+    }
+
+    /// Take the next identifier of a cleared row, to be reused by the next
+    /// rebuilt row. Returns `None` when every identifier is already taken, in
+    /// which case the rebuilt row is assigned a new identifier.
+    pub fn next_reusable_id(&mut self) -> Option<i64> {
+        let id = self.reusable_ids.pop_front();
+        if id.is_none() {
+            self.new_ids += 1;
+        }
+        id
+    }
+}
+
+/// Range of blocks being re-indexed and the range of the indices of their
+/// transactions.
+struct ReindexRange {
+    from_height: i64,
+    to_height: i64,
+    /// Index of the first transaction in the range.
+    from_transaction: i64,
+    /// Index of the last transaction in the range, below `from_transaction`
+    /// when the range contains no transactions.
+    to_transaction: i64,
+}
+
+/// Keys of the rows affected by the rebuild, for which the ordering columns and
+/// running balances are recomputed once every block is rebuilt.
+#[derive(Default)]
+struct AffectedKeys {
+    /// Account indices for the CCD account statements.
+    accounts: BTreeSet<i64>,
+    /// Pairs of account index and token index for the CIS-2 or PLT account
+    /// statements.
+    account_tokens: BTreeSet<(i64, i64)>,
+    /// Token indices for the CIS-2 token events.
+    tokens: BTreeSet<i64>,
+    /// Contract addresses for the contract events and rejected transactions.
+    contracts: BTreeSet<(i64, i64)>,
+}
+
+/// Rebuild the derived data of a single domain for the blocks in the range
+/// `from..=to`. Expects the provided database connection to hold the indexer
+/// lock.
+pub async fn run_reindex(
+    endpoints: Vec<v2::Endpoint>,
+    db_connection: &mut PgConnection,
+    config: &IndexerServiceConfig,
+    args: ReindexArgs,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        args.from <= args.to,
+        "The start of the range (--from {}) is after the end of the range (--to {})",
+        args.from,
+        args.to
+    );
+    anyhow::ensure!(args.from > 0, "The genesis block cannot be re-indexed");
+    let last_height = sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
+        .fetch_one(db_connection.as_mut())
+        .await?
+        .context("No blocks are indexed yet")?;
+    anyhow::ensure!(
+        args.to <= u64::try_from(last_height)?,
+        "The end of the range (--to {}) is above the last indexed block height {}",
+        args.to,
+        last_height
+    );
+    // Only the domains populated in the database are prepared, and the domain being
+    // re-indexed must be one of them.
+    let domains = indexing_profile::populated_domains(db_connection).await?;
+    if let Some(domain) = args.domain.indexing_domain() {
+        anyhow::ensure!(
+            domains.contains(domain),
+            "The {} data is not indexed in this database and cannot be re-indexed",
+            domain
+        );
+    }
+    let genesis_block_hash: sdk_types::hashes::BlockHash =
+        sqlx::query!("SELECT hash FROM blocks WHERE height=0")
+            .fetch_one(db_connection.as_mut())
+            .await?
+            .hash
+            .parse()?;
+    // Metrics of the block preprocessor are not exposed while re-indexing.
+    let mut registry = Registry::default();
+    let block_pre_processor = BlockPreProcessor::new(
+        genesis_block_hash,
+        config.max_successive_failures.into(),
+        domains,
+        &mut registry,
+    );
+    let traverse_config = TraverseConfig::new(endpoints, args.from.into())
+        .context("Failed setting up TraverseConfig")?
+        .set_max_parallel(config.max_parallel_block_preprocessors);
+    let (sender, mut receiver) = tokio::sync::mpsc::channel(config.max_processing_batch);
+    let traverse_future = tokio::spawn(traverse_config.traverse(block_pre_processor, sender));
+
+    info!(
+        "Re-indexing {:?} for block heights {} to {}",
+        args.domain, args.from, args.to
+    );
+    let mut tx = db_connection.begin().await?;
+    suppress_notifications(tx.as_mut()).await?;
+    let transactions = sqlx::query!(
+        "SELECT MIN(index) AS from_transaction, MAX(index) AS to_transaction
+        FROM transactions
+        WHERE block_height BETWEEN $1 AND $2",
+        i64::try_from(args.from)?,
+        i64::try_from(args.to)?
+    )
+    .fetch_one(tx.as_mut())
+    .await?;
+    let range = ReindexRange {
+        from_height: args.from.try_into()?,
+        to_height: args.to.try_into()?,
+        from_transaction: transactions.from_transaction.unwrap_or(0),
+        to_transaction: transactions.to_transaction.unwrap_or(-1),
+    };
+    // Keys of the cleared rows are tracked as well, as these might no longer have
+    // rows in the range after the rebuild.
+    let mut affected = AffectedKeys::default();
+    collect_affected_keys(&mut tx, args.domain, &range, &mut affected).await?;
+    let reusable_ids = match args.domain {
+        ReindexDomain::Cis2 => clear_cis2(&mut tx, &range).await?,
+        ReindexDomain::Contracts => clear_contracts(&mut tx, &range).await?,
+        ReindexDomain::Plt => clear_plt(&mut tx, &range).await?,
+        ReindexDomain::Statements => clear_statements(&mut tx, &range).await?,
+        ReindexDomain::Rewards => clear_rewards(&mut tx, &range).await?,
+    };
+    let mut context = ReindexContext::new(args.domain, reusable_ids);
+    let mut next_height = range.from_height;
+    while next_height <= range.to_height {
+        let Some(block) = receiver.recv().await else {
+            // The traversal stopped before the end of the range, report its error if any.
+            traverse_future.await??;
+            anyhow::bail!(
+                "Fetching blocks from the node stopped at height {}",
+                next_height
+            );
+        };
+        anyhow::ensure!(
+            block.height == next_height,
+            "Received block at height {} while expecting height {}",
+            block.height,
+            next_height
+        );
+        let transaction_indices = sqlx::query_scalar!(
+            "SELECT index FROM transactions WHERE block_height = $1 ORDER BY index",
+            block.height
+        )
+        .fetch_all(tx.as_mut())
+        .await?;
+        context.slot_time = block.slot_time;
+        block
+            .reindex(&mut tx, &mut context, &transaction_indices)
+            .await
+            .with_context(|| format!("Failed re-indexing block at height {}", block.height))?;
+        if !config.event_sinks.is_empty() {
+            block.save_to_event_sink_outbox(&mut tx, true).await?;
+        }
+        if block.height % 1000 == 0 {
+            info!("Re-indexed up to block height {}", block.height);
+        }
+        next_height += 1;
+    }
+    drop(receiver);
+    traverse_future.abort();
+    if !context.reusable_ids.is_empty() {
+        warn!(
+            "Rebuilt {} rows fewer than cleared",
+            context.reusable_ids.len()
+        );
+    }
+    if context.new_ids > 0 {
+        warn!(
+            "Rebuilt {} rows more than cleared, these got new identifiers ordering them after \
+             rows of later blocks",
+            context.new_ids
+        );
+    }
+    collect_affected_keys(&mut tx, args.domain, &range, &mut affected).await?;
+    recompute_derived_columns(&mut tx, args.domain, range.from_height, &affected).await?;
+    tx.commit().await?;
+    info!(
+        "Re-indexed {:?} for block heights {} to {}",
+        args.domain, args.from, args.to
+    );
+    Ok(())
+}
+
+/// Suppress the notifications sent by the triggers on the rows inserted and
+/// updated for the remainder of the database transaction.
+async fn suppress_notifications(tx: &mut PgConnection) -> anyhow::Result<()> {
+    sqlx::query!("SELECT set_config('ccdscan.suppress_notify', 'on', true)")
+        .fetch_one(tx)
+        .await?;
+    Ok(())
+}
+
+/// Clear the CIS-2 token events and CIS-2 account statements of the range,
+/// reverting the token balances and the total supply of the tokens.
+/// Returns the indices of the cleared token events.
+async fn clear_cis2(
+    tx: &mut sqlx::PgTransaction<'_>,
+    range: &ReindexRange,
+) -> anyhow::Result<Vec<i64>> {
+    sqlx::query!(
+        "UPDATE account_tokens
+        SET balance = account_tokens.balance - cleared.amount
+        FROM (
+            SELECT account_index, token_index, SUM(amount) AS amount
+            FROM cis2_accounts_statement
+            WHERE block_height BETWEEN $1 AND $2
+            GROUP BY account_index, token_index
+        ) AS cleared
+        WHERE account_tokens.account_index = cleared.account_index
+            AND account_tokens.token_index = cleared.token_index",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM cis2_accounts_statement WHERE block_height BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    let cleared_events = sqlx::query!(
+        "DELETE FROM cis2_token_events
+        WHERE transaction_index BETWEEN $1 AND $2
+        RETURNING index, token_index, cis2_token_event",
+        range.from_transaction,
+        range.to_transaction
+    )
+    .fetch_all(tx.as_mut())
+    .await?;
+    let mut supply_changes: BTreeMap<i64, BigDecimal> = BTreeMap::new();
+    let mut indices = Vec::with_capacity(cleared_events.len());
+    for event in cleared_events {
+        indices.push(event.index);
+        let change = match serde_json::from_value(event.cis2_token_event)? {
+            CisEvent::Mint(CisMintEvent { amount, .. }) => -BigDecimal::from_biguint(amount.0, 0),
+            CisEvent::Burn(CisBurnEvent { amount, .. }) => BigDecimal::from_biguint(amount.0, 0),
+            _ => continue,
+        };
+        *supply_changes.entry(event.token_index).or_default() += change;
+    }
+    for (token_index, change) in supply_changes {
+        sqlx::query!(
+            "UPDATE tokens SET total_supply = total_supply + $2 WHERE index = $1",
+            token_index,
+            change
+        )
+        .execute(tx.as_mut())
+        .await?;
+    }
+    indices.sort_unstable();
+    Ok(indices)
+}
+
+/// Clear the contract events and rejected contract update transactions of the
+/// range. Returns the indices of the cleared contract events.
+async fn clear_contracts(
+    tx: &mut sqlx::PgTransaction<'_>,
+    range: &ReindexRange,
+) -> anyhow::Result<Vec<i64>> {
+    sqlx::query!(
+        "DELETE FROM contract_reject_transactions WHERE transaction_index BETWEEN $1 AND $2",
+        range.from_transaction,
+        range.to_transaction
+    )
+    .execute(tx.as_mut())
+    .await?;
+    let mut indices = sqlx::query_scalar!(
+        "DELETE FROM contract_events WHERE block_height BETWEEN $1 AND $2 RETURNING index",
+        range.from_height,
+        range.to_height
+    )
+    .fetch_all(tx.as_mut())
+    .await?;
+    indices.sort_unstable();
+    Ok(indices)
+}
+
+/// Clear the PLT events and PLT account statements of the range, reverting the
+/// token balances of the accounts and the minted and burned totals of the
+/// tokens. Returns the identifiers of the cleared PLT events.
+async fn clear_plt(
+    tx: &mut sqlx::PgTransaction<'_>,
+    range: &ReindexRange,
+) -> anyhow::Result<Vec<i64>> {
+    sqlx::query!(
+        "UPDATE plt_accounts
+        SET amount = plt_accounts.amount - cleared.amount
+        FROM (
+            SELECT account_index, token_index, SUM(amount) AS amount
+            FROM plt_accounts_statement
+            WHERE block_height BETWEEN $1 AND $2
+            GROUP BY account_index, token_index
+        ) AS cleared
+        WHERE plt_accounts.account_index = cleared.account_index
+            AND plt_accounts.token_index = cleared.token_index",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "UPDATE plt_accounts_sum_amounts
+        SET total_amount = plt_accounts_sum_amounts.total_amount - cleared.amount
+        FROM (
+            SELECT account_index, SUM(amount) AS amount
+            FROM plt_accounts_statement
+            WHERE block_height BETWEEN $1 AND $2
+            GROUP BY account_index
+        ) AS cleared
+        WHERE plt_accounts_sum_amounts.account_index = cleared.account_index",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    // Burn statements hold the burned amount as a negative amount.
+    sqlx::query!(
+        "UPDATE plt_tokens
+        SET
+            total_minted = plt_tokens.total_minted - cleared.minted,
+            total_burned = plt_tokens.total_burned + cleared.burned
+        FROM (
+            SELECT
+                token_index,
+                COALESCE(SUM(amount) FILTER (WHERE entry_type = 'Mint'), 0) AS minted,
+                COALESCE(SUM(amount) FILTER (WHERE entry_type = 'Burn'), 0) AS burned
+            FROM plt_accounts_statement
+            WHERE block_height BETWEEN $1 AND $2
+            GROUP BY token_index
+        ) AS cleared
+        WHERE plt_tokens.index = cleared.token_index",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM plt_accounts_statement WHERE block_height BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    let mut ids = sqlx::query_scalar!(
+        "DELETE FROM plt_events WHERE transaction_index BETWEEN $1 AND $2 RETURNING id",
+        range.from_transaction,
+        range.to_transaction
+    )
+    .fetch_all(tx.as_mut())
+    .await?;
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Clear the CCD account statements and the history of scheduled releases of
+/// the range, reverting the balances of the accounts. Returns the identifiers
+/// of the cleared account statements.
+async fn clear_statements(
+    tx: &mut sqlx::PgTransaction<'_>,
+    range: &ReindexRange,
+) -> anyhow::Result<Vec<i64>> {
+    sqlx::query!(
+        "UPDATE accounts
+        SET amount = accounts.amount - cleared.amount
+        FROM (
+            SELECT account_index, SUM(amount)::BIGINT AS amount
+            FROM account_statements
+            WHERE block_height BETWEEN $1 AND $2
+            GROUP BY account_index
+        ) AS cleared
+        WHERE accounts.index = cleared.account_index",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM scheduled_release_history WHERE transaction_index BETWEEN $1 AND $2",
+        range.from_transaction,
+        range.to_transaction
+    )
+    .execute(tx.as_mut())
+    .await?;
+    let mut ids = sqlx::query_scalar!(
+        "DELETE FROM account_statements WHERE block_height BETWEEN $1 AND $2 RETURNING id",
+        range.from_height,
+        range.to_height
+    )
+    .fetch_all(tx.as_mut())
+    .await?;
+    ids.sort_unstable();
+    Ok(ids)
+}
+
+/// Clear the special transaction outcomes, payday rewards and payday pool
+/// stakes of the range.
+async fn clear_rewards(
+    tx: &mut sqlx::PgTransaction<'_>,
+    range: &ReindexRange,
+) -> anyhow::Result<Vec<i64>> {
+    sqlx::query!(
+        "DELETE FROM payday_baker_pool_stakes WHERE payday_block BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM payday_passive_pool_stakes WHERE payday_block BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM payday_account_rewards WHERE payday_block_height BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM bakers_payday_pool_rewards WHERE payday_block_height BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "DELETE FROM block_special_transaction_outcomes WHERE block_height BETWEEN $1 AND $2",
+        range.from_height,
+        range.to_height
+    )
+    .execute(tx.as_mut())
+    .await?;
+    Ok(Vec::new())
+}
+
+/// Track the keys of the rows of the domain derived from the range.
+async fn collect_affected_keys(
+    tx: &mut sqlx::PgTransaction<'_>,
+    domain: ReindexDomain,
+    range: &ReindexRange,
+    affected: &mut AffectedKeys,
+) -> anyhow::Result<()> {
+    match domain {
+        ReindexDomain::Cis2 => {
+            let rows = sqlx::query!(
+                "SELECT DISTINCT account_index, token_index
+                FROM cis2_accounts_statement
+                WHERE block_height BETWEEN $1 AND $2",
+                range.from_height,
+                range.to_height
+            )
+            .fetch_all(tx.as_mut())
+            .await?;
+            affected.account_tokens.extend(
+                rows.into_iter()
+                    .map(|row| (row.account_index, row.token_index)),
+            );
+            let tokens = sqlx::query_scalar!(
+                "SELECT DISTINCT token_index
+                FROM cis2_token_events
+                WHERE transaction_index BETWEEN $1 AND $2",
+                range.from_transaction,
+                range.to_transaction
+            )
+            .fetch_all(tx.as_mut())
+            .await?;
+            affected.tokens.extend(tokens);
+        }
+        ReindexDomain::Contracts => {
+            let rows = sqlx::query!(
+                r#"SELECT
+                    contract_index AS "contract_index!",
+                    contract_sub_index AS "contract_sub_index!"
+                FROM contract_events
+                WHERE block_height BETWEEN $1 AND $2
+                UNION
+                SELECT contract_index, contract_sub_index
+                FROM contract_reject_transactions
+                WHERE transaction_index BETWEEN $3 AND $4"#,
+                range.from_height,
+                range.to_height,
+                range.from_transaction,
+                range.to_transaction
+            )
+            .fetch_all(tx.as_mut())
+            .await?;
+            affected.contracts.extend(
+                rows.into_iter()
+                    .map(|row| (row.contract_index, row.contract_sub_index)),
+            );
+        }
+        ReindexDomain::Plt => {
+            let rows = sqlx::query!(
+                "SELECT DISTINCT account_index, token_index
+                FROM plt_accounts_statement
+                WHERE block_height BETWEEN $1 AND $2",
+                range.from_height,
+                range.to_height
+            )
+            .fetch_all(tx.as_mut())
+            .await?;
+            affected.account_tokens.extend(
+                rows.into_iter()
+                    .map(|row| (row.account_index, row.token_index)),
+            );
+        }
+        ReindexDomain::Statements => {
+            let accounts = sqlx::query_scalar!(
+                "SELECT DISTINCT account_index
+                FROM account_statements
+                WHERE block_height BETWEEN $1 AND $2",
+                range.from_height,
+                range.to_height
+            )
+            .fetch_all(tx.as_mut())
+            .await?;
+            affected.accounts.extend(accounts);
+        }
+        ReindexDomain::Rewards => {}
+    }
+    Ok(())
+}
+
+/// Recompute the columns which depend on the rows of other blocks, for the keys
+/// affected by the rebuild: the ordering of rows per token and per contract,
+/// the running balances of the statements from the start of the range and the
+/// baker APYs computed from the payday rewards.
+/// Running balances are anchored at the current balance, which is already
+/// updated by the rebuilt rows.
+async fn recompute_derived_columns(
+    tx: &mut sqlx::PgTransaction<'_>,
+    domain: ReindexDomain,
+    from_height: i64,
+    affected: &AffectedKeys,
+) -> anyhow::Result<()> {
+    let (account_indices, token_indices): (Vec<i64>, Vec<i64>) =
+        affected.account_tokens.iter().copied().unzip();
+    let (contract_indices, contract_sub_indices): (Vec<i64>, Vec<i64>) =
+        affected.contracts.iter().copied().unzip();
+    match domain {
+        ReindexDomain::Cis2 => {
+            let tokens: Vec<i64> = affected.tokens.iter().copied().collect();
+            sqlx::query!(
+                "UPDATE cis2_token_events
+                SET index_per_token = ordered.index_per_token
+                FROM (
+                    SELECT
+                        index,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY token_index ORDER BY transaction_index, index
+                        ) - 1 AS index_per_token
+                    FROM cis2_token_events
+                    WHERE token_index = ANY($1)
+                ) AS ordered
+                WHERE cis2_token_events.index = ordered.index
+                    AND cis2_token_events.index_per_token <> ordered.index_per_token",
+                &tokens
+            )
+            .execute(tx.as_mut())
+            .await?;
+            sqlx::query!(
+                "UPDATE cis2_accounts_statement
+                SET account_balance = recomputed.account_balance
+                FROM (
+                    SELECT
+                        statements.account_index,
+                        statements.cis2_token_event_index,
+                        statements.entry_type,
+                        account_tokens.balance - COALESCE(SUM(statements.amount) OVER (
+                            PARTITION BY statements.account_index, statements.token_index
+                            ORDER BY
+                                statements.transaction_index DESC,
+                                statements.cis2_token_event_index DESC,
+                                statements.entry_type = 'TransferOut'
+                            ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+                        ), 0) AS account_balance
+                    FROM cis2_accounts_statement AS statements
+                        JOIN UNNEST($1::BIGINT[], $2::BIGINT[])
+                            AS affected(account_index, token_index)
+                            ON affected.account_index = statements.account_index
+                            AND affected.token_index = statements.token_index
+                        JOIN account_tokens
+                            ON account_tokens.account_index = statements.account_index
+                            AND account_tokens.token_index = statements.token_index
+                    WHERE statements.block_height >= $3
+                ) AS recomputed
+                WHERE cis2_accounts_statement.account_index = recomputed.account_index
+                    AND cis2_accounts_statement.cis2_token_event_index = \
+                 recomputed.cis2_token_event_index
+                    AND cis2_accounts_statement.entry_type = recomputed.entry_type",
+                &account_indices,
+                &token_indices,
+                from_height
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+        ReindexDomain::Contracts => {
+            // The ordering of rejected transactions is part of the primary key, so the
+            // rows are first moved out of the way by storing the negated new value.
+            sqlx::query!(
+                "UPDATE contract_events
+                SET event_index_per_contract = -ordered.event_index_per_contract - 1
+                FROM (
+                    SELECT
+                        index,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY contract_index, contract_sub_index
+                            ORDER BY transaction_index, trace_element_index
+                        ) - 1 AS event_index_per_contract
+                    FROM contract_events
+                        JOIN UNNEST($1::BIGINT[], $2::BIGINT[])
+                            AS affected(contract_index, contract_sub_index)
+                            USING (contract_index, contract_sub_index)
+                ) AS ordered
+                WHERE contract_events.index = ordered.index",
+                &contract_indices,
+                &contract_sub_indices
+            )
+            .execute(tx.as_mut())
+            .await?;
+            sqlx::query!(
+                "UPDATE contract_events
+                SET event_index_per_contract = -event_index_per_contract - 1
+                FROM UNNEST($1::BIGINT[], $2::BIGINT[])
+                    AS affected(contract_index, contract_sub_index)
+                WHERE contract_events.contract_index = affected.contract_index
+                    AND contract_events.contract_sub_index = affected.contract_sub_index
+                    AND event_index_per_contract < 0",
+                &contract_indices,
+                &contract_sub_indices
+            )
+            .execute(tx.as_mut())
+            .await?;
+            sqlx::query!(
+                "UPDATE contract_reject_transactions
+                SET transaction_index_per_contract = -ordered.transaction_index_per_contract - 1
+                FROM (
+                    SELECT
+                        contract_index,
+                        contract_sub_index,
+                        transaction_index,
+                        ROW_NUMBER() OVER (
+                            PARTITION BY contract_index, contract_sub_index
+                            ORDER BY transaction_index
+                        ) - 1 AS transaction_index_per_contract
+                    FROM contract_reject_transactions
+                        JOIN UNNEST($1::BIGINT[], $2::BIGINT[])
+                            AS affected(contract_index, contract_sub_index)
+                            USING (contract_index, contract_sub_index)
+                ) AS ordered
+                WHERE contract_reject_transactions.contract_index = ordered.contract_index
+                    AND contract_reject_transactions.contract_sub_index = \
+                 ordered.contract_sub_index
+                    AND contract_reject_transactions.transaction_index = \
+                 ordered.transaction_index",
+                &contract_indices,
+                &contract_sub_indices
+            )
+            .execute(tx.as_mut())
+            .await?;
+            sqlx::query!(
+                "UPDATE contract_reject_transactions
+                SET transaction_index_per_contract = -transaction_index_per_contract - 1
+                FROM UNNEST($1::BIGINT[], $2::BIGINT[])
+                    AS affected(contract_index, contract_sub_index)
+                WHERE contract_reject_transactions.contract_index = affected.contract_index
+                    AND contract_reject_transactions.contract_sub_index = affected.contract_sub_index
+                    AND transaction_index_per_contract < 0",
+                &contract_indices,
+                &contract_sub_indices
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+        ReindexDomain::Plt => {
+            sqlx::query!(
+                "UPDATE plt_accounts_statement
+                SET account_balance = recomputed.account_balance
+                FROM (
+                    SELECT
+                        statements.account_index,
+                        statements.plt_event_id,
+                        statements.entry_type,
+                        plt_accounts.amount - COALESCE(SUM(statements.amount) OVER (
+                            PARTITION BY statements.account_index, statements.token_index
+                            ORDER BY
+                                statements.transaction_index DESC,
+                                statements.plt_event_id DESC,
+                                statements.entry_type = 'TransferOut'
+                            ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+                        ), 0) AS account_balance
+                    FROM plt_accounts_statement AS statements
+                        JOIN UNNEST($1::BIGINT[], $2::BIGINT[])
+                            AS affected(account_index, token_index)
+                            ON affected.account_index = statements.account_index
+                            AND affected.token_index = statements.token_index
+                        JOIN plt_accounts
+                            ON plt_accounts.account_index = statements.account_index
+                            AND plt_accounts.token_index = statements.token_index
+                    WHERE statements.block_height >= $3
+                ) AS recomputed
+                WHERE plt_accounts_statement.account_index = recomputed.account_index
+                    AND plt_accounts_statement.plt_event_id = recomputed.plt_event_id
+                    AND plt_accounts_statement.entry_type = recomputed.entry_type",
+                &account_indices,
+                &token_indices,
+                from_height
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+        ReindexDomain::Statements => {
+            let accounts: Vec<i64> = affected.accounts.iter().copied().collect();
+            sqlx::query!(
+                "UPDATE account_statements
+                SET account_balance = recomputed.account_balance
+                FROM (
+                    SELECT
+                        statements.id,
+                        accounts.amount - COALESCE(SUM(statements.amount) OVER (
+                            PARTITION BY statements.account_index
+                            ORDER BY statements.block_height DESC, statements.id DESC
+                            ROWS BETWEEN UNBOUNDED PRECEDING AND 1 PRECEDING
+                        ), 0)::BIGINT AS account_balance
+                    FROM account_statements AS statements
+                        JOIN accounts ON accounts.index = statements.account_index
+                    WHERE statements.account_index = ANY($1)
+                        AND statements.block_height >= $2
+                ) AS recomputed
+                WHERE account_statements.id = recomputed.id",
+                &accounts,
+                from_height
+            )
+            .execute(tx.as_mut())
+            .await?;
+        }
+        ReindexDomain::Rewards => {
+            sqlx::query!("REFRESH MATERIALIZED VIEW CONCURRENTLY latest_baker_apy_30_days")
+                .execute(tx.as_mut())
+                .await?;
+            sqlx::query!("REFRESH MATERIALIZED VIEW CONCURRENTLY latest_baker_apy_7_days")
+                .execute(tx.as_mut())
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::TimeZone;
+    use concordium_rust_sdk::id::types::AccountAddress;
+
+    /// Heights and indices far above those of any indexed chain, such that the
+    /// rows of the tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+    const CONTRACT: i64 = 1 << 40;
+
+    fn range(from_height: i64, to_height: i64) -> ReindexRange {
+        ReindexRange {
+            from_height,
+            to_height,
+            from_transaction: TRANSACTION + from_height - HEIGHT,
+            to_transaction: TRANSACTION + to_height - HEIGHT,
+        }
+    }
+
+    /// Insert blocks from `HEIGHT` with a transaction each, where the
+    /// transaction index is offset from `TRANSACTION` like the height is from
+    /// `HEIGHT`.
+    async fn insert_blocks(tx: &mut sqlx::PgTransaction<'_>, count: i64) {
+        let slot_time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        for offset in 0..count {
+            test_database::insert_block(tx.as_mut(), HEIGHT + offset, slot_time)
+                .await
+                .unwrap();
+            test_database::insert_transaction(tx.as_mut(), TRANSACTION + offset, HEIGHT + offset)
+                .await
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cleared_ids_are_reused_in_order() {
+        let mut context = ReindexContext::new(ReindexDomain::Statements, vec![3, 5]);
+        assert_eq!(context.next_reusable_id(), Some(3));
+        assert_eq!(context.next_reusable_id(), Some(5));
+        assert_eq!(context.next_reusable_id(), None);
+        assert_eq!(context.next_reusable_id(), None);
+        assert!(context.reusable_ids.is_empty());
+        assert_eq!(context.new_ids, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reindexing_suppresses_notifications() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut listener = sqlx::postgres::PgListener::connect_with(&pool)
+            .await
+            .unwrap();
+        listener.listen("contract_event_added").await.unwrap();
+        // Notifications are only sent once the transaction is committed, so the
+        // inserted event is removed again within the same transaction, leaving the
+        // test database unchanged.
+        let mut indices = Vec::new();
+        for suppressed in [true, false] {
+            let mut tx = pool.begin().await.unwrap();
+            if suppressed {
+                suppress_notifications(tx.as_mut()).await.unwrap();
+            }
+            let index: i64 = sqlx::query_scalar(
+                "INSERT INTO contract_events (
+                    transaction_index, trace_element_index, block_height, contract_index,
+                    contract_sub_index, event_index_per_contract
+                ) VALUES ($1, 0, $2, $3, 0, 0)
+                RETURNING index",
+            )
+            .bind(TRANSACTION)
+            .bind(HEIGHT)
+            .bind(CONTRACT)
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+            sqlx::query("DELETE FROM contract_events WHERE index = $1")
+                .bind(index)
+                .execute(tx.as_mut())
+                .await
+                .unwrap();
+            tx.commit().await.unwrap();
+            indices.push(index);
+        }
+        // Notifications are received in the order of the commits, so the first one is
+        // of the event inserted without suppressing the notifications.
+        let notification =
+            tokio::time::timeout(std::time::Duration::from_secs(10), listener.recv())
+                .await
+                .expect("The notification of the event inserted without suppression")
+                .unwrap();
+        assert_eq!(notification.payload(), indices[1].to_string());
+    }
+
+    #[tokio::test]
+    async fn test_clear_statements_removes_scheduled_release_history_of_range() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let slot_time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        insert_blocks(&mut tx, 3).await;
+        for offset in 0..3 {
+            sqlx::query(
+                "INSERT INTO scheduled_release_history (
+                    transaction_index, locked_at, release_time, amount
+                ) VALUES ($1, $2, $2 + INTERVAL '1 day', 10)",
+            )
+            .bind(TRANSACTION + offset)
+            .bind(slot_time)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+        clear_statements(&mut tx, &range(HEIGHT + 1, HEIGHT + 1))
+            .await
+            .unwrap();
+        let remaining: Vec<i64> = sqlx::query_scalar(
+            "SELECT transaction_index FROM scheduled_release_history
+            WHERE transaction_index >= $1
+            ORDER BY transaction_index",
+        )
+        .bind(TRANSACTION)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(remaining, vec![TRANSACTION, TRANSACTION + 2]);
+    }
+
+    #[tokio::test]
+    async fn test_statements_cleared_and_running_balances_recomputed() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        insert_blocks(&mut tx, 3).await;
+        let address = AccountAddress([0xfc; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &address, 100)
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for (offset, amount, balance) in [(0, 50, 50), (1, 30, 80), (2, 20, 100)] {
+            let id: i64 = sqlx::query_scalar(
+                "INSERT INTO account_statements (
+                    account_index, entry_type, amount, block_height, transaction_id,
+                    account_balance, slot_time
+                ) VALUES ($1, 'TransferIn', $2, $3, $4, $5, now())
+                RETURNING id",
+            )
+            .bind(ACCOUNT)
+            .bind(amount)
+            .bind(HEIGHT + offset)
+            .bind(TRANSACTION + offset)
+            .bind(balance)
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+
+        // Clearing the middle block reverts the balance and returns the identifier.
+        let range = range(HEIGHT + 1, HEIGHT + 1);
+        let mut affected = AffectedKeys::default();
+        collect_affected_keys(&mut tx, ReindexDomain::Statements, &range, &mut affected)
+            .await
+            .unwrap();
+        let cleared = clear_statements(&mut tx, &range).await.unwrap();
+        assert_eq!(cleared, vec![ids[1]]);
+        let amount: i64 = sqlx::query_scalar("SELECT amount FROM accounts WHERE index = $1")
+            .bind(ACCOUNT)
+            .fetch_one(tx.as_mut())
+            .await
+            .unwrap();
+        assert_eq!(amount, 70);
+
+        // Rebuild the block with a different amount, reusing the identifier and
+        // leaving the running balance to be recomputed.
+        sqlx::query("UPDATE accounts SET amount = amount + 40 WHERE index = $1")
+            .bind(ACCOUNT)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO account_statements (
+                id, account_index, entry_type, amount, block_height, transaction_id,
+                account_balance, slot_time
+            ) OVERRIDING SYSTEM VALUE
+            VALUES ($1, $2, 'TransferIn', 40, $3, $4, 0, now())",
+        )
+        .bind(ids[1])
+        .bind(ACCOUNT)
+        .bind(HEIGHT + 1)
+        .bind(TRANSACTION + 1)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        collect_affected_keys(&mut tx, ReindexDomain::Statements, &range, &mut affected)
+            .await
+            .unwrap();
+        recompute_derived_columns(&mut tx, ReindexDomain::Statements, HEIGHT + 1, &affected)
+            .await
+            .unwrap();
+        let balances: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT id, account_balance FROM account_statements
+            WHERE account_index = $1
+            ORDER BY id",
+        )
+        .bind(ACCOUNT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(balances, vec![(ids[0], 50), (ids[1], 90), (ids[2], 110)]);
+    }
+
+    #[tokio::test]
+    async fn test_contract_ordering_recomputed_when_swapping_rows() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        // Rebuilt rows ordered in reverse of their transactions, such that updating
+        // the ordering directly would violate the primary key of the rejected
+        // transactions.
+        for (transaction_index, index_per_contract) in [(TRANSACTION + 2, 0), (TRANSACTION, 1)] {
+            sqlx::query(
+                "INSERT INTO contract_reject_transactions (
+                    contract_index, contract_sub_index, transaction_index_per_contract,
+                    transaction_index
+                ) VALUES ($1, 0, $2, $3)",
+            )
+            .bind(CONTRACT)
+            .bind(index_per_contract)
+            .bind(transaction_index)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO contract_events (
+                    transaction_index, trace_element_index, block_height, contract_index,
+                    contract_sub_index, event_index_per_contract
+                ) VALUES ($1, 0, $2, $3, 0, $4)",
+            )
+            .bind(transaction_index)
+            .bind(HEIGHT + transaction_index - TRANSACTION)
+            .bind(CONTRACT)
+            .bind(index_per_contract)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+        let affected = AffectedKeys {
+            contracts: BTreeSet::from([(CONTRACT, 0)]),
+            ..AffectedKeys::default()
+        };
+        recompute_derived_columns(&mut tx, ReindexDomain::Contracts, HEIGHT, &affected)
+            .await
+            .unwrap();
+        let rejected: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT transaction_index, transaction_index_per_contract
+            FROM contract_reject_transactions
+            WHERE contract_index = $1
+            ORDER BY transaction_index",
+        )
+        .bind(CONTRACT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(rejected, vec![(TRANSACTION, 0), (TRANSACTION + 2, 1)]);
+        let events: Vec<(i64, i64)> = sqlx::query_as(
+            "SELECT transaction_index, event_index_per_contract
+            FROM contract_events
+            WHERE contract_index = $1
+            ORDER BY transaction_index",
+        )
+        .bind(CONTRACT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(events, vec![(TRANSACTION, 0), (TRANSACTION + 2, 1)]);
+    }
+
+    #[tokio::test]
+    async fn test_clear_rewards_removes_payday_rows_of_range() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        insert_blocks(&mut tx, 2).await;
+        for height in [HEIGHT, HEIGHT + 1] {
+            sqlx::query(
+                "INSERT INTO bakers_payday_pool_rewards (payday_block_height, pool_owner)
+                VALUES ($1, NULL)",
+            )
+            .bind(height)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO payday_passive_pool_stakes (payday_block, delegators_stake)
+                VALUES ($1, 10)",
+            )
+            .bind(height)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+        clear_rewards(&mut tx, &range(HEIGHT + 1, HEIGHT + 1))
+            .await
+            .unwrap();
+        let rewards: Vec<i64> = sqlx::query_scalar(
+            "SELECT payday_block_height FROM bakers_payday_pool_rewards
+            WHERE payday_block_height >= $1",
+        )
+        .bind(HEIGHT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(rewards, vec![HEIGHT]);
+        let stakes: Vec<i64> = sqlx::query_scalar(
+            "SELECT payday_block FROM payday_passive_pool_stakes WHERE payday_block >= $1",
+        )
+        .bind(HEIGHT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(stakes, vec![HEIGHT]);
+    }
+}
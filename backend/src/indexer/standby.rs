@@ -0,0 +1,98 @@
+//! Standby mode of the indexer, waiting for the indexer lock held by another
+//! instance, such that it takes over indexing once the other instance stops.
+//!
+//! While standing by, the nodes are only checked for availability. The
+//! connections used for checking are dropped when taking over, as the
+//! traversal of the indexer connects to the nodes on its own.
+
+use super::acquire_indexer_lock;
+use concordium_rust_sdk::v2;
+use sqlx::PgConnection;
+use std::{convert::Infallible, time::Duration};
+use tracing::{info, warn};
+
+/// Wait for the indexer lock without any timeout, meanwhile checking the
+/// availability of the nodes by querying them at the provided interval.
+/// The lock is released by Postgres once the connection of the instance
+/// holding it drops.
+pub async fn wait_for_indexer_lock(
+    db_connection: &mut PgConnection,
+    endpoints: &[v2::Endpoint],
+    node_check_interval: Duration,
+) -> anyhow::Result<()> {
+    info!("Standing by until the indexer lock is released by the active instance");
+    tokio::select! {
+        result = acquire_indexer_lock(db_connection) => result?,
+        never = check_nodes(endpoints, node_check_interval) => match never {},
+    }
+    info!("Acquired the indexer lock, taking over indexing");
+    Ok(())
+}
+
+/// Query each of the nodes at the provided interval, logging nodes which are
+/// unavailable. Never returns.
+async fn check_nodes(endpoints: &[v2::Endpoint], interval: Duration) -> Infallible {
+    let mut clients: Vec<(v2::Endpoint, Option<v2::Client>)> = endpoints
+        .iter()
+        .map(|endpoint| (endpoint.clone(), None))
+        .collect();
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        for (endpoint, client) in clients.iter_mut() {
+            if client.is_none() {
+                match v2::Client::new(endpoint.clone()).await {
+                    Ok(new_client) => *client = Some(new_client),
+                    Err(err) => {
+                        warn!(
+                            "Standby failed connecting to node {}: {}",
+                            endpoint.uri(),
+                            err
+                        );
+                        continue;
+                    }
+                }
+            }
+            let Some(connected) = client.as_mut() else {
+                continue;
+            };
+            if let Err(err) = connected.get_consensus_info().await {
+                warn!("Standby failed querying node {}: {}", endpoint.uri(), err);
+                // Reconnect on the next check.
+                *client = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+
+    #[tokio::test]
+    async fn test_standby_takes_over_once_the_active_instance_disconnects() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut active = pool.acquire().await.unwrap().detach();
+        acquire_indexer_lock(&mut active).await.unwrap();
+
+        let mut standby = pool.acquire().await.unwrap().detach();
+        let waiting = wait_for_indexer_lock(&mut standby, &[], Duration::from_secs(1));
+        tokio::pin!(waiting);
+        assert!(
+            tokio::time::timeout(Duration::from_millis(500), &mut waiting)
+                .await
+                .is_err(),
+            "The standby acquired the lock held by the active instance"
+        );
+
+        active.close().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(10), waiting)
+            .await
+            .expect("The standby did not take over")
+            .unwrap();
+        standby.close().await.unwrap();
+    }
+}
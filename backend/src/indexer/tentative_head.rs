@@ -0,0 +1,416 @@
+//! Indexing of the blocks on the live branch of the chain before they are
+//! finalized, referred to as the tentative head.
+//!
+//! Tentative blocks and their transactions are stored in the shadow tables
+//! `tentative_blocks` and `tentative_transactions`, separate from the tables
+//! maintained by the block processor, which stays authoritative. Whenever the
+//! node reports a new block, the shadow tables are updated to match the branch
+//! ending in the best block of the node, rolling back the blocks of any other
+//! branch. Blocks finalized by the node but not yet processed are kept in the
+//! shadow tables as well, such that every block above the last processed block
+//! is found. Blocks at or below the height of the last processed block are
+//! removed from the shadow tables.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::{
+    types::{hashes::BlockHash, queries::BlockInfo},
+    v2,
+};
+use futures::TryStreamExt;
+use prometheus_client::{metrics::counter::Counter, registry::Registry};
+use sqlx::{postgres::PgConnectOptions, Connection as _, PgConnection};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+/// Delay before reconnecting after failing to index the tentative head.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+/// Maximum number of blocks above the last processed block kept in the shadow
+/// tables. The tentative head is not updated while the block processor is
+/// further behind, such as when catching up with the chain.
+const MAX_TENTATIVE_BLOCKS: i64 = 1000;
+
+/// Block on the live branch which is not yet finalized, prepared for being
+/// inserted into the shadow tables.
+struct TentativeBlock {
+    hash: String,
+    height: i64,
+    parent_hash: String,
+    slot_time: DateTime<Utc>,
+    baker_id: Option<i64>,
+    total_amount: i64,
+    transaction_hashes: Vec<String>,
+    transaction_senders: Vec<Option<String>>,
+    transaction_successes: Vec<bool>,
+}
+
+/// Service indexing the tentative head of the chain into the shadow tables.
+pub struct TentativeHeadIndexer {
+    /// List of Concordium nodes to cycle through on failures.
+    endpoints: Vec<v2::Endpoint>,
+    /// Options for the database connection.
+    db_connect_options: PgConnectOptions,
+    /// Metric counting the tentative blocks added to the shadow tables.
+    blocks_added: Counter,
+    /// Metric counting the tentative blocks rolled back due to the live branch
+    /// changing.
+    blocks_rolled_back: Counter,
+}
+
+impl TentativeHeadIndexer {
+    pub fn new(
+        endpoints: Vec<v2::Endpoint>,
+        db_connect_options: PgConnectOptions,
+        registry: &mut Registry,
+    ) -> Self {
+        let blocks_added = Counter::default();
+        registry.register(
+            "blocks_added",
+            "Number of tentative blocks added before being finalized",
+            blocks_added.clone(),
+        );
+        let blocks_rolled_back = Counter::default();
+        registry.register(
+            "blocks_rolled_back",
+            "Number of tentative blocks rolled back due to the live branch changing",
+            blocks_rolled_back.clone(),
+        );
+        Self {
+            endpoints,
+            db_connect_options,
+            blocks_added,
+            blocks_rolled_back,
+        }
+    }
+
+    /// Run the service until signaled by the `cancel_token`. Failures are
+    /// logged and followed by reconnecting to the next node, such that the
+    /// tentative head never stops the indexing of finalized blocks.
+    pub async fn run(self, cancel_token: CancellationToken) -> anyhow::Result<()> {
+        for endpoint in self.endpoints.iter().cycle() {
+            let result = cancel_token
+                .run_until_cancelled(self.follow_node(endpoint.clone()))
+                .await;
+            match result {
+                None => break,
+                Some(Ok(())) => info!("Stream of arrived blocks ended for node {}", endpoint.uri()),
+                Some(Err(err)) => warn!(
+                    "Failed indexing the tentative head using node {}: {:?}",
+                    endpoint.uri(),
+                    err
+                ),
+            }
+            if cancel_token
+                .run_until_cancelled(tokio::time::sleep(RECONNECT_DELAY))
+                .await
+                .is_none()
+            {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow the blocks arriving at a node, updating the shadow tables to the
+    /// best block of the node for every arrived block.
+    async fn follow_node(&self, endpoint: v2::Endpoint) -> anyhow::Result<()> {
+        let mut client = v2::Client::new(endpoint)
+            .await
+            .context("Failed connecting to the node")?;
+        let mut db_connection = PgConnection::connect_with(&self.db_connect_options)
+            .await
+            .context("Failed establishing the database connection")?;
+        // A separate client is used for the stream of arrived blocks, as the other
+        // client is queried while the stream is open.
+        let mut arrived_blocks = client.clone().get_blocks().await?;
+        while let Some(arrived_block) = arrived_blocks.try_next().await? {
+            debug!(
+                "Block {}:{} arrived",
+                arrived_block.height, arrived_block.block_hash
+            );
+            self.update_to_best_block(&mut client, &mut db_connection)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Update the shadow tables to match the branch ending in the best block
+    /// of the node.
+    async fn update_to_best_block(
+        &self,
+        client: &mut v2::Client,
+        db_connection: &mut PgConnection,
+    ) -> anyhow::Result<()> {
+        let best_block = client.get_consensus_info().await?.best_block;
+        let last_processed_height = sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
+            .fetch_one(db_connection.as_mut())
+            .await?
+            .unwrap_or(0);
+        let best_block_info = client
+            .get_block_info(v2::BlockIdentifier::Given(best_block))
+            .await?
+            .response;
+        let best_height = i64::try_from(best_block_info.block_height.height)?;
+        if best_height - last_processed_height > MAX_TENTATIVE_BLOCKS {
+            debug!(
+                "Best block {} is more than {} blocks above the last processed block {}, waiting \
+                 for the block processor to catch up",
+                best_height, MAX_TENTATIVE_BLOCKS, last_processed_height
+            );
+            return Ok(());
+        }
+        // Walk the branch from the best block towards the last processed block, until
+        // reaching a block which is processed or already in the shadow tables. Blocks
+        // finalized by the node but not yet processed are included, such that every
+        // block above the last processed block is found in the shadow tables.
+        let mut branch = Vec::new();
+        let mut block_info = best_block_info;
+        loop {
+            let height = i64::try_from(block_info.block_height.height)?;
+            if height <= last_processed_height {
+                break;
+            }
+            let known = sqlx::query_scalar!(
+                "SELECT true FROM tentative_blocks WHERE hash = $1",
+                block_info.block_hash.to_string()
+            )
+            .fetch_optional(db_connection.as_mut())
+            .await?
+            .is_some();
+            if known {
+                break;
+            }
+            let parent = block_info.block_parent;
+            branch.push(prepare_block(client, block_info).await?);
+            block_info = client
+                .get_block_info(v2::BlockIdentifier::Given(parent))
+                .await?
+                .response;
+        }
+        branch.reverse();
+        // Blocks above the start of the new part of the branch belong to another branch.
+        let rollback_from_height = branch.first().map_or(best_height + 1, |block| block.height);
+
+        let mut tx = db_connection.begin().await?;
+        let rolled_back = replace_branch(&mut tx, rollback_from_height, &branch).await?;
+        tx.commit().await?;
+
+        if rolled_back > 0 {
+            info!(
+                "Rolled back {} tentative blocks from height {}",
+                rolled_back, rollback_from_height
+            );
+            self.blocks_rolled_back.inc_by(rolled_back);
+        }
+        self.blocks_added.inc_by(branch.len().try_into()?);
+        Ok(())
+    }
+}
+
+/// Replace the blocks of the shadow tables from the height with the blocks of
+/// the branch, and remove the blocks processed by the block processor, which
+/// are authoritative. Returns the number of blocks rolled back.
+async fn replace_branch(
+    tx: &mut sqlx::PgTransaction<'_>,
+    rollback_from_height: i64,
+    branch: &[TentativeBlock],
+) -> anyhow::Result<u64> {
+    let rolled_back = sqlx::query!(
+        "DELETE FROM tentative_blocks WHERE height >= $1",
+        rollback_from_height
+    )
+    .execute(tx.as_mut())
+    .await?
+    .rows_affected();
+    sqlx::query!("DELETE FROM tentative_blocks WHERE height <= (SELECT MAX(height) FROM blocks)")
+        .execute(tx.as_mut())
+        .await?;
+    for block in branch {
+        save_block(block, tx).await?;
+    }
+    Ok(rolled_back)
+}
+
+/// Fetch the information of a tentative block needed for the shadow tables.
+async fn prepare_block(
+    client: &mut v2::Client,
+    block_info: BlockInfo,
+) -> anyhow::Result<TentativeBlock> {
+    let block_hash: BlockHash = block_info.block_hash;
+    let total_amount = client
+        .get_tokenomics_info(v2::BlockIdentifier::Given(block_hash))
+        .await?
+        .response
+        .common_reward_data()
+        .total_amount
+        .micro_ccd();
+    let summaries = client
+        .get_block_transaction_events(v2::BlockIdentifier::Given(block_hash))
+        .await?
+        .response
+        .try_collect::<Vec<_>>()
+        .await?;
+    let mut transaction_hashes = Vec::with_capacity(summaries.len());
+    let mut transaction_senders = Vec::with_capacity(summaries.len());
+    let mut transaction_successes = Vec::with_capacity(summaries.len());
+    for summary in summaries {
+        transaction_hashes.push(summary.hash.to_string());
+        transaction_senders.push(summary.sender_account().map(|a| a.to_string()));
+        transaction_successes.push(summary.is_success().known_or_err()?);
+    }
+    Ok(TentativeBlock {
+        hash: block_hash.to_string(),
+        height: i64::try_from(block_info.block_height.height)?,
+        parent_hash: block_info.block_parent.to_string(),
+        slot_time: block_info.block_slot_time,
+        baker_id: block_info
+            .block_baker
+            .map(|baker_id| i64::try_from(baker_id.id.index))
+            .transpose()?,
+        total_amount: i64::try_from(total_amount)?,
+        transaction_hashes,
+        transaction_senders,
+        transaction_successes,
+    })
+}
+
+/// Insert a tentative block and its transactions into the shadow tables.
+async fn save_block(
+    block: &TentativeBlock,
+    tx: &mut sqlx::PgTransaction<'_>,
+) -> anyhow::Result<()> {
+    sqlx::query!(
+        "INSERT INTO tentative_blocks (
+            hash,
+            height,
+            parent_hash,
+            slot_time,
+            block_time,
+            baker_id,
+            total_amount
+        ) VALUES (
+            $1,
+            $2,
+            $3,
+            $4,
+            COALESCE((
+                EXTRACT(EPOCH FROM $4 - COALESCE(
+                    (SELECT slot_time FROM tentative_blocks WHERE hash = $3),
+                    (SELECT slot_time FROM blocks WHERE hash = $3)
+                )) * 1000
+            )::INTEGER, 0),
+            $5,
+            $6
+        )",
+        block.hash,
+        block.height,
+        block.parent_hash,
+        block.slot_time,
+        block.baker_id,
+        block.total_amount
+    )
+    .execute(tx.as_mut())
+    .await?;
+    sqlx::query!(
+        "INSERT INTO tentative_transactions (block_hash, hash, sender, success, index_in_block)
+        SELECT $1, hash, sender, success, ordinality - 1
+        FROM UNNEST($2::TEXT[], $3::TEXT[], $4::BOOLEAN[])
+            WITH ORDINALITY AS items(hash, sender, success, ordinality)",
+        block.hash,
+        &block.transaction_hashes,
+        &block.transaction_senders,
+        &block.transaction_successes
+    )
+    .execute(tx.as_mut())
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::TimeZone;
+
+    /// Height far above the height of any indexed chain, such that the rows of
+    /// the test do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+
+    fn block(height: i64, branch: u8, transactions: usize) -> TentativeBlock {
+        let hash = |height: i64, branch: u8| format!("{:02x}{:062x}", branch, height);
+        TentativeBlock {
+            hash: hash(height, branch),
+            height,
+            parent_hash: hash(height - 1, branch),
+            slot_time: Utc.timestamp_opt(1_700_000_000 + height, 0).unwrap(),
+            baker_id: None,
+            total_amount: 0,
+            transaction_hashes: (0..transactions)
+                .map(|index| format!("{:02x}{:060x}{:02x}", branch, height, index))
+                .collect(),
+            transaction_senders: vec![None; transactions],
+            transaction_successes: vec![true; transactions],
+        }
+    }
+
+    async fn tentative_transactions(tx: &mut sqlx::PgTransaction<'_>) -> Vec<(String, String)> {
+        sqlx::query_as(
+            "SELECT tentative_transactions.block_hash, tentative_transactions.hash
+            FROM tentative_transactions
+                JOIN tentative_blocks ON tentative_blocks.hash = tentative_transactions.block_hash
+            WHERE tentative_blocks.height > $1
+            ORDER BY tentative_blocks.height, tentative_transactions.index_in_block",
+        )
+        .bind(HEIGHT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replace_branch_rolls_back_other_branch() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let slot_time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        test_database::insert_block(tx.as_mut(), HEIGHT, slot_time)
+            .await
+            .unwrap();
+        let first = block(HEIGHT + 1, 0xaa, 1);
+        let replaced = block(HEIGHT + 2, 0xaa, 2);
+        let rolled_back = replace_branch(&mut tx, HEIGHT + 1, &[first, replaced])
+            .await
+            .unwrap();
+        assert_eq!(rolled_back, 0);
+        assert_eq!(tentative_transactions(&mut tx).await.len(), 3);
+
+        // The live branch changes at the second block.
+        let branch = [block(HEIGHT + 2, 0xbb, 2), block(HEIGHT + 3, 0xbb, 0)];
+        let rolled_back = replace_branch(&mut tx, HEIGHT + 2, &branch).await.unwrap();
+        assert_eq!(rolled_back, 1);
+        let mut expected = Vec::new();
+        for block in [block(HEIGHT + 1, 0xaa, 1), block(HEIGHT + 2, 0xbb, 2)] {
+            for hash in block.transaction_hashes {
+                expected.push((block.hash.clone(), hash));
+            }
+        }
+        assert_eq!(tentative_transactions(&mut tx).await, expected);
+
+        // Blocks processed by the block processor are removed.
+        test_database::insert_block(tx.as_mut(), HEIGHT + 1, slot_time)
+            .await
+            .unwrap();
+        let rolled_back = replace_branch(&mut tx, HEIGHT + 4, &[]).await.unwrap();
+        assert_eq!(rolled_back, 0);
+        let heights: Vec<i64> = sqlx::query_scalar(
+            "SELECT height FROM tentative_blocks WHERE height > $1 ORDER BY height",
+        )
+        .bind(HEIGHT)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        assert_eq!(heights, vec![HEIGHT + 2, HEIGHT + 3]);
+    }
+}
@@ -0,0 +1,783 @@
+//! Verification of the derived state in the database against the state
+//! reported by a Concordium Node.
+//!
+//! Derived state, such as balances and stakes, is only ever updated
+//! incrementally by the indexer, meaning a bug can make it drift silently from
+//! the state of the chain. The verifier compares the current state in the
+//! database with the state reported by the node for the last indexed block and
+//! writes every difference into a machine-readable report. The CCD balances of
+//! accounts can also be verified at an earlier block, deriving them from the
+//! account statements.
+//!
+//! The state is read from a snapshot of the database, such that it can run
+//! while the indexer is running.
+
+use anyhow::Context;
+use bigdecimal::BigDecimal;
+use concordium_rust_sdk::{
+    cis2,
+    id::types::AccountAddress,
+    types::{AbsoluteBlockHeight, AccountStakingInfo, Address, ContractAddress},
+    v2,
+};
+use futures::{StreamExt, TryStreamExt};
+use sqlx::{Connection as _, PgConnection};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    str::FromStr,
+};
+use tracing::{info, warn};
+
+/// The state which can be verified against the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VerifyCheck {
+    /// CCD balances and delegated stake of accounts.
+    Accounts,
+    /// Stake of validators and the number of delegators of their pools.
+    Bakers,
+    /// Protocol-level token balances of accounts.
+    Plt,
+    /// CIS-2 token balances of accounts and the total supply of CIS-2 tokens.
+    Cis2,
+}
+
+/// Arguments of the `verify` subcommand.
+#[derive(clap::Args, Debug)]
+pub struct VerifyArgs {
+    /// Height of the block to verify, the last indexed block when omitted.
+    /// Only the CCD balances of accounts can be verified at earlier blocks, as
+    /// they are derived from the account statements.
+    #[arg(long)]
+    pub height: Option<u64>,
+    /// The state to verify.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_values = ["accounts", "bakers", "plt", "cis2"]
+    )]
+    pub checks: Vec<VerifyCheck>,
+    /// File to write the JSON report into.
+    #[arg(long)]
+    pub out: PathBuf,
+    /// Maximum number of concurrent queries to the node.
+    #[arg(long, default_value = "16")]
+    pub concurrency: usize,
+}
+
+/// Kind of a difference between the database and the node.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+enum MismatchKind {
+    /// CCD balance of an account, keyed by the account address.
+    AccountAmount,
+    /// Delegated stake of an account, keyed by the account address.
+    DelegatedStake,
+    /// Stake of a validator, keyed by the validator ID.
+    BakerStake,
+    /// Number of delegators of a validator pool, keyed by the validator ID.
+    PoolDelegatorCount,
+    /// Protocol-level token balance of an account, keyed by `<token ID>/<account
+    /// address>`.
+    PltBalance,
+    /// CIS-2 token balance of an account, keyed by `<token address>/<account
+    /// address>`.
+    Cis2Balance,
+    /// Total supply of a CIS-2 token which is below the sum of the balances
+    /// held by accounts according to the node, keyed by the token address.
+    Cis2TotalSupply,
+}
+
+/// A difference between the database and the node. A missing value means the
+/// entity is missing on that side.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Mismatch {
+    kind: MismatchKind,
+    key: String,
+    database: Option<String>,
+    node: Option<String>,
+}
+
+/// A failed node query preventing part of the state from being verified.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct QueryError {
+    check: VerifyCheck,
+    key: String,
+    error: String,
+}
+
+/// Report of the differences found between the database and the node.
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyReport {
+    block_height: u64,
+    block_hash: String,
+    /// Number of entities verified per check.
+    checked: BTreeMap<VerifyCheck, u64>,
+    mismatches: Vec<Mismatch>,
+    query_errors: Vec<QueryError>,
+}
+
+impl VerifyReport {
+    fn mismatch(
+        &mut self,
+        kind: MismatchKind,
+        key: impl ToString,
+        database: Option<impl ToString>,
+        node: Option<impl ToString>,
+    ) {
+        self.mismatches.push(Mismatch {
+            kind,
+            key: key.to_string(),
+            database: database.map(|value| value.to_string()),
+            node: node.map(|value| value.to_string()),
+        });
+    }
+
+    fn checked(&mut self, check: VerifyCheck, count: u64) {
+        *self.checked.entry(check).or_default() += count;
+    }
+}
+
+/// Compare the state in the database with the state reported by the node and
+/// write the report. Fails when differences are found, such that it can be
+/// used in scheduled jobs.
+pub async fn run_verify(
+    endpoints: Vec<v2::Endpoint>,
+    db_connection: &mut PgConnection,
+    args: VerifyArgs,
+) -> anyhow::Result<()> {
+    let endpoint = endpoints
+        .first()
+        .context("At least one Concordium Node must be provided")?
+        .clone();
+    let mut client = v2::Client::new(endpoint)
+        .await
+        .context("Failed to establish connection to Concordium Node")?;
+    // Read every table from the same snapshot, such that the state is consistent
+    // with the last indexed block while the indexer keeps running.
+    let mut tx = db_connection.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(tx.as_mut())
+        .await?;
+    let last_height = sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
+        .fetch_one(tx.as_mut())
+        .await?
+        .context("No blocks are indexed yet")?;
+    let height = match args.height {
+        Some(height) => i64::try_from(height)?,
+        None => last_height,
+    };
+    anyhow::ensure!(
+        height <= last_height,
+        "The block height {} is above the last indexed block height {}",
+        height,
+        last_height
+    );
+    // Only the current state is held in the database, except for the CCD balances
+    // which are derived from the account statements at earlier blocks.
+    let historical_height = (height < last_height).then_some(height);
+    if historical_height.is_some() {
+        anyhow::ensure!(
+            args.checks
+                .iter()
+                .all(|check| *check == VerifyCheck::Accounts),
+            "Only the accounts check is supported below the last indexed block height {}, \
+             provide --checks accounts",
+            last_height
+        );
+    }
+    let block = sqlx::query!("SELECT height, hash FROM blocks WHERE height = $1", height)
+        .fetch_one(tx.as_mut())
+        .await?;
+    let block_height = AbsoluteBlockHeight::from(u64::try_from(block.height)?);
+    let node_block_hash = client
+        .get_block_info(block_height)
+        .await?
+        .response
+        .block_hash
+        .to_string();
+    anyhow::ensure!(
+        node_block_hash == block.hash,
+        "Block {} at height {} in the database is {} on the node, the node is on another network",
+        block.hash,
+        block_height,
+        node_block_hash
+    );
+
+    info!(
+        "Verifying {:?} at block height {}",
+        args.checks, block_height
+    );
+    let mut report = VerifyReport {
+        block_height: block_height.height,
+        block_hash: block.hash,
+        checked: BTreeMap::new(),
+        mismatches: Vec::new(),
+        query_errors: Vec::new(),
+    };
+    let verify_accounts = args.checks.contains(&VerifyCheck::Accounts);
+    let verify_plt = args.checks.contains(&VerifyCheck::Plt);
+    if verify_accounts || verify_plt {
+        verify_account_infos(
+            &mut tx,
+            &client,
+            block_height,
+            historical_height,
+            args.concurrency,
+            verify_accounts,
+            verify_plt,
+            &mut report,
+        )
+        .await?;
+    }
+    if args.checks.contains(&VerifyCheck::Bakers) {
+        verify_bakers(
+            &mut tx,
+            &client,
+            block_height,
+            args.concurrency,
+            &mut report,
+        )
+        .await?;
+    }
+    if args.checks.contains(&VerifyCheck::Cis2) {
+        verify_cis2(
+            &mut tx,
+            &client,
+            block_height,
+            args.concurrency,
+            &mut report,
+        )
+        .await?;
+    }
+    tx.commit().await?;
+
+    let file = std::fs::File::create(&args.out)
+        .with_context(|| format!("Failed creating {}", args.out.display()))?;
+    serde_json::to_writer_pretty(std::io::BufWriter::new(file), &report)?;
+    if !report.query_errors.is_empty() {
+        warn!(
+            "{} node queries failed, the affected state is not verified",
+            report.query_errors.len()
+        );
+    }
+    anyhow::ensure!(
+        report.mismatches.is_empty(),
+        "Found {} differences between the database and the node at block height {}, see {}",
+        report.mismatches.len(),
+        block_height,
+        args.out.display()
+    );
+    info!(
+        "No differences found between the database and the node at block height {}",
+        block_height
+    );
+    Ok(())
+}
+
+/// Verify the CCD balance and delegated stake and/or the protocol-level token
+/// balances of every account, using the account information of the node. Only
+/// the CCD balances are verified when verifying an earlier block than the last
+/// indexed block, provided as `historical_height`.
+async fn verify_account_infos(
+    tx: &mut sqlx::PgTransaction<'_>,
+    client: &v2::Client,
+    block_height: AbsoluteBlockHeight,
+    historical_height: Option<i64>,
+    concurrency: usize,
+    verify_accounts: bool,
+    verify_plt: bool,
+    report: &mut VerifyReport,
+) -> anyhow::Result<()> {
+    let mut db_accounts = db_account_amounts(tx, historical_height).await?;
+    let mut db_plt_balances: HashMap<String, HashMap<String, BigDecimal>> = HashMap::new();
+    if verify_plt {
+        let mut rows = sqlx::query!(
+            r#"SELECT
+                accounts.address,
+                plt_tokens.token_id,
+                plt_accounts.amount AS "amount!"
+            FROM plt_accounts
+                JOIN accounts ON accounts.index = plt_accounts.account_index
+                JOIN plt_tokens ON plt_tokens.index = plt_accounts.token_index"#
+        )
+        .fetch(tx.as_mut());
+        while let Some(row) = rows.try_next().await? {
+            db_plt_balances
+                .entry(row.address)
+                .or_default()
+                .insert(row.token_id, row.amount);
+        }
+    }
+
+    let addresses: Vec<AccountAddress> = client
+        .clone()
+        .get_account_list(block_height)
+        .await?
+        .response
+        .try_collect()
+        .await?;
+    let mut account_infos = futures::stream::iter(addresses)
+        .map(|address| {
+            let mut client = client.clone();
+            async move {
+                let info = client
+                    .get_account_info(&v2::AccountIdentifier::Address(address), block_height)
+                    .await?
+                    .response;
+                anyhow::Ok((address, info))
+            }
+        })
+        .buffer_unordered(concurrency);
+    while let Some((address, info)) = account_infos.try_next().await? {
+        let address = address.to_string();
+        let db_account = db_accounts.remove(&address);
+        if verify_accounts {
+            report.checked(VerifyCheck::Accounts, 1);
+            let amount = i64::try_from(info.account_amount.micro_ccd())?;
+            let delegated_stake = match info.account_stake.map(|s| s.known_or_err()).transpose()? {
+                Some(AccountStakingInfo::Delegator { staked_amount, .. }) => {
+                    i64::try_from(staked_amount.micro_ccd())?
+                }
+                _ => 0,
+            };
+            match db_account {
+                Some((db_amount, db_delegated_stake)) => {
+                    if db_amount != amount {
+                        report.mismatch(
+                            MismatchKind::AccountAmount,
+                            &address,
+                            Some(db_amount),
+                            Some(amount),
+                        );
+                    }
+                    if db_delegated_stake.is_some_and(|stake| stake != delegated_stake) {
+                        report.mismatch(
+                            MismatchKind::DelegatedStake,
+                            &address,
+                            db_delegated_stake,
+                            Some(delegated_stake),
+                        );
+                    }
+                }
+                None => report.mismatch(
+                    MismatchKind::AccountAmount,
+                    &address,
+                    None::<i64>,
+                    Some(amount),
+                ),
+            }
+        }
+        if verify_plt {
+            let mut db_balances = db_plt_balances.remove(&address).unwrap_or_default();
+            for token in info.tokens {
+                report.checked(VerifyCheck::Plt, 1);
+                let token_id = token.token_id.to_string();
+                let balance = BigDecimal::from(token.state.balance.value());
+                let db_balance = db_balances.remove(&token_id);
+                if db_balance.as_ref() != Some(&balance) {
+                    report.mismatch(
+                        MismatchKind::PltBalance,
+                        format!("{}/{}", token_id, address),
+                        db_balance,
+                        Some(balance),
+                    );
+                }
+            }
+            // Balances in the database of tokens which the node does not report for the
+            // account.
+            for (token_id, db_balance) in db_balances {
+                report.checked(VerifyCheck::Plt, 1);
+                if db_balance != BigDecimal::from(0) {
+                    report.mismatch(
+                        MismatchKind::PltBalance,
+                        format!("{}/{}", token_id, address),
+                        Some(db_balance),
+                        None::<BigDecimal>,
+                    );
+                }
+            }
+        }
+    }
+    // Accounts in the database which the node does not report.
+    if verify_accounts {
+        for (address, (db_amount, _)) in db_accounts {
+            report.mismatch(
+                MismatchKind::AccountAmount,
+                address,
+                Some(db_amount),
+                None::<i64>,
+            );
+        }
+    }
+    for (address, db_balances) in db_plt_balances {
+        for (token_id, db_balance) in db_balances {
+            report.mismatch(
+                MismatchKind::PltBalance,
+                format!("{}/{}", token_id, address),
+                Some(db_balance),
+                None::<BigDecimal>,
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Read the CCD balance and delegated stake of every account in the database,
+/// keyed by the account address. At a historical height the balances are
+/// derived by reverting the account statements of later blocks from the
+/// current balances, excluding accounts created after the height, while the
+/// delegated stake is not known.
+async fn db_account_amounts(
+    tx: &mut sqlx::PgTransaction<'_>,
+    historical_height: Option<i64>,
+) -> anyhow::Result<HashMap<String, (i64, Option<i64>)>> {
+    let Some(height) = historical_height else {
+        let accounts = sqlx::query!("SELECT address, amount, delegated_stake FROM accounts")
+            .fetch(tx.as_mut())
+            .map_ok(|row| (row.address, (row.amount, Some(row.delegated_stake))))
+            .try_collect()
+            .await?;
+        return Ok(accounts);
+    };
+    let accounts = sqlx::query!(
+        r#"SELECT
+            accounts.address,
+            (accounts.amount - COALESCE(later.amount, 0))::BIGINT AS "amount!"
+        FROM accounts
+            LEFT JOIN transactions ON transactions.index = accounts.transaction_index
+            LEFT JOIN (
+                SELECT account_index, SUM(amount) AS amount
+                FROM account_statements
+                WHERE block_height > $1
+                GROUP BY account_index
+            ) AS later ON later.account_index = accounts.index
+        WHERE transactions.block_height IS NULL OR transactions.block_height <= $1"#,
+        height
+    )
+    .fetch(tx.as_mut())
+    .map_ok(|row| (row.address, (row.amount, None)))
+    .try_collect()
+    .await?;
+    Ok(accounts)
+}
+
+/// Verify the stake of every validator and the number of delegators of their
+/// pools.
+async fn verify_bakers(
+    tx: &mut sqlx::PgTransaction<'_>,
+    client: &v2::Client,
+    block_height: AbsoluteBlockHeight,
+    concurrency: usize,
+    report: &mut VerifyReport,
+) -> anyhow::Result<()> {
+    let mut db_bakers: HashMap<i64, (i64, i64)> =
+        sqlx::query!("SELECT id, staked, pool_delegator_count FROM bakers")
+            .fetch(tx.as_mut())
+            .map_ok(|row| (row.id, (row.staked, row.pool_delegator_count)))
+            .try_collect()
+            .await?;
+    let baker_ids: Vec<_> = client
+        .clone()
+        .get_baker_list(block_height)
+        .await?
+        .response
+        .try_collect()
+        .await?;
+    let mut bakers = futures::stream::iter(baker_ids)
+        .map(|baker_id| {
+            let mut client = client.clone();
+            async move {
+                let staked = client
+                    .get_account_info(&v2::AccountIdentifier::Index(baker_id.id), block_height)
+                    .await?
+                    .response
+                    .account_stake
+                    .context("Expected account to be a validator")?
+                    .known_or_err()?
+                    .staked_amount();
+                let delegator_count = client
+                    .get_pool_delegators(block_height, baker_id)
+                    .await?
+                    .response
+                    .try_collect::<Vec<_>>()
+                    .await?
+                    .len();
+                anyhow::Ok((
+                    i64::try_from(baker_id.id.index)?,
+                    i64::try_from(staked.micro_ccd())?,
+                    i64::try_from(delegator_count)?,
+                ))
+            }
+        })
+        .buffer_unordered(concurrency);
+    while let Some((baker_id, staked, delegator_count)) = bakers.try_next().await? {
+        report.checked(VerifyCheck::Bakers, 1);
+        let Some((db_staked, db_delegator_count)) = db_bakers.remove(&baker_id) else {
+            report.mismatch(
+                MismatchKind::BakerStake,
+                baker_id,
+                None::<i64>,
+                Some(staked),
+            );
+            continue;
+        };
+        if db_staked != staked {
+            report.mismatch(
+                MismatchKind::BakerStake,
+                baker_id,
+                Some(db_staked),
+                Some(staked),
+            );
+        }
+        if db_delegator_count != delegator_count {
+            report.mismatch(
+                MismatchKind::PoolDelegatorCount,
+                baker_id,
+                Some(db_delegator_count),
+                Some(delegator_count),
+            );
+        }
+    }
+    // Validators in the database which the node does not report.
+    for (baker_id, (db_staked, _)) in db_bakers {
+        report.mismatch(
+            MismatchKind::BakerStake,
+            baker_id,
+            Some(db_staked),
+            None::<i64>,
+        );
+    }
+    Ok(())
+}
+
+/// CIS-2 token balance of an account in the database.
+struct Cis2Holder {
+    token_address: String,
+    token_id: String,
+    account_address: String,
+    balance: BigDecimal,
+}
+
+/// Verify the CIS-2 token balances of every account using the `balanceOf`
+/// query of the token contracts. CIS-2 provides no query for the total supply
+/// of a token and balances held by smart contracts are not tracked, so the
+/// total supply is only verified to not be below the sum of the balances of
+/// the accounts.
+async fn verify_cis2(
+    tx: &mut sqlx::PgTransaction<'_>,
+    client: &v2::Client,
+    block_height: AbsoluteBlockHeight,
+    concurrency: usize,
+    report: &mut VerifyReport,
+) -> anyhow::Result<()> {
+    let total_supplies: HashMap<String, BigDecimal> =
+        sqlx::query!("SELECT token_address, total_supply FROM tokens")
+            .fetch(tx.as_mut())
+            .map_ok(|row| (row.token_address, row.total_supply))
+            .try_collect()
+            .await?;
+    let mut holders_by_contract: BTreeMap<(i64, i64), Vec<Cis2Holder>> = BTreeMap::new();
+    let mut rows = sqlx::query!(
+        "SELECT
+            tokens.contract_index,
+            tokens.contract_sub_index,
+            tokens.token_address,
+            tokens.token_id,
+            accounts.address,
+            account_tokens.balance
+        FROM account_tokens
+            JOIN tokens ON tokens.index = account_tokens.token_index
+            JOIN accounts ON accounts.index = account_tokens.account_index"
+    )
+    .fetch(tx.as_mut());
+    while let Some(row) = rows.try_next().await? {
+        holders_by_contract
+            .entry((row.contract_index, row.contract_sub_index))
+            .or_default()
+            .push(Cis2Holder {
+                token_address: row.token_address,
+                token_id: row.token_id,
+                account_address: row.address,
+                balance: row.balance,
+            });
+    }
+
+    let mut contracts = futures::stream::iter(holders_by_contract)
+        .map(|((index, sub_index), holders)| {
+            let client = client.clone();
+            async move {
+                let contract_address =
+                    ContractAddress::new(index.try_into()?, sub_index.try_into()?);
+                let balances = match cis2::Cis2Contract::create(client, contract_address).await {
+                    Ok(mut contract) => {
+                        let mut balances = Vec::with_capacity(holders.len());
+                        for holder in holders.iter() {
+                            balances.push(
+                                query_cis2_balance(&mut contract, block_height, holder).await,
+                            );
+                        }
+                        balances
+                    }
+                    Err(err) => {
+                        let err = anyhow::Error::from(err);
+                        holders
+                            .iter()
+                            .map(|_| Err(anyhow::anyhow!("{:#}", err)))
+                            .collect()
+                    }
+                };
+                anyhow::Ok((holders, balances))
+            }
+        })
+        .buffer_unordered(concurrency);
+    let mut node_sums: HashMap<String, BigDecimal> = HashMap::new();
+    while let Some((holders, balances)) = contracts.try_next().await? {
+        for (holder, balance) in holders.into_iter().zip(balances) {
+            let key = format!("{}/{}", holder.token_address, holder.account_address);
+            let balance = match balance {
+                Ok(balance) => balance,
+                Err(err) => {
+                    report.query_errors.push(QueryError {
+                        check: VerifyCheck::Cis2,
+                        key,
+                        error: format!("{:#}", err),
+                    });
+                    continue;
+                }
+            };
+            report.checked(VerifyCheck::Cis2, 1);
+            if holder.balance != balance {
+                report.mismatch(
+                    MismatchKind::Cis2Balance,
+                    key,
+                    Some(&holder.balance),
+                    Some(&balance),
+                );
+            }
+            *node_sums.entry(holder.token_address).or_default() += balance;
+        }
+    }
+    for (token_address, node_sum) in node_sums {
+        let total_supply = total_supplies.get(&token_address);
+        if total_supply.is_none_or(|total_supply| *total_supply < node_sum) {
+            report.mismatch(
+                MismatchKind::Cis2TotalSupply,
+                token_address,
+                total_supply,
+                Some(node_sum),
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Query the CIS-2 token balance of an account.
+async fn query_cis2_balance(
+    contract: &mut cis2::Cis2Contract,
+    block_height: AbsoluteBlockHeight,
+    holder: &Cis2Holder,
+) -> anyhow::Result<BigDecimal> {
+    let token_id = cis2::TokenId::from_str(&holder.token_id)?;
+    let address = Address::Account(AccountAddress::from_str(&holder.account_address)?);
+    let amount = contract
+        .balance_of_single(block_height, token_id, address)
+        .await?;
+    Ok(BigDecimal::from_biguint(amount.0, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::Utc;
+
+    /// Heights and indices far above those of any indexed chain, such that the
+    /// rows of the tests do not collide with the rows of the test database.
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+
+    #[tokio::test]
+    async fn test_account_amounts_derived_at_historical_height() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        for offset in 0..3 {
+            test_database::insert_block(tx.as_mut(), HEIGHT + offset, Utc::now())
+                .await
+                .unwrap();
+            test_database::insert_transaction(tx.as_mut(), TRANSACTION + offset, HEIGHT + offset)
+                .await
+                .unwrap();
+        }
+        let existing = AccountAddress([0xfe; 32]);
+        let created_later = AccountAddress([0xfd; 32]);
+        for (index, address, transaction) in [
+            (ACCOUNT, &existing, TRANSACTION),
+            (ACCOUNT + 1, &created_later, TRANSACTION + 1),
+        ] {
+            test_database::insert_account(tx.as_mut(), index, address, 100)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE accounts SET transaction_index = $2 WHERE index = $1")
+                .bind(index)
+                .bind(transaction)
+                .execute(tx.as_mut())
+                .await
+                .unwrap();
+        }
+        for (offset, amount, balance) in [(1, 30, 110), (2, -10, 100)] {
+            sqlx::query(
+                "INSERT INTO account_statements (
+                    account_index, entry_type, amount, block_height, transaction_id,
+                    account_balance, slot_time
+                ) VALUES ($1, 'TransferIn', $2, $3, $4, $5, now())",
+            )
+            .bind(ACCOUNT)
+            .bind(amount)
+            .bind(HEIGHT + offset)
+            .bind(TRANSACTION + offset)
+            .bind(balance)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let fixture = |accounts: HashMap<String, (i64, Option<i64>)>| {
+            accounts
+                .into_iter()
+                .filter(|(address, _)| {
+                    *address == existing.to_string() || *address == created_later.to_string()
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+        let historical = fixture(db_account_amounts(&mut tx, Some(HEIGHT)).await.unwrap());
+        assert_eq!(
+            historical,
+            BTreeMap::from([(existing.to_string(), (80, None))])
+        );
+        let historical = fixture(db_account_amounts(&mut tx, Some(HEIGHT + 1)).await.unwrap());
+        assert_eq!(
+            historical,
+            BTreeMap::from([
+                (existing.to_string(), (110, None)),
+                (created_later.to_string(), (100, None)),
+            ])
+        );
+        let current = fixture(db_account_amounts(&mut tx, None).await.unwrap());
+        assert_eq!(
+            current,
+            BTreeMap::from([
+                (existing.to_string(), (100, Some(0))),
+                (created_later.to_string(), (100, Some(0))),
+            ])
+        );
+    }
+}
@@ -0,0 +1,298 @@
+//! Indexing profiles, allowing lightweight deployments of the indexer to skip
+//! whole domains of derived data. The domains populated in the database are
+//! recorded in the `indexing_domains` table, such that the API can report
+//! queries on missing domains as not indexed on the instance. The API loads the
+//! populated domains once at startup.
+
+use sqlx::PgConnection;
+use std::fmt;
+use tracing::{info, warn};
+
+/// Domain of derived data which can be skipped by an indexing profile. Data
+/// of accounts, transfers, validators and delegators is always indexed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(type_name = "indexing_domain")] // only for PostgreSQL to match a type definition
+pub enum IndexingDomain {
+    /// Smart contract events and rejected smart contract module and update
+    /// transactions. Smart contract modules and instances are always indexed,
+    /// as their CCD balances are part of the account statements.
+    Contracts,
+    /// CIS-2 tokens, token events, balances and account statements, requiring
+    /// the indexer to query the node for the support of CIS-2 by smart
+    /// contract instances.
+    Cis2,
+    /// Protocol-level tokens, token events and balances.
+    Plt,
+    /// Pool stakes, lottery powers, commission rates and APYs of validators
+    /// and passive delegation captured at paydays, requiring the indexer to
+    /// query the node for the pools at every payday.
+    Payday,
+    /// Time series of validators added and removed and rewards paid out.
+    Metrics,
+}
+
+impl IndexingDomain {
+    /// Every indexing domain.
+    pub const ALL: [IndexingDomain; 5] = [
+        IndexingDomain::Contracts,
+        IndexingDomain::Cis2,
+        IndexingDomain::Plt,
+        IndexingDomain::Payday,
+        IndexingDomain::Metrics,
+    ];
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+impl fmt::Display for IndexingDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IndexingDomain::Contracts => "smart contract events",
+            IndexingDomain::Cis2 => "CIS-2 token",
+            IndexingDomain::Plt => "protocol-level token",
+            IndexingDomain::Payday => "payday pool",
+            IndexingDomain::Metrics => "validator and reward metrics",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Set of indexing domains.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexingDomains(u8);
+
+impl IndexingDomains {
+    /// Every indexing domain.
+    pub fn all() -> Self {
+        IndexingDomain::ALL.into_iter().collect()
+    }
+
+    pub fn contains(self, domain: IndexingDomain) -> bool {
+        self.0 & domain.bit() != 0
+    }
+
+    /// Ensure the domain is part of the set, as the indexer might be running
+    /// with an indexing profile skipping it.
+    pub fn ensure_indexed(self, domain: IndexingDomain) -> Result<(), DomainNotIndexed> {
+        if self.contains(domain) {
+            Ok(())
+        } else {
+            Err(DomainNotIndexed(domain))
+        }
+    }
+}
+
+/// Error of queries on a domain which is not populated in the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The {0} data is not indexed on this instance")]
+pub struct DomainNotIndexed(pub IndexingDomain);
+
+impl FromIterator<IndexingDomain> for IndexingDomains {
+    fn from_iter<T: IntoIterator<Item = IndexingDomain>>(iter: T) -> Self {
+        Self(iter.into_iter().fold(0, |bits, domain| bits | domain.bit()))
+    }
+}
+
+/// Profile selecting the domains of derived data indexed by the indexer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum IndexingProfile {
+    /// Index every domain.
+    #[default]
+    Full,
+    /// Index only accounts, transfers, validators and delegators, skipping
+    /// every optional domain.
+    AccountsAndTransfers,
+    /// Index accounts, transfers, validators and delegators including the
+    /// payday pool data and the validator and reward metrics.
+    Validators,
+}
+
+impl IndexingProfile {
+    /// The domains indexed by the profile.
+    pub fn domains(self) -> IndexingDomains {
+        match self {
+            IndexingProfile::Full => IndexingDomains::all(),
+            IndexingProfile::AccountsAndTransfers => IndexingDomains::default(),
+            IndexingProfile::Validators => [IndexingDomain::Payday, IndexingDomain::Metrics]
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+/// Load the domains populated in the database.
+pub async fn populated_domains(
+    db_connection: &mut PgConnection,
+) -> anyhow::Result<IndexingDomains> {
+    let domains = sqlx::query_scalar!(
+        r#"SELECT domain AS "domain: IndexingDomain" FROM indexing_domains WHERE populated"#
+    )
+    .fetch_all(db_connection.as_mut())
+    .await?;
+    Ok(domains.into_iter().collect())
+}
+
+/// Record the domains skipped by the indexer from the provided block height.
+/// Fails when a domain which has previously been skipped is indexed again, as
+/// the domain is then missing the data of the blocks indexed while skipped.
+pub async fn record_indexed_domains(
+    db_connection: &mut PgConnection,
+    domains: IndexingDomains,
+    from_height: i64,
+) -> anyhow::Result<()> {
+    let rows = sqlx::query!(
+        r#"SELECT
+            domain AS "domain: IndexingDomain",
+            populated,
+            skipped_from_height
+        FROM indexing_domains"#
+    )
+    .fetch_all(db_connection.as_mut())
+    .await?;
+    for row in rows {
+        match (domains.contains(row.domain), row.populated) {
+            (true, false) => anyhow::bail!(
+                "The {} data is not populated in the database, as it was skipped from block \
+                 height {}. Index the database from genesis using a profile including it.",
+                row.domain,
+                row.skipped_from_height.unwrap_or_default()
+            ),
+            (false, true) => {
+                if from_height > 1 {
+                    warn!(
+                        "Skipping the {} data from block height {}, the data indexed so far is \
+                         no longer kept up to date",
+                        row.domain, from_height
+                    );
+                } else {
+                    info!("Skipping the {} data", row.domain);
+                }
+                sqlx::query!(
+                    "UPDATE indexing_domains
+                    SET populated = FALSE, skipped_from_height = $2
+                    WHERE domain = $1",
+                    row.domain as IndexingDomain,
+                    from_height
+                )
+                .execute(db_connection.as_mut())
+                .await?;
+            }
+            (true, true) | (false, false) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use clap::ValueEnum as _;
+
+    #[test]
+    fn test_profile_domains() {
+        let profile = |name: &str| IndexingProfile::from_str(name, false).unwrap();
+        assert_eq!(profile("full").domains(), IndexingDomains::all());
+        assert_eq!(
+            profile("accounts-and-transfers").domains(),
+            IndexingDomains::default()
+        );
+        let domains = profile("validators").domains();
+        assert!(domains.contains(IndexingDomain::Payday));
+        assert!(domains.contains(IndexingDomain::Metrics));
+        for domain in [
+            IndexingDomain::Contracts,
+            IndexingDomain::Cis2,
+            IndexingDomain::Plt,
+        ] {
+            assert!(!domains.contains(domain));
+            assert_eq!(
+                domains.ensure_indexed(domain),
+                Err(DomainNotIndexed(domain))
+            );
+        }
+        assert_eq!(domains.ensure_indexed(IndexingDomain::Payday), Ok(()));
+        assert!(IndexingProfile::from_str("minimal", false).is_err());
+    }
+
+    /// The skipped domains and the height they were skipped from.
+    async fn skipped_domains(
+        tx: &mut sqlx::PgTransaction<'_>,
+    ) -> Vec<(IndexingDomain, Option<i64>)> {
+        sqlx::query_as(
+            "SELECT domain, skipped_from_height
+            FROM indexing_domains
+            WHERE NOT populated
+            ORDER BY domain",
+        )
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_indexed_domains() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        sqlx::query("UPDATE indexing_domains SET populated = TRUE, skipped_from_height = NULL")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+
+        record_indexed_domains(tx.as_mut(), IndexingProfile::Full.domains(), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            populated_domains(tx.as_mut()).await.unwrap(),
+            IndexingDomains::all()
+        );
+
+        record_indexed_domains(tx.as_mut(), IndexingProfile::Validators.domains(), 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            populated_domains(tx.as_mut()).await.unwrap(),
+            IndexingProfile::Validators.domains()
+        );
+        let skipped = skipped_domains(&mut tx).await;
+        assert_eq!(
+            skipped,
+            vec![
+                (IndexingDomain::Contracts, Some(10)),
+                (IndexingDomain::Cis2, Some(10)),
+                (IndexingDomain::Plt, Some(10)),
+            ]
+        );
+
+        // Skipping more domains keeps the height the others were skipped from.
+        record_indexed_domains(
+            tx.as_mut(),
+            IndexingProfile::AccountsAndTransfers.domains(),
+            20,
+        )
+        .await
+        .unwrap();
+        let skipped = skipped_domains(&mut tx).await;
+        assert_eq!(
+            skipped,
+            vec![
+                (IndexingDomain::Contracts, Some(10)),
+                (IndexingDomain::Cis2, Some(10)),
+                (IndexingDomain::Plt, Some(10)),
+                (IndexingDomain::Payday, Some(20)),
+                (IndexingDomain::Metrics, Some(20)),
+            ]
+        );
+
+        // Skipped domains are missing the data of the blocks indexed meanwhile.
+        assert!(
+            record_indexed_domains(tx.as_mut(), IndexingProfile::Validators.domains(), 30)
+                .await
+                .is_err()
+        );
+    }
+}
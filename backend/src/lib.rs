@@ -2,14 +2,21 @@ pub mod graphql_api;
 pub mod indexer;
 pub mod migrations;
 pub mod monitoring;
+pub mod rate_limit;
 pub mod rest_api;
 pub mod router;
+pub mod webhooks;
 
 mod address;
 mod block_special_event;
 mod connection;
 mod decoded_text;
+mod indexing_profile;
+mod retry;
 mod scalar_types;
 mod transaction_event;
 mod transaction_reject;
 mod transaction_type;
+
+#[cfg(test)]
+mod test_database;
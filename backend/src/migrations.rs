@@ -18,6 +18,7 @@ mod m0025_fix_passive_delegator_stake;
 mod m0026_update_genesis_validator_info;
 mod m0027_reindex_credential_deployments;
 mod m0037_update_transaction_type_add_tokenupdate;
+mod m0049_cis2_accounts_statement;
 
 /// Ensure the current database schema version is compatible with the supported
 /// schema version.
@@ -296,14 +297,40 @@ pub enum SchemaVersion {
     PltAccountsStatements,
     #[display("0048: Add partial index for nonzero PLT holders by token")]
     IndexPltHolderNonZero,
+    #[display("0049: CIS-2 accounts statement table to track token balance changes")]
+    Cis2AccountsStatements,
+    #[display("0050: Track the pool originating the rewards of an account at payday")]
+    PaydayAccountRewards,
+    #[display("0051: Notify listeners of every contract event added")]
+    ContractEventAddedNotify,
+    #[display("0052: Notify listeners of every protocol-level token event added")]
+    PltEventAddedNotify,
+    #[display("0053: Notify listeners of changes in the lifecycle of validators")]
+    ValidatorEventNotify,
+    #[display("0054: Add tables for webhooks and their deliveries")]
+    Webhooks,
+    #[display("0055: Add table tracking transactions submitted through the API")]
+    TransactionSubmissions,
+    #[display("0056: Add table tracking the history of amounts locked in release schedules")]
+    ScheduledReleaseHistory,
+    #[display("0057: Add shadow tables for blocks on the live branch before finalization")]
+    TentativeHead,
+    #[display("0058: Add table for quarantined block items and special events")]
+    QuarantinedItems,
+    #[display("0059: Add tables for delivering blocks to event sinks")]
+    EventSinks,
+    #[display("0060: Add table recording the domains populated by the indexing profile")]
+    IndexingDomains,
+    #[display("0061: Add tables rolling up the metrics into hourly and daily buckets")]
+    MetricsRollups,
 }
 impl SchemaVersion {
     /// The minimum supported database schema version for the API.
     /// Fails at startup if any breaking (destructive) database schema versions
     /// have been introduced since this version.
-    pub const API_SUPPORTED_SCHEMA_VERSION: SchemaVersion = SchemaVersion::IndexPltHolderNonZero;
+    pub const API_SUPPORTED_SCHEMA_VERSION: SchemaVersion = SchemaVersion::MetricsRollups;
     /// The latest known version of the schema.
-    const LATEST: SchemaVersion = SchemaVersion::IndexPltHolderNonZero;
+    const LATEST: SchemaVersion = SchemaVersion::MetricsRollups;
 
     /// Parse version number into a database schema version.
     /// None if the version is unknown.
@@ -373,6 +400,19 @@ impl SchemaVersion {
             SchemaVersion::PltAccountsStatements => false,
             SchemaVersion::AlterTxnAddSponsoredTxn => false,
             SchemaVersion::IndexPltHolderNonZero => false,
+            SchemaVersion::Cis2AccountsStatements => false,
+            SchemaVersion::PaydayAccountRewards => false,
+            SchemaVersion::ContractEventAddedNotify => false,
+            SchemaVersion::PltEventAddedNotify => false,
+            SchemaVersion::ValidatorEventNotify => false,
+            SchemaVersion::Webhooks => false,
+            SchemaVersion::TransactionSubmissions => false,
+            SchemaVersion::ScheduledReleaseHistory => false,
+            SchemaVersion::TentativeHead => false,
+            SchemaVersion::QuarantinedItems => false,
+            SchemaVersion::EventSinks => false,
+            SchemaVersion::IndexingDomains => false,
+            SchemaVersion::MetricsRollups => false,
         }
     }
 
@@ -431,6 +471,19 @@ impl SchemaVersion {
             SchemaVersion::PltAccountsStatements => false,
             SchemaVersion::AlterTxnAddSponsoredTxn => false,
             SchemaVersion::IndexPltHolderNonZero => false,
+            SchemaVersion::Cis2AccountsStatements => false,
+            SchemaVersion::PaydayAccountRewards => false,
+            SchemaVersion::ContractEventAddedNotify => false,
+            SchemaVersion::PltEventAddedNotify => false,
+            SchemaVersion::ValidatorEventNotify => false,
+            SchemaVersion::Webhooks => false,
+            SchemaVersion::TransactionSubmissions => false,
+            SchemaVersion::ScheduledReleaseHistory => false,
+            SchemaVersion::TentativeHead => false,
+            SchemaVersion::QuarantinedItems => false,
+            SchemaVersion::EventSinks => false,
+            SchemaVersion::IndexingDomains => false,
+            SchemaVersion::MetricsRollups => false,
         }
     }
 
@@ -769,8 +822,106 @@ impl SchemaVersion {
                     .await?;
                 SchemaVersion::IndexPltHolderNonZero
             }
-
-            SchemaVersion::IndexPltHolderNonZero => unimplemented!(
+            SchemaVersion::IndexPltHolderNonZero => {
+                m0049_cis2_accounts_statement::run(&mut tx).await?
+            }
+            SchemaVersion::Cis2AccountsStatements => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0050-payday-account-rewards.sql"
+                    )))
+                    .await?;
+                SchemaVersion::PaydayAccountRewards
+            }
+            SchemaVersion::PaydayAccountRewards => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0051-contract-event-added-notify.sql"
+                    )))
+                    .await?;
+                SchemaVersion::ContractEventAddedNotify
+            }
+            SchemaVersion::ContractEventAddedNotify => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0052-plt-event-added-notify.sql"
+                    )))
+                    .await?;
+                SchemaVersion::PltEventAddedNotify
+            }
+            SchemaVersion::PltEventAddedNotify => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0053-validator-event-notify.sql"
+                    )))
+                    .await?;
+                SchemaVersion::ValidatorEventNotify
+            }
+            SchemaVersion::ValidatorEventNotify => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0054-webhooks.sql"
+                    )))
+                    .await?;
+                SchemaVersion::Webhooks
+            }
+            SchemaVersion::Webhooks => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0055-transaction-submissions.sql"
+                    )))
+                    .await?;
+                SchemaVersion::TransactionSubmissions
+            }
+            SchemaVersion::TransactionSubmissions => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0056-scheduled-release-history.sql"
+                    )))
+                    .await?;
+                SchemaVersion::ScheduledReleaseHistory
+            }
+            SchemaVersion::ScheduledReleaseHistory => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0057-tentative-head.sql"
+                    )))
+                    .await?;
+                SchemaVersion::TentativeHead
+            }
+            SchemaVersion::TentativeHead => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0058-quarantined-items.sql"
+                    )))
+                    .await?;
+                SchemaVersion::QuarantinedItems
+            }
+            SchemaVersion::QuarantinedItems => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0059-event-sinks.sql"
+                    )))
+                    .await?;
+                SchemaVersion::EventSinks
+            }
+            SchemaVersion::EventSinks => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0060-indexing-domains.sql"
+                    )))
+                    .await?;
+                SchemaVersion::IndexingDomains
+            }
+            SchemaVersion::IndexingDomains => {
+                tx.as_mut()
+                    .execute(sqlx::raw_sql(include_str!(
+                        "./migrations/m0061-metrics-rollups.sql"
+                    )))
+                    .await?;
+                SchemaVersion::MetricsRollups
+            }
+            SchemaVersion::MetricsRollups => unimplemented!(
                 "No migration implemented for database schema version {}",
                 self.as_i64()
             ),
@@ -0,0 +1,48 @@
+-- cis2_accounts_statement table for CIS-2 token balance history
+-- This table stores account-level statements for CIS-2 token operations
+
+-- enum for CIS-2 account statement entry types
+CREATE TYPE cis2_account_statement_entry_type AS ENUM (
+    'Mint',
+    'Burn',
+    'TransferIn',
+    'TransferOut'
+);
+
+-- cis2_accounts_statement table
+CREATE TABLE cis2_accounts_statement (
+    -- Composite primary key: account_index + cis2_token_event_index + entry_type for uniqueness.
+    -- The entry type is part of the key since a transfer to self produces both a `TransferOut` and
+    -- a `TransferIn` entry for the same event.
+    account_index BIGINT NOT NULL REFERENCES accounts(index),
+    cis2_token_event_index BIGINT NOT NULL REFERENCES cis2_token_events(index),
+
+    -- Token involved in this statement
+    token_index BIGINT NOT NULL REFERENCES tokens(index),
+
+    -- Type of CIS-2 operation
+    entry_type cis2_account_statement_entry_type NOT NULL,
+
+    -- Change of the token balance of the account caused by the operation.
+    -- Negative when tokens are removed from the account.
+    amount NUMERIC NOT NULL,
+
+    -- Token balance of the account after this operation.
+    account_balance NUMERIC NOT NULL,
+
+    -- Denormalized timestamp for efficient querying
+    slot_time TIMESTAMPTZ NOT NULL,
+
+    -- Block height for reference
+    block_height BIGINT NOT NULL REFERENCES blocks(height),
+
+    -- Transaction that caused this statement
+    transaction_index BIGINT NOT NULL REFERENCES transactions(index),
+
+    PRIMARY KEY (account_index, cis2_token_event_index, entry_type)
+);
+
+-- Covering index for export queries by account and time
+CREATE INDEX cis2_accounts_statement_export_idx
+ON cis2_accounts_statement(account_index, slot_time DESC)
+INCLUDE (token_index, entry_type, amount, account_balance);
@@ -0,0 +1,412 @@
+//! Migration adding the CIS-2 accounts statement table.
+//!
+//! The table tracks every change to the CIS-2 token balance of an account
+//! together with the resulting balance, similar to the account statements for
+//! CCD and PLTs.
+//!
+//! Since the CIS-2 token events only store the account address and not the
+//! account index, this migration iterates the existing events in the
+//! database and derives the canonical account address for every account
+//! involved to backfill the table with running balances.
+
+use super::SchemaVersion;
+use crate::transaction_event::{CisBurnEvent, CisEvent, CisMintEvent, CisTransferEvent};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::types::Address;
+use sqlx::Executor;
+use std::collections::HashMap;
+
+/// Resulting database schema version from running this migration.
+const NEXT_SCHEMA_VERSION: SchemaVersion = SchemaVersion::Cis2AccountsStatements;
+
+/// Number of CIS-2 token events to process in each batch.
+const BATCH_SIZE: i64 = 10_000;
+
+/// Run database migration and returns the new database schema version when
+/// successful.
+pub async fn run(tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<SchemaVersion> {
+    tx.as_mut()
+        .execute(sqlx::raw_sql(include_str!(
+            "m0049-cis2-accounts-statement.sql"
+        )))
+        .await?;
+    backfill(tx).await?;
+    Ok(NEXT_SCHEMA_VERSION)
+}
+
+/// Insert the statement entries of every CIS-2 token event in the database,
+/// computing the running balances.
+async fn backfill(tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
+    let mut backfill = Backfill::default();
+    let mut last_event_index = -1;
+    loop {
+        let rows = sqlx::query(
+            "SELECT
+                cis2_token_events.index,
+                cis2_token_events.token_index,
+                cis2_token_events.transaction_index,
+                cis2_token_events.cis2_token_event,
+                transactions.block_height,
+                blocks.slot_time
+            FROM cis2_token_events
+                JOIN transactions ON transactions.index = cis2_token_events.transaction_index
+                JOIN blocks ON blocks.height = transactions.block_height
+            WHERE cis2_token_events.index > $1
+            ORDER BY cis2_token_events.index ASC
+            LIMIT $2",
+        )
+        .bind(last_event_index)
+        .bind(BATCH_SIZE)
+        .fetch_all(tx.as_mut())
+        .await?;
+        let Some(last_row) = rows.last() else {
+            break;
+        };
+        last_event_index = sqlx::Row::try_get(last_row, "index")?;
+
+        let mut entries = StatementEntries::default();
+        for row in rows {
+            let event = CisTokenEventRow {
+                index: sqlx::Row::try_get(&row, "index")?,
+                token_index: sqlx::Row::try_get(&row, "token_index")?,
+                transaction_index: sqlx::Row::try_get(&row, "transaction_index")?,
+                block_height: sqlx::Row::try_get(&row, "block_height")?,
+                slot_time: sqlx::Row::try_get(&row, "slot_time")?,
+            };
+            let cis2_token_event: serde_json::Value = sqlx::Row::try_get(&row, "cis2_token_event")?;
+            match serde_json::from_value(cis2_token_event)? {
+                CisEvent::Mint(CisMintEvent { amount, owner, .. }) => {
+                    let amount = BigDecimal::from_biguint(amount.0, 0);
+                    backfill
+                        .add_entry(tx, &mut entries, &event, &owner, "Mint", amount)
+                        .await?;
+                }
+                CisEvent::Burn(CisBurnEvent { amount, owner, .. }) => {
+                    let amount = BigDecimal::from_biguint(amount.0, 0);
+                    backfill
+                        .add_entry(tx, &mut entries, &event, &owner, "Burn", -amount)
+                        .await?;
+                }
+                CisEvent::Transfer(CisTransferEvent {
+                    amount, from, to, ..
+                }) => {
+                    let amount = BigDecimal::from_biguint(amount.0, 0);
+                    backfill
+                        .add_entry(
+                            tx,
+                            &mut entries,
+                            &event,
+                            &from,
+                            "TransferOut",
+                            -amount.clone(),
+                        )
+                        .await?;
+                    backfill
+                        .add_entry(tx, &mut entries, &event, &to, "TransferIn", amount)
+                        .await?;
+                }
+                CisEvent::TokenMetadata(_) | CisEvent::Unknown(_) => {}
+            }
+        }
+        entries.insert(tx).await?;
+    }
+    Ok(())
+}
+
+/// The relevant information of a row in the `cis2_token_events` table.
+struct CisTokenEventRow {
+    index: i64,
+    token_index: i64,
+    transaction_index: i64,
+    block_height: i64,
+    slot_time: DateTime<Utc>,
+}
+
+/// State tracked while backfilling the statements.
+#[derive(Default)]
+struct Backfill {
+    /// Cache of account indices by the canonical account address.
+    account_indices: HashMap<Vec<u8>, Option<i64>>,
+    /// Running token balance for each pair of account index and token index.
+    balances: HashMap<(i64, i64), BigDecimal>,
+}
+
+impl Backfill {
+    /// Compute the statement entry for a change in the token balance of an
+    /// address. Only changes to balances of accounts are tracked.
+    async fn add_entry(
+        &mut self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        entries: &mut StatementEntries,
+        event: &CisTokenEventRow,
+        address: &Address,
+        entry_type: &str,
+        amount: BigDecimal,
+    ) -> anyhow::Result<()> {
+        let Address::Account(account_address) = address else {
+            return Ok(());
+        };
+        let canonical_address = account_address.get_canonical_address().0.to_vec();
+        let account_index =
+            if let Some(account_index) = self.account_indices.get(&canonical_address) {
+                *account_index
+            } else {
+                let account_index: Option<i64> =
+                    sqlx::query_scalar("SELECT index FROM accounts WHERE canonical_address = $1")
+                        .bind(&canonical_address)
+                        .fetch_optional(tx.as_mut())
+                        .await?;
+                self.account_indices
+                    .insert(canonical_address, account_index);
+                account_index
+            };
+        let Some(account_index) = account_index else {
+            return Ok(());
+        };
+        let balance = self
+            .balances
+            .entry((account_index, event.token_index))
+            .or_default();
+        *balance += &amount;
+
+        entries.account_index.push(account_index);
+        entries.cis2_token_event_index.push(event.index);
+        entries.token_index.push(event.token_index);
+        entries.entry_type.push(entry_type.to_string());
+        entries.amount.push(amount);
+        entries.account_balance.push(balance.clone());
+        entries.slot_time.push(event.slot_time);
+        entries.block_height.push(event.block_height);
+        entries.transaction_index.push(event.transaction_index);
+        Ok(())
+    }
+}
+
+/// Batch of rows to insert into the `cis2_accounts_statement` table.
+#[derive(Default)]
+struct StatementEntries {
+    account_index: Vec<i64>,
+    cis2_token_event_index: Vec<i64>,
+    token_index: Vec<i64>,
+    entry_type: Vec<String>,
+    amount: Vec<BigDecimal>,
+    account_balance: Vec<BigDecimal>,
+    slot_time: Vec<DateTime<Utc>>,
+    block_height: Vec<i64>,
+    transaction_index: Vec<i64>,
+}
+
+impl StatementEntries {
+    async fn insert(self, tx: &mut sqlx::PgTransaction<'_>) -> anyhow::Result<()> {
+        if self.account_index.is_empty() {
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT INTO cis2_accounts_statement (
+                account_index,
+                cis2_token_event_index,
+                token_index,
+                entry_type,
+                amount,
+                account_balance,
+                slot_time,
+                block_height,
+                transaction_index
+            )
+            SELECT
+                account_index,
+                cis2_token_event_index,
+                token_index,
+                entry_type::cis2_account_statement_entry_type,
+                amount,
+                account_balance,
+                slot_time,
+                block_height,
+                transaction_index
+            FROM UNNEST(
+                $1::BIGINT[],
+                $2::BIGINT[],
+                $3::BIGINT[],
+                $4::TEXT[],
+                $5::NUMERIC[],
+                $6::NUMERIC[],
+                $7::TIMESTAMPTZ[],
+                $8::BIGINT[],
+                $9::BIGINT[]
+            ) AS entries(
+                account_index,
+                cis2_token_event_index,
+                token_index,
+                entry_type,
+                amount,
+                account_balance,
+                slot_time,
+                block_height,
+                transaction_index
+            )",
+        )
+        .bind(self.account_index)
+        .bind(self.cis2_token_event_index)
+        .bind(self.token_index)
+        .bind(self.entry_type)
+        .bind(self.amount)
+        .bind(self.account_balance)
+        .bind(self.slot_time)
+        .bind(self.block_height)
+        .bind(self.transaction_index)
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use bigdecimal::num_bigint::BigUint;
+    use chrono::TimeZone;
+    use concordium_rust_sdk::{cis2, id::types::AccountAddress};
+
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+    const TOKEN: i64 = 1 << 40;
+    const EVENT: i64 = 1 << 40;
+
+    async fn insert_event(
+        tx: &mut sqlx::PgTransaction<'_>,
+        offset: i64,
+        event: CisEvent,
+    ) -> sqlx::Result<()> {
+        sqlx::query(
+            "INSERT INTO cis2_token_events (
+                index,
+                index_per_token,
+                transaction_index,
+                token_index,
+                cis2_token_event
+            )
+            OVERRIDING SYSTEM VALUE
+            VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(EVENT + offset)
+        .bind(offset)
+        .bind(TRANSACTION + offset)
+        .bind(TOKEN)
+        .bind(serde_json::to_value(event).unwrap())
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_backfill_computes_running_balances() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let alice = AccountAddress([0xa1; 32]);
+        let bob = AccountAddress([0xb2; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &alice, 0)
+            .await
+            .unwrap();
+        test_database::insert_account(tx.as_mut(), ACCOUNT + 1, &bob, 0)
+            .await
+            .unwrap();
+        let slot_time = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        test_database::insert_block(tx.as_mut(), HEIGHT, slot_time)
+            .await
+            .unwrap();
+        for offset in 0..4 {
+            test_database::insert_transaction(tx.as_mut(), TRANSACTION + offset, HEIGHT)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO tokens (
+                index,
+                token_index_per_contract,
+                contract_index,
+                contract_sub_index,
+                token_address,
+                token_id,
+                init_transaction_index
+            ) VALUES ($1, 0, $1, 0, 'test-token', '', $2)",
+        )
+        .bind(TOKEN)
+        .bind(TRANSACTION)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        let token_id = || cis2::TokenId::new_unchecked(Vec::new());
+        let amount = |amount: u32| cis2::TokenAmount(BigUint::from(amount));
+        let events = [
+            CisEvent::Mint(CisMintEvent {
+                raw_token_id: token_id(),
+                amount: amount(100),
+                owner: Address::Account(alice),
+            }),
+            CisEvent::Transfer(CisTransferEvent {
+                raw_token_id: token_id(),
+                amount: amount(30),
+                from: Address::Account(alice),
+                to: Address::Account(bob),
+            }),
+            // A transfer to self leaves the balance unchanged.
+            CisEvent::Transfer(CisTransferEvent {
+                raw_token_id: token_id(),
+                amount: amount(5),
+                from: Address::Account(bob),
+                to: Address::Account(bob),
+            }),
+            CisEvent::Burn(CisBurnEvent {
+                raw_token_id: token_id(),
+                amount: amount(20),
+                owner: Address::Account(alice),
+            }),
+        ];
+        for (offset, event) in events.into_iter().enumerate() {
+            insert_event(&mut tx, offset as i64, event).await.unwrap();
+        }
+
+        // The backfill rebuilds the statements of every event in the database.
+        sqlx::query("DELETE FROM cis2_accounts_statement")
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        backfill(&mut tx).await.unwrap();
+
+        let statements: Vec<(i64, i64, String, BigDecimal, BigDecimal)> = sqlx::query_as(
+            "SELECT account_index, cis2_token_event_index, entry_type::TEXT, amount, \
+             account_balance
+            FROM cis2_accounts_statement
+            WHERE token_index = $1
+            ORDER BY cis2_token_event_index, account_index, entry_type",
+        )
+        .bind(TOKEN)
+        .fetch_all(tx.as_mut())
+        .await
+        .unwrap();
+        let expected = [
+            (ACCOUNT, EVENT, "Mint", 100, 100),
+            (ACCOUNT, EVENT + 1, "TransferOut", -30, 70),
+            (ACCOUNT + 1, EVENT + 1, "TransferIn", 30, 30),
+            (ACCOUNT + 1, EVENT + 2, "TransferIn", 5, 30),
+            (ACCOUNT + 1, EVENT + 2, "TransferOut", -5, 25),
+            (ACCOUNT, EVENT + 3, "Burn", -20, 50),
+        ]
+        .map(|(account, event, entry_type, amount, balance)| {
+            (
+                account,
+                event,
+                entry_type.to_string(),
+                BigDecimal::from(amount),
+                BigDecimal::from(balance),
+            )
+        });
+        assert_eq!(statements, expected);
+    }
+}
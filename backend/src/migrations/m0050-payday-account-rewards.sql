@@ -0,0 +1,60 @@
+-- Table tracking the pool from which an account received its rewards at a payday.
+-- Rewards at paydays are reported by a `PaydayPoolReward` event for each pool followed by a
+-- `PaydayAccountReward` event for the pool owner and each delegator of the pool.
+CREATE TABLE payday_account_rewards (
+    -- Height of the payday block.
+    payday_block_height
+        BIGINT
+        NOT NULL
+        REFERENCES blocks(height),
+    -- Index of the account receiving the rewards.
+    account_index
+        BIGINT
+        NOT NULL
+        REFERENCES accounts(index),
+    -- Baker/validator ID of the pool the rewards originate from.
+    -- The pool owner is `NULL` if the rewards are from passive delegation.
+    pool_owner
+        BIGINT,
+    -- An account receives at most one `PaydayAccountReward` per payday.
+    PRIMARY KEY (account_index, payday_block_height)
+);
+
+-- Backfill the table from the special transaction outcomes already indexed, by associating every
+-- `PaydayAccountReward` with the `PaydayPoolReward` preceding it in the payday block.
+INSERT INTO payday_account_rewards (payday_block_height, account_index, pool_owner)
+WITH payday_outcomes AS (
+    SELECT
+        block_height,
+        block_outcome_index,
+        outcome_type,
+        outcome,
+        -- Number of pool rewards up until and including this outcome in the block, grouping each
+        -- pool reward together with the account rewards following it.
+        COUNT(*) FILTER (WHERE outcome_type = 'PaydayPoolReward') OVER (
+            PARTITION BY block_height
+            ORDER BY block_outcome_index
+        ) AS pool_group
+    FROM block_special_transaction_outcomes
+    WHERE outcome_type IN ('PaydayPoolReward', 'PaydayAccountReward')
+), grouped_outcomes AS (
+    SELECT
+        block_height,
+        outcome_type,
+        outcome,
+        pool_group,
+        FIRST_VALUE(outcome) OVER (
+            PARTITION BY block_height, pool_group
+            ORDER BY block_outcome_index
+        ) AS pool_outcome
+    FROM payday_outcomes
+)
+SELECT
+    grouped_outcomes.block_height,
+    accounts.index,
+    (pool_outcome->'PaydayPoolRewardSpecialEvent'->'pool'->'BakerPoolRewardTarget'->>'baker_id')::BIGINT
+FROM grouped_outcomes
+    JOIN accounts
+        ON accounts.address = outcome->'PaydayAccountRewardSpecialEvent'->'account'->>'as_string'
+WHERE outcome_type = 'PaydayAccountReward'
+    AND pool_group > 0;
@@ -0,0 +1,22 @@
+-- Notify listeners on the `contract_event_added` channel with the index of every contract event inserted,
+-- allowing the API to stream new events of smart contract instances to subscribers.
+-- The notifications are suppressed by setting `ccdscan.suppress_notify` to `on` for the transaction,
+-- as done by the indexer when re-indexing a range of blocks.
+CREATE OR REPLACE FUNCTION contract_event_added_notify_trigger_function() RETURNS trigger AS $trigger$
+BEGIN
+  -- Suppressed while re-indexing, as the rows rebuilt for past blocks are not new.
+  IF current_setting('ccdscan.suppress_notify', true) = 'on' THEN
+    RETURN NEW;
+  END IF;
+  CASE TG_OP
+       WHEN 'INSERT' THEN
+            PERFORM pg_notify('contract_event_added', NEW.index::TEXT);
+       ELSE NULL;
+  END CASE;
+  RETURN NEW;
+END;
+$trigger$ LANGUAGE plpgsql;
+
+CREATE TRIGGER contract_event_added_notify_trigger AFTER INSERT
+ON contract_events
+FOR EACH ROW EXECUTE PROCEDURE contract_event_added_notify_trigger_function();
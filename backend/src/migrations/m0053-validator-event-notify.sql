@@ -0,0 +1,64 @@
+-- Notify listeners on the `validator_event` channel about changes in the lifecycle of validators/bakers,
+-- allowing the API to stream these to subscribers.
+-- The payload is a JSON object with the `baker_id` and the `event_type`, where a single change of a
+-- row can result in several notifications.
+-- The notifications are suppressed by setting `ccdscan.suppress_notify` to `on` for the transaction,
+-- as done by the indexer when re-indexing a range of blocks.
+CREATE OR REPLACE FUNCTION validator_event_notify_trigger_function() RETURNS trigger AS $trigger$
+BEGIN
+  -- Suppressed while re-indexing, as the rows rebuilt for past blocks are not new.
+  IF current_setting('ccdscan.suppress_notify', true) = 'on' THEN
+    RETURN NULL;
+  END IF;
+  CASE TG_OP
+       WHEN 'UPDATE' THEN
+            IF OLD.primed_for_suspension IS NULL AND NEW.primed_for_suspension IS NOT NULL THEN
+                PERFORM pg_notify(
+                    'validator_event',
+                    json_build_object('baker_id', NEW.id, 'event_type', 'PrimedForSuspension')::TEXT
+                );
+            END IF;
+            IF (OLD.self_suspended IS NULL AND NEW.self_suspended IS NOT NULL)
+               OR (OLD.inactive_suspended IS NULL AND NEW.inactive_suspended IS NOT NULL) THEN
+                PERFORM pg_notify(
+                    'validator_event',
+                    json_build_object('baker_id', NEW.id, 'event_type', 'Suspended')::TEXT
+                );
+            END IF;
+            IF (OLD.self_suspended IS NOT NULL OR OLD.inactive_suspended IS NOT NULL)
+               AND NEW.self_suspended IS NULL
+               AND NEW.inactive_suspended IS NULL
+               AND NEW.primed_for_suspension IS NULL THEN
+                PERFORM pg_notify(
+                    'validator_event',
+                    json_build_object('baker_id', NEW.id, 'event_type', 'Resumed')::TEXT
+                );
+            END IF;
+            IF OLD.transaction_commission IS DISTINCT FROM NEW.transaction_commission
+               OR OLD.baking_commission IS DISTINCT FROM NEW.baking_commission
+               OR OLD.finalization_commission IS DISTINCT FROM NEW.finalization_commission THEN
+                PERFORM pg_notify(
+                    'validator_event',
+                    json_build_object('baker_id', NEW.id, 'event_type', 'CommissionChanged')::TEXT
+                );
+            END IF;
+            IF OLD.open_status IS DISTINCT FROM NEW.open_status THEN
+                PERFORM pg_notify(
+                    'validator_event',
+                    json_build_object('baker_id', NEW.id, 'event_type', 'OpenStatusChanged')::TEXT
+                );
+            END IF;
+       WHEN 'DELETE' THEN
+            PERFORM pg_notify(
+                'validator_event',
+                json_build_object('baker_id', OLD.id, 'event_type', 'Removed')::TEXT
+            );
+       ELSE NULL;
+  END CASE;
+  RETURN NULL;
+END;
+$trigger$ LANGUAGE plpgsql;
+
+CREATE TRIGGER validator_event_notify_trigger AFTER UPDATE OR DELETE
+ON bakers
+FOR EACH ROW EXECUTE PROCEDURE validator_event_notify_trigger_function();
@@ -0,0 +1,139 @@
+-- Tables for webhooks, notifying HTTP endpoints about transactions affecting an account or events
+-- emitted by a smart contract instance. Deliveries are written by the API service.
+
+CREATE TYPE webhook_delivery_status AS ENUM (
+    'Pending',
+    'Delivered',
+    'Failed'
+);
+
+-- Registered webhooks, each targeting either an account or a smart contract instance.
+CREATE TABLE webhooks (
+    id
+        BIGINT GENERATED ALWAYS AS IDENTITY
+        PRIMARY KEY,
+    -- URL receiving the POST requests.
+    url
+        TEXT
+        NOT NULL,
+    -- Secret used for signing the body of the requests using HMAC-SHA256.
+    secret
+        TEXT
+        NOT NULL,
+    -- Address of the account as provided when registered.
+    account_address
+        TEXT,
+    -- Canonical address of the account, such that transactions affecting aliases of the account
+    -- are delivered as well.
+    account_canonical_address
+        BYTEA,
+    contract_index
+        BIGINT,
+    contract_sub_index
+        BIGINT,
+    created_at
+        TIMESTAMPTZ
+        NOT NULL
+        DEFAULT NOW(),
+    CONSTRAINT webhooks_single_target CHECK (
+        (account_canonical_address IS NOT NULL AND account_address IS NOT NULL
+            AND contract_index IS NULL AND contract_sub_index IS NULL)
+        OR (account_canonical_address IS NULL AND account_address IS NULL
+            AND contract_index IS NOT NULL AND contract_sub_index IS NOT NULL)
+    )
+);
+
+CREATE INDEX webhooks_account_canonical_address_idx
+    ON webhooks (account_canonical_address)
+    WHERE account_canonical_address IS NOT NULL;
+CREATE INDEX webhooks_contract_idx
+    ON webhooks (contract_index, contract_sub_index)
+    WHERE contract_index IS NOT NULL;
+
+-- Events to deliver to a webhook.
+CREATE TABLE webhook_deliveries (
+    id
+        BIGINT GENERATED ALWAYS AS IDENTITY
+        PRIMARY KEY,
+    webhook_id
+        BIGINT
+        NOT NULL
+        REFERENCES webhooks(id) ON DELETE CASCADE,
+    -- Key identifying the delivered event, ensuring an event is only delivered once to a webhook
+    -- when several instances of the API service are running.
+    event_key
+        TEXT
+        NOT NULL,
+    -- Body of the requests.
+    payload
+        JSONB
+        NOT NULL,
+    status
+        webhook_delivery_status
+        NOT NULL
+        DEFAULT 'Pending',
+    -- Number of attempts since the delivery was added or last replayed.
+    attempts
+        INT
+        NOT NULL
+        DEFAULT 0,
+    -- Time of the next attempt, for pending deliveries.
+    next_attempt_at
+        TIMESTAMPTZ
+        NOT NULL
+        DEFAULT NOW(),
+    created_at
+        TIMESTAMPTZ
+        NOT NULL
+        DEFAULT NOW(),
+    UNIQUE (webhook_id, event_key)
+);
+
+CREATE INDEX webhook_deliveries_pending_idx
+    ON webhook_deliveries (next_attempt_at)
+    WHERE status = 'Pending';
+CREATE INDEX webhook_deliveries_failed_idx
+    ON webhook_deliveries (webhook_id, id)
+    WHERE status = 'Failed';
+
+-- Every attempt of delivering an event to a webhook.
+CREATE TABLE webhook_delivery_attempts (
+    id
+        BIGINT GENERATED ALWAYS AS IDENTITY
+        PRIMARY KEY,
+    delivery_id
+        BIGINT
+        NOT NULL
+        REFERENCES webhook_deliveries(id) ON DELETE CASCADE,
+    attempted_at
+        TIMESTAMPTZ
+        NOT NULL,
+    -- HTTP status code of the response, NULL when no response was received.
+    status_code
+        INT,
+    -- Reason for the attempt failing, NULL when delivered.
+    error
+        TEXT
+);
+
+CREATE INDEX webhook_delivery_attempts_delivery_id_idx
+    ON webhook_delivery_attempts (delivery_id);
+
+-- Cursor over the blocks for which webhook deliveries have been added, such that the deliveries of
+-- every block are added exactly once, also when the API service is restarted or was not running.
+CREATE TABLE webhook_cursor (
+    -- Ensures the table contains a single row.
+    id
+        BOOLEAN
+        PRIMARY KEY
+        DEFAULT true
+        CHECK (id),
+    -- Height of the last block for which deliveries have been added, -1 before the first block.
+    last_block_height
+        BIGINT
+        NOT NULL
+);
+
+-- Only blocks added after the migration are delivered.
+INSERT INTO webhook_cursor (last_block_height)
+SELECT COALESCE(MAX(height), -1) FROM blocks;
@@ -0,0 +1,13 @@
+-- Transactions submitted to the node through the API, used for tracking the status of a submission
+-- until the transaction is indexed.
+CREATE TABLE transaction_submissions (
+    -- Hash of the transaction.
+    hash
+        TEXT
+        PRIMARY KEY,
+    -- Time of the first submission through the API.
+    submitted_at
+        TIMESTAMPTZ
+        NOT NULL
+        DEFAULT NOW()
+);
@@ -0,0 +1,49 @@
+-- Every amount locked in a release schedule, including the amounts already released.
+-- Unlike `scheduled_releases`, entries are never removed when released, allowing for computing the
+-- total amount locked in release schedules at any point in time.
+CREATE TABLE scheduled_release_history (
+    -- The index of the transaction creating the scheduled transfer.
+    transaction_index
+        BIGINT
+        NOT NULL
+        REFERENCES transactions,
+    -- The slot time of the block containing the transaction, from when the amount is locked.
+    locked_at
+        TIMESTAMPTZ
+        NOT NULL,
+    -- The scheduled release time, from when the amount is no longer locked.
+    release_time
+        TIMESTAMPTZ
+        NOT NULL,
+    -- The amount locked in the scheduled release.
+    amount
+        BIGINT
+        NOT NULL
+);
+
+-- The amount locked at a point in time is found using the entries locked before and released after
+-- that point in time.
+CREATE INDEX scheduled_release_history_locked_at_idx
+    ON scheduled_release_history (locked_at, release_time);
+
+-- Clearing the history of a range of blocks being re-indexed finds the entries by the transaction.
+CREATE INDEX scheduled_release_history_transaction_index_idx
+    ON scheduled_release_history (transaction_index);
+
+-- Backfill the history from the `TransferredWithSchedule` events already indexed, as the released
+-- amounts have been removed from `scheduled_releases`.
+INSERT INTO scheduled_release_history (transaction_index, locked_at, release_time, amount)
+SELECT
+    transactions.index,
+    blocks.slot_time,
+    (release->>'timestamp')::TIMESTAMPTZ,
+    (release->>'amount')::BIGINT
+FROM transactions
+    JOIN blocks ON blocks.height = transactions.block_height
+    CROSS JOIN LATERAL jsonb_array_elements(transactions.events) AS event
+    CROSS JOIN LATERAL jsonb_array_elements(
+        event->'TransferredWithSchedule'->'amounts_schedule'
+    ) AS release
+WHERE transactions.type_account IN ('TransferWithSchedule', 'TransferWithScheduleWithMemo')
+    AND transactions.success
+    AND event ? 'TransferredWithSchedule';
@@ -0,0 +1,66 @@
+-- Shadow table of the blocks on the live branch of the chain which are not yet finalized.
+-- Maintained by the indexer when indexing of the tentative head is enabled. The blocks are rolled
+-- back when the live branch changes and removed once processed as finalized blocks.
+CREATE TABLE tentative_blocks (
+    -- The block hash encoded using HEX.
+    hash
+        CHAR(64)
+        PRIMARY KEY,
+    -- The absolute height of the block.
+    height
+        BIGINT
+        NOT NULL,
+    -- The hash of the parent block encoded using HEX.
+    parent_hash
+        CHAR(64)
+        NOT NULL,
+    -- Timestamp for when the block was baked.
+    slot_time
+        TIMESTAMPTZ
+        NOT NULL,
+    -- Milliseconds between the slot_time of this block and its parent.
+    block_time
+        INTEGER
+        NOT NULL,
+    -- Index of the account which baked the block.
+    -- NULL for blocks without a baker.
+    baker_id
+        BIGINT,
+    -- The total amount of CCD in existence at the time of this block.
+    total_amount
+        BIGINT
+        NOT NULL
+);
+
+-- The tentative blocks form a single branch, so there is at most one block per height.
+CREATE UNIQUE INDEX tentative_blocks_height_idx ON tentative_blocks (height);
+
+-- Shadow table of the transactions in the tentative blocks.
+CREATE TABLE tentative_transactions (
+    -- The hash of the tentative block containing the transaction.
+    block_hash
+        CHAR(64)
+        NOT NULL
+        REFERENCES tentative_blocks(hash)
+        ON DELETE CASCADE,
+    -- Transaction hash encoded using HEX.
+    hash
+        CHAR(64)
+        PRIMARY KEY,
+    -- The address of the account sending the transaction.
+    -- NULL for chain update and account creation transactions.
+    sender
+        TEXT,
+    -- Whether the transaction was accepted or rejected.
+    success
+        BOOLEAN
+        NOT NULL,
+    -- The position of the transaction within the tentative block, starting from 0.
+    index_in_block
+        BIGINT
+        NOT NULL
+);
+
+-- Listing the transactions of a tentative block in order.
+CREATE INDEX tentative_transactions_block_position_idx
+    ON tentative_transactions (block_hash, index_in_block);
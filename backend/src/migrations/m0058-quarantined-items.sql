@@ -0,0 +1,43 @@
+-- The kinds of items in a block which can be quarantined.
+CREATE TYPE quarantined_item_kind AS ENUM (
+    'BlockItem',
+    'SpecialEvent'
+);
+
+-- Block items and special transaction outcomes reported by the node which the indexer could not
+-- parse, typically new kinds introduced by a protocol update. They are recorded here instead of
+-- being dropped, such that they can be re-processed once the indexer is upgraded.
+-- The raw bytes of the items are not available, as the SDK does not expose the serialization of
+-- the items it cannot parse, so the blocks are fetched again from the node when re-processing.
+CREATE TABLE quarantined_items (
+    -- The absolute height of the block containing the item.
+    block_height
+        BIGINT
+        NOT NULL
+        REFERENCES blocks(height),
+    -- The kind of the item.
+    kind
+        quarantined_item_kind
+        NOT NULL,
+    -- The index of the item among the block items or the special transaction outcomes of the block.
+    item_index
+        BIGINT
+        NOT NULL,
+    -- The hash of the block item encoded using HEX.
+    -- NULL for special transaction outcomes.
+    block_item_hash
+        CHAR(64),
+    -- The summary of the outcome of the block item reported by the node.
+    -- NULL for special transaction outcomes.
+    summary
+        JSONB,
+    -- Timestamp of when the item was re-processed by an upgraded indexer.
+    -- NULL while the item is still quarantined.
+    reprocessed_at
+        TIMESTAMPTZ,
+    PRIMARY KEY (block_height, kind, item_index)
+);
+
+-- Index for efficiently finding the items still quarantined.
+CREATE INDEX quarantined_items_pending_idx ON quarantined_items (block_height)
+    WHERE reprocessed_at IS NULL;
@@ -0,0 +1,41 @@
+-- Content of the indexed blocks which is yet to be delivered to every configured event sink.
+-- Written by the indexer in the same database transaction as the block, only while event sinks
+-- are configured, and removed once delivered to every configured event sink.
+CREATE TABLE event_sink_outbox (
+    -- Position of the entry in the sequence of emitted blocks.
+    id
+        BIGINT
+        GENERATED ALWAYS AS IDENTITY
+        PRIMARY KEY,
+    -- The absolute height of the block, emitted more than once when the block is rebuilt by
+    -- re-indexing or re-processing quarantined items.
+    block_height
+        BIGINT
+        NOT NULL
+        REFERENCES blocks(height),
+    -- The content of the block as delivered to the event sinks.
+    payload
+        JSONB
+        NOT NULL
+);
+
+-- Position of each event sink in the sequence of blocks.
+CREATE TABLE event_sink_cursors (
+    -- The name of the event sink from its configuration.
+    name
+        TEXT
+        PRIMARY KEY,
+    -- The height of the last block delivered to the event sink.
+    last_delivered_height
+        BIGINT
+        NOT NULL,
+    -- Position of the last entry of the outbox delivered to the event sink.
+    last_delivered_id
+        BIGINT
+        NOT NULL,
+    -- Timestamp of when the cursor was last moved.
+    updated_at
+        TIMESTAMPTZ
+        NOT NULL
+        DEFAULT NOW()
+);
@@ -0,0 +1,34 @@
+-- Domains of derived data which can be skipped by the indexing profile of the indexer.
+CREATE TYPE indexing_domain AS ENUM (
+    'Contracts',
+    'Cis2',
+    'Plt',
+    'Payday',
+    'Metrics'
+);
+
+-- Whether each domain of derived data is populated in the database, allowing the API to report
+-- queries on domains skipped by the indexer as not indexed.
+CREATE TABLE indexing_domains (
+    -- The domain of derived data.
+    domain
+        indexing_domain
+        PRIMARY KEY,
+    -- Whether the domain is indexed for every block. Once skipped, the domain is only populated
+    -- again by indexing the database from genesis.
+    populated
+        BOOLEAN
+        NOT NULL,
+    -- Height of the first block indexed while skipping the domain, NULL while populated.
+    skipped_from_height
+        BIGINT
+);
+
+-- Every domain was indexed before the introduction of indexing profiles.
+INSERT INTO indexing_domains (domain, populated)
+VALUES
+    ('Contracts', TRUE),
+    ('Cis2', TRUE),
+    ('Plt', TRUE),
+    ('Payday', TRUE),
+    ('Metrics', TRUE);
@@ -0,0 +1,102 @@
+-- Progress of rolling up the per-block rows of the metrics tables into aggregated buckets of each
+-- width. The aggregated buckets are aligned with the buckets of `date_bin_series`.
+CREATE TABLE metrics_rollup_progress (
+    -- The width of the aggregated buckets.
+    bucket_width
+        INTERVAL
+        PRIMARY KEY,
+    -- Every per-block row before this time is rolled up into the aggregated buckets, while the
+    -- per-block rows from this time are kept. NULL until the first roll up.
+    rolled_up_until
+        TIMESTAMPTZ
+);
+
+INSERT INTO metrics_rollup_progress (bucket_width)
+VALUES
+    ('1 hour'),
+    ('1 day');
+
+-- Cumulative number of validators added and removed at the end of each aggregated bucket of
+-- `metrics_bakers`, only for the buckets where the numbers changed.
+CREATE TABLE metrics_bakers_rollups (
+    bucket_width
+        INTERVAL
+        NOT NULL,
+    bucket_start
+        TIMESTAMPTZ
+        NOT NULL,
+    total_bakers_added
+        BIGINT
+        NOT NULL,
+    total_bakers_removed
+        BIGINT
+        NOT NULL,
+    PRIMARY KEY (bucket_width, bucket_start)
+);
+
+-- Rewards paid out to each account during each aggregated bucket of `metrics_rewards`.
+CREATE TABLE metrics_rewards_rollups (
+    bucket_width
+        INTERVAL
+        NOT NULL,
+    bucket_start
+        TIMESTAMPTZ
+        NOT NULL,
+    account_index
+        BIGINT
+        NOT NULL
+        REFERENCES accounts,
+    amount
+        BIGINT
+        NOT NULL,
+    PRIMARY KEY (bucket_width, bucket_start, account_index)
+);
+
+-- Index for the reward metrics of a single account.
+CREATE INDEX metrics_rewards_rollups_account_idx
+    ON metrics_rewards_rollups (account_index, bucket_width, bucket_start);
+
+-- Cumulative transfer count and amount of each protocol-level token at the end of each aggregated
+-- bucket of `metrics_plt_transfer`, only for the buckets where the token was transferred.
+CREATE TABLE metrics_plt_transfer_rollups (
+    bucket_width
+        INTERVAL
+        NOT NULL,
+    bucket_start
+        TIMESTAMPTZ
+        NOT NULL,
+    token_index
+        BIGINT
+        NOT NULL,
+    cumulative_transfer_count
+        BIGINT
+        NOT NULL,
+    cumulative_transfer_amount
+        NUMERIC
+        NOT NULL,
+    PRIMARY KEY (bucket_width, token_index, bucket_start)
+);
+
+-- Cumulative event count, transfer amount and unique account count across every protocol-level
+-- token at the end of each aggregated bucket of `metrics_plt`, only for the buckets with events.
+CREATE TABLE metrics_plt_rollups (
+    bucket_width
+        INTERVAL
+        NOT NULL,
+    bucket_start
+        TIMESTAMPTZ
+        NOT NULL,
+    cumulative_event_count
+        BIGINT
+        NOT NULL,
+    cumulative_transfer_amount
+        NUMERIC
+        NOT NULL,
+    unique_account_count
+        BIGINT
+        NOT NULL,
+    PRIMARY KEY (bucket_width, bucket_start)
+);
+
+-- Index for rolling up and removing the per-block rows of `metrics_rewards` by time.
+CREATE INDEX metrics_rewards_block_slot_time_idx ON metrics_rewards (block_slot_time);
@@ -0,0 +1,419 @@
+//! Module containing a tower layer limiting the rate of requests per client,
+//! using a token bucket for each client.
+//!
+//! Clients are identified by their API key when provided using the
+//! `x-api-key` header and the key is one of the configured API keys, otherwise
+//! by their IP address.
+//!
+//! A single layer is shared between the GraphQL and REST API, such that the
+//! configured rate applies to the requests of a client across both.
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath},
+    http::{header, HeaderName, HeaderValue, Request, Response, StatusCode},
+};
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family},
+    registry::Registry,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+    net::SocketAddr,
+    num::{NonZeroU32, NonZeroUsize},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task,
+};
+use tokio::time::{Duration, Instant};
+
+/// Header used by clients to provide an API key.
+const API_KEY_HEADER: HeaderName = HeaderName::from_static("x-api-key");
+
+/// Minimum number of tracked clients, above which clients with a full token
+/// bucket are evicted, since these are equivalent to untracked clients.
+const EVICTION_THRESHOLD: usize = 10_000;
+
+/// Configuration of the rate limiting of requests per client.
+#[derive(Debug, clap::Args)]
+pub struct RateLimitConfig {
+    /// The number of requests per second allowed for each client on average.
+    /// Rate limiting is disabled when not provided.
+    #[arg(long, env = "CCDSCAN_API_RATE_LIMIT_REQUESTS_PER_SECOND")]
+    rate_limit_requests_per_second: Option<NonZeroU32>,
+    /// The number of requests a client can make in a burst, before being
+    /// limited to the average rate.
+    #[arg(long, env = "CCDSCAN_API_RATE_LIMIT_BURST", default_value = "50")]
+    rate_limit_burst: NonZeroU32,
+    /// Header containing the IP address of the client, such as
+    /// `x-forwarded-for`, appended by the trusted proxies in front of the
+    /// service. When not provided, or when a request is missing the header,
+    /// the address of the peer connection is used.
+    #[arg(long, env = "CCDSCAN_API_RATE_LIMIT_CLIENT_IP_HEADER")]
+    rate_limit_client_ip_header: Option<HeaderName>,
+    /// Number of trusted proxies in front of the service appending to the
+    /// client IP header. The address appended by the outermost trusted proxy
+    /// is used, being this number of entries from the right, as entries
+    /// further left are provided by the client.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_RATE_LIMIT_TRUSTED_PROXIES",
+        default_value = "1"
+    )]
+    rate_limit_trusted_proxies: NonZeroUsize,
+    /// API keys which are rate limited separately from the IP address of the
+    /// client.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_RATE_LIMIT_API_KEYS",
+        value_delimiter = ',',
+        hide_env_values = true
+    )]
+    rate_limit_api_keys: Vec<String>,
+}
+
+/// tower layer limiting the rate of requests per client. Clones share the
+/// state of the rate limiter.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer {
+    /// The state shared between every instance of the service, `None` when rate
+    /// limiting is disabled.
+    limiter: Option<Arc<RateLimiter>>,
+    /// Metric counting requests rejected by the rate limiter.
+    rejected_requests: Family<RejectedLabels, Counter>,
+}
+
+impl RateLimitLayer {
+    pub fn new(config: &RateLimitConfig, registry: &mut Registry) -> Self {
+        let rejected_requests: Family<RejectedLabels, Counter> = Default::default();
+        registry.register(
+            "rate_limited_requests",
+            "Total number of requests rejected by the rate limiter",
+            rejected_requests.clone(),
+        );
+        let limiter = config.rate_limit_requests_per_second.map(|rate| {
+            Arc::new(RateLimiter {
+                rate: f64::from(rate.get()),
+                burst: f64::from(config.rate_limit_burst.get()),
+                client_ip_header: config.rate_limit_client_ip_header.clone(),
+                trusted_proxies: config.rate_limit_trusted_proxies.get(),
+                api_keys: config.rate_limit_api_keys.iter().cloned().collect(),
+                buckets: Mutex::new(Buckets {
+                    buckets: HashMap::new(),
+                    eviction_threshold: EVICTION_THRESHOLD,
+                }),
+            })
+        });
+        Self {
+            limiter,
+            rejected_requests,
+        }
+    }
+}
+
+impl<S> tower::Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Type representing the Prometheus labels used for counting requests rejected
+/// by the rate limiter.
+#[derive(Debug, Clone, EncodeLabelSet, PartialEq, Eq, Hash)]
+struct RejectedLabels {
+    /// The route matched by the request, such as `/rest/export/rewards`,
+    /// rather than the path of the request, to bound the number of labels.
+    route: String,
+    /// Whether the client was identified by an API key.
+    api_key: bool,
+}
+
+/// Identifier of a client for rate limiting.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ClientKey {
+    /// Client identified by one of the configured API keys.
+    ApiKey(String),
+    /// Client identified by its IP address.
+    Ip(String),
+}
+
+/// State of the rate limiter shared between requests.
+#[derive(Debug)]
+struct RateLimiter {
+    /// Number of tokens added to a bucket per second.
+    rate: f64,
+    /// Maximum number of tokens in a bucket.
+    burst: f64,
+    /// Header containing the IP address of the client.
+    client_ip_header: Option<HeaderName>,
+    /// Number of trusted proxies appending to the client IP header.
+    trusted_proxies: usize,
+    /// API keys identifying clients.
+    api_keys: HashSet<String>,
+    /// Token buckets of the clients.
+    buckets: Mutex<Buckets>,
+}
+
+/// Token buckets of the tracked clients.
+#[derive(Debug)]
+struct Buckets {
+    /// Token bucket for each client.
+    buckets: HashMap<ClientKey, TokenBucket>,
+    /// Number of tracked clients at which the clients with a full token bucket
+    /// are evicted. Raised to twice the number of remaining clients after
+    /// evicting, such that the cost of evicting is amortized over the requests
+    /// of new clients.
+    eviction_threshold: usize,
+}
+
+impl RateLimiter {
+    /// Identify the client of a request.
+    fn client_key<R>(&self, req: &Request<R>) -> ClientKey {
+        if let Some(api_key) = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|api_key| self.api_keys.contains(*api_key))
+        {
+            return ClientKey::ApiKey(api_key.to_string());
+        }
+        let forwarded_ip = self
+            .client_ip_header
+            .as_ref()
+            .and_then(|header| req.headers().get(header))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                // Entries left of the one appended by the outermost trusted proxy are provided
+                // by the client and cannot be trusted.
+                let entries: Vec<&str> = value.split(',').collect();
+                let index = entries.len().saturating_sub(self.trusted_proxies);
+                entries.get(index).map(|ip| ip.trim().to_string())
+            })
+            .filter(|ip| !ip.is_empty());
+        let ip = forwarded_ip.unwrap_or_else(|| {
+            req.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string())
+                .unwrap_or_default()
+        });
+        ClientKey::Ip(ip)
+    }
+
+    /// Take a token from the bucket of the client.
+    /// Returns the duration until a token is available when the bucket is
+    /// empty.
+    fn acquire(&self, client: ClientKey, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().expect("Rate limiter lock is poisoned");
+        if buckets.buckets.len() >= buckets.eviction_threshold {
+            buckets
+                .buckets
+                .retain(|_, bucket| bucket.available(now, self.rate, self.burst) < self.burst);
+            buckets.eviction_threshold = (buckets.buckets.len() * 2).max(EVICTION_THRESHOLD);
+        }
+        buckets
+            .buckets
+            .entry(client)
+            .or_insert_with(|| TokenBucket::full(now, self.burst))
+            .take(now, self.rate, self.burst)
+    }
+}
+
+/// Token bucket of a single client.
+#[derive(Debug)]
+struct TokenBucket {
+    /// Number of tokens in the bucket at the time of the last update.
+    tokens: f64,
+    /// Time of the last update.
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn full(now: Instant, burst: f64) -> Self {
+        Self {
+            tokens: burst,
+            updated: now,
+        }
+    }
+
+    /// Number of tokens available at the provided time.
+    fn available(&self, now: Instant, rate: f64, burst: f64) -> f64 {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        (self.tokens + elapsed * rate).min(burst)
+    }
+
+    /// Take a token from the bucket. Returns the duration until a token is
+    /// available when the bucket is empty.
+    fn take(&mut self, now: Instant, rate: f64, burst: f64) -> Result<(), Duration> {
+        self.tokens = self.available(now, rate, burst);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+}
+
+/// Service middleware limiting the rate of requests per client.
+#[derive(Debug, Clone)]
+pub struct RateLimitService<S> {
+    /// The inner service.
+    inner: S,
+    /// The layer holding the state of the rate limiter.
+    layer: RateLimitLayer,
+}
+
+impl<S, R, ResBody, F> tower::Service<Request<R>> for RateLimitService<S>
+where
+    S: tower::Service<Request<R>, Response = Response<ResBody>, Future = F>,
+    F: Future<Output = Result<S::Response, S::Error>> + 'static + Send,
+    ResBody: Default + Send + 'static,
+    S::Error: Send + 'static,
+{
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+    type Response = S::Response;
+
+    fn poll_ready(&mut self, cx: &mut task::Context<'_>) -> task::Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<R>) -> Self::Future {
+        if let Some(limiter) = &self.layer.limiter {
+            let client = limiter.client_key(&req);
+            let api_key = matches!(client, ClientKey::ApiKey(_));
+            if let Err(retry_after) = limiter.acquire(client, Instant::now()) {
+                self.layer
+                    .rejected_requests
+                    .get_or_create(&RejectedLabels {
+                        route: req
+                            .extensions()
+                            .get::<MatchedPath>()
+                            .map_or("unmatched", MatchedPath::as_str)
+                            .to_string(),
+                        api_key,
+                    })
+                    .inc();
+                let mut response = Response::new(ResBody::default());
+                *response.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+                // Round up to whole seconds, as required by the header.
+                let retry_after_secs = retry_after.as_secs() + 1;
+                response
+                    .headers_mut()
+                    .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+                return Box::pin(async move { Ok(response) });
+            }
+        }
+        Box::pin(self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Buckets, ClientKey, RateLimiter, TokenBucket, EVICTION_THRESHOLD};
+    use axum::http::{HeaderName, Request};
+    use std::{collections::HashMap, sync::Mutex};
+    use tokio::time::{Duration, Instant};
+
+    fn limiter(trusted_proxies: usize) -> RateLimiter {
+        RateLimiter {
+            rate: 2.0,
+            burst: 3.0,
+            client_ip_header: Some(HeaderName::from_static("x-forwarded-for")),
+            trusted_proxies,
+            api_keys: Default::default(),
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                eviction_threshold: EVICTION_THRESHOLD,
+            }),
+        }
+    }
+
+    fn forwarded_for(value: &str) -> Request<()> {
+        Request::builder()
+            .header("x-forwarded-for", value)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_client_key_ignores_entries_provided_by_the_client() {
+        let request = forwarded_for("10.0.0.1, 192.0.2.7");
+        assert_eq!(
+            limiter(1).client_key(&request),
+            ClientKey::Ip("192.0.2.7".to_string())
+        );
+        let request = forwarded_for("10.0.0.1, 192.0.2.7, 198.51.100.2");
+        assert_eq!(
+            limiter(2).client_key(&request),
+            ClientKey::Ip("192.0.2.7".to_string())
+        );
+        // Fewer entries than trusted proxies are all appended by trusted proxies.
+        let request = forwarded_for("192.0.2.7");
+        assert_eq!(
+            limiter(2).client_key(&request),
+            ClientKey::Ip("192.0.2.7".to_string())
+        );
+    }
+
+    #[test]
+    fn test_eviction_is_amortized() {
+        let limiter = limiter(1);
+        let start = Instant::now();
+        // Clients with an empty bucket are kept when evicting.
+        for i in 0..EVICTION_THRESHOLD {
+            let client = ClientKey::Ip(i.to_string());
+            for _ in 0..3 {
+                assert!(limiter.acquire(client.clone(), start).is_ok());
+            }
+        }
+        assert!(limiter
+            .acquire(ClientKey::Ip("new".to_string()), start)
+            .is_ok());
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.buckets.len(), EVICTION_THRESHOLD + 1);
+        assert_eq!(buckets.eviction_threshold, EVICTION_THRESHOLD * 2);
+        drop(buckets);
+        // Clients with a full bucket are evicted once the raised threshold is reached.
+        let later = start + Duration::from_secs(60);
+        for i in 0..EVICTION_THRESHOLD {
+            let client = ClientKey::Ip(format!("later-{i}"));
+            assert!(limiter.acquire(client, later).is_ok());
+        }
+        let buckets = limiter.buckets.lock().unwrap();
+        assert!(buckets.buckets.len() < EVICTION_THRESHOLD * 2);
+    }
+
+    #[test]
+    fn test_token_bucket_allows_burst_then_limits_to_rate() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(start, 3.0);
+        for _ in 0..3 {
+            assert!(bucket.take(start, 2.0, 3.0).is_ok());
+        }
+        let retry_after = bucket.take(start, 2.0, 3.0).unwrap_err();
+        assert_eq!(retry_after, Duration::from_millis(500));
+        assert!(bucket
+            .take(start + Duration::from_millis(500), 2.0, 3.0)
+            .is_ok());
+        assert!(bucket
+            .take(start + Duration::from_millis(500), 2.0, 3.0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_token_bucket_refills_up_to_burst() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::full(start, 3.0);
+        assert!(bucket.take(start, 2.0, 3.0).is_ok());
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.available(later, 2.0, 3.0), 3.0);
+    }
+}
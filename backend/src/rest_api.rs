@@ -1,31 +1,60 @@
 //! Module containing the implementation of a service providing the public
 //! facing REST API for `ccdscan-api`.
 
-use crate::graphql_api::{
-    AccountStatementEntryType, ApiServiceConfig, PltAccountStatementEntryType,
+use crate::{
+    graphql_api::{
+        self,
+        account::query_account_balance_at,
+        supply_metrics::query_supply_history,
+        transaction_submission::{TransactionSubmission, TransactionSubmitter},
+        AccountStatementEntryType, ApiServiceConfig, Cis2AccountStatementEntryType,
+        PltAccountStatementEntryType,
+    },
+    indexing_profile::{DomainNotIndexed, IndexingDomain, IndexingDomains},
+    rate_limit::RateLimitLayer,
+    scalar_types::TimeSpan,
 };
 use axum::{
-    extract::{Query, State},
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    handler::Handler,
     http::HeaderName,
     response::{AppendHeaders, IntoResponse},
-    routing::get,
-    Router,
+    routing::{get, post, MethodRouter},
+    Json, Router,
 };
 use chrono::{DateTime, TimeDelta, Utc};
 use concordium_rust_sdk::{common::types::Amount, id::types::AccountAddress};
-use futures::TryStreamExt as _;
+use futures::{stream::BoxStream, Stream, StreamExt as _};
 use prometheus_client::registry::Registry;
 use reqwest::StatusCode;
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::error;
+use utoipa::{
+    openapi::{
+        path::{HttpMethod, Operation, OperationBuilder, Parameter, PathItem},
+        request_body::RequestBodyBuilder,
+        schema::Schema,
+        Content, InfoBuilder, OpenApi, OpenApiBuilder, PathsBuilder, RefOr, Required,
+        ResponseBuilder,
+    },
+    IntoParams as _,
+};
+
+/// Version of the REST API stated in the OpenAPI document. Bump when
+/// the routes or their parameters change.
+const OPENAPI_DOCUMENT_VERSION: &str = "1.3.0";
 
 /// Service providing the router for the REST API.
 #[derive(Debug)]
 pub struct Service {
     /// Layer adding monitoring for the routes.
     monitor_layer: monitor::MonitorLayer,
+    /// Layer limiting the rate of requests per client.
+    rate_limit_layer: RateLimitLayer,
     /// State shared between handlers.
     state: RouterState,
 }
@@ -37,13 +66,69 @@ struct RouterState {
     pool: PgPool,
     /// Configurations for the API.
     config: Arc<ApiServiceConfig>,
+    /// Submits transactions to the node.
+    transaction_submitter: TransactionSubmitter,
+    /// Domains of derived data populated in the database.
+    indexed_domains: IndexingDomains,
+}
+
+/// A route of the REST API together with the operation describing it in the
+/// OpenAPI document, such that the router and the document are derived from
+/// the same routes.
+struct Route {
+    path: &'static str,
+    method: HttpMethod,
+    handler: MethodRouter<RouterState>,
+    operation: Operation,
+}
+
+impl Route {
+    fn get<H, T>(path: &'static str, handler: H, operation: OperationBuilder) -> Self
+    where
+        H: Handler<T, RouterState>,
+        T: 'static,
+    {
+        Self {
+            path,
+            method: HttpMethod::Get,
+            handler: get(handler),
+            operation: operation.build(),
+        }
+    }
+
+    fn post<H, T>(path: &'static str, handler: H, operation: OperationBuilder) -> Self
+    where
+        H: Handler<T, RouterState>,
+        T: 'static,
+    {
+        Self {
+            path,
+            method: HttpMethod::Post,
+            handler: post(handler),
+            operation: operation.build(),
+        }
+    }
 }
 
 impl Service {
-    pub fn new(pool: PgPool, config: Arc<ApiServiceConfig>, registry: &mut Registry) -> Self {
+    pub fn new(
+        pool: PgPool,
+        config: Arc<ApiServiceConfig>,
+        transaction_submitter: TransactionSubmitter,
+        indexed_domains: IndexingDomains,
+        rate_limit_layer: RateLimitLayer,
+        registry: &mut Registry,
+    ) -> Self {
+        let registry = registry.sub_registry_with_prefix("rest");
         Self {
-            state: RouterState { pool, config },
-            monitor_layer: monitor::MonitorLayer::new(registry.sub_registry_with_prefix("rest")),
+            monitor_layer: monitor::MonitorLayer::new(registry),
+            rate_limit_layer,
+            state: RouterState {
+                pool,
+                config,
+                transaction_submitter,
+                indexed_domains,
+            },
         }
     }
 
@@ -52,22 +137,281 @@ impl Service {
             .allow_origin(Any) // Open access to selected route
             .allow_methods(Any)
             .allow_headers(Any);
-        Router::new()
-            .route(
+        Self::routes()
+            .into_iter()
+            .fold(Router::new(), |router, route| {
+                router.route(route.path, route.handler)
+            })
+            .route("/rest/openapi.json", get(Self::openapi_document))
+            .layer(self.rate_limit_layer)
+            .layer(cors_layer)
+            .layer(self.monitor_layer)
+            .with_state(self.state)
+    }
+
+    /// The routes of the REST API described by the OpenAPI document.
+    fn routes() -> Vec<Route> {
+        let export = |operation_id: &str, summary: &str| {
+            OperationBuilder::new()
+                .operation_id(Some(operation_id))
+                .summary(Some(summary))
+                .parameters(Some(ExportAccountStatement::into_params(|| None)))
+                .response(
+                    "200",
+                    ResponseBuilder::new()
+                        .description("The exported entries, newest first.")
+                        .content(
+                            ExportFormat::Csv.content_type(),
+                            Content::new(None::<RefOr<Schema>>),
+                        )
+                        .content(
+                            ExportFormat::Jsonl.content_type(),
+                            Content::new(None::<RefOr<Schema>>),
+                        ),
+                )
+                .response(
+                    "400",
+                    ResponseBuilder::new().description(
+                        "The time range exceeds the maximum allowed number of days for an export.",
+                    ),
+                )
+        };
+        let amount = |operation_id: &str, summary: &str, parameters: Vec<Parameter>| {
+            OperationBuilder::new()
+                .operation_id(Some(operation_id))
+                .summary(Some(summary))
+                .parameters(Some(parameters))
+                .response(
+                    "200",
+                    ResponseBuilder::new()
+                        .description("The amount in the requested unit.")
+                        .content(
+                            "text/plain; charset=utf-8",
+                            Content::new(None::<RefOr<Schema>>),
+                        ),
+                )
+        };
+        let not_indexed = ResponseBuilder::new()
+            .description("The exported data is not indexed on this instance.")
+            .build();
+        vec![
+            Route::get(
                 "/rest/balance-statistics/latest",
-                get(Self::latest_balance_statistics),
-            )
-            .route(
+                Self::latest_balance_statistics,
+                amount(
+                    "latestBalanceStatistics",
+                    "Balance statistic of the latest block.",
+                    LatestBalanceStatistics::into_params(|| None),
+                ),
+            ),
+            Route::get(
+                "/rest/account-balance",
+                Self::account_balance,
+                amount(
+                    "accountBalance",
+                    "CCD balance of an account at a block height or time.",
+                    AccountBalanceAt::into_params(|| None),
+                )
+                .response(
+                    "400",
+                    ResponseBuilder::new()
+                        .description("Not exactly one of 'blockHeight' and 'time' is provided."),
+                )
+                .response(
+                    "404",
+                    ResponseBuilder::new().description("The account or block was not found."),
+                ),
+            ),
+            Route::get(
+                "/rest/supply-history",
+                Self::supply_history,
+                OperationBuilder::new()
+                    .operation_id(Some("supplyHistory"))
+                    .summary(Some(
+                        "Total, circulating, staked and locked in release schedules supply of \
+                         CCD at the end of each bucket of a time range.",
+                    ))
+                    .parameters(Some(SupplyHistory::into_params(|| None)))
+                    .response(
+                        "200",
+                        ResponseBuilder::new()
+                            .description(
+                                "The supply for each bucket, oldest first. Every entry contains \
+                                 the 'time' of the start of the bucket and the 'totalAmount', \
+                                 'circulatingAmount', 'stakedAmount' and 'lockedAmount' in the \
+                                 requested unit.",
+                            )
+                            .content("application/json", Content::new(None::<RefOr<Schema>>)),
+                    )
+                    .response(
+                        "400",
+                        ResponseBuilder::new()
+                            .description("The time range is empty or contains too many buckets."),
+                    ),
+            ),
+            Route::get(
                 "/rest/export/account-statements",
-                get(Self::export_account_statements),
-            )
-            .route(
+                Self::export_account_statements,
+                export(
+                    "exportAccountStatements",
+                    "Export the CCD account statement of an account.",
+                ),
+            ),
+            Route::get(
                 "/rest/export/plt-statements",
-                get(Self::export_plt_statements),
+                Self::export_plt_statements,
+                export(
+                    "exportPltStatements",
+                    "Export the PLT account statement of an account.",
+                )
+                .response("501", not_indexed.clone()),
+            ),
+            Route::get(
+                "/rest/export/cis2-statements",
+                Self::export_cis2_statements,
+                export(
+                    "exportCis2Statements",
+                    "Export the CIS-2 account statement of an account.",
+                )
+                .response("501", not_indexed.clone()),
+            ),
+            Route::get(
+                "/rest/export/rewards",
+                Self::export_rewards,
+                export(
+                    "exportRewards",
+                    "Export the rewards received by an account.",
+                )
+                .response("501", not_indexed),
+            ),
+            Route::post(
+                "/rest/transactions",
+                Self::submit_transaction,
+                OperationBuilder::new()
+                    .operation_id(Some("submitTransaction"))
+                    .summary(Some(
+                        "Submit a serialized block item to the node, tracking its status using \
+                         '/rest/transactions/{transactionHash}' or the GraphQL query \
+                         'transactionSubmission'.",
+                    ))
+                    .request_body(Some(
+                        RequestBodyBuilder::new()
+                            .description(Some("The serialization of the block item."))
+                            .content(
+                                "application/octet-stream",
+                                Content::new(None::<RefOr<Schema>>),
+                            )
+                            .required(Some(Required::True))
+                            .build(),
+                    ))
+                    .response(
+                        "200",
+                        ResponseBuilder::new()
+                            .description(
+                                "The transaction was accepted by the node. The body contains the \
+                                 'transactionHash', 'status' and 'submittedAt'.",
+                            )
+                            .content("application/json", Content::new(None::<RefOr<Schema>>)),
+                    )
+                    .response(
+                        "400",
+                        ResponseBuilder::new()
+                            .description("The block item is invalid or was rejected by the node."),
+                    )
+                    .response(
+                        "503",
+                        ResponseBuilder::new().description(
+                            "Submission of transactions is disabled or the node is unavailable.",
+                        ),
+                    ),
+            ),
+            Route::get(
+                "/rest/transactions/{transactionHash}",
+                Self::transaction_submission,
+                OperationBuilder::new()
+                    .operation_id(Some("transactionSubmission"))
+                    .summary(Some(
+                        "Status of a transaction submitted through the API, until indexed.",
+                    ))
+                    .parameters(Some(TransactionSubmissionPath::into_params(|| None)))
+                    .response(
+                        "200",
+                        ResponseBuilder::new()
+                            .description(
+                                "The body contains the 'transactionHash', 'status' and \
+                                 'submittedAt', where 'submittedAt' is null when the transaction \
+                                 was indexed without being submitted through the API.",
+                            )
+                            .content("application/json", Content::new(None::<RefOr<Schema>>)),
+                    )
+                    .response(
+                        "400",
+                        ResponseBuilder::new().description("The transaction hash is invalid."),
+                    )
+                    .response(
+                        "404",
+                        ResponseBuilder::new().description(
+                            "The transaction was neither submitted through the API nor indexed.",
+                        ),
+                    ),
+            ),
+        ]
+    }
+
+    /// The OpenAPI document describing the routes of the REST API.
+    pub fn openapi() -> OpenApi {
+        let paths = Self::routes()
+            .into_iter()
+            .fold(PathsBuilder::new(), |paths, route| {
+                paths.path(route.path, PathItem::new(route.method, route.operation))
+            });
+        OpenApiBuilder::new()
+            .info(
+                InfoBuilder::new()
+                    .title("CCDScan REST API")
+                    .version(OPENAPI_DOCUMENT_VERSION)
+                    .description(Some("Public facing REST API of CCDScan.")),
             )
-            .layer(cors_layer)
-            .layer(self.monitor_layer)
-            .with_state(self.state)
+            .paths(paths)
+            .build()
+    }
+
+    /// The OpenAPI document describing the routes of the REST API as
+    /// pretty-printed JSON, with a trailing newline.
+    pub fn openapi_json() -> String {
+        let json = Self::openapi()
+            .to_pretty_json()
+            .expect("Failed to serialize the OpenAPI document");
+        format!("{}\n", json)
+    }
+
+    async fn openapi_document() -> Json<OpenApi> {
+        Json(Self::openapi())
+    }
+
+    async fn submit_transaction(
+        State(state): State<RouterState>,
+        body: Bytes,
+    ) -> ApiResult<Json<TransactionSubmission>> {
+        let submission = state
+            .transaction_submitter
+            .submit(&state.pool, &body)
+            .await
+            .map_err(ApiError::TransactionSubmission)?;
+        Ok(Json(submission))
+    }
+
+    async fn transaction_submission(
+        Path(params): Path<TransactionSubmissionPath>,
+        State(state): State<RouterState>,
+    ) -> ApiResult<Json<TransactionSubmission>> {
+        let submission = state
+            .transaction_submitter
+            .status(&state.pool, params.transaction_hash)
+            .await
+            .map_err(ApiError::TransactionSubmission)?
+            .ok_or(ApiError::NotFound)?;
+        Ok(Json(submission))
     }
 
     async fn latest_balance_statistics(
@@ -109,178 +453,339 @@ impl Service {
             }
         };
         let amount = u64::try_from(amount.ok_or(ApiError::NotFound)?)?;
-        let amount = match params.unit {
-            Unit::Ccd => amount / 1_000_000,
-            Unit::MicroCcd => amount,
-        };
-        Ok(amount.to_string())
+        Ok(params.unit.convert(amount).to_string())
     }
 
-    async fn export_account_statements(
-        Query(params): Query<ExportAccountStatement>,
+    async fn account_balance(
+        Query(params): Query<AccountBalanceAt>,
         State(state): State<RouterState>,
-    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Vec<u8>)> {
-        let to = params.to_time.unwrap_or_else(Utc::now);
-        let account_statements_export_max_days =
-            i64::try_from(state.config.export_statement_max_days).unwrap_or(32);
-        let from = params
-            .from_time
-            .unwrap_or_else(|| to - TimeDelta::days(account_statements_export_max_days));
-        if to - from > TimeDelta::days(account_statements_export_max_days) {
-            return Err(ApiError::ExceedsMaxAllowedDaysForAccountStatementExport(
-                account_statements_export_max_days,
-            ));
-        }
+    ) -> ApiResult<String> {
+        params.query(state.pool.acquire().await?.as_mut()).await
+    }
 
-        // Collect CCD account statement data
-        let mut ccd_data = Vec::new();
-        let mut ccd_rows = sqlx::query_as!(
-            ExportAccountStatementEntry,
-            r#"SELECT
-                slot_time as timestamp,
-                amount,
-                account_balance,
-                "entry_type" as "entry_type: AccountStatementEntryType"
-            FROM account_statements
-            WHERE
-                account_index = (SELECT index FROM accounts WHERE address = $1)
-                AND slot_time between $2 and $3
-            ORDER BY slot_time DESC"#,
-            params.account_address.to_string(),
-            from,
-            to
+    async fn supply_history(
+        Query(params): Query<SupplyHistory>,
+        State(state): State<RouterState>,
+    ) -> ApiResult<Json<Vec<SupplyHistoryEntry>>> {
+        let entries = query_supply_history(
+            &state.pool,
+            &state.config,
+            params.from_time,
+            params.to_time,
+            params.bucket_width.0,
         )
-        .fetch(&state.pool);
-
-        while let Some(row) = ccd_rows.try_next().await? {
-            ccd_data.push(row);
-        }
+        .await
+        .map_err(ApiError::SupplyHistory)?;
+        let entries = entries
+            .into_iter()
+            .map(|entry| SupplyHistoryEntry {
+                time: entry.time,
+                total_amount: params.unit.convert(entry.total_amount),
+                circulating_amount: params.unit.convert(entry.circulating_amount),
+                staked_amount: params.unit.convert(entry.staked_amount),
+                locked_amount: params.unit.convert(entry.locked_amount),
+            })
+            .collect();
+        Ok(Json(entries))
+    }
 
-        // Create Account Statements CSV
-        let mut csv_content = String::from("Time,Amount (CCD),Balance (CCD),Label\n");
-        for row in ccd_data.iter() {
-            let account_balance = Amount::from_micro_ccd(row.account_balance.try_into()?);
-            let amount_sign = if row.amount.is_negative() { "-" } else { "" };
-            let amount = Amount::from_micro_ccd(row.amount.abs().try_into()?);
-            csv_content.push_str(&format!(
-                "{},{}{},{},{}\n",
-                row.timestamp
-                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-                amount_sign,
-                amount,
-                account_balance,
-                row.entry_type
-            ));
-        }
+    async fn export_account_statements(
+        Query(params): Query<ExportAccountStatement>,
+        State(state): State<RouterState>,
+    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Body)> {
+        let (from, to) = params.time_range(&state.config)?;
+        let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        let account_address = params.account_address.to_string();
+        let format = params.format;
+        let pool = state.pool;
+        tokio::spawn(async move {
+            let rows = sqlx::query_as!(
+                ExportAccountStatementEntry,
+                r#"SELECT
+                    slot_time as timestamp,
+                    amount,
+                    account_balance,
+                    "entry_type" as "entry_type: AccountStatementEntryType"
+                FROM account_statements
+                WHERE
+                    account_index = (SELECT index FROM accounts WHERE address = $1)
+                    AND slot_time between $2 and $3
+                ORDER BY slot_time DESC"#,
+                account_address,
+                from,
+                to
+            )
+            .fetch(&pool);
+            forward_export_rows(rows, format, sender).await;
+        });
 
         let filename = format!(
-            "account-statements-{}_{}-{}.csv",
+            "account-statements-{}_{}-{}.{}",
             params.account_address,
             from.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            params.format.file_extension()
         );
-        let headers = AppendHeaders([
-            (
-                axum::http::header::CONTENT_TYPE,
-                "text/csv; charset=utf-8".to_string(),
-            ),
-            (
-                axum::http::header::CONTENT_DISPOSITION,
-                format!("attachment; filename=\"{}\"", filename),
-            ),
-        ]);
-
-        Ok((headers, csv_content.into_bytes()))
+        Ok(export_response(params.format, filename, receiver))
     }
 
     async fn export_plt_statements(
         Query(params): Query<ExportAccountStatement>,
         State(state): State<RouterState>,
-    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Vec<u8>)> {
-        let to = params.to_time.unwrap_or_else(Utc::now);
-        let account_statements_export_max_days =
-            i64::try_from(state.config.export_statement_max_days).unwrap_or(32);
-        let from = params
-            .from_time
-            .unwrap_or_else(|| to - TimeDelta::days(account_statements_export_max_days));
-        if to - from > TimeDelta::days(account_statements_export_max_days) {
-            return Err(ApiError::ExceedsMaxAllowedDaysForAccountStatementExport(
-                account_statements_export_max_days,
-            ));
-        }
+    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Body)> {
+        state.indexed_domains.ensure_indexed(IndexingDomain::Plt)?;
+        let (from, to) = params.time_range(&state.config)?;
+        let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        let account_address = params.account_address.to_string();
+        let format = params.format;
+        let pool = state.pool;
+        tokio::spawn(async move {
+            let rows = sqlx::query_as!(
+                ExportPltAccountStatementEntry,
+                r#"SELECT
+                    ps.slot_time as timestamp,
+                    ps.amount,
+                    ps.decimals,
+                    ps.account_balance,
+                    ps.entry_type as "entry_type: PltAccountStatementEntryType",
+                    pt.token_id,
+                    pt.name as token_name
+                FROM plt_accounts_statement ps
+                JOIN plt_tokens pt ON ps.token_index = pt.index
+                WHERE
+                    ps.account_index = (SELECT index FROM accounts WHERE address = $1)
+                    AND ps.slot_time between $2 and $3
+                ORDER BY ps.slot_time DESC"#,
+                account_address,
+                from,
+                to
+            )
+            .fetch(&pool);
+            forward_export_rows(rows, format, sender).await;
+        });
 
-        // Collect PLT account statement data
-        let mut plt_data = Vec::new();
-        let mut plt_rows = sqlx::query_as!(
-            ExportPltAccountStatementEntry,
-            r#"SELECT
-                ps.slot_time as timestamp,
-                ps.amount,
-                ps.decimals,
-                ps.account_balance,
-                ps.entry_type as "entry_type: PltAccountStatementEntryType",
-                pt.token_id,
-                pt.name as token_name
-            FROM plt_accounts_statement ps
-            JOIN plt_tokens pt ON ps.token_index = pt.index
-            WHERE
-                ps.account_index = (SELECT index FROM accounts WHERE address = $1)
-                AND ps.slot_time between $2 and $3
-            ORDER BY ps.slot_time DESC"#,
-            params.account_address.to_string(),
-            from,
-            to
-        )
-        .fetch(&state.pool);
+        let filename = format!(
+            "plt-statements-{}_{}-{}.{}",
+            params.account_address,
+            from.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            params.format.file_extension()
+        );
+        Ok(export_response(params.format, filename, receiver))
+    }
 
-        while let Some(row) = plt_rows.try_next().await? {
-            plt_data.push(row);
-        }
+    async fn export_cis2_statements(
+        Query(params): Query<ExportAccountStatement>,
+        State(state): State<RouterState>,
+    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Body)> {
+        state.indexed_domains.ensure_indexed(IndexingDomain::Cis2)?;
+        let (from, to) = params.time_range(&state.config)?;
+        let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        let canonical_address = params.account_address.get_canonical_address();
+        let format = params.format;
+        let pool = state.pool;
+        tokio::spawn(async move {
+            let rows =
+                ExportCis2AccountStatementEntry::query(&pool, &canonical_address.0, from, to);
+            forward_export_rows(rows, format, sender).await;
+        });
 
-        // Create PLT Account Statements CSV
-        let mut csv_content =
-            String::from("Time,Token Name,Token ID,Label,Amount,Balance,Decimals\n");
-        for row in plt_data.iter() {
-            csv_content.push_str(&format!(
-                "{},{},{},{},{},{},{}\n",
-                row.timestamp
-                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-                row.token_name.as_deref().unwrap_or("").replace(',', ";"), // Replace commas to avoid CSV issues
-                row.token_id.replace(',', ";"), // Replace commas to avoid CSV issues
-                row.entry_type,
-                row.amount,
-                row.account_balance,
-                row.decimals
-            ));
-        }
+        let filename = format!(
+            "cis2-statements-{}_{}-{}.{}",
+            params.account_address,
+            from.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            params.format.file_extension()
+        );
+        Ok(export_response(params.format, filename, receiver))
+    }
+
+    async fn export_rewards(
+        Query(params): Query<ExportAccountStatement>,
+        State(state): State<RouterState>,
+    ) -> ApiResult<(AppendHeaders<[(HeaderName, String); 2]>, Body)> {
+        state
+            .indexed_domains
+            .ensure_indexed(IndexingDomain::Payday)?;
+        let (from, to) = params.time_range(&state.config)?;
+        let (sender, receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        let account_address = params.account_address.to_string();
+        let format = params.format;
+        let pool = state.pool;
+        tokio::spawn(async move {
+            let rows = ExportRewardEntry::query(&pool, &account_address, from, to);
+            forward_export_rows(rows, format, sender).await;
+        });
 
         let filename = format!(
-            "plt-statements-{}_{}-{}.csv",
+            "rewards-{}_{}-{}.{}",
             params.account_address,
             from.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
-            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            to.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            params.format.file_extension()
         );
-        let headers = AppendHeaders([
-            (
-                axum::http::header::CONTENT_TYPE,
-                "text/csv; charset=utf-8".to_string(),
-            ),
-            (
-                axum::http::header::CONTENT_DISPOSITION,
-                format!("attachment; filename=\"{}\"", filename),
-            ),
-        ]);
+        Ok(export_response(params.format, filename, receiver))
+    }
+}
+
+/// Number of formatted rows buffered between the database query and the
+/// response body of a streamed export.
+const EXPORT_CHANNEL_CAPACITY: usize = 64;
+
+/// Format the rows of an export and forward them into the channel feeding the
+/// response body.
+/// Stops when all rows are forwarded, when a row fails to be fetched or
+/// formatted, or when the receiving end is dropped because the client
+/// disconnected.
+///
+/// The status code is already sent when a row fails, so the failure is stated
+/// by a last line in the export, followed by the error aborting the response
+/// body, such that the client observes an incomplete transfer rather than a
+/// truncated export.
+async fn forward_export_rows<T: ExportRow>(
+    mut rows: impl Stream<Item = Result<T, sqlx::Error>> + Unpin,
+    format: ExportFormat,
+    sender: mpsc::Sender<ApiResult<String>>,
+) {
+    if let ExportFormat::Csv = format {
+        if sender.send(Ok(T::CSV_HEADER.to_string())).await.is_err() {
+            return;
+        }
+    }
+    while let Some(row) = rows.next().await {
+        let line = row
+            .map_err(ApiError::from)
+            .and_then(|row| format.format_row(&row));
+        match line {
+            Ok(line) => {
+                if sender.send(Ok(line)).await.is_err() {
+                    return;
+                }
+            }
+            Err(err) => {
+                error!("Failed to stream export: {}", err);
+                if sender.send(Ok(format.failure_line(&err))).await.is_ok() {
+                    let _ = sender.send(Err(err)).await;
+                }
+                return;
+            }
+        }
+    }
+}
+
+/// Construct the response for an export streaming the formatted rows received
+/// on the channel.
+fn export_response(
+    format: ExportFormat,
+    filename: String,
+    receiver: mpsc::Receiver<ApiResult<String>>,
+) -> (AppendHeaders<[(HeaderName, String); 2]>, Body) {
+    let headers = AppendHeaders([
+        (
+            axum::http::header::CONTENT_TYPE,
+            format.content_type().to_string(),
+        ),
+        (
+            axum::http::header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        ),
+    ]);
+    (
+        headers,
+        Body::from_stream(tokio_stream::wrappers::ReceiverStream::new(receiver)),
+    )
+}
+
+/// The file format of an export.
+#[derive(Debug, Default, Clone, Copy, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+enum ExportFormat {
+    /// Comma-separated values with a header line.
+    #[default]
+    Csv,
+    /// JSON Lines, one JSON object per line.
+    Jsonl,
+}
+
+impl ExportFormat {
+    fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Jsonl => "application/jsonl; charset=utf-8",
+        }
+    }
 
-        Ok((headers, csv_content.into_bytes()))
+    fn file_extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Jsonl => "jsonl",
+        }
+    }
+
+    /// Format a row as a line in the export, including the line break.
+    fn format_row(&self, row: &impl ExportRow) -> ApiResult<String> {
+        match self {
+            ExportFormat::Csv => Ok(format!("{}\n", row.csv_record()?)),
+            ExportFormat::Jsonl => Ok(format!("{}\n", row.json_record()?)),
+        }
+    }
+
+    /// The last line of an export failing part-way, stating the failure.
+    fn failure_line(&self, err: &ApiError) -> String {
+        match self {
+            ExportFormat::Csv => format!("# Export failed: {}\n", err),
+            ExportFormat::Jsonl => format!("{}\n", serde_json::json!({ "error": err.to_string() })),
+        }
     }
 }
 
-#[derive(Debug, serde::Deserialize)]
+/// A row of an exported statement.
+trait ExportRow: Send + 'static {
+    /// Header line of the CSV format, including the line break.
+    const CSV_HEADER: &'static str;
+
+    /// The row as a CSV record, without the line break.
+    fn csv_record(&self) -> ApiResult<String>;
+
+    /// The row as a JSON object.
+    fn json_record(&self) -> ApiResult<serde_json::Value>;
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
 #[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
 struct ExportAccountStatement {
+    /// Address of the account to export for.
+    #[param(value_type = String)]
     account_address: AccountAddress,
+    /// Start of the time range to export. Defaults to the maximum allowed
+    /// number of days before the end of the time range.
     from_time: Option<DateTime<Utc>>,
+    /// End of the time range to export. Defaults to now.
     to_time: Option<DateTime<Utc>>,
+    /// The file format of the export.
+    #[serde(default)]
+    #[param(inline)]
+    format: ExportFormat,
+}
+
+impl ExportAccountStatement {
+    /// The time range to export, defaulting to the maximum allowed number of
+    /// days up until now.
+    fn time_range(&self, config: &ApiServiceConfig) -> ApiResult<(DateTime<Utc>, DateTime<Utc>)> {
+        let to = self.to_time.unwrap_or_else(Utc::now);
+        let account_statements_export_max_days =
+            i64::try_from(config.export_statement_max_days).unwrap_or(32);
+        let from = self
+            .from_time
+            .unwrap_or_else(|| to - TimeDelta::days(account_statements_export_max_days));
+        if to - from > TimeDelta::days(account_statements_export_max_days) {
+            return Err(ApiError::ExceedsMaxAllowedDaysForAccountStatementExport(
+                account_statements_export_max_days,
+            ));
+        }
+        Ok((from, to))
+    }
 }
 
 struct ExportAccountStatementEntry {
@@ -290,6 +795,215 @@ struct ExportAccountStatementEntry {
     entry_type: AccountStatementEntryType,
 }
 
+impl ExportAccountStatementEntry {
+    /// The signed amount and the account balance formatted in CCD.
+    fn formatted_amounts(&self) -> ApiResult<(String, String)> {
+        let account_balance = Amount::from_micro_ccd(self.account_balance.try_into()?);
+        let amount_sign = if self.amount.is_negative() { "-" } else { "" };
+        let amount = Amount::from_micro_ccd(self.amount.abs().try_into()?);
+        Ok((
+            format!("{}{}", amount_sign, amount),
+            account_balance.to_string(),
+        ))
+    }
+}
+
+impl ExportRow for ExportAccountStatementEntry {
+    const CSV_HEADER: &'static str = "Time,Amount (CCD),Balance (CCD),Label\n";
+
+    fn csv_record(&self) -> ApiResult<String> {
+        let (amount, account_balance) = self.formatted_amounts()?;
+        Ok(format!(
+            "{},{},{},{}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            amount,
+            account_balance,
+            self.entry_type
+        ))
+    }
+
+    fn json_record(&self) -> ApiResult<serde_json::Value> {
+        let (amount, account_balance) = self.formatted_amounts()?;
+        Ok(serde_json::json!({
+            "time": self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "amount": amount,
+            "balance": account_balance,
+            "label": self.entry_type.to_string(),
+        }))
+    }
+}
+
+struct ExportCis2AccountStatementEntry {
+    timestamp: DateTime<Utc>,
+    amount: sqlx::types::BigDecimal,
+    account_balance: sqlx::types::BigDecimal,
+    entry_type: Cis2AccountStatementEntryType,
+    contract_index: i64,
+    contract_sub_index: i64,
+    token_id: String,
+}
+
+impl ExportCis2AccountStatementEntry {
+    /// Query the CIS-2 statement entries of the account with the canonical
+    /// address in the time range, latest first.
+    fn query<'e>(
+        executor: impl sqlx::PgExecutor<'e> + 'e,
+        canonical_address: &'e [u8],
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> BoxStream<'e, Result<Self, sqlx::Error>> {
+        sqlx::query_as!(
+            ExportCis2AccountStatementEntry,
+            r#"SELECT
+                cs.slot_time as timestamp,
+                cs.amount,
+                cs.account_balance,
+                cs.entry_type as "entry_type: Cis2AccountStatementEntryType",
+                t.contract_index,
+                t.contract_sub_index,
+                t.token_id
+            FROM cis2_accounts_statement cs
+            JOIN tokens t ON cs.token_index = t.index
+            WHERE
+                cs.account_index = (SELECT index FROM accounts WHERE canonical_address = $1)
+                AND cs.slot_time between $2 and $3
+            ORDER BY cs.slot_time DESC, cs.cis2_token_event_index DESC"#,
+            canonical_address,
+            from,
+            to
+        )
+        .fetch(executor)
+    }
+}
+
+impl ExportRow for ExportCis2AccountStatementEntry {
+    const CSV_HEADER: &'static str =
+        "Time,Contract Index,Contract Subindex,Token ID,Label,Amount,Balance\n";
+
+    fn csv_record(&self) -> ApiResult<String> {
+        Ok(format!(
+            "{},{},{},{},{},{},{}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.contract_index,
+            self.contract_sub_index,
+            self.token_id,
+            self.entry_type,
+            self.amount,
+            self.account_balance
+        ))
+    }
+
+    fn json_record(&self) -> ApiResult<serde_json::Value> {
+        Ok(serde_json::json!({
+            "time": self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "contractIndex": self.contract_index,
+            "contractSubindex": self.contract_sub_index,
+            "tokenId": self.token_id,
+            "label": self.entry_type.to_string(),
+            "amount": self.amount.to_string(),
+            "balance": self.account_balance.to_string(),
+        }))
+    }
+}
+
+struct ExportRewardEntry {
+    timestamp: DateTime<Utc>,
+    block_height: i64,
+    block_hash: String,
+    amount: i64,
+    entry_type: AccountStatementEntryType,
+    /// Whether the reward was paid out from a pool at a payday.
+    from_pool: bool,
+    /// Baker ID of the pool the reward originates from, `None` for passive
+    /// delegation or when the reward is not from a pool.
+    pool_owner: Option<i64>,
+}
+
+impl ExportRewardEntry {
+    /// Query the rewards received by the account with the address in the time
+    /// range, latest first, along with the pool of rewards paid out at paydays.
+    fn query<'e>(
+        executor: impl sqlx::PgExecutor<'e> + 'e,
+        account_address: &'e str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> BoxStream<'e, Result<Self, sqlx::Error>> {
+        sqlx::query_as!(
+            ExportRewardEntry,
+            r#"SELECT
+                s.slot_time as timestamp,
+                s.block_height,
+                blocks.hash as block_hash,
+                s.amount,
+                s.entry_type as "entry_type: AccountStatementEntryType",
+                rewards.payday_block_height IS NOT NULL as "from_pool!",
+                rewards.pool_owner as "pool_owner?"
+            FROM account_statements s
+            JOIN blocks ON blocks.height = s.block_height
+            LEFT JOIN payday_account_rewards rewards
+                ON rewards.account_index = s.account_index
+                AND rewards.payday_block_height = s.block_height
+                AND s.entry_type != 'FoundationReward'
+            WHERE
+                s.account_index = (SELECT index FROM accounts WHERE address = $1)
+                AND s.slot_time between $2 and $3
+                AND s.entry_type IN (
+                    'FinalizationReward',
+                    'FoundationReward',
+                    'BakerReward',
+                    'TransactionFeeReward'
+                )
+            ORDER BY s.slot_time DESC, s.id DESC"#,
+            account_address,
+            from,
+            to
+        )
+        .fetch(executor)
+    }
+
+    /// The pool the reward originates from, either the baker ID of the pool or
+    /// `Passive` for passive delegation. Empty when the reward is not from a
+    /// pool, such as the foundation reward and rewards before protocol
+    /// version 4.
+    fn pool(&self) -> String {
+        match (self.from_pool, self.pool_owner) {
+            (true, Some(baker_id)) => baker_id.to_string(),
+            (true, None) => "Passive".to_string(),
+            (false, _) => String::new(),
+        }
+    }
+}
+
+impl ExportRow for ExportRewardEntry {
+    const CSV_HEADER: &'static str = "Time,Block Height,Block Hash,Reward Type,Pool,Amount (CCD)\n";
+
+    fn csv_record(&self) -> ApiResult<String> {
+        Ok(format!(
+            "{},{},{},{},{},{}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.block_height,
+            self.block_hash,
+            self.entry_type,
+            self.pool(),
+            Amount::from_micro_ccd(self.amount.try_into()?)
+        ))
+    }
+
+    fn json_record(&self) -> ApiResult<serde_json::Value> {
+        Ok(serde_json::json!({
+            "time": self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "blockHeight": self.block_height,
+            "blockHash": self.block_hash,
+            "rewardType": self.entry_type.to_string(),
+            "pool": self.pool(),
+            "amount": Amount::from_micro_ccd(self.amount.try_into()?).to_string(),
+        }))
+    }
+}
+
 struct ExportPltAccountStatementEntry {
     timestamp: DateTime<Utc>,
     amount: sqlx::types::BigDecimal,
@@ -300,11 +1014,54 @@ struct ExportPltAccountStatementEntry {
     token_name: Option<String>,
 }
 
-#[derive(Debug, serde::Deserialize)]
+impl ExportRow for ExportPltAccountStatementEntry {
+    const CSV_HEADER: &'static str = "Time,Token Name,Token ID,Label,Amount,Balance,Decimals\n";
+
+    fn csv_record(&self) -> ApiResult<String> {
+        Ok(format!(
+            "{},{},{},{},{},{},{}",
+            self.timestamp
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            self.token_name.as_deref().unwrap_or("").replace(',', ";"), // Replace commas to avoid CSV issues
+            self.token_id.replace(',', ";"), // Replace commas to avoid CSV issues
+            self.entry_type,
+            self.amount,
+            self.account_balance,
+            self.decimals
+        ))
+    }
+
+    fn json_record(&self) -> ApiResult<serde_json::Value> {
+        Ok(serde_json::json!({
+            "time": self.timestamp.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "tokenName": self.token_name,
+            "tokenId": self.token_id,
+            "label": self.entry_type.to_string(),
+            "amount": self.amount.to_string(),
+            "balance": self.account_balance.to_string(),
+            "decimals": self.decimals,
+        }))
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Path)]
+struct TransactionSubmissionPath {
+    /// Hash of the transaction.
+    transaction_hash: String,
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
 #[serde(rename_all = "lowercase")]
+#[into_params(parameter_in = Query)]
 struct LatestBalanceStatistics {
+    /// The balance statistic to query.
+    #[param(inline)]
     field: Balance,
+    /// The unit of the returned amount. Defaults to `microccd`.
     #[serde(default = "default_balance_statistics_unit_microccd")]
+    #[param(inline)]
     unit: Unit,
 }
 
@@ -314,7 +1071,84 @@ fn default_balance_statistics_unit_microccd() -> Unit {
     Unit::MicroCcd
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct AccountBalanceAt {
+    /// Address of the account to query the balance of.
+    #[param(value_type = String)]
+    account_address: AccountAddress,
+    /// Height of the block to query the balance at the end of.
+    block_height: Option<u64>,
+    /// Query the balance at the end of the last block baked before or at this
+    /// time.
+    time: Option<DateTime<Utc>>,
+    /// The unit of the returned amount. Defaults to `microccd`.
+    #[serde(default = "default_balance_statistics_unit_microccd")]
+    #[param(inline)]
+    unit: Unit,
+}
+
+impl AccountBalanceAt {
+    /// Query the balance of the account in the requested unit.
+    async fn query(&self, connection: &mut PgConnection) -> ApiResult<String> {
+        let account_index = sqlx::query_scalar!(
+            "SELECT index FROM accounts WHERE canonical_address = $1::bytea",
+            self.account_address.get_canonical_address().0.as_slice()
+        )
+        .fetch_optional(&mut *connection)
+        .await?
+        .ok_or(ApiError::NotFound)?;
+        let time = match (self.block_height, self.time) {
+            (Some(block_height), None) => sqlx::query_scalar!(
+                "SELECT slot_time FROM blocks WHERE height = $1",
+                i64::try_from(block_height)?
+            )
+            .fetch_optional(&mut *connection)
+            .await?
+            .ok_or(ApiError::NotFound)?,
+            (None, Some(time)) => time,
+            _ => return Err(ApiError::BlockHeightOrTime),
+        };
+        let amount = query_account_balance_at(connection, account_index, time)
+            .await?
+            .ok_or(ApiError::NotFound)?;
+        let amount = u64::try_from(amount)?;
+        Ok(self.unit.convert(amount).to_string())
+    }
+}
+
+#[derive(Debug, serde::Deserialize, utoipa::IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query)]
+struct SupplyHistory {
+    /// Start of the time range.
+    from_time: DateTime<Utc>,
+    /// End of the time range.
+    to_time: DateTime<Utc>,
+    /// The width of each bucket as an ISO-8601 duration, such as `P1D`.
+    #[param(value_type = String)]
+    bucket_width: TimeSpan,
+    /// The unit of the returned amounts. Defaults to `microccd`.
+    #[serde(default = "default_balance_statistics_unit_microccd")]
+    #[param(inline)]
+    unit: Unit,
+}
+
+/// The supply of CCD at the end of a bucket.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SupplyHistoryEntry {
+    /// Start of the bucket.
+    time: DateTime<Utc>,
+    total_amount: u64,
+    circulating_amount: u64,
+    staked_amount: u64,
+    locked_amount: u64,
+}
+
+/// Balance statistic of a block.
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "lowercase")]
 #[allow(clippy::enum_variant_names)]
 enum Balance {
@@ -332,23 +1166,42 @@ enum Balance {
                          * 'totalAmounUnlocked' as 'totalAmounUnlocked' is now deprecated and
                          * should be removed when no more activity is registered for this field */
 }
-#[derive(Debug, serde::Deserialize)]
+/// Unit of an amount of CCD.
+#[derive(Debug, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "lowercase")]
 enum Unit {
     Ccd,
     MicroCcd,
 }
 
+impl Unit {
+    /// Convert an amount in micro CCD to this unit.
+    fn convert(&self, micro_ccd: u64) -> u64 {
+        match self {
+            Unit::Ccd => micro_ccd / 1_000_000,
+            Unit::MicroCcd => micro_ccd,
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error, Clone)]
 enum ApiError {
     #[error("Information was not found.")]
     NotFound,
     #[error("Chosen time span exceeds max allowed days for account statement export: {0}")]
     ExceedsMaxAllowedDaysForAccountStatementExport(i64),
+    #[error("Exactly one of the 'blockHeight' and 'time' parameters must be provided.")]
+    BlockHeightOrTime,
     #[error("Internal error (FailedDatabaseQuery): {0}")]
     FailedDatabaseQuery(Arc<sqlx::Error>),
     #[error("Invalid integer: {0}")]
     InvalidInt(#[from] std::num::TryFromIntError),
+    #[error("{0}")]
+    TransactionSubmission(graphql_api::ApiError),
+    #[error("{0}")]
+    SupplyHistory(graphql_api::ApiError),
+    #[error(transparent)]
+    DomainNotIndexed(#[from] DomainNotIndexed),
 }
 impl From<sqlx::Error> for ApiError {
     fn from(value: sqlx::Error) -> Self {
@@ -362,9 +1215,23 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> axum::response::Response {
         let status = match self {
             ApiError::ExceedsMaxAllowedDaysForAccountStatementExport(_) => StatusCode::BAD_REQUEST,
+            ApiError::BlockHeightOrTime => StatusCode::BAD_REQUEST,
             ApiError::FailedDatabaseQuery(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::InvalidInt(_) => StatusCode::INTERNAL_SERVER_ERROR,
             ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::DomainNotIndexed(_) => StatusCode::NOT_IMPLEMENTED,
+            ApiError::TransactionSubmission(ref err) => match err {
+                graphql_api::ApiError::InvalidTransaction(_)
+                | graphql_api::ApiError::InvalidTransactionHash(_)
+                | graphql_api::ApiError::TransactionRejected(_) => StatusCode::BAD_REQUEST,
+                graphql_api::ApiError::TransactionSubmissionDisabled
+                | graphql_api::ApiError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+            ApiError::SupplyHistory(ref err) => match err {
+                graphql_api::ApiError::InvalidTimeRange(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
         };
         (status, self.to_string()).into_response()
     }
@@ -474,3 +1341,424 @@ mod monitor {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_database;
+    use chrono::TimeZone;
+    use futures::TryStreamExt as _;
+
+    const HEIGHT: i64 = 1 << 40;
+    const TRANSACTION: i64 = 1 << 40;
+    const ACCOUNT: i64 = 1 << 40;
+    const TOKEN: i64 = 1 << 40;
+    const EVENT: i64 = 1 << 40;
+
+    #[test]
+    fn test_openapi_document_describes_every_route() {
+        let document = Service::openapi();
+        let routes = Service::routes();
+        assert_eq!(
+            document.paths.paths.len(),
+            routes.len(),
+            "Duplicate route paths"
+        );
+        for route in routes {
+            let item = document
+                .paths
+                .paths
+                .get(route.path)
+                .unwrap_or_else(|| panic!("Route {} is not documented", route.path));
+            let operation = match route.method {
+                HttpMethod::Get => item.get.as_ref(),
+                HttpMethod::Post => item.post.as_ref(),
+                _ => None,
+            };
+            assert!(
+                operation.is_some(),
+                "Method of route {} is not documented",
+                route.path
+            );
+        }
+    }
+
+    fn statement_entry() -> ExportAccountStatementEntry {
+        ExportAccountStatementEntry {
+            timestamp: DateTime::from_timestamp(1_700_000_000, 0).unwrap(),
+            amount: -1_500_000,
+            account_balance: 2_000_000,
+            entry_type: AccountStatementEntryType::TransferOut,
+        }
+    }
+
+    /// Collect the lines of an export streaming the rows, and whether the
+    /// response body is aborted by an error.
+    async fn collect_export(
+        rows: Vec<Result<ExportAccountStatementEntry, sqlx::Error>>,
+        format: ExportFormat,
+    ) -> (Vec<String>, bool) {
+        let (sender, mut receiver) = mpsc::channel(EXPORT_CHANNEL_CAPACITY);
+        forward_export_rows(futures::stream::iter(rows), format, sender).await;
+        let mut lines = Vec::new();
+        let mut aborted = false;
+        while let Some(line) = receiver.recv().await {
+            match line {
+                Ok(line) => lines.push(line),
+                Err(_) => aborted = true,
+            }
+        }
+        (lines, aborted)
+    }
+
+    #[tokio::test]
+    async fn test_export_completes() {
+        let (lines, aborted) = collect_export(vec![Ok(statement_entry())], ExportFormat::Csv).await;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], ExportAccountStatementEntry::CSV_HEADER);
+        assert!(lines[1].starts_with("2023-11-14T22:13:20Z,-"));
+        assert!(lines[1].ends_with(",TransferOut\n"));
+        assert!(!aborted);
+    }
+
+    #[tokio::test]
+    async fn test_export_failing_part_way_states_failure_and_aborts() {
+        let rows = vec![
+            Ok(statement_entry()),
+            Err(sqlx::Error::PoolTimedOut),
+            Ok(statement_entry()),
+        ];
+        let (lines, aborted) = collect_export(rows, ExportFormat::Csv).await;
+        assert_eq!(lines.len(), 3);
+        assert!(lines[2].starts_with("# Export failed: "));
+        assert!(aborted);
+
+        let rows = vec![Ok(statement_entry()), Err(sqlx::Error::PoolTimedOut)];
+        let (lines, aborted) = collect_export(rows, ExportFormat::Jsonl).await;
+        assert_eq!(lines.len(), 2);
+        let failure: serde_json::Value = serde_json::from_str(&lines[1]).unwrap();
+        assert!(failure["error"].is_string());
+        assert!(aborted);
+    }
+
+    #[tokio::test]
+    async fn test_export_cis2_statements_of_account_in_time_range() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let account = AccountAddress([0xc2; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &account, 0)
+            .await
+            .unwrap();
+        test_database::insert_account(tx.as_mut(), ACCOUNT + 1, &AccountAddress([0xc3; 32]), 0)
+            .await
+            .unwrap();
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        for offset in 0..3 {
+            test_database::insert_block(
+                tx.as_mut(),
+                HEIGHT + offset,
+                start + TimeDelta::days(offset),
+            )
+            .await
+            .unwrap();
+            test_database::insert_transaction(tx.as_mut(), TRANSACTION + offset, HEIGHT + offset)
+                .await
+                .unwrap();
+        }
+        sqlx::query(
+            "INSERT INTO tokens (
+                index,
+                token_index_per_contract,
+                contract_index,
+                contract_sub_index,
+                token_address,
+                token_id,
+                init_transaction_index
+            ) VALUES ($1, 0, 7, 1, 'test-token', 'ff', $2)",
+        )
+        .bind(TOKEN)
+        .bind(TRANSACTION)
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+        // A mint, a transfer to the other account and a burn on consecutive days.
+        let entries = [
+            (0, ACCOUNT, "Mint", 100, 100),
+            (1, ACCOUNT, "TransferOut", -30, 70),
+            (1, ACCOUNT + 1, "TransferIn", 30, 30),
+            (2, ACCOUNT, "Burn", -20, 50),
+        ];
+        for (offset, account_index, entry_type, amount, balance) in entries {
+            sqlx::query(
+                "INSERT INTO cis2_token_events (
+                    index,
+                    index_per_token,
+                    transaction_index,
+                    token_index,
+                    cis2_token_event
+                )
+                OVERRIDING SYSTEM VALUE
+                VALUES ($1, $2, $3, $4, '{}')
+                ON CONFLICT DO NOTHING",
+            )
+            .bind(EVENT + offset)
+            .bind(offset)
+            .bind(TRANSACTION + offset)
+            .bind(TOKEN)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+            sqlx::query(
+                "INSERT INTO cis2_accounts_statement (
+                    account_index,
+                    cis2_token_event_index,
+                    token_index,
+                    entry_type,
+                    amount,
+                    account_balance,
+                    slot_time,
+                    block_height,
+                    transaction_index
+                )
+                SELECT $1, $2, $3, $4::cis2_account_statement_entry_type, $5, $6, slot_time, \
+                 height, $7
+                FROM blocks WHERE height = $8",
+            )
+            .bind(account_index)
+            .bind(EVENT + offset)
+            .bind(TOKEN)
+            .bind(entry_type)
+            .bind(sqlx::types::BigDecimal::from(amount))
+            .bind(sqlx::types::BigDecimal::from(balance))
+            .bind(TRANSACTION + offset)
+            .bind(HEIGHT + offset)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let rows: Vec<ExportCis2AccountStatementEntry> = ExportCis2AccountStatementEntry::query(
+            tx.as_mut(),
+            &account.get_canonical_address().0,
+            start,
+            start + TimeDelta::days(2),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        let lines: Vec<String> = rows.iter().map(|row| row.csv_record().unwrap()).collect();
+        // Latest first, only of the account, with the balance after each entry.
+        assert_eq!(
+            lines,
+            [
+                "2100-01-03T00:00:00Z,7,1,ff,Burn,-20,50",
+                "2100-01-02T00:00:00Z,7,1,ff,TransferOut,-30,70",
+                "2100-01-01T00:00:00Z,7,1,ff,Mint,100,100",
+            ]
+        );
+
+        // The end of the time range is inclusive, while earlier entries are left out.
+        let rows: Vec<ExportCis2AccountStatementEntry> = ExportCis2AccountStatementEntry::query(
+            tx.as_mut(),
+            &account.get_canonical_address().0,
+            start + TimeDelta::hours(1),
+            start + TimeDelta::days(1),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].account_balance, sqlx::types::BigDecimal::from(70));
+    }
+
+    #[tokio::test]
+    async fn test_export_rewards_with_pool_of_payday_rewards() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let account = AccountAddress([0xd4; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &account, 0)
+            .await
+            .unwrap();
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        for offset in 0..4 {
+            test_database::insert_block(
+                tx.as_mut(),
+                HEIGHT + offset,
+                start + TimeDelta::days(offset),
+            )
+            .await
+            .unwrap();
+        }
+        // Rewards of the pool of validator 42 at the first payday and of passive
+        // delegation at the second, while the last block is not a payday.
+        for (offset, pool_owner) in [(0, Some(42)), (1, None), (2, Some(42))] {
+            sqlx::query(
+                "INSERT INTO payday_account_rewards (payday_block_height, account_index, \
+                 pool_owner)
+                VALUES ($1, $2, $3)",
+            )
+            .bind(HEIGHT + offset)
+            .bind(ACCOUNT)
+            .bind(pool_owner)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+        let statements = [
+            (0, "BakerReward"),
+            (0, "TransactionFeeReward"),
+            (1, "FinalizationReward"),
+            (2, "FoundationReward"),
+            (2, "TransferIn"),
+            (3, "BakerReward"),
+        ];
+        for (offset, entry_type) in statements {
+            sqlx::query(
+                "INSERT INTO account_statements (
+                    account_index,
+                    entry_type,
+                    amount,
+                    account_balance,
+                    block_height,
+                    slot_time
+                )
+                SELECT $1, $2::account_statement_entry_type, 1000000, 0, height, slot_time
+                FROM blocks WHERE height = $3",
+            )
+            .bind(ACCOUNT)
+            .bind(entry_type)
+            .bind(HEIGHT + offset)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        }
+
+        let rows: Vec<ExportRewardEntry> = ExportRewardEntry::query(
+            tx.as_mut(),
+            &account.to_string(),
+            start,
+            start + TimeDelta::days(3),
+        )
+        .try_collect()
+        .await
+        .unwrap();
+        let rewards: Vec<(i64, String, String)> = rows
+            .iter()
+            .map(|row| (row.block_height, row.entry_type.to_string(), row.pool()))
+            .collect();
+        // Latest first, leaving out the transfer, and the foundation reward is not from a
+        // pool even when paid out at a payday.
+        assert_eq!(
+            rewards,
+            [
+                (HEIGHT + 3, "BakerReward".to_string(), String::new()),
+                (HEIGHT + 2, "FoundationReward".to_string(), String::new()),
+                (
+                    HEIGHT + 1,
+                    "FinalizationReward".to_string(),
+                    "Passive".to_string()
+                ),
+                (HEIGHT, "TransactionFeeReward".to_string(), "42".to_string()),
+                (HEIGHT, "BakerReward".to_string(), "42".to_string()),
+            ]
+        );
+        let record = rows[2].json_record().unwrap();
+        assert_eq!(record["rewardType"], "FinalizationReward");
+        assert_eq!(record["pool"], "Passive");
+        assert_eq!(record["blockHash"], format!("{:0>64x}", HEIGHT + 1));
+    }
+
+    #[tokio::test]
+    async fn test_account_balance_at_block_height_or_time() {
+        let Some(pool) = test_database::connect().await else {
+            return;
+        };
+        let mut tx = pool.begin().await.unwrap();
+        let start = Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap();
+        for offset in 0..2 {
+            test_database::insert_block(
+                tx.as_mut(),
+                HEIGHT + offset,
+                start + TimeDelta::days(offset),
+            )
+            .await
+            .unwrap();
+        }
+        test_database::insert_transaction(tx.as_mut(), TRANSACTION, HEIGHT)
+            .await
+            .unwrap();
+        let account = AccountAddress([0xe6; 32]);
+        test_database::insert_account(tx.as_mut(), ACCOUNT, &account, 3_000_000)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE accounts SET transaction_index = $1 WHERE index = $2")
+            .bind(TRANSACTION)
+            .bind(ACCOUNT)
+            .execute(tx.as_mut())
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO account_statements (
+                account_index,
+                entry_type,
+                amount,
+                account_balance,
+                block_height,
+                slot_time
+            )
+            VALUES ($1, 'TransferIn', 2000000, 3000000, $2, $3)",
+        )
+        .bind(ACCOUNT)
+        .bind(HEIGHT + 1)
+        .bind(start + TimeDelta::days(1))
+        .execute(tx.as_mut())
+        .await
+        .unwrap();
+
+        let params =
+            |block_height: Option<i64>, time: Option<DateTime<Utc>>, unit: Unit| AccountBalanceAt {
+                account_address: account,
+                block_height: block_height.map(|height| height.try_into().unwrap()),
+                time,
+                unit,
+            };
+        let cases = [
+            (params(Some(HEIGHT), None, Unit::MicroCcd), "1000000"),
+            (params(Some(HEIGHT + 1), None, Unit::MicroCcd), "3000000"),
+            (params(Some(HEIGHT + 1), None, Unit::Ccd), "3"),
+            (
+                params(None, Some(start + TimeDelta::hours(12)), Unit::MicroCcd),
+                "1000000",
+            ),
+            (
+                params(None, Some(start + TimeDelta::days(2)), Unit::MicroCcd),
+                "3000000",
+            ),
+        ];
+        for (params, expected) in cases {
+            assert_eq!(params.query(tx.as_mut()).await.unwrap(), expected);
+        }
+        assert!(matches!(
+            params(None, Some(start - TimeDelta::hours(1)), Unit::MicroCcd)
+                .query(tx.as_mut())
+                .await,
+            Err(ApiError::NotFound)
+        ));
+        assert!(matches!(
+            params(Some(HEIGHT + 2), None, Unit::MicroCcd)
+                .query(tx.as_mut())
+                .await,
+            Err(ApiError::NotFound)
+        ));
+        assert!(matches!(
+            params(Some(HEIGHT), Some(start), Unit::MicroCcd)
+                .query(tx.as_mut())
+                .await,
+            Err(ApiError::BlockHeightOrTime)
+        ));
+    }
+}
@@ -0,0 +1,29 @@
+//! Exponential backoff shared by the deliveries retried after failures, such
+//! as the webhook deliveries and the deliveries to the event sinks.
+
+use std::time::Duration;
+
+/// Delay before the retry following the provided number of successive failed
+/// attempts, starting from `initial_delay` and doubled for every further
+/// failure up to `max_delay`.
+pub(crate) fn retry_delay(initial_delay: Duration, max_delay: Duration, attempts: u32) -> Duration {
+    let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+    initial_delay.saturating_mul(factor).min(max_delay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::retry_delay;
+    use std::time::Duration;
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_max() {
+        let initial = Duration::from_secs(10);
+        let max = Duration::from_secs(60);
+        assert_eq!(retry_delay(initial, max, 1), Duration::from_secs(10));
+        assert_eq!(retry_delay(initial, max, 2), Duration::from_secs(20));
+        assert_eq!(retry_delay(initial, max, 3), Duration::from_secs(40));
+        assert_eq!(retry_delay(initial, max, 4), Duration::from_secs(60));
+        assert_eq!(retry_delay(initial, max, 100), Duration::from_secs(60));
+    }
+}
@@ -4,19 +4,22 @@ use std::sync::Arc;
 use tokio::net::TcpListener;
 use tokio_util::sync::CancellationToken;
 
-/// Run server exposing the Prometheus metrics and health endpoint.
+/// Run server exposing the Prometheus metrics and health endpoint, along with
+/// the routes for administrating the service.
 pub async fn serve(
     registry: Registry,
     tcp_listener: TcpListener,
     stop_signal: CancellationToken,
     health_routes: Router,
+    admin_routes: Router,
 ) -> anyhow::Result<()> {
     let metric_routes = Router::new()
         .route("/", get(metrics))
         .with_state(Arc::new(registry));
     let app = Router::new()
         .nest("/metrics", metric_routes)
-        .nest("/health", health_routes);
+        .nest("/health", health_routes)
+        .merge(admin_routes);
     axum::serve(tcp_listener, app)
         .with_graceful_shutdown(stop_signal.cancelled_owned())
         .await?;
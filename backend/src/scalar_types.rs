@@ -247,7 +247,7 @@ impl TryFrom<&BigDecimal> for Decimal {
 }
 
 /// The `TimeSpan` scalar represents an ISO-8601 compliant duration type.
-#[derive(serde::Serialize, serde::Deserialize, Clone)]
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
 #[repr(transparent)]
 #[serde(try_from = "String", into = "String")]
 pub struct TimeSpan(pub chrono::Duration);
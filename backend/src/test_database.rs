@@ -0,0 +1,99 @@
+//! Database used by the tests reading and writing the database.
+//!
+//! The tests connect to the database provided by `CCDSCAN_TEST_DATABASE_URL`,
+//! which must be migrated to the latest database schema version, such as by
+//! running `ccdscan-indexer --migrate`. The tests are skipped when the
+//! database is not provided. Tests of the indexed data insert their rows
+//! using the helpers below, within a database transaction which is never
+//! committed.
+
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::id::types::AccountAddress;
+use sqlx::{PgConnection, PgPool};
+
+/// Connect to the test database, `None` when not provided.
+pub async fn connect() -> Option<PgPool> {
+    let Ok(database_url) = std::env::var("CCDSCAN_TEST_DATABASE_URL") else {
+        eprintln!("Skipping test using the database, as CCDSCAN_TEST_DATABASE_URL is not set");
+        return None;
+    };
+    let pool = PgPool::connect(&database_url)
+        .await
+        .expect("Failed to connect to the test database");
+    let mut connection = pool
+        .acquire()
+        .await
+        .expect("Failed to acquire a connection");
+    crate::migrations::ensure_latest_schema_version(&mut connection)
+        .await
+        .expect("The test database must be migrated to the latest schema version");
+    Some(pool)
+}
+
+/// Insert a block at the height, with the slot time and no transactions.
+pub async fn insert_block(
+    connection: &mut PgConnection,
+    height: i64,
+    slot_time: DateTime<Utc>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO blocks (
+            height,
+            hash,
+            slot_time,
+            block_time,
+            total_amount,
+            total_staked,
+            cumulative_num_txs
+        ) VALUES ($1, LPAD(TO_HEX($1), 64, '0'), $2, 0, 0, 0, 0)",
+    )
+    .bind(height)
+    .bind(slot_time)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+/// Insert a successful account transaction into the block at the height.
+pub async fn insert_transaction(
+    connection: &mut PgConnection,
+    index: i64,
+    block_height: i64,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO transactions (
+            index,
+            block_height,
+            hash,
+            ccd_cost,
+            energy_cost,
+            type,
+            success
+        ) VALUES ($1, $2, LPAD(TO_HEX($1), 64, 'f'), 0, 0, 'Account', true)",
+    )
+    .bind(index)
+    .bind(block_height)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
+
+/// Insert an account with the balance.
+pub async fn insert_account(
+    connection: &mut PgConnection,
+    index: i64,
+    address: &AccountAddress,
+    amount: i64,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO accounts (index, address, canonical_address, amount)
+        VALUES ($1, $2, $3, $4)",
+    )
+    .bind(index)
+    .bind(address.to_string())
+    .bind(address.get_canonical_address().0.as_slice())
+    .bind(amount)
+    .execute(connection)
+    .await?;
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::{
-    address::Address as ScalarAddress,
+    address::{Address as ScalarAddress, ContractAddress},
     decoded_text::DecodedText,
     graphql_api::{ApiResult, InternalError},
     scalar_types::{BigInteger, Byte, DateTime, UnsignedLong},
@@ -77,6 +77,20 @@ pub enum Event {
     TokenCreationDetails(protocol_level_tokens::TokenCreationDetails),
 }
 
+impl Event {
+    /// The smart contract instance this event is about, if any.
+    pub fn contract_address(&self) -> Option<ContractAddress> {
+        match self {
+            Event::ContractInitialized(event) => Some(event.contract_address),
+            Event::ContractUpdated(event) => Some(event.contract_address),
+            Event::ContractInterrupted(event) => Some(event.contract_address),
+            Event::ContractResumed(event) => Some(event.contract_address),
+            Event::ContractUpgraded(event) => Some(event.contract_address),
+            _ => None,
+        }
+    }
+}
+
 #[derive(SimpleObject, serde::Serialize, serde::Deserialize)]
 #[graphql(complex)]
 pub struct DataRegistered {
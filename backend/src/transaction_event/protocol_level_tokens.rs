@@ -534,6 +534,18 @@ impl TokenUpdate {
         let prepared: PreparedTokenUpdate = self.clone().try_into()?;
         prepared.save(tx, transaction_index, slot_time).await
     }
+
+    /// Rebuild the event when re-indexing, see [`PreparedTokenUpdate::reindex`].
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+        slot_time: chrono::DateTime<chrono::Utc>,
+        id: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let prepared: PreparedTokenUpdate = self.clone().try_into()?;
+        prepared.reindex(tx, transaction_index, slot_time, id).await
+    }
 }
 
 impl TryFrom<TokenUpdate> for PreparedTokenUpdate {
@@ -610,37 +622,7 @@ impl PreparedTokenUpdate {
         transaction_index: i64,
         slot_time: chrono::DateTime<chrono::Utc>,
     ) -> anyhow::Result<()> {
-        let token_event: serde_json::Value =
-            serde_json::to_value(&self.event).unwrap_or(serde_json::Value::Null);
-
-        sqlx::query!(
-            "
-            INSERT INTO plt_events (
-                id,
-                transaction_index,
-                event_type,
-                token_module_type,
-                token_index,
-                token_event
-            )
-            VALUES (
-                (SELECT COALESCE(MAX(id) + 1, 0) FROM plt_events),
-                $1,
-                 $2,
-                 $3,
-                (SELECT index FROM plt_tokens WHERE token_id = $4),
-                $5
-              
-                )
-            ",
-            transaction_index,
-            self.event_type as TokenUpdateEventType,
-            self.token_module_type as Option<TokenUpdateModuleType>,
-            self.token_id,
-            token_event
-        )
-        .execute(tx.as_mut())
-        .await?;
+        self.insert_plt_event(tx, transaction_index, None).await?;
 
         // Update cumulative event count metrics (metrics_plt table)
         self.update_metrics_plt_cumulative_event_count(tx, slot_time)
@@ -910,6 +892,111 @@ impl PreparedTokenUpdate {
         Ok(())
     }
 
+    /// Rebuild the event, the balances of the accounts, the minted and burned
+    /// totals of the token and the account statements when re-indexing. The
+    /// event reuses the provided identifier of a cleared event if any.
+    /// Cumulative metrics and the paused state of the token are left untouched,
+    /// since these cannot be reverted for a single event.
+    pub async fn reindex(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+        slot_time: chrono::DateTime<chrono::Utc>,
+        id: Option<i64>,
+    ) -> anyhow::Result<()> {
+        self.insert_plt_event(tx, transaction_index, id).await?;
+        let decimals = match &self.event {
+            TokenEventDetails::Mint(e) => &e.amount.decimals,
+            TokenEventDetails::Burn(e) => &e.amount.decimals,
+            TokenEventDetails::Transfer(e) => &e.amount.decimals,
+            TokenEventDetails::Module(_) => return Ok(()),
+        };
+        let decimals = decimals
+            .parse::<i32>()
+            .context("Failed to parse decimal places for token event")?;
+        // Account statements to insert, in the same order as when saving the event.
+        let statements = match (self.event_type, &self.target, &self.from, &self.to) {
+            (TokenUpdateEventType::Mint, Some(target), _, _) => {
+                self.update_total_minted(tx).await?;
+                self.update_account_balance(tx, target, &self.plt_amount_change)
+                    .await?;
+                vec![(target, "Mint", self.plt_amount_change.clone())]
+            }
+            (TokenUpdateEventType::Burn, Some(target), _, _) => {
+                self.update_total_burned(tx).await?;
+                self.update_account_balance(tx, target, &(-&self.plt_amount_change))
+                    .await?;
+                vec![(target, "Burn", -&self.plt_amount_change)]
+            }
+            (TokenUpdateEventType::Transfer, _, Some(from), Some(to)) => {
+                self.update_transfer_balances(tx, from, to, &self.plt_amount_change)
+                    .await?;
+                vec![
+                    (from, "TransferOut", -&self.plt_amount_change),
+                    (to, "TransferIn", self.plt_amount_change.clone()),
+                ]
+            }
+            _ => Vec::new(),
+        };
+        for (account_address, entry_type, amount) in statements {
+            self.insert_plt_account_statement(
+                tx,
+                PltAccountStatementParams {
+                    transaction_index,
+                    slot_time,
+                    account_address,
+                    entry_type,
+                    amount: &amount,
+                    decimals,
+                },
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Insert the event into the PLT events, using the provided identifier or
+    /// otherwise the next identifier.
+    async fn insert_plt_event(
+        &self,
+        tx: &mut sqlx::PgTransaction<'_>,
+        transaction_index: i64,
+        id: Option<i64>,
+    ) -> anyhow::Result<()> {
+        let token_event: serde_json::Value =
+            serde_json::to_value(&self.event).unwrap_or(serde_json::Value::Null);
+
+        sqlx::query!(
+            "
+            INSERT INTO plt_events (
+                id,
+                transaction_index,
+                event_type,
+                token_module_type,
+                token_index,
+                token_event
+            )
+            VALUES (
+                COALESCE($6, (SELECT COALESCE(MAX(id) + 1, 0) FROM plt_events)),
+                $1,
+                $2,
+                $3,
+                (SELECT index FROM plt_tokens WHERE token_id = $4),
+                $5
+            )
+            ",
+            transaction_index,
+            self.event_type as TokenUpdateEventType,
+            self.token_module_type as Option<TokenUpdateModuleType>,
+            self.token_id,
+            token_event,
+            id
+        )
+        .execute(tx.as_mut())
+        .await?;
+        Ok(())
+    }
+
     async fn plt_amount_accross_tokens_by_account(
         &self,
         tx: &mut sqlx::PgTransaction<'_>,
@@ -12,6 +12,7 @@ use concordium_rust_sdk::base::{
     smart_contracts::ReceiveName,
 };
 use serde::Serialize;
+use sqlx::PgPool;
 
 #[derive(Enum, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum ContractVersion {
@@ -136,47 +137,16 @@ impl ContractInitialized {
         &self,
         ctx: &Context<'a>,
     ) -> ApiResult<connection::Connection<String, String>> {
-        let pool = get_pool(ctx)?;
-
-        let row = sqlx::query!(
-            "
-            SELECT
-                name as contract_name,
-                schema as display_schema
-            FROM contracts
-            JOIN smart_contract_modules ON smart_contract_modules.module_reference = \
-             contracts.module_reference
-            WHERE index = $1 AND sub_index = $2
-            ",
-            self.contract_address.index.0 as i64,
-            self.contract_address.sub_index.0 as i64
+        let decoded_logs = decode_contract_logs(
+            get_pool(ctx)?,
+            self.contract_address,
+            &self.contract_logs_raw,
         )
-        .fetch_optional(pool)
-        .await?
-        .ok_or(ApiError::NotFound)?;
-
-        // Get the event schema if it exists.
-        let opt_event_schema = if let Some(schema) = row.display_schema.as_ref() {
-            let versioned_schema = VersionedModuleSchema::new(schema, &None).map_err(|_| {
-                InternalError::InternalError(
-                    "Database bytes should be a valid VersionedModuleSchema".to_string(),
-                )
-            })?;
-
-            versioned_schema.get_event_schema(&row.contract_name).ok()
-        } else {
-            None
-        };
+        .await?;
 
         let mut connection = connection::Connection::new(true, true);
 
-        for (index, log) in self.contract_logs_raw.iter().enumerate() {
-            let decoded_log = decode_value_with_schema(
-                opt_event_schema.as_ref(),
-                log,
-                SmartContractSchemaNames::Event,
-            )?;
-
+        for (index, decoded_log) in decoded_logs.into_iter().enumerate() {
             connection
                 .edges
                 .push(connection::Edge::new(index.to_string(), decoded_log));
@@ -279,47 +249,16 @@ impl ContractUpdated {
         &self,
         ctx: &Context<'a>,
     ) -> ApiResult<connection::Connection<String, String>> {
-        let pool = get_pool(ctx)?;
-
-        let row = sqlx::query!(
-            "
-            SELECT
-                name as contract_name,
-                schema as display_schema
-            FROM contracts
-            JOIN smart_contract_modules ON smart_contract_modules.module_reference = \
-             contracts.module_reference
-            WHERE index = $1 AND sub_index = $2
-            ",
-            self.contract_address.index.0 as i64,
-            self.contract_address.sub_index.0 as i64
+        let decoded_logs = decode_contract_logs(
+            get_pool(ctx)?,
+            self.contract_address,
+            &self.contract_logs_raw,
         )
-        .fetch_optional(pool)
-        .await?
-        .ok_or(ApiError::NotFound)?;
-
-        // Get the event schema if it exists.
-        let opt_event_schema = if let Some(schema) = row.display_schema.as_ref() {
-            let versioned_schema = VersionedModuleSchema::new(schema, &None).map_err(|_| {
-                InternalError::InternalError(
-                    "Database bytes should be a valid VersionedModuleSchema".to_string(),
-                )
-            })?;
-
-            versioned_schema.get_event_schema(&row.contract_name).ok()
-        } else {
-            None
-        };
+        .await?;
 
         let mut connection = connection::Connection::new(true, true);
 
-        for (index, log) in self.contract_logs_raw.iter().enumerate() {
-            let decoded_log = decode_value_with_schema(
-                opt_event_schema.as_ref(),
-                log,
-                SmartContractSchemaNames::Event,
-            )?;
-
+        for (index, decoded_log) in decoded_logs.into_iter().enumerate() {
             connection
                 .edges
                 .push(connection::Edge::new(index.to_string(), decoded_log));
@@ -367,47 +306,16 @@ impl ContractInterrupted {
         &self,
         ctx: &Context<'a>,
     ) -> ApiResult<connection::Connection<String, String>> {
-        let pool = get_pool(ctx)?;
-
-        let row = sqlx::query!(
-            "
-            SELECT
-                name as contract_name,
-                schema as display_schema
-            FROM contracts
-            JOIN smart_contract_modules ON smart_contract_modules.module_reference = \
-             contracts.module_reference
-            WHERE index = $1 AND sub_index = $2
-            ",
-            self.contract_address.index.0 as i64,
-            self.contract_address.sub_index.0 as i64
+        let decoded_logs = decode_contract_logs(
+            get_pool(ctx)?,
+            self.contract_address,
+            &self.contract_logs_raw,
         )
-        .fetch_optional(pool)
-        .await?
-        .ok_or(ApiError::NotFound)?;
-
-        // Get the event schema if it exists.
-        let opt_event_schema = if let Some(schema) = row.display_schema.as_ref() {
-            let versioned_schema = VersionedModuleSchema::new(schema, &None).map_err(|_| {
-                InternalError::InternalError(
-                    "Database bytes should be a valid VersionedModuleSchema".to_string(),
-                )
-            })?;
-
-            versioned_schema.get_event_schema(&row.contract_name).ok()
-        } else {
-            None
-        };
+        .await?;
 
         let mut connection = connection::Connection::new(true, true);
 
-        for (index, log) in self.contract_logs_raw.iter().enumerate() {
-            let decoded_log = decode_value_with_schema(
-                opt_event_schema.as_ref(),
-                log,
-                SmartContractSchemaNames::Event,
-            )?;
-
+        for (index, decoded_log) in decoded_logs.into_iter().enumerate() {
             connection
                 .edges
                 .push(connection::Edge::new(index.to_string(), decoded_log));
@@ -488,6 +396,55 @@ struct SchemaDecodingError {
     error: String,
 }
 
+/// Decode the events logged by a smart contract instance into JSON, using the
+/// event schema embedded in the module of the instance.
+pub(crate) async fn decode_contract_logs(
+    pool: &PgPool,
+    contract_address: ContractAddress,
+    contract_logs_raw: &[Vec<u8>],
+) -> ApiResult<Vec<String>> {
+    let row = sqlx::query!(
+        "
+            SELECT
+                name as contract_name,
+                schema as display_schema
+            FROM contracts
+            JOIN smart_contract_modules ON smart_contract_modules.module_reference = \
+             contracts.module_reference
+            WHERE index = $1 AND sub_index = $2
+            ",
+        contract_address.index.0 as i64,
+        contract_address.sub_index.0 as i64
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or(ApiError::NotFound)?;
+
+    // Get the event schema if it exists.
+    let opt_event_schema = if let Some(schema) = row.display_schema.as_ref() {
+        let versioned_schema = VersionedModuleSchema::new(schema, &None).map_err(|_| {
+            InternalError::InternalError(
+                "Database bytes should be a valid VersionedModuleSchema".to_string(),
+            )
+        })?;
+
+        versioned_schema.get_event_schema(&row.contract_name).ok()
+    } else {
+        None
+    };
+
+    contract_logs_raw
+        .iter()
+        .map(|log| {
+            decode_value_with_schema(
+                opt_event_schema.as_ref(),
+                log,
+                SmartContractSchemaNames::Event,
+            )
+        })
+        .collect()
+}
+
 fn decode_value_with_schema(
     opt_schema: Option<&Type>,
     value: &[u8],
@@ -75,6 +75,19 @@ pub enum TransactionRejectReason {
     UnauthorizedTokenGovernance(UnauthorizedTokenGovernance),
 }
 
+impl TransactionRejectReason {
+    /// The smart contract instance causing the rejection, if any.
+    pub fn contract_address(&self) -> Option<ContractAddress> {
+        match self {
+            TransactionRejectReason::InvalidContractAddress(reason) => {
+                Some(reason.contract_address)
+            }
+            TransactionRejectReason::RejectedReceive(reason) => Some(reason.contract_address),
+            _ => None,
+        }
+    }
+}
+
 #[derive(SimpleObject, serde::Serialize, serde::Deserialize, Clone, Copy)]
 pub struct ModuleNotWf {
     #[graphql(
@@ -0,0 +1,1020 @@
+//! Module containing the delivery of webhooks, notifying HTTP endpoints about
+//! transactions affecting an account or events emitted by a smart contract
+//! instance.
+//!
+//! Webhooks are registered using the admin API, served on the monitoring
+//! address, and stored in the `webhooks` table. The blocks indexed are
+//! processed in order, tracked by the cursor in `webhook_cursor`, where the
+//! transactions and contract events of a block are stored as deliveries in
+//! `webhook_deliveries` in the same database transaction as moving the cursor
+//! past the block. Deliveries are sent as POST requests with a JSON body.
+//!
+//! The blocks are processed when the `block_added` notification received by
+//! the listener of the GraphQL subscriptions wakes up the service, and
+//! otherwise polled periodically. The notifications alone are not enough, as
+//! they are lost while the API service is not running or the listener is
+//! reconnecting, and every instance of the API service receives them, while
+//! the cursor ensures the deliveries of every block are added exactly once.
+//! Failed attempts are retried with exponential backoff and every attempt is
+//! recorded in `webhook_delivery_attempts`.
+//!
+//! Each request contains the headers:
+//! - `x-ccdscan-webhook-id`: The ID of the webhook.
+//! - `x-ccdscan-delivery-id`: The ID of the delivery, identical for every
+//!   attempt.
+//! - `x-ccdscan-timestamp`: The time of the attempt as Unix time in seconds.
+//! - `x-ccdscan-signature`: `sha256=` followed by the hex encoded HMAC-SHA256
+//!   of `<timestamp>.<body>` using the secret of the webhook.
+
+use crate::{graphql_api::contract::ContractEventsSubscriptionItem, retry::retry_delay};
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::{header, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use concordium_rust_sdk::id::types::AccountAddress;
+use hmac::{Hmac, Mac};
+use prometheus_client::{
+    encoding::EncodeLabelSet,
+    metrics::{counter::Counter, family::Family},
+    registry::Registry,
+};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use sqlx::{PgConnection, PgPool};
+use std::{num::NonZeroU32, sync::Arc, time::Duration};
+use tokio::sync::{broadcast, Notify};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info};
+
+/// Header containing the ID of the webhook.
+const WEBHOOK_ID_HEADER: &str = "x-ccdscan-webhook-id";
+/// Header containing the ID of the delivery.
+const DELIVERY_ID_HEADER: &str = "x-ccdscan-delivery-id";
+/// Header containing the time of the attempt as Unix time in seconds.
+const TIMESTAMP_HEADER: &str = "x-ccdscan-timestamp";
+/// Header containing the signature of the timestamp and body.
+const SIGNATURE_HEADER: &str = "x-ccdscan-signature";
+
+/// Interval for checking for blocks added and deliveries which are due, in
+/// addition to being woken up when blocks or deliveries are added.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// The most blocks for which deliveries are added in a single database
+/// transaction.
+const BLOCK_BATCH_SIZE: i64 = 100;
+/// The most deliveries attempted at once.
+const DELIVERY_BATCH_SIZE: i64 = 32;
+/// Time in addition to the request timeout before a delivery claimed by an
+/// instance of the service can be claimed by another instance.
+const DELIVERY_LEASE_MARGIN: Duration = Duration::from_secs(30);
+/// The most deliveries returned when listing the deliveries of a webhook.
+const DELIVERIES_LIMIT: i64 = 100;
+
+/// Configuration of the delivery of webhooks.
+#[derive(Debug, clap::Args)]
+pub struct WebhookConfig {
+    /// Bearer token required by the admin API of webhooks, served at
+    /// `/admin/webhooks` on the monitoring address. Webhooks are disabled when
+    /// not provided.
+    #[arg(long, env = "CCDSCAN_API_WEBHOOK_ADMIN_TOKEN", hide_env_values = true)]
+    webhook_admin_token: Option<String>,
+    /// The number of attempts of delivering an event to a webhook, before the
+    /// delivery is marked as failed.
+    #[arg(long, env = "CCDSCAN_API_WEBHOOK_MAX_ATTEMPTS", default_value = "8")]
+    webhook_max_attempts: NonZeroU32,
+    /// Delay in seconds before the first retry of a delivery, doubled for
+    /// every following retry.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_WEBHOOK_RETRY_INITIAL_DELAY_SECS",
+        default_value_t = 10
+    )]
+    webhook_retry_initial_delay_secs: u64,
+    /// The maximum delay in seconds between retries of a delivery.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_WEBHOOK_RETRY_MAX_DELAY_SECS",
+        default_value_t = 3600
+    )]
+    webhook_retry_max_delay_secs: u64,
+    /// Timeout in seconds of the requests delivering events.
+    #[arg(
+        long,
+        env = "CCDSCAN_API_WEBHOOK_REQUEST_TIMEOUT_SECS",
+        default_value_t = 10
+    )]
+    webhook_request_timeout_secs: u64,
+}
+
+/// Service adding and sending deliveries of webhooks.
+pub struct Service {
+    /// State shared with the admin API.
+    state: Arc<ServiceState>,
+    cancellation_token: CancellationToken,
+}
+
+/// State shared between the delivery of webhooks and the admin API.
+struct ServiceState {
+    pool: PgPool,
+    /// Client used for sending the deliveries.
+    client: Client,
+    /// Bearer token required by the admin API.
+    admin_token: String,
+    /// The number of attempts before a delivery is marked as failed.
+    max_attempts: u32,
+    /// Delay before the first retry of a delivery.
+    retry_initial_delay: Duration,
+    /// The maximum delay between retries of a delivery.
+    retry_max_delay: Duration,
+    /// Time before a claimed delivery can be claimed again.
+    lease: Duration,
+    /// Notified when deliveries are added or replayed.
+    deliveries_added: Notify,
+    /// Metric counting attempts of delivering events.
+    attempts: Family<AttemptLabels, Counter>,
+}
+
+/// Type representing the Prometheus labels used for counting attempts of
+/// delivering events.
+#[derive(Debug, Clone, EncodeLabelSet, PartialEq, Eq, Hash)]
+struct AttemptLabels {
+    /// Whether the endpoint responded with a successful status code.
+    delivered: bool,
+}
+
+impl Service {
+    /// Construct the service, `None` when webhooks are disabled.
+    pub fn new(
+        config: &WebhookConfig,
+        pool: PgPool,
+        cancellation_token: CancellationToken,
+        registry: &mut Registry,
+    ) -> anyhow::Result<Option<Self>> {
+        let Some(admin_token) = config.webhook_admin_token.clone() else {
+            return Ok(None);
+        };
+        let request_timeout = Duration::from_secs(config.webhook_request_timeout_secs);
+        let client = Client::builder().timeout(request_timeout).build()?;
+        let attempts: Family<AttemptLabels, Counter> = Default::default();
+        registry.register(
+            "webhook_delivery_attempts",
+            "Total number of attempts of delivering events to webhooks",
+            attempts.clone(),
+        );
+        Ok(Some(Self {
+            state: Arc::new(ServiceState {
+                pool,
+                client,
+                admin_token,
+                max_attempts: config.webhook_max_attempts.get(),
+                retry_initial_delay: Duration::from_secs(config.webhook_retry_initial_delay_secs),
+                retry_max_delay: Duration::from_secs(config.webhook_retry_max_delay_secs),
+                lease: request_timeout + DELIVERY_LEASE_MARGIN,
+                deliveries_added: Notify::new(),
+                attempts,
+            }),
+            cancellation_token,
+        }))
+    }
+
+    /// Router for the admin API of webhooks.
+    pub fn admin_router(&self) -> Router {
+        let state = Arc::clone(&self.state);
+        Router::new()
+            .route("/admin/webhooks", get(list_webhooks).post(register_webhook))
+            .route("/admin/webhooks/{id}", delete(delete_webhook))
+            .route("/admin/webhooks/{id}/deliveries", get(list_deliveries))
+            .route(
+                "/admin/webhooks/{id}/deliveries/replay",
+                post(replay_failed_deliveries),
+            )
+            .route("/admin/webhook-deliveries/{id}", get(get_delivery))
+            .route(
+                "/admin/webhook-deliveries/{id}/replay",
+                post(replay_delivery),
+            )
+            .layer(middleware::from_fn_with_state(
+                Arc::clone(&state),
+                authorize,
+            ))
+            .with_state(state)
+    }
+
+    /// Add deliveries for the transactions and contract events of the blocks
+    /// added, woken up by `blocks_added`, and send the deliveries until
+    /// cancelled.
+    pub async fn serve<B: Clone>(self, blocks_added: broadcast::Receiver<B>) -> anyhow::Result<()> {
+        let Self {
+            state,
+            cancellation_token,
+        } = self;
+        let add_deliveries = async {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            let mut blocks_added = Some(blocks_added);
+            loop {
+                let closed = tokio::select! {
+                    _ = interval.tick() => false,
+                    result = async {
+                        match blocks_added.as_mut() {
+                            Some(receiver) => receiver.recv().await,
+                            None => std::future::pending().await,
+                        }
+                    } => matches!(result, Err(broadcast::error::RecvError::Closed)),
+                };
+                if closed {
+                    // Fall back to polling only.
+                    blocks_added = None;
+                }
+                // Keep adding while the cursor is behind the last block.
+                loop {
+                    match state.add_block_deliveries().await {
+                        Ok((added, caught_up)) => {
+                            if added > 0 {
+                                state.deliveries_added.notify_one();
+                            }
+                            if caught_up {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            error!("Failed to add webhook deliveries: {}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+        };
+        let send_deliveries = async {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => {}
+                    _ = state.deliveries_added.notified() => {}
+                }
+                // Keep sending while full batches are due.
+                loop {
+                    match state.send_due_deliveries().await {
+                        Ok(count) if count == DELIVERY_BATCH_SIZE as usize => {}
+                        Ok(_) => break,
+                        Err(err) => {
+                            error!("Failed to send webhook deliveries: {}", err);
+                            break;
+                        }
+                    }
+                }
+            }
+        };
+        tokio::select! {
+            _ = add_deliveries => {}
+            _ = send_deliveries => {}
+            _ = cancellation_token.cancelled() => {
+                info!("Cancellation token triggered. Shutting down gracefully.");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ServiceState {
+    /// Add the deliveries of the blocks following the cursor, at most
+    /// `BLOCK_BATCH_SIZE` blocks, and move the cursor past these blocks.
+    /// Returns the number of deliveries added and whether the cursor reached
+    /// the last block.
+    async fn add_block_deliveries(&self) -> anyhow::Result<(u64, bool)> {
+        let mut tx = self.pool.begin().await?;
+        // Locking the cursor ensures the deliveries of a block are only added by one
+        // instance of the service.
+        let from_height =
+            sqlx::query_scalar!("SELECT last_block_height FROM webhook_cursor FOR UPDATE")
+                .fetch_one(tx.as_mut())
+                .await?;
+        let Some(last_height) = sqlx::query_scalar!("SELECT MAX(height) FROM blocks")
+            .fetch_one(tx.as_mut())
+            .await?
+        else {
+            return Ok((0, true));
+        };
+        let has_webhooks =
+            sqlx::query_scalar!(r#"SELECT EXISTS(SELECT FROM webhooks) as "exists!""#)
+                .fetch_one(tx.as_mut())
+                .await?;
+        // Without webhooks there is nothing to deliver, so the cursor moves directly to
+        // the last block.
+        let to_height = if has_webhooks {
+            last_height.min(from_height + BLOCK_BATCH_SIZE)
+        } else {
+            last_height
+        };
+        if to_height <= from_height {
+            return Ok((0, true));
+        }
+
+        let mut added = add_transaction_deliveries(tx.as_mut(), from_height, to_height).await?;
+        let contract_event_indices = sqlx::query_scalar!(
+            "SELECT DISTINCT contract_events.index
+            FROM contract_events
+                JOIN webhooks
+                    ON webhooks.contract_index = contract_events.contract_index
+                    AND webhooks.contract_sub_index = contract_events.contract_sub_index
+            WHERE contract_events.block_height > $1 AND contract_events.block_height <= $2
+            ORDER BY contract_events.index",
+            from_height,
+            to_height
+        )
+        .fetch_all(tx.as_mut())
+        .await?;
+        for index in contract_event_indices {
+            if let Some(item) =
+                ContractEventsSubscriptionItem::query_by_index(&self.pool, index).await?
+            {
+                added += add_contract_event_deliveries(tx.as_mut(), &item).await?;
+            }
+        }
+        sqlx::query!(
+            "UPDATE webhook_cursor SET last_block_height = $1",
+            to_height
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        Ok((added, to_height == last_height))
+    }
+
+    /// Claim and attempt the pending deliveries which are due. Claimed
+    /// deliveries are postponed by the lease, preventing other instances of
+    /// the service from attempting them at the same time.
+    /// Returns the number of deliveries attempted.
+    async fn send_due_deliveries(&self) -> anyhow::Result<usize> {
+        let deliveries = sqlx::query_as!(
+            Delivery,
+            r#"WITH claimed AS (
+                UPDATE webhook_deliveries
+                SET next_attempt_at = NOW() + make_interval(secs => $2)
+                WHERE id IN (
+                    SELECT id
+                    FROM webhook_deliveries
+                    WHERE status = 'Pending' AND next_attempt_at <= NOW()
+                    ORDER BY next_attempt_at
+                    LIMIT $1
+                    FOR UPDATE SKIP LOCKED
+                )
+                RETURNING id, webhook_id, payload, attempts
+            )
+            SELECT
+                claimed.id as "id!",
+                claimed.webhook_id as "webhook_id!",
+                claimed.payload as "payload!",
+                claimed.attempts as "attempts!",
+                webhooks.url,
+                webhooks.secret
+            FROM claimed
+                JOIN webhooks ON webhooks.id = claimed.webhook_id"#,
+            DELIVERY_BATCH_SIZE,
+            self.lease.as_secs_f64()
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let count = deliveries.len();
+        let results = futures::future::join_all(
+            deliveries
+                .into_iter()
+                .map(|delivery| self.attempt_delivery(delivery)),
+        )
+        .await;
+        for result in results {
+            if let Err(err) = result {
+                error!("Failed to record webhook delivery attempt: {}", err);
+            }
+        }
+        Ok(count)
+    }
+
+    /// Send the delivery and record the attempt, scheduling a retry when the
+    /// attempt failed.
+    async fn attempt_delivery(&self, delivery: Delivery) -> anyhow::Result<()> {
+        let attempted_at = Utc::now();
+        let (status_code, error) =
+            send_delivery(&self.client, &delivery, attempted_at.timestamp()).await;
+        let delivered = error.is_none();
+        let attempts = delivery.attempts.saturating_add(1);
+        let (status, next_attempt_at) = if delivered {
+            (DeliveryStatus::Delivered, attempted_at)
+        } else if u32::try_from(attempts).unwrap_or(u32::MAX) >= self.max_attempts {
+            (DeliveryStatus::Failed, attempted_at)
+        } else {
+            let delay = retry_delay(
+                self.retry_initial_delay,
+                self.retry_max_delay,
+                u32::try_from(attempts).unwrap_or(u32::MAX),
+            );
+            (DeliveryStatus::Pending, attempted_at + delay)
+        };
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query!(
+            "INSERT INTO webhook_delivery_attempts (delivery_id, attempted_at, status_code, error)
+            VALUES ($1, $2, $3, $4)",
+            delivery.id,
+            attempted_at,
+            status_code.map(|status_code| i32::from(status_code.as_u16())),
+            error
+        )
+        .execute(tx.as_mut())
+        .await?;
+        sqlx::query!(
+            "UPDATE webhook_deliveries
+            SET status = $2, attempts = $3, next_attempt_at = $4
+            WHERE id = $1",
+            delivery.id,
+            status as DeliveryStatus,
+            attempts,
+            next_attempt_at
+        )
+        .execute(tx.as_mut())
+        .await?;
+        tx.commit().await?;
+        self.attempts
+            .get_or_create(&AttemptLabels { delivered })
+            .inc();
+        Ok(())
+    }
+}
+
+/// Add a delivery of every transaction in the blocks after `from_height` up to
+/// and including `to_height` for every webhook of an affected account.
+/// Returns the number of deliveries added.
+async fn add_transaction_deliveries(
+    connection: &mut PgConnection,
+    from_height: i64,
+    to_height: i64,
+) -> anyhow::Result<u64> {
+    let result = sqlx::query!(
+        "INSERT INTO webhook_deliveries (webhook_id, event_key, payload)
+        SELECT
+            webhooks.id,
+            'transaction:' || transactions.index,
+            jsonb_build_object(
+                'type', 'TransactionAdded',
+                'transaction', jsonb_build_object(
+                    'index', transactions.index,
+                    'hash', transactions.hash,
+                    'blockHeight', transactions.block_height,
+                    'success', transactions.success
+                )
+            )
+        FROM transactions
+            JOIN affected_accounts ON affected_accounts.transaction_index = transactions.index
+            JOIN accounts ON accounts.index = affected_accounts.account_index
+            JOIN webhooks ON webhooks.account_canonical_address = accounts.canonical_address
+        WHERE transactions.block_height > $1 AND transactions.block_height <= $2
+        ON CONFLICT (webhook_id, event_key) DO NOTHING",
+        from_height,
+        to_height
+    )
+    .execute(connection)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Add a delivery of the contract event for every webhook of the smart
+/// contract instance. Returns the number of deliveries added.
+async fn add_contract_event_deliveries(
+    connection: &mut PgConnection,
+    item: &ContractEventsSubscriptionItem,
+) -> anyhow::Result<u64> {
+    let payload = json!({
+        "type": "ContractEventAdded",
+        "contractEvent": {
+            "contractAddress": {
+                "index": item.contract_address.index.0,
+                "subIndex": item.contract_address.sub_index.0,
+            },
+            "transactionHash": item.contract_event.transaction_hash,
+            "blockHeight": item.contract_event.block_height,
+            "receiveName": item.receive_name,
+            "decodedEvents": item.decoded_events,
+        },
+    });
+    let result = sqlx::query!(
+        "INSERT INTO webhook_deliveries (webhook_id, event_key, payload)
+        SELECT id, $3, $4
+        FROM webhooks
+        WHERE contract_index = $1 AND contract_sub_index = $2
+        ON CONFLICT (webhook_id, event_key) DO NOTHING",
+        i64::try_from(item.contract_address.index)?,
+        i64::try_from(item.contract_address.sub_index)?,
+        format!("contract_event:{}", item.index),
+        payload
+    )
+    .execute(connection)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Send the payload of a delivery. Returns the status code of the response
+/// when received and the reason for the attempt failing.
+async fn send_delivery(
+    client: &Client,
+    delivery: &Delivery,
+    timestamp: i64,
+) -> (Option<StatusCode>, Option<String>) {
+    let body = match serde_json::to_vec(&delivery.payload) {
+        Ok(body) => body,
+        Err(err) => return (None, Some(format!("Failed to serialize payload: {}", err))),
+    };
+    let result = client
+        .post(&delivery.url)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(WEBHOOK_ID_HEADER, delivery.webhook_id)
+        .header(DELIVERY_ID_HEADER, delivery.id)
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(
+            SIGNATURE_HEADER,
+            signature(&delivery.secret, timestamp, &body),
+        )
+        .body(body)
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status()), None),
+        Ok(response) => (
+            Some(response.status()),
+            Some(format!(
+                "Unsuccessful response status: {}",
+                response.status()
+            )),
+        ),
+        Err(err) => (err.status(), Some(err.to_string())),
+    }
+}
+
+/// Delivery claimed for an attempt.
+struct Delivery {
+    id: i64,
+    webhook_id: i64,
+    payload: serde_json::Value,
+    /// The number of attempts before this one.
+    attempts: i32,
+    url: String,
+    secret: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::Type, Serialize, Deserialize)]
+#[sqlx(type_name = "webhook_delivery_status")]
+enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+/// Compute the value of the signature header for a request.
+fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Compare the bytes in constant time, preventing the admin token from being
+/// guessed using the time of the comparison.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Middleware rejecting requests without the admin token.
+async fn authorize(
+    State(state): State<Arc<ServiceState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|token| constant_time_eq(token.as_bytes(), state.admin_token.as_bytes()));
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+/// Registered webhook, without its secret.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Webhook {
+    id: i64,
+    url: String,
+    account_address: Option<String>,
+    contract_index: Option<i64>,
+    contract_sub_index: Option<i64>,
+    created_at: DateTime<Utc>,
+}
+
+/// Body of the request registering a webhook, where exactly one of the account
+/// address and contract index must be provided.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RegisterWebhook {
+    url: String,
+    /// Deliver the transactions affecting the account.
+    account_address: Option<AccountAddress>,
+    /// Deliver the events of the smart contract instance.
+    contract_index: Option<u64>,
+    /// Sub index of the smart contract instance, defaults to 0.
+    contract_sub_index: Option<u64>,
+}
+
+/// Response of registering a webhook, the only response containing the
+/// secret.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegisteredWebhook {
+    id: i64,
+    /// Secret used for signing the requests.
+    secret: String,
+}
+
+async fn list_webhooks(State(state): State<Arc<ServiceState>>) -> AdminResult<Json<Vec<Webhook>>> {
+    let webhooks = sqlx::query_as!(
+        Webhook,
+        "SELECT id, url, account_address, contract_index, contract_sub_index, created_at
+        FROM webhooks
+        ORDER BY id"
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(webhooks))
+}
+
+async fn register_webhook(
+    State(state): State<Arc<ServiceState>>,
+    Json(body): Json<RegisterWebhook>,
+) -> AdminResult<(StatusCode, Json<RegisteredWebhook>)> {
+    let url = reqwest::Url::parse(&body.url).map_err(|_| AdminError::InvalidUrl(body.url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(AdminError::InvalidUrl(url.to_string()));
+    }
+    let (account_address, account_canonical_address, contract_index, contract_sub_index) = match (
+        body.account_address,
+        body.contract_index,
+        body.contract_sub_index,
+    ) {
+        (Some(address), None, None) => (
+            Some(address.to_string()),
+            Some(address.get_canonical_address().0.to_vec()),
+            None,
+            None,
+        ),
+        (None, Some(index), sub_index) => (
+            None,
+            None,
+            Some(i64::try_from(index)?),
+            Some(i64::try_from(sub_index.unwrap_or(0))?),
+        ),
+        _ => return Err(AdminError::InvalidTarget),
+    };
+    let secret = hex::encode(rand::random::<[u8; 32]>());
+    let id = sqlx::query_scalar!(
+        "INSERT INTO webhooks (
+            url,
+            secret,
+            account_address,
+            account_canonical_address,
+            contract_index,
+            contract_sub_index
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING id",
+        url.as_str(),
+        secret,
+        account_address,
+        account_canonical_address,
+        contract_index,
+        contract_sub_index
+    )
+    .fetch_one(&state.pool)
+    .await?;
+    Ok((StatusCode::CREATED, Json(RegisteredWebhook { id, secret })))
+}
+
+async fn delete_webhook(
+    State(state): State<Arc<ServiceState>>,
+    Path(id): Path<i64>,
+) -> AdminResult<StatusCode> {
+    let result = sqlx::query!("DELETE FROM webhooks WHERE id = $1", id)
+        .execute(&state.pool)
+        .await?;
+    if result.rows_affected() == 0 {
+        return Err(AdminError::NotFound);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delivery of an event to a webhook, including the latest attempt.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliverySummary {
+    id: i64,
+    webhook_id: i64,
+    event_key: String,
+    payload: serde_json::Value,
+    status: DeliveryStatus,
+    /// The number of attempts since the delivery was added or replayed.
+    attempts: i32,
+    next_attempt_at: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    last_attempted_at: Option<DateTime<Utc>>,
+    last_status_code: Option<i32>,
+    last_error: Option<String>,
+}
+
+/// Delivery of an event to a webhook, including every attempt.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryDetails {
+    #[serde(flatten)]
+    delivery: DeliverySummary,
+    attempt_history: Vec<DeliveryAttempt>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeliveryAttempt {
+    attempted_at: DateTime<Utc>,
+    /// HTTP status code of the response, `None` when no response was
+    /// received.
+    status_code: Option<i32>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListDeliveries {
+    /// Only list deliveries with this status, defaults to failed deliveries.
+    status: Option<DeliveryStatus>,
+    /// List deliveries with an ID below this, used for paging.
+    before: Option<i64>,
+}
+
+/// List the latest deliveries of a webhook with the provided status.
+async fn list_deliveries(
+    State(state): State<Arc<ServiceState>>,
+    Path(webhook_id): Path<i64>,
+    Query(params): Query<ListDeliveries>,
+) -> AdminResult<Json<Vec<DeliverySummary>>> {
+    let deliveries = sqlx::query_as!(
+        DeliverySummary,
+        r#"SELECT
+            webhook_deliveries.id,
+            webhook_id,
+            event_key,
+            payload,
+            status as "status: DeliveryStatus",
+            attempts,
+            next_attempt_at,
+            created_at,
+            last_attempt.attempted_at as "last_attempted_at?",
+            last_attempt.status_code as "last_status_code?",
+            last_attempt.error as "last_error?"
+        FROM webhook_deliveries
+            LEFT JOIN LATERAL (
+                SELECT attempted_at, status_code, error
+                FROM webhook_delivery_attempts
+                WHERE delivery_id = webhook_deliveries.id
+                ORDER BY id DESC
+                LIMIT 1
+            ) last_attempt ON true
+        WHERE webhook_id = $1
+            AND status = $2
+            AND webhook_deliveries.id < $3
+        ORDER BY webhook_deliveries.id DESC
+        LIMIT $4"#,
+        webhook_id,
+        params.status.unwrap_or(DeliveryStatus::Failed) as DeliveryStatus,
+        params.before.unwrap_or(i64::MAX),
+        DELIVERIES_LIMIT
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    Ok(Json(deliveries))
+}
+
+/// Get a delivery including every attempt.
+async fn get_delivery(
+    State(state): State<Arc<ServiceState>>,
+    Path(id): Path<i64>,
+) -> AdminResult<Json<DeliveryDetails>> {
+    let attempt_history = sqlx::query_as!(
+        DeliveryAttempt,
+        "SELECT attempted_at, status_code, error
+        FROM webhook_delivery_attempts
+        WHERE delivery_id = $1
+        ORDER BY id",
+        id
+    )
+    .fetch_all(&state.pool)
+    .await?;
+    let delivery = sqlx::query!(
+        r#"SELECT
+            id,
+            webhook_id,
+            event_key,
+            payload,
+            status as "status: DeliveryStatus",
+            attempts,
+            next_attempt_at,
+            created_at
+        FROM webhook_deliveries
+        WHERE id = $1"#,
+        id
+    )
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or(AdminError::NotFound)?;
+    let last_attempt = attempt_history.last();
+    let delivery = DeliverySummary {
+        id: delivery.id,
+        webhook_id: delivery.webhook_id,
+        event_key: delivery.event_key,
+        payload: delivery.payload,
+        status: delivery.status,
+        attempts: delivery.attempts,
+        next_attempt_at: delivery.next_attempt_at,
+        created_at: delivery.created_at,
+        last_attempted_at: last_attempt.map(|attempt| attempt.attempted_at),
+        last_status_code: last_attempt.and_then(|attempt| attempt.status_code),
+        last_error: last_attempt.and_then(|attempt| attempt.error.clone()),
+    };
+    Ok(Json(DeliveryDetails {
+        delivery,
+        attempt_history,
+    }))
+}
+
+/// Number of deliveries scheduled by a replay.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Replayed {
+    replayed: u64,
+}
+
+/// Schedule a failed delivery for immediate delivery, with a new set of
+/// attempts.
+async fn replay_delivery(
+    State(state): State<Arc<ServiceState>>,
+    Path(id): Path<i64>,
+) -> AdminResult<Json<Replayed>> {
+    let result = sqlx::query!(
+        "UPDATE webhook_deliveries
+        SET status = 'Pending', attempts = 0, next_attempt_at = NOW()
+        WHERE id = $1 AND status = 'Failed'",
+        id
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(AdminError::NotFound);
+    }
+    state.deliveries_added.notify_one();
+    Ok(Json(Replayed {
+        replayed: result.rows_affected(),
+    }))
+}
+
+/// Schedule every failed delivery of a webhook for immediate delivery, with a
+/// new set of attempts.
+async fn replay_failed_deliveries(
+    State(state): State<Arc<ServiceState>>,
+    Path(webhook_id): Path<i64>,
+) -> AdminResult<Json<Replayed>> {
+    let result = sqlx::query!(
+        "UPDATE webhook_deliveries
+        SET status = 'Pending', attempts = 0, next_attempt_at = NOW()
+        WHERE webhook_id = $1 AND status = 'Failed'",
+        webhook_id
+    )
+    .execute(&state.pool)
+    .await?;
+    if result.rows_affected() > 0 {
+        state.deliveries_added.notify_one();
+    }
+    Ok(Json(Replayed {
+        replayed: result.rows_affected(),
+    }))
+}
+
+#[derive(Debug, thiserror::Error)]
+enum AdminError {
+    #[error("Information was not found.")]
+    NotFound,
+    #[error("Invalid URL, only absolute http and https URLs are supported: {0}")]
+    InvalidUrl(String),
+    #[error("Exactly one of 'accountAddress' and 'contractIndex' must be provided.")]
+    InvalidTarget,
+    #[error("Internal error (FailedDatabaseQuery): {0}")]
+    FailedDatabaseQuery(#[from] sqlx::Error),
+    #[error("Invalid integer: {0}")]
+    InvalidInt(#[from] std::num::TryFromIntError),
+}
+
+impl IntoResponse for AdminError {
+    fn into_response(self) -> Response {
+        let status = match self {
+            AdminError::NotFound => StatusCode::NOT_FOUND,
+            AdminError::InvalidUrl(_) => StatusCode::BAD_REQUEST,
+            AdminError::InvalidTarget => StatusCode::BAD_REQUEST,
+            AdminError::FailedDatabaseQuery(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AdminError::InvalidInt(_) => StatusCode::BAD_REQUEST,
+        };
+        (status, self.to_string()).into_response()
+    }
+}
+
+type AdminResult<A> = Result<A, AdminError>;
+
+#[cfg(test)]
+mod tests {
+    use super::{send_delivery, signature, Delivery};
+    use hmac::{Hmac, Mac};
+    use serde_json::json;
+    use sha2::Sha256;
+
+    fn delivery(url: String) -> Delivery {
+        Delivery {
+            id: 7,
+            webhook_id: 3,
+            payload: json!({ "type": "TransactionAdded" }),
+            attempts: 0,
+            url,
+            secret: "webhook-secret".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_delivery_signs_payload() {
+        let mut server = mockito::Server::new_async().await;
+        let body = r#"{"type":"TransactionAdded"}"#;
+        let timestamp = 1_700_000_000;
+        let mock = server
+            .mock("POST", "/hook")
+            .match_header("content-type", "application/json")
+            .match_header("x-ccdscan-webhook-id", "3")
+            .match_header("x-ccdscan-delivery-id", "7")
+            .match_header("x-ccdscan-timestamp", "1700000000")
+            .match_header(
+                "x-ccdscan-signature",
+                signature("webhook-secret", timestamp, body.as_bytes()).as_str(),
+            )
+            .match_body(body)
+            .with_status(204)
+            .create_async()
+            .await;
+
+        let (status_code, error) = send_delivery(
+            &reqwest::Client::new(),
+            &delivery(format!("{}/hook", server.url())),
+            timestamp,
+        )
+        .await;
+
+        mock.assert_async().await;
+        assert_eq!(status_code.map(|status| status.as_u16()), Some(204));
+        assert_eq!(error, None);
+
+        // The receiver verifies the signature using the shared secret.
+        let signature = signature("webhook-secret", timestamp, body.as_bytes());
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"webhook-secret").unwrap();
+        mac.update(format!("{}.{}", timestamp, body).as_bytes());
+        let signature = hex::decode(signature.strip_prefix("sha256=").unwrap()).unwrap();
+        assert!(mac.verify_slice(&signature).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_delivery_fails_on_unsuccessful_status() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/hook")
+            .with_status(500)
+            .create_async()
+            .await;
+
+        let (status_code, error) = send_delivery(
+            &reqwest::Client::new(),
+            &delivery(format!("{}/hook", server.url())),
+            1_700_000_000,
+        )
+        .await;
+
+        mock.assert_async().await;
+        assert_eq!(status_code.map(|status| status.as_u16()), Some(500));
+        assert!(error.is_some());
+    }
+}